@@ -45,6 +45,19 @@ pub(crate) fn command_wrapping_plugin(_app: &mut App) {}
 /// or [`Task`]. If you return something else than `()`, the command will be considered finished when the respective [`TaskFinishedIndicator`] says so.
 /// Until then, the dialogue will not be advanced when [`DialogueRunner::continue_in_next_update`] is called. This allows you to e.g. move the camera before the dialogue continues.
 /// If you return `()`, the command will be considered finished immediately.
+///
+/// To write variables back into the dialogue, return a [`CommandResult`]. Its variable writes are applied right after the command returns,
+/// so that e.g. an `<<if>>` following the command sees the new values:
+/// ```rust
+/// # use bevy_yarnspinner::prelude::*;
+/// # use bevy::prelude::*;
+/// # let mut commands = YarnCommands::new();
+/// commands.add_command("give_gold", give_gold);
+///
+/// fn give_gold(In(amount): In<f32>) -> CommandResult {
+///     CommandResult::Continue.with_variable_set("$gold", amount)
+/// }
+/// ```
 pub trait YarnCommand<Marker>: Send + Sync + 'static + Clone {
     /// The input type used to determine the parameters passed to the command from Yarn. A tuple of values will be interpreted as multiple parameters.
     /// This also counts for arbitrarily nested tuples, which will be flattened.
@@ -104,6 +117,22 @@ all_tuples!(impl_command_function, 0, 16, F);
 pub trait TaskFinishedIndicator: Debug + Send + Sync + 'static {
     /// Returns `true` if the task is finished.
     fn is_finished(&self) -> bool;
+
+    /// Variables that should be written to the [`VariableStorage`] of the [`DialogueRunner`] that called the command.
+    /// They are applied once, right after the command returns. Defaults to no writes.
+    fn variable_writes(&self) -> &[(String, YarnValue)] {
+        &[]
+    }
+}
+
+impl TaskFinishedIndicator for CommandResult {
+    fn is_finished(&self) -> bool {
+        true
+    }
+
+    fn variable_writes(&self) -> &[(String, YarnValue)] {
+        CommandResult::variable_writes(self)
+    }
 }
 
 impl TaskFinishedIndicator for AtomicBool {
@@ -22,8 +22,20 @@ fn execute_commands(world: &mut World, mut cursor: Local<EventCursor<ExecuteComm
         };
         let params = event.command.parameters;
         let task_finished_indicator = command.call(params, world);
+        let mut dialogue_runner = get_dialogue_runner_mut(world, event.source);
+        for (name, value) in task_finished_indicator.variable_writes() {
+            dialogue_runner
+                .variable_storage_mut()
+                .set(name.clone(), value.clone())
+                .unwrap_or_else(|e| {
+                    panic!(
+                        "Failed to set variable {name} as requested by command \"{}\": {e}",
+                        event.command.name
+                    )
+                });
+        }
         if !task_finished_indicator.is_finished() {
-            get_dialogue_runner_mut(world, event.source).add_command_task(task_finished_indicator);
+            dialogue_runner.add_command_task(task_finished_indicator);
         }
     }
 }
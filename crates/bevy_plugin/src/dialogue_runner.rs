@@ -4,6 +4,7 @@ pub use self::events::{
 };
 pub use self::{
     builder::DialogueRunnerBuilder,
+    dialogue_language::DialogueLanguage,
     dialogue_option::DialogueOption,
     inner::{InnerDialogue, InnerDialogueMut},
     localized_line::LocalizedLine,
@@ -19,9 +20,10 @@ use bevy::{prelude::*, utils::HashMap};
 pub(crate) use runtime_interaction::DialogueExecutionSystemSet;
 use std::any::TypeId;
 use std::fmt::Debug;
-use yarnspinner::core::Library;
+use yarnspinner::core::{FunctionType, Library};
 
 mod builder;
+mod dialogue_language;
 mod dialogue_option;
 mod events;
 mod inner;
@@ -33,6 +35,7 @@ pub(crate) fn dialogue_plugin(app: &mut App) {
         .add_plugins(localized_line::localized_line_plugin)
         .add_plugins(events::dialogue_runner_events_plugin)
         .add_plugins(dialogue_option::dialogue_option_plugin)
+        .add_plugins(dialogue_language::dialogue_language_plugin)
         .add_plugins(builder::dialogue_runner_builder_plugin)
         .add_plugins(inner::inner_dialogue_runner_plugin);
 }
@@ -80,7 +83,9 @@ impl DialogueRunner {
     }
 
     /// If the dialogue is currently waiting for the user to select an option, this method will select the option with the given id.
-    /// Implies [`DialogueRunner::continue_in_next_update`].
+    /// Implies [`DialogueRunner::continue_in_next_update`] if the selection was accepted.
+    /// Selecting an unavailable option may be ignored instead, depending on [`Dialogue::with_unavailable_selection_policy`],
+    /// in which case the dialogue runner keeps waiting for an option selection.
     pub fn select_option(&mut self, option: OptionId) -> Result<&mut Self> {
         if !self.is_running {
             bail!("Can't select option {option}: the dialogue is currently not running. Please call `DialogueRunner::continue_in_next_update()` only after receiving a `PresentOptionsEvent`.")
@@ -88,6 +93,9 @@ impl DialogueRunner {
         self.dialogue
             .set_selected_option(option)
             .map_err(Error::from)?;
+        if self.dialogue.is_waiting_for_option_selection() {
+            return Ok(self);
+        }
         self.last_selected_option.replace(option);
         self.continue_in_next_update();
         Ok(self)
@@ -200,6 +208,34 @@ impl DialogueRunner {
         self.dialogue.variable_storage_mut()
     }
 
+    /// Returns the current values of all variables in the [`VariableStorage`], e.g. for displaying them in an inspector.
+    /// Changing the returned map does not affect the dialogue; use [`DialogueRunner::set_variable`] for that.
+    #[must_use]
+    pub fn variable_snapshot(&self) -> std::collections::HashMap<String, YarnValue> {
+        self.variable_storage().variables()
+    }
+
+    /// Sets a variable in the [`VariableStorage`], e.g. to live-edit it from an inspector.
+    /// The name must include the leading `$`.
+    pub fn set_variable(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<YarnValue>,
+    ) -> Result<&mut Self> {
+        self.variable_storage_mut().set(name.into(), value.into())?;
+        Ok(self)
+    }
+
+    /// Returns the names and signatures of all functions that can be called from Yarn files,
+    /// including the operators of the standard library. See [`Library::signatures`].
+    #[must_use]
+    pub fn function_signatures(&self) -> std::collections::HashMap<String, FunctionType> {
+        self.library()
+            .signatures()
+            .map(|(name, signature)| (name.to_owned(), signature))
+            .collect()
+    }
+
     /// Returns whether both the text and asset providers have loaded all their lines.
     #[must_use]
     pub fn update_line_availability(
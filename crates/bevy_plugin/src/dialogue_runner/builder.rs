@@ -21,6 +21,8 @@ pub struct DialogueRunnerBuilder {
     compilation: Compilation,
     localizations: Option<Localizations>,
     asset_server: SkipDebug<AssetServer>,
+    start_node: Option<String>,
+    instruction_trace_capacity: usize,
 }
 
 impl DialogueRunnerBuilder {
@@ -37,6 +39,8 @@ impl DialogueRunnerBuilder {
             compilation: yarn_project.compilation().clone(),
             localizations: yarn_project.localizations().cloned(),
             asset_server: yarn_project.asset_server.clone(),
+            start_node: yarn_project.start_node.clone(),
+            instruction_trace_capacity: 0,
         }
     }
 
@@ -62,6 +66,14 @@ impl DialogueRunnerBuilder {
         self
     }
 
+    /// Records the last `capacity` instructions run by the [`DialogueRunner`], readable via [`InnerDialogue::instruction_trace`].
+    /// See [`Dialogue::with_instruction_trace`]. By default, nothing is recorded.
+    #[must_use]
+    pub fn with_instruction_trace(mut self, capacity: usize) -> Self {
+        self.instruction_trace_capacity = capacity;
+        self
+    }
+
     /// Builds the [`DialogueRunner`]. See [`DialogueRunnerBuilder::try_build`] for the fallible version.
     pub fn build(self) -> DialogueRunner {
         self.try_build().unwrap_or_else(|error| {
@@ -73,7 +85,11 @@ impl DialogueRunnerBuilder {
     pub fn try_build(mut self) -> Result<DialogueRunner> {
         let text_provider = Box::new(self.text_provider);
 
-        let mut dialogue = Dialogue::new(self.variable_storage, text_provider.clone());
+        let mut dialogue = Dialogue::new(self.variable_storage, text_provider.clone())
+            .with_instruction_trace(self.instruction_trace_capacity);
+        if let Some(start_node) = self.start_node {
+            dialogue = dialogue.with_default_start_node(start_node);
+        }
         dialogue
             .set_line_hints_enabled(true)
             .library_mut()
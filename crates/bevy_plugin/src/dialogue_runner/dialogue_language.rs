@@ -0,0 +1,52 @@
+use crate::dialogue_runner::DialogueExecutionSystemSet;
+use crate::prelude::*;
+use bevy::prelude::*;
+
+pub(crate) fn dialogue_language_plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        apply_dialogue_language
+            .before(DialogueExecutionSystemSet)
+            .in_set(YarnSpinnerSystemSet),
+    );
+}
+
+/// Sets the language of the [`DialogueRunner`] on the same entity, as if calling [`DialogueRunner::set_language`].
+/// The language is applied whenever this component or the [`DialogueRunner`] is added and whenever this component changes.
+///
+/// Since every [`DialogueRunner`] has its own text and asset providers, runners with different languages can run side by side,
+/// e.g. for players in a split-screen game that prefer different languages.
+///
+/// Panics if the [`YarnProject`] has no [`Localizations`] for the language, just like [`DialogueRunner::set_language`].
+///
+/// ## Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use bevy_yarnspinner::prelude::*;
+///
+/// fn spawn_dialogue_runners(mut commands: Commands, project: Res<YarnProject>) {
+///     commands.spawn((project.create_dialogue_runner(), DialogueLanguage::new("en-US")));
+///     commands.spawn((project.create_dialogue_runner(), DialogueLanguage::new("de-CH")));
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Component)]
+pub struct DialogueLanguage(pub Language);
+
+impl DialogueLanguage {
+    /// Creates a new [`DialogueLanguage`] from anything that can be converted into a [`Language`].
+    pub fn new(language: impl Into<Language>) -> Self {
+        Self(language.into())
+    }
+}
+
+fn apply_dialogue_language(
+    mut dialogue_runners: Query<
+        (&DialogueLanguage, &mut DialogueRunner),
+        Or<(Changed<DialogueLanguage>, Added<DialogueRunner>)>,
+    >,
+) {
+    for (language, mut dialogue_runner) in dialogue_runners.iter_mut() {
+        dialogue_runner.set_language(language.0.clone());
+    }
+}
@@ -30,6 +30,11 @@ pub struct DialogueOption {
     /// This is intended for situations where games wish to show options that the player _could_ have taken,
     /// if some other condition had been met (e.g. having enough "charisma" points).
     pub is_available: bool,
+
+    /// A description of what is needed to select this option, taken from the `#requirement:` metadata of its line,
+    /// e.g. `50_gold` for `-> Bribe the guard <<if $gold >= 50>> #requirement:50_gold`.
+    /// Useful for showing why an option is not available.
+    pub requirement_hint: Option<String>,
 }
 
 impl DialogueOption {
@@ -43,6 +48,7 @@ impl DialogueOption {
             id: yarn_dialogue_option.id,
             destination_node: yarn_dialogue_option.destination_node,
             is_available: yarn_dialogue_option.is_available,
+            requirement_hint: yarn_dialogue_option.requirement_hint,
         }
     }
 }
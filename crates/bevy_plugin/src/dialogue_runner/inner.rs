@@ -31,6 +31,18 @@ impl InnerDialogue<'_> {
         self.0.analyse(context);
         self
     }
+
+    /// Proxy for [`Dialogue::pending_command`]. The [`DialogueRunner`] continues once all tasks of the command are finished,
+    /// so this is the command that is currently awaiting completion.
+    #[must_use]
+    pub fn pending_command(&self) -> Option<(String, Vec<YarnValue>)> {
+        self.0.pending_command()
+    }
+
+    /// Proxy for [`Dialogue::instruction_trace`]. Empty unless recording was enabled with [`DialogueRunnerBuilder::with_instruction_trace`].
+    pub fn instruction_trace(&self) -> impl ExactSizeIterator<Item = &TraceEntry> {
+        self.0.instruction_trace()
+    }
 }
 
 impl InnerDialogueMut<'_> {
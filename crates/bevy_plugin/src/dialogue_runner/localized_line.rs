@@ -1,6 +1,9 @@
 use crate::line_provider::LineAssets;
 use crate::prelude::*;
 use bevy::prelude::*;
+use std::ops::Range;
+use std::time::Duration;
+use unicode_segmentation::UnicodeSegmentation;
 use yarnspinner::runtime::{CHARACTER_ATTRIBUTE, CHARACTER_ATTRIBUTE_NAME_PROPERTY};
 
 pub(crate) fn localized_line_plugin(_app: &mut App) {}
@@ -21,6 +24,7 @@ pub struct LocalizedLine {
     pub metadata: Vec<String>,
     /// The assets associated with this line, provided by [`AssetProvider`]s that were added with [`DialogueRunnerBuilder::add_asset_provider`].
     pub assets: LineAssets,
+    audio_duration: Option<Duration>,
 }
 impl LocalizedLine {
     // Documentation taken from `YarnLine`
@@ -29,26 +33,45 @@ impl LocalizedLine {
         self.attributes.iter().find(|attr| attr.name == name)
     }
 
+    // Documentation taken from `YarnLine`
+    /// Returns the attributes that apply to any part of the text in `grapheme_range`, in the order they appear in [`LocalizedLine::attributes`].
+    /// The range is measured in grapheme clusters, just like [`MarkupAttribute::position`] and [`MarkupAttribute::length`].
+    ///
+    /// Useful for typewriter-style views that reveal a line piece by piece and need to know which effects to apply to the newly revealed text.
+    /// Self-closing attributes like `[pause/]` have no length and are returned if their position lies within the range.
+    pub fn active_attributes_in(&self, grapheme_range: Range<usize>) -> Vec<&MarkupAttribute> {
+        self.attributes
+            .iter()
+            .filter(|attribute| {
+                let start = attribute.position;
+                let end = attribute.position + attribute.length;
+                if attribute.length == 0 {
+                    grapheme_range.contains(&start)
+                } else {
+                    start < grapheme_range.end && end > grapheme_range.start
+                }
+            })
+            .collect()
+    }
+
     // Documentation taken from `YarnLine`
     /// The name of the character, if present.
     /// ## Examples
     /// When there is a name:
     /// ```rust
     /// # use std::collections::HashMap;
-    /// # use bevy_yarnspinner::prelude::*;
-    /// # let line = LocalizedLine {
-    /// #    id: "line".into(),
-    /// #    text: "Alice: Hello! How are you today?".to_owned(),
-    /// #    attributes: vec![MarkupAttribute {
-    /// #        name: "character".to_owned(),
-    /// #        position: 0,
-    /// #        length: 7,
-    /// #        properties: HashMap::from([("name".to_owned(), "Alice".into())]),
-    /// #        source_position: 0,
-    /// #    }],
-    /// #    metadata: vec![],
-    /// #    assets: Default::default(),
-    /// # };
+    /// # use bevy_yarnspinner::{prelude::*, UnderlyingYarnLine};
+    /// # let line = LocalizedLine::from_yarn_line(UnderlyingYarnLine {
+    /// #     id: "line".into(),
+    /// #     text: "Alice: Hello! How are you today?".to_owned(),
+    /// #     attributes: vec![MarkupAttribute {
+    /// #         name: "character".to_owned(),
+    /// #         position: 0,
+    /// #         length: 7,
+    /// #         properties: HashMap::from([("name".to_owned(), "Alice".into())]),
+    /// #         source_position: 0,
+    /// #     }],
+    /// # }, Default::default(), vec![]);
     /// assert_eq!("Alice: Hello! How are you today?", line.text);
     /// assert_eq!(Some("Alice"), line.character_name());
     /// ```
@@ -56,14 +79,12 @@ impl LocalizedLine {
     /// When there is no name:
     /// ```rust
     /// # use std::collections::HashMap;
-    /// # use bevy_yarnspinner::prelude::*;
-    /// # let line = LocalizedLine {
-    /// #    id: "line".into(),
-    /// #    text: "Great, thanks".to_owned(),
-    /// #    attributes: vec![],
-    /// #    metadata: vec![],
-    /// #    assets: Default::default(),
-    /// # };
+    /// # use bevy_yarnspinner::{prelude::*, UnderlyingYarnLine};
+    /// # let line = LocalizedLine::from_yarn_line(UnderlyingYarnLine {
+    /// #     id: "line".into(),
+    /// #     text: "Great, thanks".to_owned(),
+    /// #     attributes: vec![],
+    /// # }, Default::default(), vec![]);
     /// assert_eq!("Great, thanks", line.text);
     /// assert!(line.character_name().is_none());
     pub fn character_name(&self) -> Option<&str> {
@@ -88,20 +109,18 @@ impl LocalizedLine {
     /// When there is a name:
     /// ```rust
     /// # use std::collections::HashMap;
-    /// # use bevy_yarnspinner::prelude::*;
-    /// # let line = LocalizedLine {
-    /// #    id: "line".into(),
-    /// #    text: "Alice: Hello! How are you today?".to_owned(),
-    /// #    attributes: vec![MarkupAttribute {
-    /// #        name: "character".to_owned(),
-    /// #        position: 0,
-    /// #        length: 7,
-    /// #        properties: HashMap::from([("name".to_owned(), "Alice".into())]),
-    /// #        source_position: 0,
-    /// #    }],
-    /// #    metadata: vec![],
-    /// #    assets: Default::default(),
-    /// # };
+    /// # use bevy_yarnspinner::{prelude::*, UnderlyingYarnLine};
+    /// # let line = LocalizedLine::from_yarn_line(UnderlyingYarnLine {
+    /// #     id: "line".into(),
+    /// #     text: "Alice: Hello! How are you today?".to_owned(),
+    /// #     attributes: vec![MarkupAttribute {
+    /// #         name: "character".to_owned(),
+    /// #         position: 0,
+    /// #         length: 7,
+    /// #         properties: HashMap::from([("name".to_owned(), "Alice".into())]),
+    /// #         source_position: 0,
+    /// #     }],
+    /// # }, Default::default(), vec![]);
     /// assert_eq!("Alice: Hello! How are you today?", line.text);
     /// assert_eq!("Hello! How are you today?", &line.text_without_character_name());
     /// ```
@@ -109,21 +128,27 @@ impl LocalizedLine {
     /// When there is no name:
     /// ```rust
     /// # use std::collections::HashMap;
-    /// # use bevy_yarnspinner::prelude::*;
-    /// # let line = LocalizedLine {
-    /// #    id: "line".into(),
-    /// #    text: "Great, thanks".to_owned(),
-    /// #    attributes: vec![],
-    /// #    metadata: vec![],
-    /// #    assets: Default::default(),
-    /// # };
+    /// # use bevy_yarnspinner::{prelude::*, UnderlyingYarnLine};
+    /// # let line = LocalizedLine::from_yarn_line(UnderlyingYarnLine {
+    /// #     id: "line".into(),
+    /// #     text: "Great, thanks".to_owned(),
+    /// #     attributes: vec![],
+    /// # }, Default::default(), vec![]);
     /// assert_eq!("Great, thanks", line.text);
     /// assert_eq!("Great, thanks", &line.text_without_character_name());
     pub fn text_without_character_name(&self) -> String {
+        self.without_character_name().text
+    }
+
+    // Documentation taken from `YarnLine`
+    /// Returns a copy of this line with the text covered by the `character` attribute removed,
+    /// like [`LocalizedLine::text_without_character_name`], but also keeps the other attributes,
+    /// with their positions shifted to account for the removed name. See [`LocalizedLine::delete_range`] for details.
+    pub fn without_character_name(&self) -> Self {
         if let Some(attribute) = self.attribute(CHARACTER_ATTRIBUTE) {
-            self.delete_range(attribute).text
+            self.delete_range(attribute)
         } else {
-            self.text.to_owned()
+            self.clone()
         }
     }
 
@@ -165,6 +190,7 @@ impl LocalizedLine {
         let yarn_line: YarnLine = self.clone().into();
         let deleted_range = yarn_line.delete_range(attribute_to_delete);
         Self::from_yarn_line(deleted_range, self.assets.clone(), self.metadata.clone())
+            .with_audio_duration(self.audio_duration)
     }
 
     /// Returns `true` if this line comes right before an options block.
@@ -173,6 +199,61 @@ impl LocalizedLine {
     pub fn is_last_line_before_options(&self) -> bool {
         self.metadata.iter().any(|m| m == "lastline")
     }
+
+    /// Gets the value of a metadata tag in the form `key:value`, e.g. `1.5` for the key `pause_after` on the line `Hello! #pause_after:1.5`.
+    /// Whitespace around the key and the value is ignored. Returns the value of the first matching tag, if any.
+    pub fn metadata_value(&self, key: &str) -> Option<&str> {
+        self.metadata.iter().find_map(|tag| {
+            let (tag_key, value) = tag.split_once(':')?;
+            (tag_key.trim() == key).then(|| value.trim())
+        })
+    }
+
+    /// Returns the number of user-perceived characters, i.e. grapheme clusters, in [`LocalizedLine::text_without_character_name`].
+    /// Since [`LocalizedLine::text`] already has all markup removed, this is the number of characters the player will actually see.
+    pub fn grapheme_count(&self) -> usize {
+        self.text_without_character_name().graphemes(true).count()
+    }
+
+    /// Returns [`LocalizedLine::text`] shortened so that the part after the character name has at most `max_graphemes` grapheme clusters,
+    /// e.g. for showing long lines in a single-line HUD. The text is cut on grapheme cluster boundaries, so that no
+    /// user-perceived character is split in half, and `…` is appended, which counts as one of the `max_graphemes`.
+    /// The character name is always kept. If the text already fits, it is returned unchanged.
+    pub fn truncated(&self, max_graphemes: usize) -> String {
+        const ELLIPSIS: char = '…';
+        let text = self.text_without_character_name();
+        let character_name_prefix = self.text.strip_suffix(text.as_str()).unwrap_or_default();
+        if text.graphemes(true).count() <= max_graphemes {
+            return self.text.clone();
+        }
+        let kept_graphemes = max_graphemes.saturating_sub(1);
+        let mut truncated = character_name_prefix.to_owned();
+        truncated.extend(text.graphemes(true).take(kept_graphemes));
+        if max_graphemes > 0 {
+            truncated.push(ELLIPSIS);
+        }
+        truncated
+    }
+
+    /// Estimates how long it takes to read this line at the given reading speed, e.g. to automatically advance the dialogue after an appropriate delay.
+    ///
+    /// The words are counted by splitting [`LocalizedLine::text_without_character_name`] at unicode whitespace.
+    /// An empty line thus takes [`Duration::ZERO`] to read.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `words_per_minute` is not a positive number.
+    pub fn estimated_reading_time(&self, words_per_minute: f32) -> Duration {
+        assert!(
+            words_per_minute > 0.0,
+            "Cannot estimate reading time: words per minute must be positive, but was {words_per_minute}"
+        );
+        let word_count = self
+            .text_without_character_name()
+            .split_whitespace()
+            .count();
+        Duration::from_secs_f32(word_count as f32 / words_per_minute * 60.0)
+    }
 }
 
 impl From<LocalizedLine> for YarnLine {
@@ -186,17 +267,112 @@ impl From<LocalizedLine> for YarnLine {
 }
 
 impl LocalizedLine {
-    pub(crate) fn from_yarn_line(
-        line: YarnLine,
-        assets: LineAssets,
-        metadata: Vec<String>,
-    ) -> Self {
+    /// Creates a line from a [`YarnLine`] emitted by the [`Dialogue`], with the given assets and metadata.
+    /// The line has no [`LocalizedLine::audio_duration`].
+    pub fn from_yarn_line(line: YarnLine, assets: LineAssets, metadata: Vec<String>) -> Self {
         Self {
             id: line.id,
             text: line.text,
             attributes: line.attributes,
             metadata,
             assets,
+            audio_duration: None,
         }
     }
+
+    /// The length of the voice-over [`AudioSource`](bevy::audio::AudioSource) in [`LocalizedLine::assets`], e.g. for revealing the text in sync with it.
+    /// This is only set if the `audio_assets` feature is enabled and the length can be determined from the audio file.
+    pub fn audio_duration(&self) -> Option<Duration> {
+        self.audio_duration
+    }
+
+    /// Sets the [`LocalizedLine::audio_duration`].
+    #[must_use]
+    pub fn with_audio_duration(mut self, audio_duration: impl Into<Option<Duration>>) -> Self {
+        self.audio_duration = audio_duration.into();
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn line(text: &str, attributes: Vec<MarkupAttribute>) -> LocalizedLine {
+        LocalizedLine::from_yarn_line(
+            YarnLine {
+                id: "line".into(),
+                text: text.to_owned(),
+                attributes,
+            },
+            Default::default(),
+            vec![],
+        )
+    }
+
+    #[test]
+    fn empty_line_takes_no_time_to_read() {
+        let line = line("", vec![]);
+        assert_eq!(0, line.grapheme_count());
+        assert_eq!(Duration::ZERO, line.estimated_reading_time(200.0));
+    }
+
+    #[test]
+    fn estimates_reading_time_without_character_name() {
+        let line = line(
+            "Alice: Hello there, how are you?",
+            vec![MarkupAttribute {
+                name: "character".to_owned(),
+                position: 0,
+                length: 7,
+                properties: HashMap::from([("name".to_owned(), "Alice".into())]),
+                source_position: 0,
+            }],
+        );
+        // 5 words at 120 words per minute
+        assert_eq!(
+            Duration::from_millis(2500),
+            line.estimated_reading_time(120.0)
+        );
+        assert_eq!(25, line.grapheme_count());
+    }
+
+    #[test]
+    fn truncates_on_grapheme_boundaries() {
+        // The family emoji consists of several code points joined by zero-width joiners.
+        let family = "👨‍👩‍👧";
+        let line = line(
+            &format!("Alice: Hi {family} there"),
+            vec![MarkupAttribute {
+                name: "character".to_owned(),
+                position: 0,
+                length: 7,
+                properties: HashMap::from([("name".to_owned(), "Alice".into())]),
+                source_position: 0,
+            }],
+        );
+        assert_eq!(format!("Alice: Hi {family}…"), line.truncated(5));
+        assert_eq!("Alice: Hi …", line.truncated(4));
+        assert_eq!(line.text, line.truncated(line.grapheme_count()));
+    }
+
+    #[test]
+    fn reads_key_value_metadata() {
+        let mut line = line("Hello!", vec![]);
+        line.metadata = vec![
+            "lastline".to_owned(),
+            "pacing:slow".to_owned(),
+            "pause_after: 1.5".to_owned(),
+        ];
+        assert_eq!(Some("slow"), line.metadata_value("pacing"));
+        assert_eq!(Some("1.5"), line.metadata_value("pause_after"));
+        assert_eq!(None, line.metadata_value("lastline"));
+    }
+
+    #[test]
+    fn counts_graphemes_instead_of_chars() {
+        let line = line("e\u{301}🇨🇭", vec![]);
+        assert_eq!(2, line.grapheme_count());
+    }
 }
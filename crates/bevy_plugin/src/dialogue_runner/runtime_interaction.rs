@@ -41,6 +41,7 @@ fn continue_runtime(
     mut last_options: Local<HashMap<Entity, Vec<DialogueOption>>>,
     loaded_untyped_assets: Res<Assets<LoadedUntypedAsset>>,
     project: Res<YarnProject>,
+    #[cfg(feature = "audio_assets")] audio_sources: Option<Res<Assets<AudioSource>>>,
 ) -> SystemResult {
     for (source, mut dialogue_runner) in dialogue_runners.iter_mut() {
         let is_sending_missed_events = !dialogue_runner.unsent_events.is_empty();
@@ -99,10 +100,16 @@ fn continue_runtime(
                 DialogueEvent::Line(line) => {
                     let assets = dialogue_runner.get_assets(&line);
                     let metadata = project.line_metadata(&line.id).unwrap_or_default().to_vec();
-                    present_line_events.send(PresentLineEvent {
-                        line: LocalizedLine::from_yarn_line(line, assets, metadata),
-                        source,
-                    });
+                    #[allow(unused_mut)]
+                    let mut line = LocalizedLine::from_yarn_line(line, assets, metadata);
+                    #[cfg(feature = "audio_assets")]
+                    {
+                        let audio_duration = audio_sources.as_deref().and_then(|audio_sources| {
+                            crate::line_provider::audio_duration(&line.assets, audio_sources)
+                        });
+                        line = line.with_audio_duration(audio_duration);
+                    }
+                    present_line_events.send(PresentLineEvent { line, source });
                 }
                 DialogueEvent::Options(options) => {
                     let options: Vec<DialogueOption> = options
@@ -136,11 +136,15 @@ pub mod prelude {
         commands::{YarnCommand, YarnCommands},
         default_impl::FileExtensionAssetProvider,
         development_file_generation::DevelopmentFileGeneration,
-        dialogue_runner::{DialogueOption, DialogueRunner, DialogueRunnerBuilder, LocalizedLine},
+        dialogue_runner::{
+            DialogueLanguage, DialogueOption, DialogueRunner, DialogueRunnerBuilder, LocalizedLine,
+        },
         line_provider::{AssetProvider, LineAssets, TextProvider},
-        localization::{Localization, Localizations},
+        localization::{
+            Localization, Localizations, StringsFileParseError, StringsFileParseErrorEvent,
+        },
         plugin::{YarnFileSource, YarnSpinnerPlugin, YarnSpinnerSystemSet},
-        project::YarnProject,
+        project::{YarnCompilationFailedEvent, YarnProject},
         yarn_file_asset::YarnFile,
     };
     pub(crate) use crate::{localization::StringsFile, utils::*};
@@ -148,18 +152,19 @@ pub mod prelude {
     pub(crate) use serde::{Deserialize, Serialize};
     pub(crate) use yarnspinner::prelude::*;
     pub use yarnspinner::prelude::{
-        IntoYarnValueFromNonYarnValue, Language, LineId, MarkupAttribute, MarkupValue, OptionId,
-        VariableStorage, YarnFn, YarnLibrary, YarnValue,
+        CommandResult, IntoYarnValueFromNonYarnValue, Language, LineId, MarkupAttribute,
+        MarkupValue, OptionId, VariableStorage, YarnFn, YarnLibrary, YarnValue,
     };
     pub(crate) type SystemResult = Result<()>;
 }
 
 pub use crate::commands::{TaskFinishedIndicator, UntypedYarnCommand};
 pub use crate::dialogue_runner::{InnerDialogue, InnerDialogueMut};
-pub use yarnspinner::core::{yarn_fn_type, UntypedYarnFn};
+pub use yarnspinner::compiler::Diagnostic;
+pub use yarnspinner::core::{yarn_fn_type, FunctionType, UntypedYarnFn};
 pub use yarnspinner::prelude::{
-    Compilation, StringInfo, TextProvider as UnderlyingTextProvider, YarnAnalysisContext,
-    YarnCommand as UnderlyingYarnCommand, YarnLine as UnderlyingYarnLine,
+    Compilation, StringInfo, TextProvider as UnderlyingTextProvider, TraceEntry,
+    YarnAnalysisContext, YarnCommand as UnderlyingYarnCommand, YarnLine as UnderlyingYarnLine,
 };
 
 pub mod deferred_loading {
@@ -1,4 +1,6 @@
 #[cfg(feature = "audio_assets")]
+pub(crate) use asset_provider::audio_duration;
+#[cfg(feature = "audio_assets")]
 pub use asset_provider::AudioAssetProvider;
 pub use asset_provider::{file_extensions, AssetProvider, FileExtensionAssetProvider, LineAssets};
 use bevy::prelude::*;
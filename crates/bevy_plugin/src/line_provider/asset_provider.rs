@@ -1,6 +1,8 @@
 use crate::prelude::*;
 use crate::UnderlyingYarnLine;
 #[cfg(feature = "audio_assets")]
+pub(crate) use audio_asset_provider_plugin::audio_duration;
+#[cfg(feature = "audio_assets")]
 pub use audio_asset_provider_plugin::AudioAssetProvider;
 use bevy::asset::{Asset, LoadedUntypedAsset};
 use bevy::prelude::*;
@@ -1,8 +1,10 @@
 use crate::prelude::*;
 use bevy::asset::LoadedUntypedAsset;
+use bevy::audio::{Decodable, Source};
 use bevy::prelude::*;
 use std::any::Any;
 use std::fmt::Debug;
+use std::time::Duration;
 
 pub(crate) fn audio_asset_provider_plugin(_app: &mut App) {}
 
@@ -73,3 +75,12 @@ impl AssetProvider for AudioAssetProvider {
         self.0.get_assets(line)
     }
 }
+
+/// Returns the length of the [`AudioSource`] provided for a line, if it is loaded and its format allows determining the length without playing it.
+pub(crate) fn audio_duration(
+    assets: &LineAssets,
+    audio_sources: &Assets<AudioSource>,
+) -> Option<Duration> {
+    let handle = assets.get_handle::<AudioSource>()?;
+    audio_sources.get(&handle)?.decoder().total_duration()
+}
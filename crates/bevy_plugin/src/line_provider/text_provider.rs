@@ -31,6 +31,11 @@ pub trait TextProvider: UnderlyingTextProvider {
     /// Extends the string table set by [`TextProvider::set_base_string_table`] with additional strings.
     fn extend_base_string_table(&mut self, string_table: HashMap<LineId, StringInfo>);
 
+    /// Sets the language that lines fall back to when they are missing in the selected language, see [`YarnProject::set_base_language`].
+    /// [`None`] means the language of [`Localizations::base_localization`], whose text is found in the string table set by [`TextProvider::set_base_string_table`].
+    /// The default implementation ignores this.
+    fn set_base_language(&mut self, _language: Option<Language>) {}
+
     /// Stores the assets fetched by [`TextProvider::fetch_assets`].
     /// This functionality is split into two functions because [`TextProvider::take_fetched_assets`] is mutable,
     /// so we lose access to the [`World`] when calling it since it contains this very [`TextProvider`].
@@ -36,6 +36,10 @@ impl TextProvider for SharedTextProvider {
             .extend_base_string_table(string_table)
     }
 
+    fn set_base_language(&mut self, language: Option<Language>) {
+        self.0.write().unwrap().set_base_language(language)
+    }
+
     fn take_fetched_assets(&mut self, asset: Box<dyn Any>) {
         self.0.write().unwrap().take_fetched_assets(asset)
     }
@@ -58,6 +62,10 @@ impl UnderlyingTextProvider for SharedTextProvider {
         self.0.read().unwrap().get_text(id)
     }
 
+    fn get_texts(&self, ids: &[LineId]) -> HashMap<LineId, String> {
+        self.0.read().unwrap().get_texts(ids)
+    }
+
     fn set_language(&mut self, language: Option<Language>) {
         self.0.write().unwrap().set_language(language)
     }
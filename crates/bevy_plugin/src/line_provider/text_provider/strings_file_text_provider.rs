@@ -15,6 +15,8 @@ pub(crate) fn strings_file_text_provider_plugin(_app: &mut App) {}
 /// this will send the lines as they appear in the Yarn file. If [`DialogueRunner::set_language`] or [`DialogueRunner::set_text_language`] were used to
 /// set the language to a language supported by a translation in the [`Localizations`], this loads the strings file for that translation from the disk at the
 /// specified path. If this fails, the base language is used as a fallback.
+/// If [`YarnProject::set_base_language`] was used to make a translation the base language, lines missing in the selected language
+/// are looked up in that translation's strings file first.
 #[derive(Debug, Clone)]
 pub struct StringsFileTextProvider {
     asset_server: SkipDebug<AssetServer>,
@@ -23,6 +25,9 @@ pub struct StringsFileTextProvider {
     base_string_table: HashMap<LineId, StringInfo>,
     strings_file_handle: Option<Handle<StringsFile>>,
     translation_string_table: Option<HashMap<LineId, String>>,
+    fallback_language: Option<Language>,
+    fallback_strings_file_handle: Option<Handle<StringsFile>>,
+    fallback_string_table: Option<HashMap<LineId, String>>,
     event_cursor: Arc<RwLock<EventCursor<AssetEvent<StringsFile>>>>,
 }
 
@@ -36,8 +41,14 @@ impl UnderlyingTextProvider for StringsFileTextProvider {
     }
 
     fn get_text(&self, id: &LineId) -> Option<String> {
+        // Shadow lines are not part of strings files, so they are looked up under the line they shadow.
+        let id = self
+            .base_string_table
+            .get(id)
+            .and_then(|info| info.shadow_line_id.as_ref())
+            .unwrap_or(id);
         if self.is_base_language() {
-            return self.base_string_table.get(id).map(|info| info.text.clone());
+            return self.get_fallback_text(id);
         }
 
         self.translation_string_table
@@ -50,7 +61,7 @@ impl UnderlyingTextProvider for StringsFileTextProvider {
                 } else {
                     warn!("Did not find translation for line {id} in language {language} because the strings file has not been loaded yet, falling back to base language.");
                 }
-                self.base_string_table.get(id).map(|info| info.text.clone())
+                self.get_fallback_text(id)
             })
     }
 
@@ -72,18 +83,8 @@ impl UnderlyingTextProvider for StringsFileTextProvider {
             self.set_language_invalidating_translation(None);
             return;
         }
-        let Some(localization) = localizations.translation(&language) else {
-            let languages = localizations
-                .supported_languages()
-                .map(ToString::to_string)
-                .collect::<Vec<_>>()
-                .join(", ");
-            panic!("Set language to {language}, but that language is not supported. Expected one of {languages}.");
-        };
-        let path = localization.strings_file.as_path();
-        let asset_path = path.to_string_lossy().replace('\\', "/");
-        self.strings_file_handle
-            .replace(self.asset_server.load(asset_path));
+        let handle = self.load_strings_file(&localizations, &language);
+        self.strings_file_handle.replace(handle);
     }
 
     fn get_language(&self) -> Option<Language> {
@@ -93,7 +94,9 @@ impl UnderlyingTextProvider for StringsFileTextProvider {
     fn are_lines_available(&self) -> bool {
         let is_base_language = self.is_base_language();
         let has_fetched_translation = || self.translation_string_table.is_some();
-        is_base_language || has_fetched_translation()
+        let has_fetched_fallback =
+            self.fallback_language.is_none() || self.fallback_string_table.is_some();
+        (is_base_language || has_fetched_translation()) && has_fetched_fallback
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -108,22 +111,61 @@ impl UnderlyingTextProvider for StringsFileTextProvider {
 impl StringsFileTextProvider {
     /// Create a new text provider from a Yarn project. This will be done for you when using [`YarnProject::create_dialogue_runner`] or [`YarnProject::build_dialogue_runner`].
     pub fn from_yarn_project(yarn_project: &YarnProject) -> Self {
-        Self {
+        let mut text_provider = Self {
             asset_server: yarn_project.asset_server.clone(),
             localizations: yarn_project.localizations.clone(),
             language: None,
             base_string_table: yarn_project.compilation.string_table.clone(),
             strings_file_handle: None,
             translation_string_table: None,
+            fallback_language: None,
+            fallback_strings_file_handle: None,
+            fallback_string_table: None,
             event_cursor: Default::default(),
-        }
+        };
+        text_provider.set_base_language(yarn_project.base_language.clone());
+        text_provider
     }
+
     fn set_language_invalidating_translation(&mut self, language: impl Into<Option<Language>>) {
         self.language = language.into();
         self.translation_string_table = None;
         self.strings_file_handle = None;
     }
 
+    /// Looks up a line in the strings file of the base language set by [`YarnProject::set_base_language`],
+    /// falling back to the text of the Yarn files.
+    /// Lines whose text was stripped by [`Compiler::with_strip_line_text`](yarnspinner::compiler::Compiler::with_strip_line_text) have no text to fall back to.
+    fn get_fallback_text(&self, id: &LineId) -> Option<String> {
+        self.fallback_string_table
+            .as_ref()
+            .and_then(|table| table.get(id).cloned())
+            .or_else(|| {
+                self.base_string_table
+                    .get(id)
+                    .filter(|info| !info.is_text_stripped)
+                    .map(|info| info.text.clone())
+            })
+    }
+
+    fn load_strings_file(
+        &self,
+        localizations: &Localizations,
+        language: &Language,
+    ) -> Handle<StringsFile> {
+        let Some(localization) = localizations.translation(language) else {
+            let languages = localizations
+                .supported_languages()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            panic!("Set language to {language}, but that language is not supported. Expected one of {languages}.");
+        };
+        let path = localization.strings_file.as_path();
+        let asset_path = path.to_string_lossy().replace('\\', "/");
+        self.asset_server.load(asset_path)
+    }
+
     fn is_base_language(&self) -> bool {
         self.language.is_none()
             || self.language.as_ref()
@@ -143,46 +185,113 @@ impl TextProvider for StringsFileTextProvider {
         self.base_string_table.extend(string_table);
     }
 
+    fn set_base_language(&mut self, language: Option<Language>) {
+        let language = language.filter(|language| {
+            self.localizations
+                .as_ref()
+                .is_some_and(|localizations| *language != localizations.base_localization.language)
+        });
+        if language == self.fallback_language {
+            return;
+        }
+        self.fallback_string_table = None;
+        self.fallback_strings_file_handle = None;
+        self.fallback_language = language.clone();
+        let Some(language) = language else {
+            return;
+        };
+        let Some(localizations) = self.localizations.clone() else {
+            panic!("Set base language to {language}, but no localizations have been registered as supported.");
+        };
+        let handle = self.load_strings_file(&localizations, &language);
+        self.fallback_strings_file_handle.replace(handle);
+    }
+
     fn take_fetched_assets(&mut self, asset: Box<dyn Any>) {
-        let string_table: Box<HashMap<LineId, String>> = asset.downcast().unwrap();
-        self.translation_string_table.replace(*string_table);
+        let string_tables: Box<FetchedStringTables> = asset.downcast().unwrap();
+        if let Some(translation) = string_tables.translation {
+            self.translation_string_table.replace(translation);
+        }
+        if let Some(fallback) = string_tables.fallback {
+            self.fallback_string_table.replace(fallback);
+        }
     }
 
     fn fetch_assets(&self, world: &World) -> Option<Box<dyn Any + 'static>> {
-        if self.is_base_language() {
-            return None;
-        }
-        let handle = self.strings_file_handle.as_ref()?;
-        if !self.asset_server.is_loaded_with_dependencies(handle) {
+        let translation_handle = self
+            .strings_file_handle
+            .as_ref()
+            .filter(|_| !self.is_base_language());
+        let fallback_handle = self.fallback_strings_file_handle.as_ref();
+        if translation_handle.is_none() && fallback_handle.is_none() {
             return None;
         }
         let asset_events = world.resource::<Events<AssetEvent<StringsFile>>>();
-        let strings_file_has_changed = || {
-            let mut cursor = self.event_cursor.write().unwrap();
-            cursor.read(asset_events).any(|event| match event {
-                AssetEvent::Modified { id } => *id == handle.id(),
-                _ => false,
+        let modified_ids: Vec<_> = self
+            .event_cursor
+            .write()
+            .unwrap()
+            .read(asset_events)
+            .filter_map(|event| match event {
+                AssetEvent::Modified { id } => Some(*id),
+                _ => None,
             })
-        };
-        let has_no_translation_yet = self.translation_string_table.is_none();
-        if has_no_translation_yet || strings_file_has_changed() {
-            let strings_file = world.resource::<Assets<StringsFile>>().get(handle).unwrap();
-            let expected_language = self.language.as_ref().unwrap();
-            if let Some(record) = strings_file.get_offending_language(expected_language) {
-                let path = self.asset_server.get_path(handle).unwrap();
-                panic!("Expected strings file at {path} to only contain language {expected_language}, but its entry with id \"{id}\" is for language {actual_language}.",
-                           path = path.path().display(),
-                           id = record.id,
-                           actual_language = record.language,
-                    );
+            .collect();
+        let fetch = |handle: Option<&Handle<StringsFile>>,
+                     language: Option<&Language>,
+                     current_table: &Option<HashMap<LineId, String>>| {
+            let handle = handle?;
+            if !self.asset_server.is_loaded_with_dependencies(handle) {
+                return None;
             }
-            let string_table: HashMap<LineId, String> = strings_file
-                .iter()
-                .map(|(id, record)| (id.clone(), record.text.clone()))
-                .collect();
-            Some(Box::new(string_table))
-        } else {
-            None
+            let has_changed = modified_ids.contains(&handle.id());
+            if current_table.is_some() && !has_changed {
+                return None;
+            }
+            Some(self.read_string_table(world, handle, language.unwrap()))
+        };
+        let string_tables = FetchedStringTables {
+            translation: fetch(
+                translation_handle,
+                self.language.as_ref(),
+                &self.translation_string_table,
+            ),
+            fallback: fetch(
+                fallback_handle,
+                self.fallback_language.as_ref(),
+                &self.fallback_string_table,
+            ),
+        };
+        (string_tables.translation.is_some() || string_tables.fallback.is_some())
+            .then(|| Box::new(string_tables) as Box<dyn Any>)
+    }
+}
+
+impl StringsFileTextProvider {
+    fn read_string_table(
+        &self,
+        world: &World,
+        handle: &Handle<StringsFile>,
+        expected_language: &Language,
+    ) -> HashMap<LineId, String> {
+        let strings_file = world.resource::<Assets<StringsFile>>().get(handle).unwrap();
+        if let Some(record) = strings_file.get_offending_language(expected_language) {
+            let path = self.asset_server.get_path(handle).unwrap();
+            panic!("Expected strings file at {path} to only contain language {expected_language}, but its entry with id \"{id}\" is for language {actual_language}.",
+                       path = path.path().display(),
+                       id = record.id,
+                       actual_language = record.language,
+                );
         }
+        strings_file
+            .iter()
+            .map(|(id, record)| (id.clone(), record.text.clone()))
+            .collect()
     }
 }
+
+/// The string tables passed from [`TextProvider::fetch_assets`] to [`TextProvider::take_fetched_assets`].
+struct FetchedStringTables {
+    translation: Option<HashMap<LineId, String>>,
+    fallback: Option<HashMap<LineId, String>>,
+}
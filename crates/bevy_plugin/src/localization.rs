@@ -1,4 +1,5 @@
 pub use self::localizations::*;
+pub use self::strings_file::{StringsFileParseError, StringsFileParseErrorEvent};
 pub(crate) use self::{
     line_id_generation::LineIdUpdateSystemSet,
     strings_file::UpdateAllStringsFilesForStringTableEvent, strings_file::*,
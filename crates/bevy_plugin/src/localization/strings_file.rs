@@ -1,10 +1,13 @@
+pub use self::parse_error::{StringsFileParseError, StringsFileParseErrorEvent};
 pub(crate) use self::{asset::StringsFile, updating::UpdateAllStringsFilesForStringTableEvent};
 use bevy::prelude::*;
 
 mod asset;
+mod parse_error;
 mod updating;
 
 pub(crate) fn strings_file_plugin(app: &mut App) {
-    app.add_plugins(asset::strings_file_asset_plugin)
+    app.add_plugins(parse_error::strings_file_parse_error_plugin)
+        .add_plugins(asset::strings_file_asset_plugin)
         .add_plugins(updating::strings_file_updating_plugin);
 }
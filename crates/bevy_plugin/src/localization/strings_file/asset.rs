@@ -1,5 +1,8 @@
 //! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner-Unity/blob/462c735766a4c4881cd1ef1f15de28c83b2ba0a8/Runtime/StringTableEntry.cs>
 
+use super::parse_error::{
+    StringsFileParseError, StringsFileParseErrorEvent, StringsFileParseErrorQueue,
+};
 use crate::prelude::*;
 use anyhow::{anyhow, bail};
 use bevy::asset::{io::Reader, AssetLoader, LoadContext};
@@ -16,8 +19,20 @@ pub(crate) fn strings_file_asset_plugin(app: &mut App) {
         .init_asset_loader::<StringsFileAssetLoader>();
 }
 
-#[derive(Debug, Default)]
-struct StringsFileAssetLoader;
+#[derive(Debug)]
+struct StringsFileAssetLoader {
+    parse_errors: StringsFileParseErrorQueue,
+}
+
+impl FromWorld for StringsFileAssetLoader {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            parse_errors: world
+                .get_resource_or_init::<StringsFileParseErrorQueue>()
+                .clone(),
+        }
+    }
+}
 
 impl AssetLoader for StringsFileAssetLoader {
     type Asset = StringsFile;
@@ -27,14 +42,21 @@ impl AssetLoader for StringsFileAssetLoader {
         &self,
         reader: &mut dyn Reader,
         _settings: &(),
-        _load_context: &mut LoadContext<'_>,
+        load_context: &mut LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
-        let mut csv_reader = csv::Reader::from_reader(bytes.as_slice());
-        let records: csv::Result<Vec<_>> = csv_reader.deserialize().collect();
-        let strings_file = StringsFile::new_with_single_language(records?)?;
-        Ok(strings_file)
+        let content = String::from_utf8(bytes)?;
+        StringsFile::from_csv(&content).map_err(|error| {
+            let path = load_context.path().to_path_buf();
+            let message = anyhow!(
+                "Failed to parse strings file \"{}\" at {error}",
+                path.display()
+            );
+            self.parse_errors
+                .push(StringsFileParseErrorEvent { path, error });
+            message
+        })
     }
 
     fn extensions(&self) -> &[&str] {
@@ -47,16 +69,41 @@ impl AssetLoader for StringsFileAssetLoader {
 pub(crate) struct StringsFile(HashMap<LineId, StringsFileRecord>);
 
 impl StringsFile {
-    pub(crate) fn new_with_single_language(records: Vec<StringsFileRecord>) -> Result<Self> {
-        if let Some(language) = records.first().map(|record| &record.language) {
-            for record in records.iter().skip(1) {
-                if record.language != *language {
-                    bail!("Loaded strings file with mixed languages records must have the same language. Expected \"{}\", got \"{}\" in record: {:#?}",
-                    language,
-                    record.language,
-                    record)
+    /// Parses the contents of a `.strings.csv` file. All records must be in the same language.
+    pub(crate) fn from_csv(content: &str) -> std::result::Result<Self, StringsFileParseError> {
+        let mut csv_reader = csv::Reader::from_reader(content.as_bytes());
+        let headers = csv_reader
+            .headers()
+            .map_err(|e| StringsFileParseError::from_csv_error(e, &csv::StringRecord::new()))?
+            .clone();
+        let language_column = headers
+            .iter()
+            .position(|header| header == "language")
+            .unwrap_or_default();
+        let mut records: Vec<StringsFileRecord> = Vec::new();
+        for raw_record in csv_reader.records() {
+            let raw_record =
+                raw_record.map_err(|e| StringsFileParseError::from_csv_error(e, &headers))?;
+            let record: StringsFileRecord = raw_record
+                .deserialize(Some(&headers))
+                .map_err(|e| StringsFileParseError::from_csv_error(e, &headers))?;
+            if let Some(first) = records.first() {
+                if record.language != first.language {
+                    let line = raw_record
+                        .position()
+                        .map_or(1, |position| position.line() as usize);
+                    return Err(StringsFileParseError::at_field(
+                        line,
+                        language_column,
+                        &headers,
+                        format!(
+                            "All records must have the same language. Expected \"{}\", got \"{}\"",
+                            first.language, record.language
+                        ),
+                    ));
                 }
             }
+            records.push(record);
         }
         let records = records
             .into_iter()
@@ -142,6 +189,10 @@ impl StringsFile {
         let language = language.into();
         let mut records = HashMap::new();
         for (id, string_info) in string_table {
+            if string_info.shadow_line_id.is_some() {
+                // Shadow lines reuse the translation of the line they shadow.
+                continue;
+            }
             if string_info.is_implicit_tag {
                 bail!(
                     "Cannot build strings file from not fully tagged Yarn files (line {} in \"{}\" is not tagged).",
@@ -150,6 +201,7 @@ impl StringsFile {
                 )
             }
             let lock = Lock::compute_from(&string_info.text);
+            let metadata = line_metadata(string_info.metadata);
             records.insert(
                 id.clone(),
                 StringsFileRecord {
@@ -160,7 +212,8 @@ impl StringsFile {
                     node: string_info.node_name,
                     line_number: string_info.line_number,
                     lock,
-                    comment: read_comments(string_info.metadata),
+                    comment: read_comments(&metadata),
+                    metadata: metadata.join(" "),
                 },
             );
         }
@@ -219,6 +272,7 @@ fn records_equal_except_for_text(lhs: &StringsFileRecord, rhs: &StringsFileRecor
         && lhs.line_number == rhs.line_number
         && lhs.lock == rhs.lock
         && lhs.comment == rhs.comment
+        && lhs.metadata == rhs.metadata
 }
 const UPDATE_PREFIX: &str = "(NEEDS UPDATE) ";
 
@@ -279,6 +333,15 @@ pub(crate) struct StringsFileRecord {
     pub(crate) lock: Lock,
     /// A comment used to describe this line to translators.
     pub(crate) comment: String,
+    /// The line's metadata, i.e. its hashtags without the `#` and excluding the line ID, separated by whitespace.
+    /// Unlike the [`comment`](StringsFileRecord::comment), this is not meant to be edited, so that tools such as
+    /// recording script exports can rely on finding e.g. voice direction like `vo:whispered` here.
+    ///
+    /// ## Implementation notes
+    ///
+    /// Defaults to an empty string when reading strings files that were written without this column.
+    #[serde(default)]
+    pub(crate) metadata: String,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Reflect, Serialize, Deserialize)]
@@ -306,12 +369,8 @@ impl Lock {
 /// piece of metadata separated by whitespace. If no metadata exists or
 /// only the line ID is part of the metadata, returns an empty string
 /// instead.
-fn read_comments(metadata: impl IntoIterator<Item = String>) -> String {
+fn read_comments(cleaned_metadata: &[String]) -> String {
     // Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner-Unity/blob/462c735766a4c4881cd1ef1f15de28c83b2ba0a8/Editor/Importers/YarnProjectImporter.cs#L652>
-    let cleaned_metadata: Vec<_> = metadata
-        .into_iter()
-        .filter(|metadata| !metadata.starts_with("line:"))
-        .collect();
     if cleaned_metadata.is_empty() {
         String::new()
     } else {
@@ -319,10 +378,80 @@ fn read_comments(metadata: impl IntoIterator<Item = String>) -> String {
     }
 }
 
+/// Removes the line ID from the line metadata.
+fn line_metadata(metadata: impl IntoIterator<Item = String>) -> Vec<String> {
+    metadata
+        .into_iter()
+        .filter(|metadata| !metadata.starts_with("line:"))
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    const STRINGS_FILE_HEADER: &str = "language,id,text,file,node,line_number,lock,comment";
+
+    #[test]
+    fn parses_strings_file_from_csv() {
+        let content = format!(
+            "{STRINGS_FILE_HEADER}\n\
+            de-CH,line:1,Hallo,test.yarn,Start,3,abcdef12,\n\
+            de-CH,line:2,Tschüss,test.yarn,Start,4,12abcdef,\n"
+        );
+        let strings_file = StringsFile::from_csv(&content).unwrap();
+        assert_eq!(Some(&Language::from("de-CH")), strings_file.language());
+        assert_eq!(2, strings_file.records().count());
+    }
+
+    #[test]
+    fn reports_position_of_malformed_field() {
+        let content = format!(
+            "{STRINGS_FILE_HEADER}\n\
+            de-CH,line:1,Hallo,test.yarn,Start,3,abcdef12,\n\
+            de-CH,line:2,Tschüss,test.yarn,Start,four,12abcdef,\n"
+        );
+        let error = StringsFile::from_csv(&content).unwrap_err();
+        assert_eq!(3, error.line);
+        assert_eq!(6, error.column);
+        assert_eq!("line_number", error.field);
+    }
+
+    #[test]
+    fn reports_position_of_mixed_language() {
+        let content = format!(
+            "{STRINGS_FILE_HEADER}\n\
+            de-CH,line:1,Hallo,test.yarn,Start,3,abcdef12,\n\
+            fr-CH,line:2,Salut,test.yarn,Start,4,12abcdef,\n"
+        );
+        let error = StringsFile::from_csv(&content).unwrap_err();
+        assert_eq!(3, error.line);
+        assert_eq!(1, error.column);
+        assert_eq!("language", error.field);
+    }
+
+    #[test]
+    fn keeps_line_metadata_in_dedicated_column() {
+        let string_table = YarnCompiler::new()
+            .with_compilation_type(CompilationType::StringsOnly)
+            .add_file(yarnspinner::compiler::File {
+                file_name: "test.yarn".to_owned(),
+                source: "title: Start\n---\nPsst! #line:1 #vo:whispered\n===\n".to_owned(),
+            })
+            .compile()
+            .unwrap()
+            .string_table;
+        let strings_file = StringsFile::from_string_table("en-US", string_table).unwrap();
+        let record = strings_file.records().next().unwrap();
+        assert_eq!("vo:whispered", record.metadata);
+
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer.serialize(record).unwrap();
+        let content = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        let parsed = StringsFile::from_csv(&content).unwrap();
+        assert_eq!("vo:whispered", parsed.records().next().unwrap().metadata);
+    }
+
     #[test]
     fn combines_comments_without_change() {
         let old = "Foo, Line metadata: Bar";
@@ -0,0 +1,106 @@
+use crate::prelude::*;
+use bevy::prelude::*;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+pub(crate) fn strings_file_parse_error_plugin(app: &mut App) {
+    app.init_resource::<StringsFileParseErrorQueue>()
+        .add_event::<StringsFileParseErrorEvent>()
+        .add_systems(
+            Update,
+            send_strings_file_parse_error_events.in_set(YarnSpinnerSystemSet),
+        );
+}
+
+/// An error encountered while parsing a strings file, i.e. a `.strings.csv` file containing the translations of a [`Localization`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StringsFileParseError {
+    /// The 1-indexed line of the CSV file on which the error occurred.
+    pub line: usize,
+    /// The 1-indexed column, i.e. CSV field, in which the error occurred.
+    pub column: usize,
+    /// The name of the column in which the error occurred, as given by the CSV header.
+    /// Empty if the column has no header.
+    pub field: String,
+    /// A description of what went wrong.
+    pub message: String,
+}
+
+impl Error for StringsFileParseError {}
+
+impl Display for StringsFileParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)?;
+        if !self.field.is_empty() {
+            write!(f, " (\"{}\")", self.field)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl StringsFileParseError {
+    pub(crate) fn from_csv_error(error: csv::Error, headers: &csv::StringRecord) -> Self {
+        let line = error
+            .position()
+            .map_or(1, |position| position.line() as usize);
+        let (field_index, message) = match error.kind() {
+            csv::ErrorKind::Deserialize { err, .. } => (
+                err.field().map(|field| field as usize),
+                err.kind().to_string(),
+            ),
+            csv::ErrorKind::UnequalLengths {
+                expected_len, len, ..
+            } => (
+                Some(*expected_len.min(len) as usize),
+                format!("Expected {expected_len} fields, but found {len}"),
+            ),
+            csv::ErrorKind::Utf8 { err, .. } => (Some(err.field()), err.to_string()),
+            _ => (None, error.to_string()),
+        };
+        Self::at_field(line, field_index.unwrap_or_default(), headers, message)
+    }
+
+    pub(crate) fn at_field(
+        line: usize,
+        field_index: usize,
+        headers: &csv::StringRecord,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            line,
+            column: field_index + 1,
+            field: headers.get(field_index).unwrap_or_default().to_owned(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Sent when a strings file could not be loaded because it is malformed.
+/// Useful for showing such errors in a development overlay without needing to look at the console.
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct StringsFileParseErrorEvent {
+    /// The asset path of the strings file that failed to load.
+    pub path: PathBuf,
+    /// What went wrong and where.
+    pub error: StringsFileParseError,
+}
+
+/// Collects the parse errors of the [`StringsFile`] asset loader, which runs outside of the ECS and thus cannot send events itself.
+#[derive(Debug, Clone, Default, Resource)]
+pub(crate) struct StringsFileParseErrorQueue(Arc<Mutex<Vec<StringsFileParseErrorEvent>>>);
+
+impl StringsFileParseErrorQueue {
+    pub(crate) fn push(&self, event: StringsFileParseErrorEvent) {
+        self.0.lock().unwrap().push(event);
+    }
+}
+
+fn send_strings_file_parse_error_events(
+    queue: Res<StringsFileParseErrorQueue>,
+    mut events: EventWriter<StringsFileParseErrorEvent>,
+) {
+    let parse_errors = std::mem::take(&mut *queue.0.lock().unwrap());
+    events.send_batch(parse_errors);
+}
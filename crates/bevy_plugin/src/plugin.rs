@@ -155,6 +155,48 @@ impl YarnSpinnerPlugin {
             .with_development_file_generation(development_file_generation);
         self
     }
+
+    /// Sets the node that [`DialogueRunner`]s created from the [`YarnProject`] start at by default,
+    /// i.e. the node started by [`YarnSpinnerPlugin::with_auto_start`] and the node hot reloading falls back to when the current node was removed.
+    /// Defaults to [`Dialogue::DEFAULT_START_NODE_NAME`].
+    #[must_use]
+    pub fn with_start_node(mut self, node_name: impl Into<String>) -> Self {
+        self.project = self.project.with_start_node(node_name);
+        self
+    }
+
+    /// Sets whether a [`DialogueRunner`] should be spawned and started at the start node once the [`YarnProject`] has been compiled.
+    /// This is convenient for simple games that only need a single dialogue running from the beginning.
+    /// The dialogue runner is created with [`YarnProject::create_dialogue_runner`], so it uses all defaults of the [`DialogueRunnerBuilder`].
+    /// Defaults to `false`.
+    #[must_use]
+    pub fn with_auto_start(mut self, auto_start: bool) -> Self {
+        self.project = self.project.with_auto_start(auto_start);
+        self
+    }
+
+    /// Sets a path, relative to the assets directory, to which the variable declarations of the compiled Yarn project are written as JSON,
+    /// e.g. for IDE plugins, documentation generators or binding generators. See [`Compilation::declarations_json`] for the format.
+    /// The file is rewritten every time the Yarn files are recompiled because of changes.
+    /// This is only done with [`DevelopmentFileGeneration::Full`], as it writes to disk.
+    /// By default, no declarations are written.
+    #[must_use]
+    pub fn with_declarations_output(mut self, path: impl Into<PathBuf>) -> Self {
+        self.project = self.project.with_declarations_output(path);
+        self
+    }
+
+    /// Sets whether the app panics when the Yarn files fail to compile.
+    /// If set to `false`, a [`YarnCompilationFailedEvent`] is sent instead, e.g. to show the errors in a development overlay,
+    /// and the [`YarnProject`] is not created until the Yarn files compile successfully.
+    /// Defaults to `true`.
+    #[must_use]
+    pub fn with_panic_on_compile_error(mut self, panic_on_compile_error: bool) -> Self {
+        self.project = self
+            .project
+            .with_panic_on_compile_error(panic_on_compile_error);
+        self
+    }
 }
 
 impl Plugin for YarnSpinnerPlugin {
@@ -209,6 +251,7 @@ impl YarnApp for App {
             .register_type::<YarnValue>()
             .register_type::<yarnspinner::core::InvalidOpCodeError>()
             .register_type::<yarnspinner::core::Program>()
+            .register_type::<yarnspinner::core::ProgramExtensions>()
             .register_type::<yarnspinner::core::Node>()
             .register_type::<yarnspinner::core::Header>()
             .register_type::<yarnspinner::core::Instruction>()
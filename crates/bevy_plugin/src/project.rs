@@ -1,18 +1,29 @@
 use crate::fmt_utils::SkipDebug;
+use crate::line_provider::LineProviderSystemSet;
 use crate::prelude::*;
 use bevy::prelude::*;
 use bevy::utils::{HashMap, HashSet};
+pub use compilation::YarnCompilationFailedEvent;
 pub(crate) use compilation::{
     RecompileLoadedYarnFilesEvent, YarnFilesBeingLoaded, YarnProjectConfigToLoad,
 };
 use std::fmt::Debug;
 use std::iter;
+use std::path::PathBuf;
 
 mod compilation;
 
 pub(crate) fn project_plugin(app: &mut App) {
     app.add_plugins(compilation::project_compilation_plugin)
-        .add_event::<LoadYarnProjectEvent>();
+        .add_event::<LoadYarnProjectEvent>()
+        .add_systems(
+            Update,
+            update_base_language_of_dialogue_runners
+                .run_if(resource_exists_and_changed::<YarnProject>)
+                .after(CompilationSystemSet)
+                .before(LineProviderSystemSet)
+                .in_set(YarnSpinnerSystemSet),
+        );
 }
 
 #[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, SystemSet)]
@@ -39,6 +50,11 @@ pub struct YarnProject {
     pub(crate) metadata: HashMap<LineId, Vec<String>>,
     pub(crate) watching_for_changes: bool,
     pub(crate) development_file_generation: DevelopmentFileGeneration,
+    pub(crate) start_node: Option<String>,
+    pub(crate) auto_start: bool,
+    pub(crate) declarations_output: Option<PathBuf>,
+    /// Set by [`YarnProject::set_base_language`]. `None` means the language of the base localization.
+    pub(crate) base_language: Option<Language>,
 }
 
 impl YarnProject {
@@ -57,6 +73,41 @@ impl YarnProject {
         self.localizations.as_ref()
     }
 
+    /// Returns the language that lines fall back to when they are missing in the language selected for a [`DialogueRunner`].
+    /// This is the language of [`Localizations::base_localization`], unless changed with [`YarnProject::set_base_language`].
+    /// Returns [`None`] if there are no [`Localizations`].
+    pub fn base_language(&self) -> Option<&Language> {
+        self.base_language.as_ref().or_else(|| {
+            self.localizations
+                .as_ref()
+                .map(|localizations| &localizations.base_localization.language)
+        })
+    }
+
+    /// Sets the language that lines fall back to when they are missing in the language selected for a [`DialogueRunner`],
+    /// e.g. for a localization editor that switches which language translations are based on.
+    /// If this is a translation, the [`StringsFileTextProvider`](crate::default_impl::StringsFileTextProvider) loads its strings file
+    /// and only falls back to the text of the Yarn files for lines that are missing there as well.
+    /// This applies to all [`DialogueRunner`]s of this project, including the ones that already exist.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if there are no [`Localizations`] or if they do not support the given language.
+    pub fn set_base_language(&mut self, language: impl Into<Language>) -> &mut Self {
+        let language = language.into();
+        let localizations = self.localizations.as_ref().expect(
+            "Tried to set the base language, but no localizations are available. \
+            Did you forget to call `YarnSpinnerPlugin::with_localizations(..)` on the plugin setup?",
+        );
+        assert!(
+            localizations.supports_language(&language),
+            "Tried to set the base language to {language}, but no localizations are available for that language."
+        );
+        self.base_language =
+            (language != localizations.base_localization.language).then_some(language);
+        self
+    }
+
     /// Constructs a [`DialogueRunner`] from this project using all defaults of [`DialogueRunnerBuilder`] .
     /// This is a convenience method for calling [`DialogueRunnerBuilder::build`] on an unconfigured builder returned by [`YarnProject::build_dialogue_runner`].
     pub fn create_dialogue_runner(&self) -> DialogueRunner {
@@ -68,6 +119,21 @@ impl YarnProject {
         DialogueRunnerBuilder::from_yarn_project(self)
     }
 
+    /// Returns the name of the node that [`DialogueRunner`]s of this project start at by default.
+    /// This is the node set via [`YarnSpinnerPlugin::with_start_node`] or [`LoadYarnProjectEvent::with_start_node`],
+    /// or [`Dialogue::DEFAULT_START_NODE_NAME`] if none was set.
+    pub fn start_node(&self) -> &str {
+        self.start_node
+            .as_deref()
+            .unwrap_or(Dialogue::DEFAULT_START_NODE_NAME)
+    }
+
+    /// Returns whether a [`DialogueRunner`] is spawned and started automatically once this project has been compiled.
+    /// See [`YarnSpinnerPlugin::with_auto_start`].
+    pub fn auto_start(&self) -> bool {
+        self.auto_start
+    }
+
     /// Returns the metadata associated with the given [`LineId`], if any. This can also be accessed on a given [`LocalizedLine`] via its `metadata` field.
     pub fn line_metadata(&self, line_id: &LineId) -> Option<&[String]> {
         self.metadata.get(line_id).map(|v| v.as_slice())
@@ -93,6 +159,17 @@ impl YarnProject {
     }
 }
 
+fn update_base_language_of_dialogue_runners(
+    project: Res<YarnProject>,
+    mut dialogue_runners: Query<&mut DialogueRunner>,
+) {
+    for mut dialogue_runner in dialogue_runners.iter_mut() {
+        dialogue_runner
+            .text_provider
+            .set_base_language(project.base_language.clone());
+    }
+}
+
 /// Used to late initialize a [`YarnProject`] with a set of Yarn files when using [`YarnSpinnerPlugin::deferred`].
 /// If you know the Yarn files at the start of the game, you should use [`YarnSpinnerPlugin::with_yarn_sources`] instead.
 #[derive(Debug, Clone, PartialEq, Eq, Event)]
@@ -100,6 +177,10 @@ pub struct LoadYarnProjectEvent {
     pub(crate) localizations: Option<Localizations>,
     pub(crate) yarn_files: HashSet<YarnFileSource>,
     pub(crate) development_file_generation: DevelopmentFileGeneration,
+    pub(crate) start_node: Option<String>,
+    pub(crate) auto_start: bool,
+    pub(crate) declarations_output: Option<PathBuf>,
+    pub(crate) panic_on_compile_error: bool,
 }
 
 impl Default for LoadYarnProjectEvent {
@@ -108,6 +189,10 @@ impl Default for LoadYarnProjectEvent {
             localizations: None,
             yarn_files: HashSet::from([YarnFileSource::Folder(DEFAULT_ASSET_DIR.into())]),
             development_file_generation: default(),
+            start_node: None,
+            auto_start: false,
+            declarations_output: None,
+            panic_on_compile_error: true,
         }
     }
 }
@@ -144,6 +229,10 @@ impl LoadYarnProjectEvent {
             localizations: None,
             yarn_files,
             development_file_generation: default(),
+            start_node: None,
+            auto_start: false,
+            declarations_output: None,
+            panic_on_compile_error: true,
         }
     }
 
@@ -192,6 +281,34 @@ impl LoadYarnProjectEvent {
         }
         self
     }
+
+    /// See [`YarnSpinnerPlugin::with_start_node`].
+    #[must_use]
+    pub fn with_start_node(mut self, node_name: impl Into<String>) -> Self {
+        self.start_node = Some(node_name.into());
+        self
+    }
+
+    /// See [`YarnSpinnerPlugin::with_auto_start`].
+    #[must_use]
+    pub fn with_auto_start(mut self, auto_start: bool) -> Self {
+        self.auto_start = auto_start;
+        self
+    }
+
+    /// See [`YarnSpinnerPlugin::with_declarations_output`].
+    #[must_use]
+    pub fn with_declarations_output(mut self, path: impl Into<PathBuf>) -> Self {
+        self.declarations_output = Some(path.into());
+        self
+    }
+
+    /// See [`YarnSpinnerPlugin::with_panic_on_compile_error`].
+    #[must_use]
+    pub fn with_panic_on_compile_error(mut self, panic_on_compile_error: bool) -> Self {
+        self.panic_on_compile_error = panic_on_compile_error;
+        self
+    }
 }
 
 impl<T, U> From<T> for LoadYarnProjectEvent
@@ -3,16 +3,20 @@ use crate::localization::{LineIdUpdateSystemSet, UpdateAllStringsFilesForStringT
 use crate::plugin::AssetRoot;
 use crate::prelude::*;
 use crate::project::{CompilationSystemSet, LoadYarnProjectEvent, WatchingForChanges};
-use anyhow::bail;
+use anyhow::{anyhow, bail};
 use bevy::prelude::*;
 use bevy::utils::{error, HashSet};
 use std::fmt::Debug;
+use std::fs;
+use std::path::{Path, PathBuf};
+use yarnspinner::compiler::Diagnostic;
 
 pub(crate) fn project_compilation_plugin(app: &mut App) {
     app.register_type::<YarnFilesToLoad>()
         .init_resource::<YarnFilesToLoad>()
         .init_resource::<YarnFilesBeingLoaded>()
         .add_event::<RecompileLoadedYarnFilesEvent>()
+        .add_event::<YarnCompilationFailedEvent>()
         .add_systems(
             Update,
             (
@@ -27,6 +31,7 @@ pub(crate) fn project_compilation_plugin(app: &mut App) {
                     .map(error)
                     .run_if(events_in_queue::<RecompileLoadedYarnFilesEvent>()),
                 clear_temp_yarn_project.run_if(resource_added::<YarnProject>),
+                auto_start_dialogue_runner.run_if(resource_added::<YarnProject>),
             )
                 .chain()
                 .after(LineIdUpdateSystemSet)
@@ -40,6 +45,10 @@ pub(crate) struct YarnProjectConfigToLoad {
     pub(crate) localizations: Option<Option<Localizations>>,
     pub(crate) watching_for_changes: bool,
     pub(crate) development_file_generation: DevelopmentFileGeneration,
+    pub(crate) start_node: Option<String>,
+    pub(crate) auto_start: bool,
+    pub(crate) declarations_output: Option<PathBuf>,
+    pub(crate) panic_on_compile_error: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default, Resource, Reflect)]
@@ -75,6 +84,10 @@ fn load_project(
             localizations: Some(event.localizations),
             watching_for_changes: is_watching_for_changes.0,
             development_file_generation: event.development_file_generation,
+            start_node: event.start_node,
+            auto_start: event.auto_start,
+            declarations_output: event.declarations_output,
+            panic_on_compile_error: event.panic_on_compile_error,
         });
         commands.insert_resource(YarnFilesToLoad(event.yarn_files));
         *already_loaded = true;
@@ -108,24 +121,47 @@ fn add_yarn_files_to_load_queue(
 #[reflect(Debug, Default, PartialEq)]
 pub(crate) struct RecompileLoadedYarnFilesEvent;
 
+/// Sent when the Yarn files of the [`YarnProject`] fail to compile, e.g. to show the errors in a development overlay.
+/// When loading the project, this is only sent if [`YarnSpinnerPlugin::with_panic_on_compile_error`] is set to `false`.
+/// Failed recompilations because of hot reloading never panic and always send this.
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct YarnCompilationFailedEvent {
+    /// The diagnostics describing why the compilation failed.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
 fn recompile_loaded_yarn_files(
     yarn_files: Res<Assets<YarnFile>>,
     yarn_project: Option<ResMut<YarnProject>>,
     mut dialogue_runners: Query<&mut DialogueRunner>,
     mut events: ResMut<Events<RecompileLoadedYarnFilesEvent>>,
+    mut compilation_failed_events: EventWriter<YarnCompilationFailedEvent>,
+    asset_root: Res<AssetRoot>,
 ) -> SystemResult {
     let Some(mut yarn_project) = yarn_project else {
         return Ok(());
     };
-    let Some(compilation) = compile_yarn_files(
+    let compilation = compile_yarn_files(
         &yarn_project.yarn_files,
         &yarn_files,
         yarn_project.localizations.as_ref(),
         yarn_project.development_file_generation,
-    )?
-    else {
+    )
+    .inspect_err(|error| {
+        if let Some(compiler_error) = error.downcast_ref::<CompilerError>() {
+            compilation_failed_events.send(YarnCompilationFailedEvent {
+                diagnostics: compiler_error.0.clone(),
+            });
+        }
+    })?;
+    let Some(compilation) = compilation else {
         return Ok(());
     };
+    if yarn_project.development_file_generation == DevelopmentFileGeneration::Full {
+        if let Some(path) = yarn_project.declarations_output.as_ref() {
+            write_declarations(&compilation, &asset_root.0.join(path))?;
+        }
+    }
     let metadata = compilation
         .string_table
         .iter()
@@ -147,7 +183,8 @@ fn recompile_loaded_yarn_files(
                 .map(|_| ())
                 .ok()
                 .unwrap_or_else(|| {
-                    dialogue_runner.start_node("Start");
+                    let start_node = dialogue_runner.dialogue.default_start_node().to_owned();
+                    dialogue_runner.start_node(start_node);
                 });
         }
     }
@@ -161,6 +198,8 @@ fn compile_loaded_yarn_files(
     mut yarn_files_being_loaded: ResMut<YarnFilesBeingLoaded>,
     yarn_files: Res<Assets<YarnFile>>,
     mut update_strings_files_writer: EventWriter<UpdateAllStringsFilesForStringTableEvent>,
+    mut compilation_failed_events: EventWriter<YarnCompilationFailedEvent>,
+    mut yarn_file_events: EventReader<AssetEvent<YarnFile>>,
     mut dirty: Local<bool>,
     yarn_project_config_to_load: Option<Res<YarnProjectConfigToLoad>>,
     asset_server: Res<AssetServer>,
@@ -169,6 +208,13 @@ fn compile_loaded_yarn_files(
     if yarn_files_being_loaded.is_changed() {
         *dirty = true;
     }
+    // Retry a failed compilation once one of the files changes
+    let any_file_modified = yarn_file_events.read().any(|event| {
+        matches!(event, AssetEvent::Modified { id } if yarn_files_being_loaded.0.contains(&Handle::Weak(*id)))
+    });
+    if any_file_modified {
+        *dirty = true;
+    }
     if yarn_files_being_loaded.0.is_empty() {
         *dirty = false;
     }
@@ -190,18 +236,33 @@ fn compile_loaded_yarn_files(
         .unwrap()
         .as_ref();
     let development_file_generation = yarn_project_config_to_load.development_file_generation;
-    let Some(compilation) = compile_yarn_files(
+    let compilation = match compile_yarn_files(
         &yarn_files_being_loaded.0,
         &yarn_files,
         localizations,
         development_file_generation,
-    )?
-    else {
-        return Ok(());
+    ) {
+        Ok(Some(compilation)) => compilation,
+        Ok(None) => return Ok(()),
+        Err(error) if !yarn_project_config_to_load.panic_on_compile_error => {
+            let Some(compiler_error) = error.downcast_ref::<CompilerError>() else {
+                return Err(error);
+            };
+            error!("Failed to compile Yarn files: {compiler_error}");
+            compilation_failed_events.send(YarnCompilationFailedEvent {
+                diagnostics: compiler_error.0.clone(),
+            });
+            *dirty = false;
+            return Ok(());
+        }
+        Err(error) => return Err(error),
     };
     let file_count = yarn_files_being_loaded.0.len();
 
     if development_file_generation == DevelopmentFileGeneration::Full {
+        if let Some(path) = yarn_project_config_to_load.declarations_output.as_ref() {
+            write_declarations(&compilation, &asset_root.0.join(path))?;
+        }
         if let Some(localizations) = yarn_project_config_to_load.localizations.as_ref().unwrap() {
             update_strings_files_writer.send(UpdateAllStringsFilesForStringTableEvent(
                 compilation.string_table.clone(),
@@ -242,6 +303,10 @@ fn compile_loaded_yarn_files(
         watching_for_changes: yarn_project_config_to_load.watching_for_changes,
         development_file_generation,
         metadata,
+        start_node: yarn_project_config_to_load.start_node.clone(),
+        auto_start: yarn_project_config_to_load.auto_start,
+        declarations_output: yarn_project_config_to_load.declarations_output.clone(),
+        base_language: None,
     });
 
     let file_plural = if file_count == 1 { "file" } else { "files" };
@@ -256,6 +321,38 @@ fn clear_temp_yarn_project(mut commands: Commands) {
     commands.remove_resource::<YarnProjectConfigToLoad>();
 }
 
+fn auto_start_dialogue_runner(mut commands: Commands, project: Res<YarnProject>) {
+    if !project.auto_start {
+        return;
+    }
+    let mut dialogue_runner = project.create_dialogue_runner();
+    dialogue_runner.start_node(project.start_node());
+    commands.spawn(dialogue_runner);
+}
+
+fn write_declarations(compilation: &Compilation, path: &Path) -> Result<()> {
+    if let Some(parent_dir) = path.parent() {
+        fs::create_dir_all(parent_dir).map_err(|e| {
+            anyhow!(
+                "Failed to create directory for declarations \"{}\": {e}",
+                parent_dir.display()
+            )
+        })?;
+    }
+    let json = format!("{:#}", compilation.declarations_json());
+    fs::write(path, json).map_err(|e| {
+        anyhow!(
+            "Failed to write declarations to \"{}\": {e}",
+            path.display()
+        )
+    })?;
+    info!(
+        "Wrote Yarn variable declarations to \"{}\".",
+        path.display()
+    );
+    Ok(())
+}
+
 fn compile_yarn_files(
     yarn_file_handles: &HashSet<Handle<YarnFile>>,
     yarn_files: &Res<Assets<YarnFile>>,
@@ -0,0 +1,38 @@
+use bevy::ecs::event::EventCursor;
+use bevy::prelude::*;
+use bevy_yarnspinner::prelude::*;
+use utils::prelude::*;
+
+mod utils;
+
+#[test]
+fn sends_event_instead_of_panicking_on_compile_error() {
+    let mut app = App::new();
+
+    let broken_file = YarnFile::new(
+        "broken.yarn",
+        "title: Start\n---\n<<set $x to 1 + true>>\n===\n",
+    );
+    app.setup_default_plugins().add_plugins(
+        YarnSpinnerPlugin::with_yarn_source(YarnFileSource::InMemory(broken_file))
+            .with_panic_on_compile_error(false),
+    );
+
+    let mut diagnostics = Vec::new();
+    let mut cursor = EventCursor::<YarnCompilationFailedEvent>::default();
+    for _ in 0..20 {
+        app.update();
+        let events = app.world().resource::<Events<YarnCompilationFailedEvent>>();
+        diagnostics.extend(
+            cursor
+                .read(events)
+                .flat_map(|event| event.diagnostics.clone()),
+        );
+    }
+
+    assert!(!diagnostics.is_empty());
+    assert!(diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.file_name.as_deref() == Some("broken.yarn")));
+    assert!(!app.world().contains_resource::<YarnProject>());
+}
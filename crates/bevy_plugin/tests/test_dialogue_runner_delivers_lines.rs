@@ -1,4 +1,5 @@
 use anyhow::Result;
+use bevy::ecs::event::EventCursor;
 use bevy::prelude::*;
 use bevy_yarnspinner::{events::*, prelude::*};
 use utils::prelude::*;
@@ -215,6 +216,56 @@ fn serves_translations() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn runners_with_different_languages_run_side_by_side() -> Result<()> {
+    let mut app = App::new();
+    let project = app
+        .setup_default_plugins()
+        .add_plugins(
+            YarnSpinnerPlugin::with_yarn_source(YarnFileSource::file("lines_with_ids.yarn"))
+                .with_localizations(Localizations {
+                    base_localization: "en-US".into(),
+                    translations: vec!["de-CH".into()],
+                })
+                .with_development_file_generation(DevelopmentFileGeneration::None),
+        )
+        .load_project();
+    let mut english_runner = project.create_dialogue_runner();
+    let mut german_runner = project.create_dialogue_runner();
+    english_runner.start_node("Start");
+    german_runner.start_node("Start");
+    let english_entity = app
+        .world_mut()
+        .spawn((english_runner, DialogueLanguage::new("en-US")))
+        .id();
+    let german_entity = app
+        .world_mut()
+        .spawn((german_runner, DialogueLanguage::new("de-CH")))
+        .id();
+
+    let mut english_line = None;
+    let mut german_line = None;
+    let mut cursor = EventCursor::<PresentLineEvent>::default();
+    for _ in 0..100 {
+        app.update();
+        let events = app.world().resource::<Events<PresentLineEvent>>();
+        for event in cursor.read(events) {
+            if event.source == english_entity {
+                english_line = Some(event.line.text.clone());
+            } else if event.source == german_entity {
+                german_line = Some(event.line.text.clone());
+            }
+        }
+        if english_line.is_some() && german_line.is_some() {
+            break;
+        }
+    }
+
+    assert_eq!(Some(english_lines()[0].clone()), english_line);
+    assert_eq!(Some(german_lines()[0].clone()), german_line);
+    Ok(())
+}
+
 #[test]
 fn default_language_is_none_without_localizations() {
     let mut app = App::new();
@@ -260,6 +311,62 @@ fn default_language_is_base_language() {
     }
 }
 
+#[test]
+fn auto_start_spawns_and_starts_dialogue_runner() -> Result<()> {
+    let mut app = App::new();
+    let mut asserter = EventAsserter::new();
+    app.setup_default_plugins().add_plugins(
+        YarnSpinnerPlugin::with_yarn_source(YarnFileSource::file("lines.yarn"))
+            .with_auto_start(true),
+    );
+    assert_eq!("Start", app.load_project().start_node());
+    app.update();
+
+    let dialogue_runner_count = app
+        .world_mut()
+        .query::<&DialogueRunner>()
+        .iter(app.world())
+        .count();
+    assert_eq!(1, dialogue_runner_count);
+    assert!(app.dialogue_runner().is_running());
+    assert_events!(asserter, app contains [
+        DialogueStartEvent,
+        NodeStartEvent,
+        PresentLineEvent with |event| event.line.text == english_lines()[0],
+    ]);
+
+    Ok(())
+}
+
+#[test]
+fn exposes_instruction_trace() -> Result<()> {
+    let mut app = App::new();
+    let dialogue_runner = app
+        .setup_default_plugins()
+        .add_plugins(YarnSpinnerPlugin::with_yarn_source(YarnFileSource::file(
+            "lines.yarn",
+        )))
+        .load_project()
+        .build_dialogue_runner()
+        .with_instruction_trace(4)
+        .build();
+    app.world_mut().spawn(dialogue_runner);
+    assert_eq!(0, app.dialogue_runner().inner().instruction_trace().len());
+
+    app.dialogue_runner_mut().start_node("Start");
+    app.update();
+    let trace: Vec<_> = app
+        .dialogue_runner()
+        .inner()
+        .instruction_trace()
+        .cloned()
+        .collect();
+    assert!(!trace.is_empty() && trace.len() <= 4);
+    assert!(trace.iter().all(|entry| entry.node_name == "Start"));
+
+    Ok(())
+}
+
 fn setup_dialogue_runner_without_localizations(app: &mut App) -> Mut<DialogueRunner> {
     app.setup_default_plugins()
         .add_plugins(YarnSpinnerPlugin::with_yarn_source(YarnFileSource::file(
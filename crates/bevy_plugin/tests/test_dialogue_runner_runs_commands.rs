@@ -43,6 +43,25 @@ fn waits_on_command() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn exposes_pending_command() -> Result<()> {
+    let mut app = App::new();
+    app.setup_dialogue_runner_for_wait().start_node("Start");
+    app.update();
+    assert!(app.dialogue_runner().inner().pending_command().is_none());
+
+    app.continue_dialogue_and_update();
+    let (name, parameters) = app.dialogue_runner().inner().pending_command().unwrap();
+    assert_eq!("wait", name);
+    assert_eq!(1, parameters.len());
+    assert_eq!(1.0, f32::try_from(&parameters[0]).unwrap());
+
+    app.dialogue_runner_mut().stop();
+    assert!(app.dialogue_runner().inner().pending_command().is_none());
+
+    Ok(())
+}
+
 #[test]
 fn executes_commands_and_fns() -> Result<()> {
     let mut app = App::new();
@@ -110,6 +129,38 @@ fn executes_commands_and_fns() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn exposes_variables_and_functions_for_inspection() -> Result<()> {
+    let mut app = App::new();
+    app.setup_dialogue_runner().start_node("Start");
+    app.update();
+    assert!(!app
+        .dialogue_runner()
+        .variable_snapshot()
+        .contains_key("$data"));
+
+    app.continue_dialogue_and_update();
+    let snapshot = app.dialogue_runner().variable_snapshot();
+    assert_eq!(Some(&YarnValue::from("foo")), snapshot.get("$data"));
+
+    app.dialogue_runner_mut().set_variable("$data", "bar")?;
+    let data: String = app
+        .dialogue_runner()
+        .variable_storage()
+        .get("$data")
+        .unwrap()
+        .into();
+    assert_eq!("bar", data.as_str());
+
+    let signatures = app.dialogue_runner().function_signatures();
+    assert_eq!(
+        "Fn(String) -> String",
+        signatures["triplicate_data"].to_string()
+    );
+
+    Ok(())
+}
+
 #[derive(Debug, Resource)]
 struct Data(String);
 
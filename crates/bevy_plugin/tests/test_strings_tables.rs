@@ -300,3 +300,26 @@ fn does_not_panic_on_missing_language_when_not_selected() {
 
     app.load_project();
 }
+
+#[test]
+fn writes_declarations_json() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    fs::copy(
+        project_root_path().join("assets/commands.yarn"),
+        dir.path().join("commands.yarn"),
+    )?;
+
+    let mut app = App::new();
+
+    app.setup_default_plugins_for_path(dir.path()).add_plugins(
+        YarnSpinnerPlugin::with_yarn_source(YarnFileSource::file("commands.yarn"))
+            .with_development_file_generation(DevelopmentFileGeneration::Full)
+            .with_declarations_output("generated/declarations.json"),
+    );
+
+    app.load_project();
+    let declarations = fs::read_to_string(dir.path().join("generated/declarations.json"))?;
+    assert!(declarations.trim_start().starts_with('['));
+    assert!(declarations.contains("\"$data\""));
+    Ok(())
+}
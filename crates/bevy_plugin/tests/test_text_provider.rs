@@ -153,3 +153,53 @@ fn loads_line_from_translated_language() {
         .unwrap();
     assert_eq!("Mann: Also gut. Ich glaub das zwar nicht, aber es kann ja nicht schaden, wenn ich mir was wünsche. Ich möchte wissen, wer ich bin.", line);
 }
+
+#[test]
+fn falls_back_to_changed_base_language() {
+    let mut app = App::new();
+
+    app.setup_default_plugins().add_plugins(
+        YarnSpinnerPlugin::with_yarn_source(YarnFileSource::file("lines_with_ids.yarn"))
+            .with_localizations(Localizations {
+                base_localization: "en-US".into(),
+                translations: vec!["de-CH".into(), "fr-CH".into()],
+            })
+            .with_development_file_generation(DevelopmentFileGeneration::None),
+    );
+
+    app.dialogue_runner_mut().set_text_language("fr-CH");
+    app.load_lines();
+
+    let line = app
+        .dialogue_runner()
+        .text_provider()
+        .get_text(&LineId("line:9".to_owned()))
+        .unwrap();
+    assert_eq!("Man: All right. I don't believe this; but there's no harm in wishing. I wish to know who I am.", line);
+
+    app.world_mut()
+        .resource_mut::<YarnProject>()
+        .set_base_language("de-CH");
+    app.update();
+    app.load_lines();
+
+    assert_eq!(
+        Some(&Language::new("de-CH")),
+        app.load_project().base_language()
+    );
+    let line = app
+        .dialogue_runner()
+        .text_provider()
+        .get_text(&LineId("line:9".to_owned()))
+        .unwrap();
+    assert_eq!("Mann: Also gut. Ich glaub das zwar nicht, aber es kann ja nicht schaden, wenn ich mir was wünsche. Ich möchte wissen, wer ich bin.", line);
+    let line = app
+        .dialogue_runner()
+        .text_provider()
+        .get_text(&LineId("line:1".to_owned()))
+        .unwrap();
+    assert_eq!(
+        "Il était une fois un vieil homme assis seul sur un chemin sombre.",
+        line
+    );
+}
@@ -1,12 +1,23 @@
 use std::env;
+use std::fs;
 use std::io::Result;
+use std::path::Path;
 use yarnspinner_codegen::*;
 
+/// The field holding the data of a `Program` that is not part of the upstream `yarn_spinner.proto`.
+/// Its type is written by hand in `ext.rs`.
+const PROGRAM_EXTENSIONS_FIELD: &str =
+    "    /// Data that is specific to Yarn Spinner for Rust, see [`ProgramExtensions`].
+    #[cfg_attr(feature = \"serde\", serde(default))]
+    #[prost(message, required, tag = \"100\")]
+    pub extensions: ProgramExtensions,
+";
+
 fn main() -> Result<()> {
     let include_dir = path(ProjectPath::ThirdPersonYarnSpinner).join("YarnSpinner");
     let proto_file = include_dir.join("yarn_spinner.proto");
     let output_dir = path(ProjectPath::Core).join("src/generated");
-    env::set_var("OUT_DIR", output_dir);
+    env::set_var("OUT_DIR", &output_dir);
 
     prost_build::Config::new()
         .type_attribute(
@@ -21,5 +32,19 @@ fn main() -> Result<()> {
              )]",
         )
         .compile_protos(&[proto_file], &[include_dir])?;
-    Ok(())
+    add_program_extensions(&output_dir.join("yarn.rs"))
+}
+
+fn add_program_extensions(generated_file: &Path) -> Result<()> {
+    let mut code = fs::read_to_string(generated_file)?;
+    let struct_start = code
+        .find("pub struct Program {")
+        .expect("The generated code contains no Program struct");
+    let struct_end = struct_start
+        + code[struct_start..]
+            .find("\n}\n")
+            .expect("The Program struct is not closed")
+        + 1;
+    code.insert_str(struct_end, PROGRAM_EXTENSIONS_FIELD);
+    fs::write(generated_file, code)
 }
@@ -1,5 +1,10 @@
 mod add_initial_value_registrations;
+mod add_option_tags;
 mod add_tracking_declarations;
+mod build_definition_map;
+mod check_commands;
+mod check_interpolated_variables;
+mod check_node_titles;
 mod check_types;
 mod clean_up_diagnostics;
 mod create_declarations_for_tracking_nodes;
@@ -11,12 +16,17 @@ mod parse_files;
 mod register_initial_variables;
 mod register_strings;
 mod resolve_deferred_type_diagnostic;
+mod resolve_shadow_lines;
+mod strip_line_text;
 mod validate_unique_node_names;
+mod verify_program;
 
 pub(crate) use self::{
-    add_initial_value_registrations::*, add_tracking_declarations::*, check_types::*,
-    clean_up_diagnostics::*, create_declarations_for_tracking_nodes::*, early_breaks::*,
-    find_tracking_nodes::*, generate_code::*, get_declarations::*, parse_files::*,
-    register_initial_variables::*, register_strings::*, resolve_deferred_type_diagnostic::*,
-    validate_unique_node_names::*,
+    add_initial_value_registrations::*, add_option_tags::*, add_tracking_declarations::*,
+    build_definition_map::*, check_commands::*, check_interpolated_variables::*,
+    check_node_titles::*, check_types::*, clean_up_diagnostics::*,
+    create_declarations_for_tracking_nodes::*, early_breaks::*, find_tracking_nodes::*,
+    generate_code::*, get_declarations::*, parse_files::*, register_initial_variables::*,
+    register_strings::*, resolve_deferred_type_diagnostic::*, resolve_shadow_lines::*,
+    strip_line_text::*, validate_unique_node_names::*, verify_program::*,
 };
@@ -19,11 +19,14 @@ pub(crate) fn add_initial_value_registrations(
 
     for declaration in declarations {
         let Some(default_value) = declaration.default_value.clone() else {
-            state.diagnostics.push(Diagnostic::from_message(format!(
-                "Variable declaration {} (type {}) has a null default value. This is not allowed.",
-                declaration.name,
-                declaration.r#type.format()
-            )));
+            state.diagnostics.push(
+                Diagnostic::from_message(format!(
+                    "Variable declaration {} (type {}) has a null default value. This is not allowed.",
+                    declaration.name,
+                    declaration.r#type.format()
+                ))
+                .with_kind(DiagnosticKind::Type),
+            );
             continue;
         };
         if let Some(ref mut program) = compilation.program {
@@ -36,6 +39,13 @@ pub(crate) fn add_initial_value_registrations(
             program
                 .initial_values
                 .insert(declaration.name.clone(), value);
+            if declaration.is_constant {
+                // Lets the runtime refuse assignments from programs compiled separately
+                program
+                    .extensions
+                    .constant_variables
+                    .push(declaration.name.clone());
+            }
         }
     }
 
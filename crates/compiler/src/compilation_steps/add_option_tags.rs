@@ -0,0 +1,64 @@
+use crate::prelude::*;
+use std::collections::HashMap;
+use yarnspinner_core::prelude::*;
+
+/// Lists the lines whose metadata contains one of the option hashtags, e.g. [`ONCE_TAG`] or [`REQUIREMENT_TAG`], in the [`Program`],
+/// so that the runtime can apply them when the program is loaded without looking at the string table.
+pub(crate) fn add_option_tags(mut state: CompilationIntermediate) -> CompilationIntermediate {
+    let Some(Ok(Compilation {
+        program: Some(program),
+        string_table,
+        ..
+    })) = state.result.as_mut()
+    else {
+        return state;
+    };
+    program.extensions.once_lines = lines_tagged(string_table, ONCE_TAG);
+    program.extensions.multiselect_lines = lines_tagged(string_table, MULTISELECT_TAG);
+    program.extensions.pinned_lines = lines_tagged(string_table, PINNED_TAG);
+    program.extensions.requirement_hints = string_table
+        .iter()
+        .filter_map(|(line_id, string_info)| {
+            let hint = string_info.metadata.iter().find_map(|tag| {
+                let (key, value) = tag.split_once(':')?;
+                (key == REQUIREMENT_TAG).then(|| value.to_owned())
+            })?;
+            Some((line_id.0.clone(), hint))
+        })
+        .collect();
+
+    // The runtime stores a multi-selection as the IDs of the selected lines joined by the separator,
+    // so an ID containing it could not be told apart from two IDs.
+    for line_id in &program.extensions.multiselect_lines {
+        if !line_id.contains(MULTISELECT_SEPARATOR) {
+            continue;
+        }
+        let string_info = &string_table[&LineId(line_id.clone())];
+        // The line number is 1-indexed, while positions are 0-indexed.
+        let line = string_info.line_number.saturating_sub(1);
+        let position = Position { line, character: 0 };
+        state.diagnostics.push(
+            Diagnostic::from_message(format!(
+                "Line {line_id} is tagged with #{MULTISELECT_TAG}, so its ID must not contain \"{MULTISELECT_SEPARATOR}\""
+            ))
+            .with_file_name(string_info.file_name.clone())
+            .with_range(position..position)
+            .with_start_line(line)
+            .with_kind(DiagnosticKind::InvalidLineTag {
+                line_id: line_id.clone(),
+            }),
+        );
+    }
+    state
+}
+
+fn lines_tagged(string_table: &HashMap<LineId, StringInfo>, tag: &str) -> Vec<String> {
+    let mut line_ids: Vec<_> = string_table
+        .iter()
+        .filter(|(_, string_info)| string_info.metadata.iter().any(|t| t == tag))
+        .map(|(line_id, _)| line_id.0.clone())
+        .collect();
+    // The string table is a HashMap, so sort to keep the program deterministic
+    line_ids.sort();
+    line_ids
+}
@@ -0,0 +1,57 @@
+use crate::prelude::*;
+use crate::visitors::DefinitionVisitor;
+use antlr_rust::tree::ParseTreeVisitorCompat;
+use std::collections::HashMap;
+
+/// Maps every reference to a node or variable to the place where it is defined, see [`Compilation::definition_map`].
+///
+/// - Nodes are defined by their `title` header.
+/// - Variables are defined by their earliest `<<declare>>` statement or, if they are only declared implicitly, by their first `<<set>>` statement.
+pub(crate) fn build_definition_map(mut state: CompilationIntermediate) -> CompilationIntermediate {
+    let mut node_definitions = HashMap::new();
+    let mut variable_assignments = HashMap::new();
+    let mut node_references = Vec::new();
+    let mut variable_references = Vec::new();
+    for (file, _) in &state.parsed_files {
+        let mut visitor = DefinitionVisitor::new();
+        visitor.visit(file.tree.as_ref());
+        let in_file = |(name, position): (String, Position)| (name, (file.name.clone(), position));
+        for (name, definition) in visitor.node_definitions.into_iter().map(in_file) {
+            node_definitions.entry(name).or_insert(definition);
+        }
+        for (name, definition) in visitor.variable_assignments.into_iter().map(in_file) {
+            variable_assignments.entry(name).or_insert(definition);
+        }
+        node_references.extend(visitor.node_references.into_iter().map(in_file));
+        variable_references.extend(visitor.variable_references.into_iter().map(in_file));
+    }
+
+    let mut variable_declarations = HashMap::new();
+    for declaration in &state.known_variable_declarations {
+        if declaration.is_implicit {
+            continue;
+        }
+        let (DeclarationSource::File(file_name), Some(range)) =
+            (&declaration.source_file_name, &declaration.range)
+        else {
+            continue;
+        };
+        variable_declarations
+            .entry(declaration.name.clone())
+            .or_insert((file_name.clone(), range.start));
+    }
+
+    let node_uses = node_references
+        .into_iter()
+        .filter_map(|(name, use_site)| Some((use_site, node_definitions.get(&name)?.clone())));
+    let variable_uses = variable_references
+        .into_iter()
+        .filter_map(|(name, use_site)| {
+            let definition = variable_declarations
+                .get(&name)
+                .or_else(|| variable_assignments.get(&name))?;
+            Some((use_site, definition.clone()))
+        });
+    state.definition_map = node_uses.chain(variable_uses).collect();
+    state
+}
@@ -0,0 +1,21 @@
+use crate::prelude::*;
+use crate::visitors::CommandCheckVisitor;
+use antlr_rust::tree::ParseTreeVisitorCompat;
+
+/// Checks the commands declared via [`Compiler::with_external_definitions`] for their number of arguments
+/// and the types of their literal arguments.
+///
+/// ## Implementation notes
+///
+/// This step is not present in the original implementation, which treats command text as free-form.
+pub(crate) fn check_commands(mut state: CompilationIntermediate) -> CompilationIntermediate {
+    if state.job.command_declarations.is_empty() {
+        return state;
+    }
+    for (file, _) in &state.parsed_files {
+        let mut visitor = CommandCheckVisitor::new(file.clone(), &state.job.command_declarations);
+        visitor.visit(file.tree.as_ref());
+        state.diagnostics.extend(visitor.diagnostics);
+    }
+    state
+}
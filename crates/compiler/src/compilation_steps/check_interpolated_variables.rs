@@ -0,0 +1,35 @@
+use crate::prelude::*;
+use crate::visitors::InterpolatedVariableVisitor;
+use antlr_rust::tree::ParseTreeVisitorCompat;
+use std::collections::HashSet;
+
+/// Warns about variables interpolated in lines or options that never receive a value,
+/// i.e. that are neither explicitly declared nor assigned anywhere. These are usually typos.
+///
+/// Variables without any declaration at all are skipped, since `resolve_deferred_type_diagnostic` already reports them.
+pub(crate) fn check_interpolated_variables(
+    mut state: CompilationIntermediate,
+) -> CompilationIntermediate {
+    let mut interpolated_variables = Vec::new();
+    let mut assigned_variables = HashSet::new();
+    for (file, _) in &state.parsed_files {
+        let mut visitor = InterpolatedVariableVisitor::new(file.clone());
+        visitor.visit(file.tree.as_ref());
+        interpolated_variables.extend(visitor.interpolated_variables);
+        assigned_variables.extend(visitor.assigned_variables);
+    }
+
+    for (name, diagnostic) in interpolated_variables {
+        let mut declarations = state
+            .known_variable_declarations
+            .iter()
+            .filter(|declaration| declaration.name == name)
+            .peekable();
+        let is_undeclared = declarations.peek().is_none();
+        let is_explicitly_declared = declarations.any(|declaration| !declaration.is_implicit);
+        if !is_undeclared && !is_explicitly_declared && !assigned_variables.contains(&name) {
+            state.diagnostics.push(diagnostic);
+        }
+    }
+    state
+}
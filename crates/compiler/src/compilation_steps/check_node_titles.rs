@@ -0,0 +1,25 @@
+use crate::prelude::generated::yarnspinnerparser::{DialogueContextAttrs, NodeContextAttrs};
+use crate::prelude::*;
+use antlr_rust::token::Token;
+
+/// Reports nodes without a `title` header. During a full compilation, these are reported while generating code,
+/// so this step is only needed when validating, which skips code generation.
+pub(crate) fn check_node_titles(mut state: CompilationIntermediate) -> CompilationIntermediate {
+    for (file, _) in &state.parsed_files {
+        for node in file.tree.node_all() {
+            let has_title = node
+                .header_all()
+                .iter()
+                .any(|header| header.header_key.as_ref().unwrap().get_text() == "title");
+            if !has_title {
+                state.diagnostics.push(
+                    Diagnostic::from_message("Missing title header for node")
+                        .with_file_name(file.name.clone())
+                        .with_parser_context(node.as_ref(), file.tokens())
+                        .with_kind(DiagnosticKind::Syntax),
+                );
+            }
+        }
+    }
+    state
+}
@@ -23,6 +23,14 @@ pub(crate) fn clean_up_diagnostics(mut state: CompilationIntermediate) -> Compil
             ordered_unique_diagnostics.push(diagnostic);
         }
     }
+    // ## Implementation note
+    // The original reports diagnostics in the order they were found. We sort them by their location instead,
+    // so that tools displaying them get a stable order. Diagnostics without a range come first in their file.
+    ordered_unique_diagnostics.sort_by(|lhs, rhs| {
+        lhs.file_name
+            .cmp(&rhs.file_name)
+            .then_with(|| start_position(lhs).cmp(&start_position(rhs)))
+    });
     state.diagnostics = ordered_unique_diagnostics;
     if state.diagnostics.has_errors() {
         state.result = Some(Err(CompilerError(state.diagnostics.clone())));
@@ -31,3 +39,7 @@ pub(crate) fn clean_up_diagnostics(mut state: CompilationIntermediate) -> Compil
     }
     state
 }
+
+fn start_position(diagnostic: &Diagnostic) -> Option<Position> {
+    diagnostic.range.as_ref().map(|range| range.start)
+}
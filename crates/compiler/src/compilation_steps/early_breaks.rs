@@ -23,6 +23,7 @@ pub(crate) fn break_on_job_with_only_declarations(
             declarations: state.derived_variable_declarations.clone(),
             warnings: state.diagnostics.clone(),
             file_tags: state.file_tags.clone(),
+            definition_map: state.definition_map.clone(),
             ..Default::default()
         }));
         state.early_break = true;
@@ -12,8 +12,11 @@ pub(crate) fn register_strings(mut state: CompilationIntermediate) -> Compilatio
         let mut last_line_tagger = LastLineBeforeOptionsVisitor::default();
         last_line_tagger.visit(file.tree.as_ref());
 
-        let mut visitor =
-            StringTableGeneratorVisitor::new(state.string_table.clone(), file.clone());
+        let mut visitor = StringTableGeneratorVisitor::new(
+            state.string_table.clone(),
+            file.clone(),
+            state.job.line_id_strategy,
+        );
         visitor.visit(file.tree.as_ref());
         state.diagnostics.extend(visitor.diagnostics);
         state.string_table.extend(visitor.string_table_manager);
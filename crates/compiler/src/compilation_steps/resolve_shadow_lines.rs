@@ -0,0 +1,38 @@
+use crate::prelude::*;
+
+/// Replaces the text of every line tagged with `#shadow:` by the text of the line it shadows,
+/// and reports shadow lines whose source line does not exist or is a shadow line itself.
+pub(crate) fn resolve_shadow_lines(mut state: CompilationIntermediate) -> CompilationIntermediate {
+    let mut resolved_texts = Vec::new();
+    for (line_id, string_info) in state.string_table.iter() {
+        let Some(shadow_line_id) = string_info.shadow_line_id.as_ref() else {
+            continue;
+        };
+        let message = match state.string_table.get(shadow_line_id) {
+            Some(source) if source.shadow_line_id.is_none() => {
+                resolved_texts.push((line_id.clone(), source.text.clone()));
+                continue;
+            }
+            Some(_) => format!(
+                "Line {line_id} shadows line {shadow_line_id}, which is itself a shadow line"
+            ),
+            None => format!("Line {line_id} shadows line {shadow_line_id}, which does not exist"),
+        };
+        // The line number is 1-indexed, while positions are 0-indexed.
+        let line = string_info.line_number.saturating_sub(1);
+        let position = Position { line, character: 0 };
+        state.diagnostics.push(
+            Diagnostic::from_message(message)
+                .with_file_name(string_info.file_name.clone())
+                .with_range(position..position)
+                .with_start_line(line)
+                .with_kind(DiagnosticKind::InvalidLineTag {
+                    line_id: line_id.0.clone(),
+                }),
+        );
+    }
+    for (line_id, text) in resolved_texts {
+        state.string_table.get_mut(&line_id).unwrap().text = text;
+    }
+    state
+}
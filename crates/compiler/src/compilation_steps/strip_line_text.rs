@@ -0,0 +1,15 @@
+use crate::prelude::*;
+
+/// Empties the text of every line in the string table of the result and marks it as stripped if [`Compiler::strip_line_text`] is set.
+pub(crate) fn strip_line_text(mut state: CompilationIntermediate) -> CompilationIntermediate {
+    if !state.job.strip_line_text() {
+        return state;
+    }
+    if let Some(Ok(compilation)) = state.result.as_mut() {
+        for string_info in compilation.string_table.values_mut() {
+            string_info.text.clear();
+            string_info.is_text_stripped = true;
+        }
+    }
+    state
+}
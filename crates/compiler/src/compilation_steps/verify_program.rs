@@ -0,0 +1,29 @@
+use crate::prelude::*;
+
+/// In debug builds, checks the generated [`Program`] for internal inconsistencies via [`Program::verify`]
+/// and panics if any of them indicates a bug in the compiler.
+pub(crate) fn verify_program(state: CompilationIntermediate) -> CompilationIntermediate {
+    if !cfg!(debug_assertions) {
+        return state;
+    }
+    let Some(Ok(Compilation {
+        program: Some(program),
+        ..
+    })) = &state.result
+    else {
+        return state;
+    };
+    let errors: Vec<_> = program
+        .verify()
+        .into_iter()
+        .filter(|error| error.indicates_compiler_bug())
+        .map(|error| error.to_string())
+        .collect();
+    assert!(
+        errors.is_empty(),
+        "The compiler generated an inconsistent program:\n{}\n\
+        This is a bug. Please report it at https://github.com/YarnSpinnerTool/YarnSpinner-Rust/issues/new",
+        errors.join("\n")
+    );
+    state
+}
@@ -7,6 +7,7 @@ use yarnspinner_core::prelude::*;
 
 mod add_tags_to_lines;
 pub(crate) mod antlr_rust_ext;
+mod current_indent_depth;
 pub(crate) mod run_compilation;
 pub(crate) mod utils;
 
@@ -43,6 +44,17 @@ pub struct Compiler {
 
     /// The declarations for variables.
     pub variable_declarations: Vec<Declaration>,
+
+    /// How line IDs are generated for lines that have no `#line:` tag.
+    /// Read it with [`Compiler::line_id_strategy`] and set it with [`Compiler::with_line_id_strategy`].
+    pub(crate) line_id_strategy: LineIdStrategy,
+
+    /// Whether the text of all lines is removed from the string table of the [`Compilation`], keeping only their IDs and metadata.
+    /// Read it with [`Compiler::strip_line_text`] and set it with [`Compiler::with_strip_line_text`].
+    pub(crate) strip_line_text: bool,
+
+    /// The commands declared via [`Compiler::with_external_definitions`], which are checked by the `check_commands` step.
+    pub(crate) command_declarations: Vec<Declaration>,
 }
 
 impl Compiler {
@@ -85,22 +97,91 @@ impl Compiler {
         self
     }
 
+    /// Makes the functions of the given [`Library`] known to the type checker, e.g. the library of the `Dialogue` that will run the compiled program.
+    /// Calls to these functions are then checked against their registered signatures instead of being implicitly declared.
+    ///
+    /// ## Implementation notes
+    ///
+    /// It is a convenience over [`Compiler::extend_library`] that borrows the library instead of taking ownership of it.
+    pub fn with_library(&mut self, library: &Library) -> &mut Self {
+        self.extend_library(library.clone())
+    }
+
     /// Sets the compilation type, which allows premature stopping of the compilation process. By default, this is [`CompilationType::FullCompilation`].
     pub fn with_compilation_type(&mut self, compilation_type: CompilationType) -> &mut Self {
         self.compilation_type = compilation_type;
         self
     }
 
+    /// How line IDs are generated for lines that have no `#line:` tag.
+    pub fn line_id_strategy(&self) -> LineIdStrategy {
+        self.line_id_strategy
+    }
+
+    /// Sets how line IDs are generated for lines that have no `#line:` tag. By default, this is [`LineIdStrategy::Global`].
+    pub fn with_line_id_strategy(&mut self, line_id_strategy: LineIdStrategy) -> &mut Self {
+        self.line_id_strategy = line_id_strategy;
+        self
+    }
+
+    /// Whether the text of all lines is removed from the string table of the [`Compilation`], keeping only their IDs and metadata.
+    pub fn strip_line_text(&self) -> bool {
+        self.strip_line_text
+    }
+
+    /// Sets whether the text of all lines is removed from the string table of the [`Compilation`], keeping only their IDs and metadata.
+    /// The [`StringInfo::is_text_stripped`] of these lines is set.
+    /// This is intended for builds in which all text is provided by strings files, so that the original text is neither shipped nor shown by accident.
+    /// The text provider of the dialogue then has to supply the text of every line, e.g. from the strings file of the base language.
+    /// Has no effect on [`CompilationType::StringsOnly`], whose purpose is extracting the text. By default, this is `false`.
+    pub fn with_strip_line_text(&mut self, strip_line_text: bool) -> &mut Self {
+        self.strip_line_text = strip_line_text;
+        self
+    }
+
     /// Adds a variable declaration to the compilation.
     pub fn declare_variable(&mut self, declaration: Declaration) -> &mut Self {
         self.variable_declarations.push(declaration);
         self
     }
 
+    /// Declares the functions and commands of the given [`ExternalDefinitions`], e.g. loaded from a `.ysls.json` file,
+    /// so that calls to them are checked against their parameter and return types even without a [`Library`] implementing them.
+    /// Fails if a definition uses an unknown type.
+    ///
+    /// Commands are checked for their number of arguments and for arguments that are literals of the wrong type, e.g. `<<wait soon>>`
+    /// for a `number` parameter. Arguments built from `{...}` expressions are not type checked, and commands containing
+    /// such expressions are not checked for their number of arguments, since the value of an expression may contain whitespace.
+    pub fn with_external_definitions(
+        &mut self,
+        definitions: &ExternalDefinitions,
+    ) -> std::result::Result<&mut Self, ExternalDefinitionsError> {
+        let declarations = definitions.function_declarations()?;
+        let command_declarations = definitions.command_declarations()?;
+        self.variable_declarations.extend(declarations);
+        self.command_declarations.extend(command_declarations);
+        Ok(self)
+    }
+
     /// Compiles the Yarn files previously added into a [`Compilation`].
     pub fn compile(&self) -> Result<Compilation> {
         run_compilation::compile(self)
     }
+
+    /// Compiles the Yarn files previously added and returns the resulting program together with all diagnostics.
+    /// Warnings do not prevent the program from being returned, only errors do. See [`CompilationResult`].
+    pub fn compile_with_diagnostics(&self) -> CompilationResult {
+        self.compile().into()
+    }
+
+    /// Checks the Yarn files previously added for problems without generating a [`Program`], e.g. for linting them in CI.
+    /// This runs the same parsing, type checking and declaration passes as [`Compiler::compile`] and returns all errors and warnings found by them.
+    /// If the result is empty, the files compile without any diagnostics.
+    ///
+    /// The [`Compiler::compilation_type`] is ignored.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        run_compilation::validate(self)
+    }
 }
 
 /// Represents the contents of a file to compile.
@@ -171,3 +252,36 @@ a {1 + 3} cool expression
         Compiler::new().add_file(file).compile().unwrap();
     }
 }
+
+/// How the [`Compiler`] generates line IDs for lines that have no `#line:` tag.
+/// These implicit line IDs are prefixed with the file and node name, e.g. `line:intro.yarn-Start-0`.
+/// Which edits change them depends on the strategy.
+///
+/// Neither strategy is as stable as tagging the lines, e.g. with [`Compiler::add_tags_to_lines`], which should be done before translating a project.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq, Hash, Default))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+pub enum LineIdStrategy {
+    /// Numbers the lines by their position in the string table of the whole compilation, e.g. `line:intro.yarn-Start-12`.
+    /// This is how the original implementation generates implicit line IDs.
+    /// The ID of a line stays the same when its text is edited.
+    /// However, adding, removing or reordering lines changes the IDs of all lines compiled after them, even in other nodes and files.
+    #[default]
+    Global,
+
+    /// Numbers the lines of each node in source order, e.g. `line:intro.yarn-Start-0`, `line:intro.yarn-Start-1`,
+    /// so edits in other nodes never change them.
+    /// The ID of a line stays the same when its text is edited, so its translation is kept and can be updated.
+    /// However, adding, removing or reordering lines changes the IDs of all following lines in the node.
+    Sequential,
+
+    /// Derives the ID from the text of the line, e.g. `line:intro.yarn-Start-1a2b3c4d`.
+    /// The ID of a line stays the same when lines are added, removed or reordered around it, in the same node or elsewhere.
+    /// However, any edit to its text gives it a new ID, so its translation is treated as missing.
+    ContentHash,
+}
@@ -15,6 +15,8 @@ impl Compiler {
     ///
     /// Line tags are added to any line of source code that contains
     /// user-visible text: lines, options, and shortcut options.
+    /// The tag is inserted after the last token of the line, and all other
+    /// characters, including indentation and line endings, are left untouched.
     ///
     /// ## Parameters
     ///
@@ -58,10 +60,11 @@ impl Compiler {
         // Apply these text replacements to the original source and return it.
 
         if rewrote_anything.load(Ordering::Relaxed) {
+            // ## Implementation note
+            // The original joins the lines with the platform's newline, which converts all line endings.
+            // Since the lines still contain their original endings, we simply concatenate them.
             let result = rewritten_nodes.take();
-            let mut string = result.join("\n");
-            string.push('\n');
-            Ok(Some(string))
+            Ok(Some(result.concat()))
         } else {
             Ok(None)
         }
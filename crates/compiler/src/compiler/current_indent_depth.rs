@@ -0,0 +1,12 @@
+use crate::prelude::*;
+use antlr_rust::InputStream;
+
+impl Compiler {
+    /// Returns the indentation, in columns, of the innermost option block that is still open at the end of the given Yarn source,
+    /// or `0` if no option block is open there. The source may be incomplete, e.g. a node whose body is still being typed.
+    ///
+    /// Editors can use this to indent the next line of a partially typed option body correctly.
+    pub fn current_indent_depth(source: &str) -> isize {
+        YarnSpinnerLexer::new(InputStream::new(source), "<input>".to_owned()).indent_depth_at_end()
+    }
+}
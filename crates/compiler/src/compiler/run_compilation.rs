@@ -12,19 +12,65 @@ pub(crate) fn compile(compiler: &Compiler) -> Result<Compilation> {
         &register_initial_variables,
         &parse_files,
         &register_strings,
+        &resolve_shadow_lines,
         &validate_unique_node_names,
         &break_on_job_with_only_strings,
         &get_declarations,
         &check_types,
+        &check_commands,
         &find_tracking_nodes,
         &create_declarations_for_tracking_nodes,
         &add_tracking_declarations,
         &resolve_deferred_type_diagnostic,
+        &check_interpolated_variables,
+        &build_definition_map,
         &break_on_job_with_only_declarations,
         &generate_code,
         &add_initial_value_registrations,
+        &add_option_tags,
+        &strip_line_text,
+        &verify_program,
     ];
 
+    run_steps(compiler, compiler_steps, |intermediate| {
+        // Cleaning up diagnostics doesn't change the state but makes sure
+        // that diagnostics are unique, there are no errors in the warnings, etc.
+        // So we execute it even if we've had early breaks.
+        clean_up_diagnostics(intermediate).result.unwrap()
+    })
+}
+
+/// Runs all analysis steps of [`compile`], but neither generates code nor stops early for a [`CompilationType`].
+/// Returns the cleaned up diagnostics, which are empty if the compiled files have no problems.
+pub(crate) fn validate(compiler: &Compiler) -> Vec<Diagnostic> {
+    let validation_steps: Vec<&CompilationStep> = vec![
+        &register_initial_variables,
+        &parse_files,
+        &register_strings,
+        &resolve_shadow_lines,
+        &validate_unique_node_names,
+        &check_node_titles,
+        &get_declarations,
+        &check_types,
+        &check_commands,
+        &find_tracking_nodes,
+        &create_declarations_for_tracking_nodes,
+        &add_tracking_declarations,
+        &resolve_deferred_type_diagnostic,
+        &check_interpolated_variables,
+    ];
+    run_steps(compiler, validation_steps, |intermediate| {
+        clean_up_diagnostics(intermediate).diagnostics
+    })
+}
+
+/// Runs the steps on the files of the compiler and passes the final state to `finish`.
+/// The state borrows the characters of the files, which only live until this function returns.
+fn run_steps<T>(
+    compiler: &Compiler,
+    compiler_steps: Vec<&CompilationStep>,
+    finish: impl FnOnce(CompilationIntermediate) -> T,
+) -> T {
     let chars: Vec<Vec<u32>> = compiler
         .files
         .iter()
@@ -48,11 +94,7 @@ pub(crate) fn compile(compiler: &Compiler) -> Result<Compilation> {
             step(state)
         }
     });
-    // Cleaning up diagnostics doesn't change the state but makes sure
-    // that diagnostics are unique, there are no errors in the warnings, etc.
-    // So we execute it even if we've had early breaks.
-    let result = clean_up_diagnostics(intermediate).result.unwrap();
-    result
+    finish(intermediate)
 }
 
 type CompilationStep = dyn Fn(CompilationIntermediate) -> CompilationIntermediate;
@@ -71,6 +113,7 @@ pub(crate) struct CompilationIntermediate<'input> {
     pub(crate) string_table: StringTableManager,
     pub(crate) diagnostics: Vec<Diagnostic>,
     pub(crate) file_tags: HashMap<String, Vec<String>>,
+    pub(crate) definition_map: HashMap<(String, Position), (String, Position)>,
     pub(crate) early_break: bool,
 }
 
@@ -88,6 +131,7 @@ impl<'input> CompilationIntermediate<'input> {
             string_table: Default::default(),
             diagnostics: Default::default(),
             file_tags: Default::default(),
+            definition_map: Default::default(),
             early_break: Default::default(),
         }
     }
@@ -0,0 +1,167 @@
+//! Declarations of commands and functions that are implemented by the game instead of in Yarn.
+//!
+//! ## Implementation notes
+//!
+//! This module is not present in the original implementation. Its file format follows the `.ysls.json` files
+//! read by the Yarn Spinner language server.
+
+use crate::prelude::*;
+use std::error::Error;
+use std::fmt::{Debug, Display};
+use yarnspinner_core::types::{FunctionType, Type};
+
+/// The commands and functions that a game implements for its Yarn scripts, e.g. loaded from a `.ysls.json` file
+/// so that scripts can be compiled without having the Rust implementations of the functions at hand.
+///
+/// Pass these to [`Compiler::with_external_definitions`] to type check calls to the functions and commands against them.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase", default))]
+pub struct ExternalDefinitions {
+    /// The commands, e.g. `wait` for `<<wait 2>>`.
+    /// The text of a command is split into its name and arguments like at runtime, i.e. at whitespace outside of double quotes.
+    pub commands: Vec<ExternalDefinition>,
+    /// The functions, e.g. `dice` for `{dice(6)}`.
+    pub functions: Vec<ExternalDefinition>,
+}
+
+/// A single command or function of [`ExternalDefinitions`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase", default))]
+pub struct ExternalDefinition {
+    /// The name used in Yarn scripts.
+    pub yarn_name: String,
+    /// A description shown by editors, e.g. when hovering over a call.
+    pub documentation: Option<String>,
+    /// The parameters, in the order in which they are passed.
+    pub parameters: Vec<ExternalParameter>,
+    /// The name of the returned type, one of `number`, `string`, `bool` or `any`. Ignored for commands.
+    /// [`None`] is treated like `any`.
+    pub return_type: Option<String>,
+}
+
+/// A parameter of an [`ExternalDefinition`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase", default))]
+pub struct ExternalParameter {
+    /// The name of the parameter, only used for documentation.
+    pub name: String,
+    /// The name of the type of the parameter, one of `number`, `string`, `bool` or `any`.
+    pub r#type: String,
+    /// A description shown by editors.
+    pub documentation: Option<String>,
+}
+
+impl ExternalDefinitions {
+    /// Parses definitions from the JSON content of a `.ysls.json` file. Fields that are not part of [`ExternalDefinitions`] are ignored.
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> std::result::Result<Self, ExternalDefinitionsError> {
+        serde_json::from_str(json).map_err(ExternalDefinitionsError::Json)
+    }
+
+    /// Serializes the definitions to JSON in the format read by [`ExternalDefinitions::from_json`].
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self)
+            .unwrap_or_else(|e| panic!("Failed to serialize external definitions to JSON: {e}"))
+    }
+
+    /// Creates the declarations of the [`ExternalDefinitions::functions`], with a [`DeclarationSource::External`].
+    pub fn function_declarations(
+        &self,
+    ) -> std::result::Result<Vec<Declaration>, ExternalDefinitionsError> {
+        self.functions
+            .iter()
+            .map(|function| {
+                let return_type = function.return_type.as_deref().unwrap_or("any");
+                declare(function, Some(parse_type(function, return_type)?))
+            })
+            .collect()
+    }
+
+    /// Creates the declarations of the [`ExternalDefinitions::commands`], with a [`DeclarationSource::External`].
+    /// Their type is a [`FunctionType`] without a return type.
+    pub fn command_declarations(
+        &self,
+    ) -> std::result::Result<Vec<Declaration>, ExternalDefinitionsError> {
+        self.commands
+            .iter()
+            .map(|command| declare(command, None))
+            .collect()
+    }
+}
+
+fn declare(
+    definition: &ExternalDefinition,
+    return_type: Option<Type>,
+) -> std::result::Result<Declaration, ExternalDefinitionsError> {
+    let mut function_type = FunctionType::default();
+    for parameter in &definition.parameters {
+        function_type.add_parameter(parse_type(definition, &parameter.r#type)?);
+    }
+    if let Some(return_type) = return_type {
+        function_type.set_return_type(return_type);
+    }
+    Ok(Declaration::new(&definition.yarn_name, function_type)
+        .with_description_optional(definition.documentation.clone())
+        .with_source_file_name(DeclarationSource::External))
+}
+
+fn parse_type(
+    definition: &ExternalDefinition,
+    type_name: &str,
+) -> std::result::Result<Type, ExternalDefinitionsError> {
+    match type_name.to_lowercase().as_str() {
+        "number" => Ok(Type::Number),
+        "string" => Ok(Type::String),
+        "bool" | "boolean" => Ok(Type::Boolean),
+        "any" => Ok(Type::Any),
+        _ => Err(ExternalDefinitionsError::UnknownType {
+            definition: definition.yarn_name.clone(),
+            type_name: type_name.to_owned(),
+        }),
+    }
+}
+
+/// An error encountered while reading [`ExternalDefinitions`].
+#[derive(Debug)]
+pub enum ExternalDefinitionsError {
+    /// The definitions are not valid JSON or do not have the expected structure.
+    #[cfg(feature = "serde")]
+    Json(serde_json::Error),
+    /// A parameter or return type is not one of `number`, `string`, `bool` or `any`.
+    UnknownType {
+        /// The name of the command or function using the type.
+        definition: String,
+        /// The unknown type name.
+        type_name: String,
+    },
+}
+
+impl Error for ExternalDefinitionsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            #[cfg(feature = "serde")]
+            Self::Json(e) => Some(e),
+            Self::UnknownType { .. } => None,
+        }
+    }
+}
+
+impl Display for ExternalDefinitionsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(feature = "serde")]
+            Self::Json(e) => write!(f, "Failed to parse external definitions: {e}"),
+            Self::UnknownType {
+                definition,
+                type_name,
+            } => write!(
+                f,
+                "\"{definition}\" uses the unknown type \"{type_name}\". Expected one of \"number\", \"string\", \"bool\" or \"any\""
+            ),
+        }
+    }
+}
@@ -12,8 +12,10 @@ mod collections;
 pub(crate) mod compilation_steps;
 pub(crate) mod compiler;
 pub(crate) mod error_strategy;
+mod external_definitions;
 mod file_parse_result;
 pub(crate) mod listeners;
+mod markup_validation;
 mod output;
 mod parser;
 pub(crate) mod parser_rule_context_ext;
@@ -27,12 +29,15 @@ pub mod prelude {
     //! Everything you need to get started with the Yarn Spinner compiler.
     pub(crate) use crate::{
         compiler::antlr_rust_ext::*, compiler::run_compilation::*, compiler::utils::*,
-        file_parse_result::*, parser::*, parser_rule_context_ext::*, string_table_manager::*,
-        token_ext::*,
+        file_parse_result::*, markup_validation::*, parser::*, parser_rule_context_ext::*,
+        string_table_manager::*, token_ext::*,
     };
     pub use crate::{
-        compiler::{CompilationType, Compiler, File},
-        listeners::{Diagnostic, DiagnosticSeverity, DiagnosticVec},
+        compiler::{CompilationType, Compiler, File, LineIdStrategy},
+        external_definitions::*,
+        listeners::{
+            Diagnostic, DiagnosticKind, DiagnosticSeverity, DiagnosticVec, RelatedInformation,
+        },
         output::*,
     };
     pub(crate) use yarnspinner_core::prelude::*;
@@ -34,7 +34,7 @@ pub(crate) struct CompilerListener<'input> {
     /// Whether we are currently parsing the
     /// current node as a 'raw text' node, or as a fully syntactic node.
     is_current_node_raw_text: bool,
-    file: FileParseResult<'input>,
+    pub(crate) file: FileParseResult<'input>,
     label_count: usize,
 }
 
@@ -87,7 +87,8 @@ impl<'input> YarnSpinnerParserListener<'input> for CompilerListener<'input> {
             self.diagnostics.borrow_mut().push(
                 Diagnostic::from_message("Missing title header for node")
                     .with_file_name(self.file.name.clone())
-                    .with_parser_context(ctx, self.file.tokens()),
+                    .with_parser_context(ctx, self.file.tokens())
+                    .with_kind(DiagnosticKind::Syntax),
             );
         } else {
             if !self.program.borrow().nodes.contains_key(name) {
@@ -52,7 +52,8 @@ impl<'input, T: Recognizer<'input>> ErrorListener<'input, T> for LexerErrorListe
         self.diagnostics.borrow_mut().push(
             Diagnostic::from_message(msg)
                 .with_range(range)
-                .with_file_name(&self.file_name),
+                .with_file_name(&self.file_name)
+                .with_kind(DiagnosticKind::Syntax),
         );
     }
 }
@@ -95,7 +96,8 @@ impl<'input, T: Recognizer<'input>> ErrorListener<'input, T> for ParserErrorList
         };
         let mut diagnostic = Diagnostic::from_message(msg)
             .with_file_name(&self.file.file_name)
-            .with_range(range);
+            .with_range(range)
+            .with_kind(DiagnosticKind::Syntax);
         if let Some(offending_symbol) = offending_symbol {
             let mut string = String::new();
 
@@ -46,6 +46,101 @@ pub struct Diagnostic {
 
     /// The line the context starts on.
     pub start_line: usize,
+
+    /// Other source locations that are relevant to the issue, such as the place a variable was originally declared.
+    pub related_information: Vec<RelatedInformation>,
+
+    /// What kind of problem was found, for tools that want to react to specific issues instead of parsing [`Diagnostic::message`].
+    /// Always set for errors. `None` for warnings that do not fit any [`DiagnosticKind`].
+    pub kind: Option<DiagnosticKind>,
+}
+
+/// The problem described by a [`Diagnostic`], in a structured form. Set on [`Diagnostic::kind`].
+/// The location of the problem is found in [`Diagnostic::file_name`] and [`Diagnostic::range`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq, Hash))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+pub enum DiagnosticKind {
+    /// The source could not be parsed.
+    Syntax,
+    /// An expression, assignment, function call or declaration uses a value of the wrong type,
+    /// or a declaration's default value is not a constant.
+    Type,
+    /// A variable is used without being declared, so its type cannot be inferred or it always has its default value.
+    UndeclaredVariable {
+        /// The name of the variable, including the `$`.
+        variable: String,
+    },
+    /// A variable is explicitly declared more than once.
+    DuplicateDeclaration {
+        /// The name of the variable, including the `$`.
+        variable: String,
+    },
+    /// More than one node has the same title.
+    DuplicateNodeName {
+        /// The title shared by the nodes.
+        node_name: String,
+    },
+    /// More than one line has the same `#line:` ID.
+    DuplicateLineId {
+        /// The shared line ID, e.g. `line:1234`.
+        line_id: String,
+    },
+    /// A hashtag on a line cannot be applied, e.g. a `#shadow:` tag names a line that does not exist,
+    /// or the ID of a line tagged with `#multiselect` contains the separator the runtime uses to store selections.
+    InvalidLineTag {
+        /// The ID of the line carrying the hashtag.
+        line_id: String,
+    },
+    /// The markup of a line is malformed, e.g. a close marker like `[/i]` does not match an open marker.
+    Markup,
+    /// A `<<set>>` statement assigns to a variable that was declared with `<<const>>`.
+    ConstantAssignment {
+        /// The name of the constant, including the `$`.
+        variable: String,
+    },
+}
+
+/// A source location that is related to a [`Diagnostic`], together with a message explaining the relation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq, Hash))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct RelatedInformation {
+    /// The path, URI or file-name of the related location.
+    pub file_name: Option<String>,
+
+    /// The range of the file indicated by [`RelatedInformation::file_name`] that is related to the issue.
+    pub range: Option<Range<Position>>,
+
+    /// Describes how the location is related to the issue, e.g. "Originally declared here".
+    pub message: String,
+}
+
+impl Display for RelatedInformation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let file_name = self.file_name.as_deref().unwrap_or("<unknown file>");
+        match self.range.as_ref() {
+            Some(range) => write!(
+                f,
+                "{} ({file_name}:{}:{})",
+                self.message,
+                range.start.line + 1,
+                range.start.character + 1
+            ),
+            None => write!(f, "{} ({file_name})", self.message),
+        }
+    }
 }
 
 impl Diagnostic {
@@ -57,6 +152,8 @@ impl Diagnostic {
             context: Default::default(),
             severity: Default::default(),
             start_line: Default::default(),
+            related_information: Default::default(),
+            kind: Default::default(),
         }
     }
 
@@ -103,6 +200,19 @@ impl Diagnostic {
         self.severity = severity;
         self
     }
+
+    pub(crate) fn with_related_information(
+        mut self,
+        related_information: RelatedInformation,
+    ) -> Self {
+        self.related_information.push(related_information);
+        self
+    }
+
+    pub(crate) fn with_kind(mut self, kind: DiagnosticKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
 }
 
 impl Display for Diagnostic {
@@ -112,13 +222,25 @@ impl Display for Diagnostic {
             DiagnosticSeverity::Error => AnnotationType::Error,
             DiagnosticSeverity::Warning => AnnotationType::Warning,
         };
+        let related_information: Vec<_> = self
+            .related_information
+            .iter()
+            .map(ToString::to_string)
+            .collect();
         let snippet = Snippet {
             title: Some(Annotation {
                 label: Some(label),
                 id: None,
                 annotation_type,
             }),
-            footer: vec![],
+            footer: related_information
+                .iter()
+                .map(|related| Annotation {
+                    label: Some(related),
+                    id: None,
+                    annotation_type: AnnotationType::Note,
+                })
+                .collect(),
             slices: vec![Slice {
                 source: self.context.as_deref().unwrap_or("<unknown line>"),
                 line_start: self.start_line + 1,
@@ -26,10 +26,13 @@ pub(crate) struct UntaggedLineListener<'input> {
 
 impl<'input> UntaggedLineListener<'input> {
     pub fn new(existing_line_tags: Vec<LineId>, file: FileParseResult<'input>) -> Self {
+        // ## Implementation note
+        // The lines keep their line endings so that joining them reproduces the source byte for byte,
+        // including `\r\n` line endings and the presence or absence of a final newline.
         let original_source = file
             .tokens()
             .get_all_text()
-            .lines()
+            .split_inclusive('\n')
             .map(|s| s.to_owned())
             .collect();
         Self {
@@ -110,7 +113,17 @@ impl<'input> YarnSpinnerParserListener<'input> for UntaggedLineListener<'input>
                 panic!("Internal error: failed to convert char pos to byte pos for insertion index on line {line_index}. \
                         This is a bug. Please report it at https://github.com/YarnSpinnerTool/YarnSpinner-Rust/issues/new"))
             + previous_token.get_text().len();
-        line.insert_str(insertion_index, &format!(" #{new_line_id} "));
+        // Only separate the tag from what follows if there is no whitespace or line ending already
+        let needs_trailing_space = line[insertion_index..]
+            .chars()
+            .next()
+            .is_some_and(|c| !c.is_whitespace());
+        let tag = if needs_trailing_space {
+            format!(" #{new_line_id} ")
+        } else {
+            format!(" #{new_line_id}")
+        };
+        line.insert_str(insertion_index, &tag);
         self.rewrote_anything
             .store(true, std::sync::atomic::Ordering::Relaxed);
     }
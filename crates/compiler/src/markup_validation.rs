@@ -0,0 +1,87 @@
+//! Checks the markup of lines at compile time, so that mistakes like `[b]Hello[/i]` are reported with their position in the source
+//! instead of only failing when the line is parsed at runtime.
+
+use crate::prelude::*;
+use std::ops::Range;
+use yarnspinner_core::markup::{LineParser, MarkupParseError};
+
+/// A problem with the markup of a line, found by [`validate_markup`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MarkupIssue {
+    pub(crate) message: String,
+    /// The characters of the offending marker, as indices into the validated text.
+    pub(crate) range: Range<usize>,
+    pub(crate) severity: DiagnosticSeverity,
+}
+
+/// Checks the markup in the text of a line, given as its characters, with the same [`LineParser`] the runtime parses lines with.
+///
+/// Markup that cannot be parsed, e.g. a close marker without a matching open marker, is an error.
+/// Open markers that are never closed are warnings, since the runtime drops their attributes by default instead of failing,
+/// see `UnclosedAttributePolicy`.
+pub(crate) fn validate_markup(text: &[char]) -> Vec<MarkupIssue> {
+    let text: String = text.iter().collect();
+    LineParser::new()
+        .find_markup_issues(&text)
+        .into_iter()
+        .map(|issue| {
+            let severity = match issue.error {
+                MarkupParseError::UnclosedAttribute { .. } => DiagnosticSeverity::Warning,
+                _ => DiagnosticSeverity::Error,
+            };
+            MarkupIssue {
+                message: issue.error.to_string(),
+                range: issue.source_range,
+                severity,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issues(text: &str) -> Vec<MarkupIssue> {
+        let text: Vec<_> = text.chars().collect();
+        validate_markup(&text)
+    }
+
+    #[test]
+    fn accepts_well_formed_markup() {
+        assert!(issues("[b]Hello[/b] [wave size=2]there[/wave] [pause/]").is_empty());
+        assert!(issues("[b][i]Hello[/b][/i] [shake]world[/]").is_empty());
+        assert!(issues("An \\[escaped\\] bracket and [nomarkup][b][/nomarkup]").is_empty());
+        assert!(issues("[color=\"]\"]quoted[/color]").is_empty());
+    }
+
+    #[test]
+    fn reports_mismatched_close_marker() {
+        let issues = issues("[b]Hello[/i]");
+        assert_eq!(1, issues.len());
+        assert_eq!(8..12, issues[0].range);
+        assert_eq!(DiagnosticSeverity::Error, issues[0].severity);
+    }
+
+    #[test]
+    fn reports_unclosed_markers() {
+        let issues = issues("[b]Hello [i]there");
+        assert_eq!(2, issues.len());
+        assert_eq!(0..3, issues[0].range);
+        assert_eq!(9..12, issues[1].range);
+        assert!(issues
+            .iter()
+            .all(|issue| issue.severity == DiagnosticSeverity::Warning));
+    }
+
+    #[test]
+    fn reports_unterminated_markers() {
+        let issue = issues("Hello [b").remove(0);
+        assert_eq!(6..8, issue.range);
+        assert_eq!(DiagnosticSeverity::Error, issue.severity);
+        assert_eq!(
+            DiagnosticSeverity::Error,
+            issues("[nomarkup][b]").remove(0).severity
+        );
+    }
+}
@@ -86,10 +86,21 @@ pub struct Compilation {
 
     /// The collection of [`DebugInfo`] objects for each node in [`Program`].
     pub debug_info: HashMap<String, DebugInfo>,
+
+    /// Maps every place where a node or variable is referenced to the place where it is defined,
+    /// e.g. to implement "go to definition" in an editor. Both sides are a file name and a [`Position`] in that file.
+    ///
+    /// - Nodes are referenced by `<<jump>>` statements and by calls to `visited` and `visited_count` with a string literal.
+    ///   They are defined by the `title` header of the node.
+    /// - Variables are referenced wherever they appear. They are defined by their earliest `<<declare>>` statement or,
+    ///   if they are not declared explicitly, by the first `<<set>>` statement assigning them.
+    ///
+    /// References to nodes or variables without a definition in the compiled files are not included.
+    pub definition_map: HashMap<(String, Position), (String, Position)>,
 }
 
 impl Compilation {
-    /// Combines multiple [`CompilationResult`] objects together into one object.
+    /// Combines multiple [`Compilation`] objects together into one object.
     pub(crate) fn combine(
         compilations: impl Iterator<Item = Compilation>,
         string_table_manager: StringTableManager,
@@ -99,6 +110,7 @@ impl Compilation {
         let mut tags = HashMap::new();
         let mut diagnostics = Vec::new();
         let mut node_debug_infos = HashMap::new();
+        let mut definition_map = HashMap::new();
 
         for compilation in compilations {
             programs.push(compilation.program.unwrap());
@@ -106,6 +118,7 @@ impl Compilation {
             tags.extend(compilation.file_tags);
             diagnostics.extend(compilation.warnings);
             node_debug_infos.extend(compilation.debug_info);
+            definition_map.extend(compilation.definition_map);
         }
         let combined_program = Program::combine(programs);
         let contains_implicit_string_tags = string_table_manager.contains_implicit_string_tags();
@@ -117,6 +130,53 @@ impl Compilation {
             contains_implicit_string_tags,
             file_tags: tags,
             warnings: diagnostics,
+            definition_map,
+        }
+    }
+
+    /// Serializes all [`Compilation::declarations`] as a JSON array, e.g. for IDE plugins, documentation generators or binding generators.
+    #[cfg(feature = "serde")]
+    pub fn declarations_json(&self) -> serde_json::Value {
+        serde_json::to_value(&self.declarations)
+            .unwrap_or_else(|e| panic!("Failed to serialize declarations to JSON: {e}"))
+    }
+}
+
+/// The program and all diagnostics of a compilation, produced by [`Compiler::compile_with_diagnostics`].
+/// Unlike [`Compiler::compile`], this also reports the diagnostics when the compilation failed, which is what e.g. language servers expect.
+///
+/// ## Implementation notes
+///
+/// This type is not present in the original implementation. Its name is that of the original's equivalent of [`Compilation`],
+/// but it only holds the parts of a compilation that can be inspected regardless of whether it succeeded.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, Default))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct CompilationResult {
+    /// The compiled Yarn program, if no [`Diagnostic`] has a severity of [`DiagnosticSeverity::Error`].
+    /// Like [`Compilation::program`], this is also [`None`] if the [`Compiler::compilation_type`] was not [`CompilationType::FullCompilation`].
+    pub program: Option<Program>,
+
+    /// All errors and warnings found during compilation.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl From<crate::Result<Compilation>> for CompilationResult {
+    fn from(result: crate::Result<Compilation>) -> Self {
+        match result {
+            Ok(compilation) => Self {
+                program: compilation.program,
+                diagnostics: compilation.warnings,
+            },
+            Err(CompilerError(diagnostics)) => Self {
+                program: None,
+                diagnostics,
+            },
         }
     }
 }
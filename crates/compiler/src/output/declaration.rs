@@ -56,6 +56,11 @@ pub struct Declaration {
     /// If `false`, this declaration appears in the source code.
     pub is_implicit: bool,
 
+    /// A value indicating whether this declaration is a constant, i.e. was declared with `<<const $x = 1>>`.
+    /// The compiler reports an error for any `<<set>>` to a constant.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub is_constant: bool,
+
     /// The type of the variable, as represented by an object found
     /// in a variant of [`Type`].
     pub r#type: Type,
@@ -89,6 +94,7 @@ impl Declaration {
             source_file_name: Default::default(),
             source_node_name: Default::default(),
             is_implicit: Default::default(),
+            is_constant: Default::default(),
             range: Default::default(),
         }
     }
@@ -150,6 +156,12 @@ impl Declaration {
         self
     }
 
+    #[doc(hidden)]
+    pub fn with_constant(mut self) -> Self {
+        self.is_constant = true;
+        self
+    }
+
     #[doc(hidden)]
     pub fn with_range(mut self, range: impl Into<Range<Position>>) -> Self {
         self.range = Some(range.into());
@@ -163,6 +175,7 @@ impl Declaration {
             && self.source_file_name == other.source_file_name
             && self.source_node_name == other.source_node_name
             && self.is_implicit == other.is_implicit
+            && self.is_constant == other.is_constant
             && self.r#type == other.r#type
             && self.range == other.range
             && match (&self.default_value, &other.default_value) {
@@ -209,6 +222,13 @@ where
     <<<<Self as CustomRuleContext<'input>>::TF as TokenFactory<'input>>::Inner as Token>::Data as ToOwned>::Owned:
         Into<String>,
 {
+    /// Returns the range of text covered by this context, from the first character of its start token
+    /// up to (but excluding) the character following its stop token.
+    ///
+    /// ## Implementation notes
+    ///
+    /// The width of the stop token is counted in unicode code points, which is what [`Position::character`] is measured in,
+    /// and not in bytes.
     fn range(&self) -> Range<Position> {
         let start = Position {
             line: self.start().get_line_as_usize().saturating_sub(1),
@@ -217,7 +237,7 @@ where
         let text: String = self.stop().get_text().to_owned().into();
         let stop = Position {
             line: self.stop().get_line_as_usize().saturating_sub(1),
-            character: self.stop().get_column_as_usize() + text.len(),
+            character: self.stop().get_column_as_usize() + text.chars().count(),
         };
         start..stop
     }
@@ -243,3 +263,123 @@ pub(crate) struct DeferredTypeDiagnostic {
     pub(crate) name: String,
     pub(crate) diagnostic: Diagnostic,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::generated::yarnspinnerparser::*;
+    use std::rc::Rc;
+
+    const SOURCE: &str = r#"title: Start
+---
+<<declare $name = "Zoë">>
+<<set $gold to $gold + 1>>
+<<if $gold > 3 and $name == "Zoë">>
+    Wealthy!
+<<endif>>
+-> Buy café
+    <<jump Shop>>
+<<call greet($name)>>
+==="#;
+
+    fn pos(line: usize, character: usize) -> Position {
+        Position { line, character }
+    }
+
+    fn with_statements(test: impl for<'a> FnOnce(Vec<Rc<StatementContextAll<'a>>>)) {
+        let file = File {
+            file_name: "test.yarn".to_owned(),
+            source: SOURCE.to_owned(),
+        };
+        let chars: Vec<_> = file.source.chars().map(|c| c as u32).collect();
+        let mut diagnostics = Vec::new();
+        let parsed_file = parse_syntax_tree(&file, &chars, &mut diagnostics);
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+
+        let statements = parsed_file
+            .tree
+            .node(0)
+            .unwrap()
+            .body()
+            .unwrap()
+            .statement_all();
+        test(statements)
+    }
+
+    #[test]
+    fn declare_statement_ranges_are_exact() {
+        with_statements(|statements| {
+            let declare_statement = statements[0].declare_statement().unwrap();
+            assert_eq!(pos(2, 0)..pos(2, 25), declare_statement.range());
+
+            let variable = declare_statement.variable().unwrap();
+            assert_eq!(pos(2, 10)..pos(2, 15), variable.range());
+
+            // Contains a non-ASCII character, so the byte length differs from the character count.
+            let value = declare_statement.value().unwrap();
+            assert_eq!(pos(2, 18)..pos(2, 23), value.range());
+        });
+    }
+
+    #[test]
+    fn set_statement_ranges_are_exact() {
+        with_statements(|statements| {
+            let set_statement = statements[1].set_statement().unwrap();
+            assert_eq!(pos(3, 0)..pos(3, 26), set_statement.range());
+
+            let variable = set_statement.variable().unwrap();
+            assert_eq!(pos(3, 6)..pos(3, 11), variable.range());
+
+            let expression = set_statement.expression().unwrap();
+            assert_eq!(pos(3, 15)..pos(3, 24), expression.range());
+        });
+    }
+
+    #[test]
+    fn conditional_expression_ranges_are_exact() {
+        with_statements(|statements| {
+            let if_clause = statements[2].if_statement().unwrap().if_clause().unwrap();
+            let expression = if_clause.expression().unwrap();
+            assert_eq!(pos(4, 5)..pos(4, 33), expression.range());
+        });
+    }
+
+    #[test]
+    fn option_ranges_are_exact() {
+        with_statements(|statements| {
+            let shortcut_option = statements[3]
+                .shortcut_option_statement()
+                .unwrap()
+                .shortcut_option(0)
+                .unwrap();
+
+            let line_text = shortcut_option
+                .line_statement()
+                .unwrap()
+                .line_formatted_text()
+                .unwrap();
+            assert_eq!(pos(7, 3)..pos(7, 11), line_text.range());
+
+            let jump_statement = shortcut_option
+                .statement(0)
+                .unwrap()
+                .jump_statement()
+                .unwrap();
+            assert_eq!(pos(8, 4)..pos(8, 17), jump_statement.range());
+        });
+    }
+
+    #[test]
+    fn call_statement_ranges_are_exact() {
+        with_statements(|statements| {
+            let call_statement = statements[4].call_statement().unwrap();
+            assert_eq!(pos(9, 0)..pos(9, 21), call_statement.range());
+
+            let function_call = call_statement.function_call().unwrap();
+            assert_eq!(pos(9, 7)..pos(9, 19), function_call.range());
+
+            let argument = function_call.expression(0).unwrap();
+            assert_eq!(pos(9, 13)..pos(9, 18), argument.range());
+        });
+    }
+}
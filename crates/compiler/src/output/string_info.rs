@@ -1,6 +1,5 @@
 //! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner.Compiler/StringInfo.cs>
 
-#[cfg(any(feature = "bevy", feature = "serde"))]
 use crate::prelude::*;
 
 /// Information about a string. Stored inside a string table, which is
@@ -8,6 +7,12 @@ use crate::prelude::*;
 ///
 /// You do not create instances of this class yourself. They are
 /// generated by the [`Compiler`].
+///
+/// ## Implementation notes
+///
+/// This struct is marked as `#[non_exhaustive]` so that fields not present in the original implementation
+/// such as [`StringInfo::shadow_line_id`] can be added without breaking changes.
+#[non_exhaustive]
 #[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "bevy", derive(Reflect))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -43,4 +48,14 @@ pub struct StringInfo {
     /// This array will contain any hashtags associated with this
     /// string besides the `#line:` hashtag.
     pub metadata: Vec<String>,
+
+    /// The ID of the line whose text this line reuses, as set by a `#shadow:` hashtag.
+    /// A line tagged with `#shadow:abc` shadows the line tagged with `#line:abc`.
+    /// The [`StringInfo::text`] of a shadow line is always the text of the line it shadows,
+    /// so that it only needs to be translated once.
+    pub shadow_line_id: Option<LineId>,
+
+    /// Whether [`StringInfo::text`] was removed because the compilation was made with [`Compiler::with_strip_line_text`].
+    /// The text of such a line has to be supplied by other means, e.g. a strings file.
+    pub is_text_stripped: bool,
 }
@@ -5,4 +5,6 @@ pub(crate) mod generated;
 mod indent_aware_lexer;
 
 pub(crate) use actual_types::*;
-pub(crate) use indent_aware_lexer::IndentAwareYarnSpinnerLexer as YarnSpinnerLexer;
+pub(crate) use indent_aware_lexer::{
+    IndentAwareYarnSpinnerLexer as YarnSpinnerLexer, LINE_GROUP_ARROW,
+};
@@ -10,14 +10,15 @@ use super::generated::yarnspinnerlexer::{
 };
 use crate::collections::*;
 use crate::listeners::Diagnostic;
-use crate::prelude::{create_common_token, DiagnosticSeverity, TokenExt};
+use crate::prelude::{create_common_token, DiagnosticKind, DiagnosticSeverity, TokenExt};
 use antlr_rust::token::CommonToken;
 use antlr_rust::{
     char_stream::CharStream,
-    token::{Token, TOKEN_DEFAULT_CHANNEL},
+    token::{Token, TOKEN_DEFAULT_CHANNEL, TOKEN_HIDDEN_CHANNEL},
     token_factory::{CommonTokenFactory, TokenFactory},
     Lexer, TokenSource,
 };
+use std::borrow::Cow;
 use std::cell::RefCell;
 use std::ops::{Deref, DerefMut, Range};
 use std::rc::Rc;
@@ -27,6 +28,13 @@ use yarnspinner_core::prelude::*;
 #[allow(dead_code)]
 type YarnSpinnerLexer = ();
 
+/// The type of the `=>` token that marks a line as a candidate of a line group, e.g. `=> Hello there!`.
+/// Consecutive candidates form a group, of which only one line is run.
+///
+/// Since the bundled grammar cannot be regenerated, the parser does not know this token.
+/// It is emitted on the [`TOKEN_HIDDEN_CHANNEL`] right before the text of the line, where the compiler looks it up.
+pub(crate) const LINE_GROUP_ARROW: isize = yarnspinnerlexer::_SYMBOLIC_NAMES.len() as isize;
+
 antlr_rust::tid! { impl<'input, Input> TidAble<'input> for IndentAwareYarnSpinnerLexer<'input, Input> where Input:CharStream<From<'input>> }
 
 /// A Lexer subclass that detects newlines and generates indent and dedent tokens accordingly.
@@ -61,6 +69,12 @@ pub(crate) struct IndentAwareYarnSpinnerLexer<
     /// holds the line number of the last seen option.
     /// Lets us work out if the blank line needs to end the option.
     last_seen_option_content: Option<isize>,
+    /// Whether we are between a node's `---` and `===`.
+    ///
+    /// ## Implementation notes
+    ///
+    /// This field is not present in the original implementation. See [`IndentAwareYarnSpinnerLexer::handle_eof_token`].
+    in_body: bool,
     file_name: String,
     pub(crate) diagnostics: Rc<RefCell<Vec<Diagnostic>>>,
 }
@@ -136,13 +150,41 @@ where
             last_indent: Default::default(),
             unbalanced_indents: Default::default(),
             last_seen_option_content: None,
+            in_body: false,
             diagnostics: Default::default(),
         }
     }
 
+    /// Returns the indentation, in columns, of the innermost option block that is still open,
+    /// or `0` if no option block is currently open.
+    /// Every open option block will be closed by a DEDENT once a shallower line or the end of the file is reached.
+    ///
+    /// Editors can use this to indent the next line of a partially typed option body correctly.
+    pub fn current_indent_depth(&self) -> isize {
+        self.unbalanced_indents.peek().copied().unwrap_or_default()
+    }
+
+    /// Lexes the rest of the input and returns the [`IndentAwareYarnSpinnerLexer::current_indent_depth`] right before the end of the file
+    /// closes all open option blocks.
+    ///
+    /// Since [`TokenSource::next_token`] enqueues tokens ahead of the one it returns, the depth cannot be read from the returned tokens.
+    pub(crate) fn indent_depth_at_end(mut self) -> isize {
+        loop {
+            let depth = self.current_indent_depth();
+            let token = self.base.next_token();
+            if token.token_type == antlr_rust::token::TOKEN_EOF {
+                return depth;
+            }
+            self.handle_token(token);
+        }
+    }
+
     fn check_next_token(&mut self) {
         let current = self.base.next_token();
+        self.handle_token(current);
+    }
 
+    fn handle_token(&mut self, current: Box<antlr_rust::token::GenericToken<Cow<'input, str>>>) {
         match current.token_type {
             // Insert indents or dedents depending on the next token's
             // indentation, and enqueues the newline at the correct place
@@ -161,7 +203,20 @@ where
                 self.diagnose_newlines_in_commands(&current);
                 self.pending_tokens.enqueue(current.clone());
             }
+            // The generated lexer emits the first character of a line as its own token
+            yarnspinnerlexer::TEXT
+                if self.is_at_start_of_body_line() && current.get_text() == "=" =>
+            {
+                // Takes care of updating the last token itself, as it looks ahead
+                self.handle_potential_line_group_arrow(current);
+                return;
+            }
+            yarnspinnerlexer::BODY_START => {
+                self.in_body = true;
+                self.pending_tokens.enqueue(current.clone());
+            }
             yarnspinnerlexer::BODY_END => {
+                self.in_body = false;
                 self.line_contains_shortcut = false;
                 self.last_indent = 0;
                 self.unbalanced_indents.0.clear();
@@ -176,6 +231,52 @@ where
         self.last_token = Some(current);
     }
 
+    fn is_at_start_of_body_line(&self) -> bool {
+        self.in_body
+            && self.last_token.as_ref().is_some_and(|last| {
+                matches!(
+                    last.token_type,
+                    yarnspinnerlexer::NEWLINE | yarnspinnerlexer::BODY_START
+                )
+            })
+    }
+
+    /// Called with a `=` at the start of a line. If it is directly followed by a `>`, enqueues a [`LINE_GROUP_ARROW`] token
+    /// and the rest of the text following it instead. Otherwise, enqueues the `=` and handles the token following it as usual.
+    /// An escaped `=\>` is not followed directly by the `>`, so it starts a regular line.
+    fn handle_potential_line_group_arrow(
+        &mut self,
+        current: Box<antlr_rust::token::GenericToken<Cow<'input, str>>>,
+    ) {
+        let next = self.base.next_token();
+        let is_arrow = next.token_type == yarnspinnerlexer::TEXT
+            && next.start == current.stop + 1
+            && next.get_text().starts_with('>');
+        if !is_arrow {
+            self.pending_tokens.enqueue(current.clone());
+            self.last_token = Some(current);
+            self.handle_token(next);
+            return;
+        }
+
+        let mut arrow = current;
+        arrow.token_type = LINE_GROUP_ARROW;
+        arrow.channel = TOKEN_HIDDEN_CHANNEL;
+        arrow.stop = next.start;
+        arrow.text = Cow::Borrowed("=>");
+        self.pending_tokens.enqueue(arrow);
+
+        let rest = next.get_text()[1..].to_owned();
+        if !rest.is_empty() {
+            let mut text = next.clone();
+            text.start += 1;
+            text.column += 1;
+            text.text = Cow::Owned(rest);
+            self.pending_tokens.enqueue(text);
+        }
+        self.last_token = Some(next);
+    }
+
     fn handle_newline_token(
         &mut self,
         current_token: Box<antlr_rust::token::GenericToken<std::borrow::Cow<'input, str>>>,
@@ -269,10 +370,23 @@ where
         self.last_indent = current_indentation_length;
     }
 
+    /// ## Implementation notes
+    ///
+    /// If the file ends inside a node body without a final newline, the original implementation emits the dedents
+    /// without a NEWLINE before them, so the last line parses differently than it would with a trailing newline.
+    /// We insert the missing NEWLINE, so that the tokens are the same regardless of how the file ends.
     fn handle_eof_token(
         &mut self,
         current_token: Box<antlr_rust::token::GenericToken<std::borrow::Cow<'input, str>>>,
     ) {
+        let ends_with_newline = self
+            .last_token
+            .as_ref()
+            .is_some_and(|last| last.token_type == yarnspinnerlexer::NEWLINE);
+        if self.in_body && !ends_with_newline {
+            self.insert_token("", yarnspinnerlexer::NEWLINE);
+        }
+
         // We're at the end of the file. Emit as many dedents as we currently have on the stack.
         while let Some(_indent) = self.unbalanced_indents.pop() {
             // so that we don't end up printing <dedent from 8> into the stream we set the text to be empty
@@ -354,7 +468,7 @@ where
     fn diagnose_newlines_in_commands(&mut self, token: &CommonToken<'input>) {
         if token.get_text().contains('\n') {
             let line_len = token.get_text().lines().count();
-            let last_line_len = token.get_text().lines().last().unwrap().len();
+            let last_line_len = token.get_text().lines().last().unwrap().chars().count();
             self.diagnostics.borrow_mut().push(
                 Diagnostic::from_message("Newlines are not allowed in commands")
                     .with_range(
@@ -369,7 +483,8 @@ where
                     .with_context(token.get_text().to_string())
                     .with_start_line(token.get_line_as_usize() - 1)
                     .with_file_name(self.file_name.clone())
-                    .with_severity(DiagnosticSeverity::Error),
+                    .with_severity(DiagnosticSeverity::Error)
+                    .with_kind(DiagnosticKind::Syntax),
             );
         }
     }
@@ -567,6 +682,107 @@ This is the one and only line
         assert_eq!(expected, symbols);
     }
 
+    #[test]
+    fn reports_indent_depth_of_partially_typed_option_block() {
+        const PARTIAL_INPUT: &str = "title: Start
+---
+-> Option 1
+    Nice.
+    ";
+
+        let mut indent_aware_lexer = IndentAwareYarnSpinnerLexer::new(
+            InputStream::new(PARTIAL_INPUT),
+            "input.yarn".to_owned(),
+        );
+        assert_eq!(0, indent_aware_lexer.current_indent_depth());
+
+        loop {
+            let token = indent_aware_lexer.next_token();
+            assert_ne!(TOKEN_EOF, token.token_type, "Expected an INDENT before EOF");
+            if token.token_type == yarnspinnerlexer::INDENT {
+                break;
+            }
+        }
+        assert_eq!(4, indent_aware_lexer.current_indent_depth());
+
+        while indent_aware_lexer.next_token().token_type != TOKEN_EOF {}
+        assert_eq!(0, indent_aware_lexer.current_indent_depth());
+    }
+
+    #[test]
+    fn trailing_newline_does_not_change_tokens_of_complete_node() {
+        const INPUT: &str = "title: Start
+---
+-> Option 1
+    Nice.
+-> Option 2
+===";
+        assert_eq!(
+            default_channel_symbols(INPUT),
+            default_channel_symbols(&format!("{INPUT}\n"))
+        );
+    }
+
+    #[test]
+    fn trailing_newline_does_not_change_tokens_of_unterminated_node() {
+        const INPUT: &str = "title: Start
+---
+-> Option 1
+    Nice.";
+        let symbols = default_channel_symbols(INPUT);
+        assert_eq!(symbols, default_channel_symbols(&format!("{INPUT}\n")));
+        assert_eq!(["NEWLINE", "DEDENT"], symbols[symbols.len() - 2..]);
+    }
+
+    #[test]
+    fn emits_line_group_arrow_on_hidden_channel() {
+        const INPUT: &str = "title: Start
+---
+=> Hi
+=\\> Hey
+===
+";
+        let mut indent_aware_lexer =
+            IndentAwareYarnSpinnerLexer::new(InputStream::new(INPUT), "input.yarn".to_owned());
+        let mut tokens = Vec::new();
+        loop {
+            let token = indent_aware_lexer.next_token();
+            if token.token_type == TOKEN_EOF {
+                break;
+            }
+            tokens.push((token.token_type, token.channel, token.get_text().to_owned()));
+        }
+
+        let arrows: Vec<_> = tokens
+            .iter()
+            .filter(|(token_type, ..)| *token_type == LINE_GROUP_ARROW)
+            .collect();
+        assert_eq!(
+            vec![&(LINE_GROUP_ARROW, TOKEN_HIDDEN_CHANNEL, "=>".to_owned())],
+            arrows
+        );
+        assert!(tokens.contains(&(
+            yarnspinnerlexer::TEXT,
+            TOKEN_DEFAULT_CHANNEL,
+            " Hi".to_owned()
+        )));
+    }
+
+    fn default_channel_symbols(input: &str) -> Vec<&'static str> {
+        let indent_aware_lexer =
+            IndentAwareYarnSpinnerLexer::new(InputStream::new(input), "input.yarn".to_owned());
+        let mut indent_aware_token_stream = CommonTokenStream::new(indent_aware_lexer);
+
+        let mut tokens = vec![indent_aware_token_stream.iter().next().unwrap()];
+        while indent_aware_token_stream.la(1) != TOKEN_EOF {
+            tokens.push(indent_aware_token_stream.iter().next().unwrap());
+        }
+        tokens
+            .into_iter()
+            .map(|t| yarnspinnerlexer::_SYMBOLIC_NAMES[t as usize].unwrap())
+            .collect()
+    }
+
     #[test]
     fn generated_lexer_output_is_same_as_reference() {
         let option_indentation_relevant_input: &str = include_str!("significant_whitespace.yarn");
@@ -1,5 +1,6 @@
 //! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner.Compiler/StringTableManager.cs>
 
+use crate::compiler::LineIdStrategy;
 use crate::output::StringInfo;
 use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
@@ -13,37 +14,53 @@ impl StringTableManager {
         self.values().any(|x| x.is_implicit_tag)
     }
 
-    /// Inserts a new string into the string table, optionally generating a new line ID.
+    /// Inserts a new string into the string table under an explicitly given line ID, e.g. from a `#line:` tag.
     /// The `is_implicit_tag` field of the `string_info` is automatically set; its original value is ignored.
     ///
     /// ## Returns
     ///
-    /// The line ID used for insertion. This will be `line_id` if it is `Some`, otherwise it will be an autogenerated line ID.
-    pub(crate) fn insert(
+    /// The line ID used for insertion.
+    pub(crate) fn insert(&mut self, line_id: LineId, string_info: StringInfo) -> LineId {
+        let string_info = StringInfo {
+            is_implicit_tag: false,
+            ..string_info
+        };
+        self.0.insert(line_id.clone(), string_info);
+        line_id
+    }
+
+    /// Inserts a new string for a line without a `#line:` tag into the string table, generating its line ID according to `strategy`.
+    /// `index_in_node` is the 0-based position of the line among all lines of its node.
+    /// The `is_implicit_tag` field of the `string_info` is automatically set; its original value is ignored.
+    ///
+    /// ## Returns
+    ///
+    /// The generated line ID used for insertion.
+    pub(crate) fn insert_implicit(
         &mut self,
-        line_id: impl Into<Option<LineId>>,
+        strategy: LineIdStrategy,
+        index_in_node: usize,
         string_info: StringInfo,
     ) -> LineId {
-        let line_id = line_id.into();
-        let (line_id, string_info) = if let Some(line_id) = line_id {
-            let string_info = StringInfo {
-                is_implicit_tag: false,
-                ..string_info
-            };
-            (line_id, string_info)
-        } else {
-            let line_id = format!(
-                "line:{}-{}-{}",
-                string_info.file_name,
-                string_info.node_name,
-                self.len()
-            )
-            .into();
-            let string_info = StringInfo {
-                is_implicit_tag: true,
-                ..string_info
-            };
-            (line_id, string_info)
+        let prefix = format!("line:{}-{}", string_info.file_name, string_info.node_name);
+        let line_id: LineId = match strategy {
+            LineIdStrategy::Global => format!("{prefix}-{}", self.len()).into(),
+            LineIdStrategy::Sequential => format!("{prefix}-{index_in_node}").into(),
+            LineIdStrategy::ContentHash => {
+                let hashed_id = format!("{prefix}-{:08x}", fnv1a_hash(&string_info.text));
+                // Lines with the same text in the same node are numbered in source order.
+                (0..)
+                    .map(|duplicate| match duplicate {
+                        0 => LineId(hashed_id.clone()),
+                        _ => LineId(format!("{hashed_id}-{duplicate}")),
+                    })
+                    .find(|line_id| !self.contains_key(line_id))
+                    .unwrap()
+            }
+        };
+        let string_info = StringInfo {
+            is_implicit_tag: true,
+            ..string_info
         };
         self.0.insert(line_id.clone(), string_info);
         line_id
@@ -54,6 +71,13 @@ impl StringTableManager {
     }
 }
 
+/// The 32-bit FNV-1a hash, which unlike the hashers of the standard library is guaranteed to be the same on every platform and Rust version.
+fn fnv1a_hash(text: &str) -> u32 {
+    text.bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
 impl Deref for StringTableManager {
     type Target = HashMap<LineId, StringInfo>;
 
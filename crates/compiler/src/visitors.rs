@@ -1,14 +1,18 @@
 mod code_generation_visitor;
+mod command_check_visitor;
 mod constant_value_visitor;
 mod declaration_visitor;
+mod definition_visitor;
 mod hashable_interval;
+mod interpolated_variable_visitor;
 mod last_line_before_options_visitor;
 mod node_tracking_visitor;
 mod string_table_generator_visitor;
 mod type_check_visitor;
 
 pub(crate) use self::{
-    code_generation_visitor::*, declaration_visitor::*, hashable_interval::*,
+    code_generation_visitor::*, command_check_visitor::*, declaration_visitor::*,
+    definition_visitor::*, hashable_interval::*, interpolated_variable_visitor::*,
     last_line_before_options_visitor::*, node_tracking_visitor::*,
     string_table_generator_visitor::*, type_check_visitor::*,
 };
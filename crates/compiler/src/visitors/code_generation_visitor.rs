@@ -5,9 +5,13 @@ use crate::prelude::generated::yarnspinnerlexer;
 use crate::prelude::generated::yarnspinnerparser::*;
 use crate::prelude::generated::yarnspinnerparservisitor::YarnSpinnerParserVisitorCompat;
 use crate::prelude::*;
+use crate::visitors::{
+    get_hashtag_texts, get_line_group_weight, is_line_group_candidate, parse_constant_declaration,
+};
 use antlr_rust::parser_rule_context::ParserRuleContext;
 use antlr_rust::token::Token;
 use antlr_rust::tree::{ParseTree, ParseTreeVisitorCompat, Tree};
+use better_any::TidExt;
 use std::ops::Deref;
 use std::rc::Rc;
 use yarnspinner_core::prelude::OpCode;
@@ -17,6 +21,10 @@ use yarnspinner_core::types::Type;
 pub(crate) struct CodeGenerationVisitor<'a, 'input: 'a> {
     compiler_listener: &'a mut CompilerListener<'input>,
     tracking_enabled: Option<String>,
+    /// The number of line groups generated so far in the current node.
+    line_group_count: usize,
+    /// The number of line group candidates that are still going to be visited, but whose code was already generated with their group.
+    pending_line_group_candidates: usize,
     _dummy: (),
 }
 
@@ -28,6 +36,8 @@ impl<'a, 'input: 'a> CodeGenerationVisitor<'a, 'input> {
         Self {
             compiler_listener,
             tracking_enabled: tracking_enabled.into(),
+            line_group_count: Default::default(),
+            pending_line_group_candidates: Default::default(),
             _dummy: Default::default(),
         }
     }
@@ -95,6 +105,11 @@ impl<'a, 'input: 'a> YarnSpinnerParserVisitorCompat<'input> for CodeGenerationVi
         //
         // <<if true>> Mae: here's a line <<endif>>
 
+        if is_line_group_candidate(ctx, self.compiler_listener.file.tokens()) {
+            self.generate_code_for_line_group_candidate(ctx);
+            return;
+        }
+
         // Evaluate the inline expressions and push the results onto the
         // stack.
         let formatted_text = ctx.line_formatted_text().unwrap();
@@ -121,11 +136,13 @@ impl<'a, 'input: 'a> YarnSpinnerParserVisitorCompat<'input> for CodeGenerationVi
         let operator_token = ctx.op.as_ref().unwrap();
         let operator = Self::token_to_operator(operator_token.get_token_type()).unwrap();
         let r#type = self.compiler_listener.types.get(ctx).unwrap().clone();
-        let expressions = vec![
-            ctx.expression(0).unwrap() as Rc<ActualParserContext<'input>>,
-            ctx.expression(1).unwrap(),
-        ];
-        self.generate_code_for_operation(operator, operator_token.deref(), &r#type, &expressions)
+        let operands = [ctx.expression(0).unwrap(), ctx.expression(1).unwrap()];
+        self.generate_code_for_expression_operation(
+            operator,
+            operator_token.deref(),
+            &r#type,
+            &operands,
+        )
     }
 
     /// < <= > >=
@@ -133,23 +150,24 @@ impl<'a, 'input: 'a> YarnSpinnerParserVisitorCompat<'input> for CodeGenerationVi
         let operator_token = ctx.op.as_ref().unwrap();
         let operator = Self::token_to_operator(operator_token.get_token_type()).unwrap();
         let r#type = self.compiler_listener.types.get(ctx).unwrap().clone();
-        let expressions = vec![
-            ctx.expression(0).unwrap() as Rc<ActualParserContext<'input>>,
-            ctx.expression(1).unwrap(),
-        ];
-        self.generate_code_for_operation(operator, operator_token.deref(), &r#type, &expressions)
+        let operands = [ctx.expression(0).unwrap(), ctx.expression(1).unwrap()];
+        self.generate_code_for_expression_operation(
+            operator,
+            operator_token.deref(),
+            &r#type,
+            &operands,
+        )
     }
 
     /// -expression
     fn visit_expNegative(&mut self, ctx: &ExpNegativeContext<'input>) -> Self::Return {
         let operator_token = ctx.op.as_ref().unwrap();
         let r#type = self.compiler_listener.types.get(ctx).unwrap().clone();
-        let expressions = vec![ctx.expression().unwrap() as Rc<ActualParserContext<'input>>];
-        self.generate_code_for_operation(
+        self.generate_code_for_expression_operation(
             Operator::UnarySubtract,
             operator_token.deref(),
             &r#type,
-            &expressions,
+            &[ctx.expression().unwrap()],
         )
     }
 
@@ -158,11 +176,13 @@ impl<'a, 'input: 'a> YarnSpinnerParserVisitorCompat<'input> for CodeGenerationVi
         let operator_token = ctx.op.as_ref().unwrap();
         let operator = Self::token_to_operator(operator_token.get_token_type()).unwrap();
         let r#type = self.compiler_listener.types.get(ctx).unwrap().clone();
-        let expressions = vec![
-            ctx.expression(0).unwrap() as Rc<ActualParserContext<'input>>,
-            ctx.expression(1).unwrap(),
-        ];
-        self.generate_code_for_operation(operator, operator_token.deref(), &r#type, &expressions)
+        let operands = [ctx.expression(0).unwrap(), ctx.expression(1).unwrap()];
+        self.generate_code_for_expression_operation(
+            operator,
+            operator_token.deref(),
+            &r#type,
+            &operands,
+        )
     }
 
     /// + -
@@ -170,23 +190,24 @@ impl<'a, 'input: 'a> YarnSpinnerParserVisitorCompat<'input> for CodeGenerationVi
         let operator_token = ctx.op.as_ref().unwrap();
         let operator = Self::token_to_operator(operator_token.get_token_type()).unwrap();
         let r#type = self.compiler_listener.types.get(ctx).unwrap().clone();
-        let expressions = vec![
-            ctx.expression(0).unwrap() as Rc<ActualParserContext<'input>>,
-            ctx.expression(1).unwrap(),
-        ];
-        self.generate_code_for_operation(operator, operator_token.deref(), &r#type, &expressions)
+        let operands = [ctx.expression(0).unwrap(), ctx.expression(1).unwrap()];
+        self.generate_code_for_expression_operation(
+            operator,
+            operator_token.deref(),
+            &r#type,
+            &operands,
+        )
     }
 
     /// [sic] (not NOT !)expression
     fn visit_expNot(&mut self, ctx: &ExpNotContext<'input>) -> Self::Return {
         let operator_token = ctx.op.as_ref().unwrap();
         let r#type = self.compiler_listener.types.get(ctx).unwrap().clone();
-        let expressions = vec![ctx.expression().unwrap() as Rc<ActualParserContext<'input>>];
-        self.generate_code_for_operation(
+        self.generate_code_for_expression_operation(
             Operator::Not,
             operator_token.deref(),
             &r#type,
-            &expressions,
+            &[ctx.expression().unwrap()],
         )
     }
 
@@ -200,11 +221,13 @@ impl<'a, 'input: 'a> YarnSpinnerParserVisitorCompat<'input> for CodeGenerationVi
         let operator_token = ctx.op.as_ref().unwrap();
         let operator = Self::token_to_operator(operator_token.get_token_type()).unwrap();
         let r#type = self.compiler_listener.types.get(ctx).unwrap().clone();
-        let expressions = vec![
-            ctx.expression(0).unwrap() as Rc<ActualParserContext<'input>>,
-            ctx.expression(1).unwrap(),
-        ];
-        self.generate_code_for_operation(operator, operator_token.deref(), &r#type, &expressions)
+        let operands = [ctx.expression(0).unwrap(), ctx.expression(1).unwrap()];
+        self.generate_code_for_expression_operation(
+            operator,
+            operator_token.deref(),
+            &r#type,
+            &operands,
+        )
     }
 
     fn visit_valueNumber(&mut self, ctx: &ValueNumberContext<'input>) -> Self::Return {
@@ -430,6 +453,11 @@ impl<'a, 'input: 'a> YarnSpinnerParserVisitorCompat<'input> for CodeGenerationVi
             },
         );
 
+        // Constants are registered by the `DeclarationVisitor` and have no runtime effect
+        if expression_count == 0 && parse_constant_declaration(&composed_string).is_some() {
+            return;
+        }
+
         // [sic] TODO: look into replacing this as it seems a bit odd
         match composed_string.as_str() {
             "stop" => {
@@ -593,6 +621,158 @@ impl<'a, 'input: 'a> YarnSpinnerParserVisitorCompat<'input> for CodeGenerationVi
 }
 
 impl<'a, 'input: 'a> CodeGenerationVisitor<'a, 'input> {
+    /// Emits the code for the line group starting with this candidate, which runs one of its candidates.
+    /// The code of the following candidates of the group is generated along with the first one, so visiting them does nothing.
+    fn generate_code_for_line_group_candidate(&mut self, ctx: &Line_statementContext<'input>) {
+        if self.pending_line_group_candidates > 0 {
+            self.pending_line_group_candidates -= 1;
+            return;
+        }
+        let candidates = self.get_line_group_candidates(ctx);
+        self.pending_line_group_candidates = candidates.len() - 1;
+
+        let token = ctx.start();
+        let node_name = self
+            .compiler_listener
+            .current_node
+            .as_ref()
+            .unwrap()
+            .name
+            .clone();
+        let variable =
+            Library::generate_unique_line_group_variable(&node_name, self.line_group_count);
+        self.line_group_count += 1;
+
+        // Pick a candidate and remember which one it was
+        for candidate in &candidates {
+            let hashtag_texts = get_hashtag_texts(&candidate.hashtag_all());
+            let weight = get_line_group_weight(&hashtag_texts)
+                .and_then(Result::ok)
+                .unwrap_or(1.0);
+            let once_variable = if hashtag_texts.iter().any(|tag| tag == "once") {
+                Library::generate_unique_once_variable_for_line_group_candidate(&get_line_id(
+                    candidate,
+                ))
+            } else {
+                String::new()
+            };
+            self.compiler_listener.emit(
+                Emit::from_op_code(OpCode::PushFloat)
+                    .with_token(candidate.start().deref())
+                    .with_operand(weight),
+            );
+            self.compiler_listener.emit(
+                Emit::from_op_code(OpCode::PushString)
+                    .with_token(candidate.start().deref())
+                    .with_operand(once_variable),
+            );
+        }
+        self.compiler_listener.emit(
+            Emit::from_op_code(OpCode::PushFloat)
+                .with_token(token.deref())
+                .with_operand(candidates.len() * 2),
+        );
+        self.compiler_listener.emit(
+            Emit::from_op_code(OpCode::CallFunc)
+                .with_token(token.deref())
+                .with_operand(Library::LINE_GROUP_SELECTION_FUNCTION_NAME.to_owned()),
+        );
+        self.compiler_listener.emit(
+            Emit::from_op_code(OpCode::StoreVariable)
+                .with_token(token.deref())
+                .with_operand(variable.clone()),
+        );
+        self.compiler_listener
+            .emit(Emit::from_op_code(OpCode::Pop).with_token(token.deref()));
+
+        let end_of_group_label = self.compiler_listener.register_label("line_group_end");
+        for (candidate_index, candidate) in candidates.iter().enumerate() {
+            let token = candidate.start();
+
+            // Only run the line if it is the picked candidate
+            let skip_label = self.compiler_listener.register_label("skipcandidate");
+            self.compiler_listener.emit(
+                Emit::from_op_code(OpCode::PushVariable)
+                    .with_token(token.deref())
+                    .with_operand(variable.clone()),
+            );
+            self.compiler_listener.emit(
+                Emit::from_op_code(OpCode::PushFloat)
+                    .with_token(token.deref())
+                    .with_operand(candidate_index),
+            );
+            self.compiler_listener.emit(
+                Emit::from_op_code(OpCode::PushFloat)
+                    .with_token(token.deref())
+                    .with_operand(2.),
+            );
+            self.compiler_listener.emit(
+                Emit::from_op_code(OpCode::CallFunc)
+                    .with_token(token.deref())
+                    .with_operand(
+                        Type::Number.get_canonical_name_for_method(&Operator::EqualTo.to_string()),
+                    ),
+            );
+            self.compiler_listener.emit(
+                Emit::from_op_code(OpCode::JumpIfFalse)
+                    .with_token(token.deref())
+                    .with_operand(skip_label.clone()),
+            );
+            self.compiler_listener
+                .emit(Emit::from_op_code(OpCode::Pop).with_token(token.deref()));
+
+            let formatted_text = candidate.line_formatted_text().unwrap();
+            let expression_count =
+                self.generate_code_for_expressions_in_formatted_text(formatted_text.get_children());
+            self.compiler_listener.emit(
+                Emit::from_op_code(OpCode::RunLine)
+                    .with_token(token.deref())
+                    .with_operand(get_line_id(candidate))
+                    .with_operand(expression_count),
+            );
+            self.compiler_listener.emit(
+                Emit::from_op_code(OpCode::JumpTo)
+                    .with_token(token.deref())
+                    .with_operand(end_of_group_label.clone()),
+            );
+
+            let current_node = self.compiler_listener.current_node.as_mut().unwrap();
+            current_node
+                .labels
+                .insert(skip_label, current_node.instructions.len() as i32);
+            self.compiler_listener
+                .emit(Emit::from_op_code(OpCode::Pop).with_token(token.deref()));
+        }
+
+        let current_node = self.compiler_listener.current_node.as_mut().unwrap();
+        current_node
+            .labels
+            .insert(end_of_group_label, current_node.instructions.len() as i32);
+    }
+
+    /// Returns the candidates of the line group starting with the given candidate,
+    /// i.e. it and the line group candidates directly following it in the same block of statements.
+    fn get_line_group_candidates(
+        &self,
+        ctx: &Line_statementContext<'input>,
+    ) -> Vec<Rc<Line_statementContextAll<'input>>> {
+        let tokens = self.compiler_listener.file.tokens();
+        let statement = ctx.get_parent().unwrap();
+        let interval = statement.get_source_interval();
+        statement
+            .get_parent()
+            .unwrap()
+            .get_children()
+            .filter_map(|child| child.downcast_rc::<StatementContextAll>().ok())
+            .skip_while(|statement| statement.get_source_interval() != interval)
+            .map_while(|statement| {
+                statement
+                    .line_statement()
+                    .filter(|line| is_line_group_candidate(line, tokens))
+            })
+            .collect()
+    }
+
     fn generate_code_for_expressions_in_formatted_text(
         &mut self,
         nodes: impl Iterator<Item = Rc<ActualParserContext<'input>>>,
@@ -614,6 +794,116 @@ impl<'a, 'input: 'a> CodeGenerationVisitor<'a, 'input> {
             .count()
     }
 
+    /// Like [`CodeGenerationVisitor::generate_code_for_operation`], but if all operands are made only of literals, e.g. in `2 + 3`,
+    /// the operation is evaluated at compile time and only its result is pushed.
+    fn generate_code_for_expression_operation(
+        &mut self,
+        op: Operator,
+        operator_token: &impl Token,
+        r#type: &Type,
+        operands: &[Rc<ExpressionContextAll<'input>>],
+    ) {
+        let Some(value) = self.fold_operation(op, r#type, operands) else {
+            let operands: Vec<_> = operands
+                .iter()
+                .map(|operand| operand.clone() as Rc<ActualParserContext<'input>>)
+                .collect();
+            self.generate_code_for_operation(op, operator_token, r#type, &operands);
+            return;
+        };
+        let emit = match value {
+            YarnValue::Number(number) => Emit::from_op_code(OpCode::PushFloat).with_operand(number),
+            YarnValue::String(string) => {
+                Emit::from_op_code(OpCode::PushString).with_operand(string)
+            }
+            YarnValue::Boolean(boolean) => {
+                Emit::from_op_code(OpCode::PushBool).with_operand(boolean)
+            }
+        };
+        self.compiler_listener.emit(emit.with_token(operator_token));
+    }
+
+    /// Evaluates the operation with the same functions the VM would call, if all operands are constant.
+    ///
+    /// Operations whose result may differ at runtime are not folded:
+    /// numbers are compared with the float epsilon configured in the `Dialogue`,
+    /// and results that are not finite, e.g. from dividing by zero, are left to the VM.
+    fn fold_operation(
+        &self,
+        op: Operator,
+        r#type: &Type,
+        operands: &[Rc<ExpressionContextAll<'input>>],
+    ) -> Option<YarnValue> {
+        if *r#type == Type::Number && matches!(op, Operator::EqualTo | Operator::NotEqualTo) {
+            return None;
+        }
+        let values = operands
+            .iter()
+            .map(|operand| self.constant_value_of(operand))
+            .collect::<Option<Vec<_>>>()?;
+        let methods = r#type.methods();
+        let value = methods.get(&op.to_string())?.call(values);
+        match value {
+            YarnValue::Number(number) if !number.is_finite() => None,
+            value => Some(value),
+        }
+    }
+
+    /// Returns the value of an expression made only of literals and operations on them, or [`None`] if it depends on anything else.
+    fn constant_value_of(&self, expression: &ExpressionContextAll<'input>) -> Option<YarnValue> {
+        let binary_operands = |left: Option<_>, right: Option<_>| Some([left?, right?]);
+        match expression {
+            ExpressionContextAll::ExpParensContext(ctx) => {
+                self.constant_value_of(ctx.expression()?.as_ref())
+            }
+            ExpressionContextAll::ExpValueContext(ctx) => match ctx.value()?.as_ref() {
+                ValueContextAll::ValueNumberContext(ctx) => {
+                    ctx.NUMBER()?.get_text().parse::<f32>().ok().map(Into::into)
+                }
+                ValueContextAll::ValueTrueContext(_) => Some(true.into()),
+                ValueContextAll::ValueFalseContext(_) => Some(false.into()),
+                ValueContextAll::ValueStringContext(ctx) => {
+                    Some(ctx.STRING()?.get_text().trim_matches('"').to_owned().into())
+                }
+                _ => None,
+            },
+            ExpressionContextAll::ExpNegativeContext(ctx) => {
+                let r#type = self.compiler_listener.types.get(ctx)?;
+                self.fold_operation(Operator::UnarySubtract, r#type, &[ctx.expression()?])
+            }
+            ExpressionContextAll::ExpNotContext(ctx) => {
+                let r#type = self.compiler_listener.types.get(ctx)?;
+                self.fold_operation(Operator::Not, r#type, &[ctx.expression()?])
+            }
+            ExpressionContextAll::ExpMultDivModContext(ctx) => self.fold_operation(
+                Self::token_to_operator(ctx.op.as_ref()?.get_token_type())?,
+                self.compiler_listener.types.get(ctx)?,
+                &binary_operands(ctx.expression(0), ctx.expression(1))?,
+            ),
+            ExpressionContextAll::ExpComparisonContext(ctx) => self.fold_operation(
+                Self::token_to_operator(ctx.op.as_ref()?.get_token_type())?,
+                self.compiler_listener.types.get(ctx)?,
+                &binary_operands(ctx.expression(0), ctx.expression(1))?,
+            ),
+            ExpressionContextAll::ExpAndOrXorContext(ctx) => self.fold_operation(
+                Self::token_to_operator(ctx.op.as_ref()?.get_token_type())?,
+                self.compiler_listener.types.get(ctx)?,
+                &binary_operands(ctx.expression(0), ctx.expression(1))?,
+            ),
+            ExpressionContextAll::ExpAddSubContext(ctx) => self.fold_operation(
+                Self::token_to_operator(ctx.op.as_ref()?.get_token_type())?,
+                self.compiler_listener.types.get(ctx)?,
+                &binary_operands(ctx.expression(0), ctx.expression(1))?,
+            ),
+            ExpressionContextAll::ExpEqualityContext(ctx) => self.fold_operation(
+                Self::token_to_operator(ctx.op.as_ref()?.get_token_type())?,
+                self.compiler_listener.types.get(ctx)?,
+                &binary_operands(ctx.expression(0), ctx.expression(1))?,
+            ),
+            ExpressionContextAll::Error(_) => None,
+        }
+    }
+
     /// Emits code that calls a method appropriate for the operator
     fn generate_code_for_operation(
         &mut self,
@@ -695,3 +985,9 @@ impl<'a, 'input: 'a> CodeGenerationVisitor<'a, 'input> {
         }
     }
 }
+
+fn get_line_id(ctx: &Line_statementContext) -> String {
+    let line_id_tag = get_line_id_tag(&ctx.hashtag_all())
+        .expect("Internal error: line should have an implicit or explicit line ID tag, but none was found. This is a bug. Please report it at https://github.com/YarnSpinnerTool/YarnSpinner-Rust/issues/new");
+    line_id_tag.text.as_ref().unwrap().get_text().to_owned()
+}
@@ -0,0 +1,128 @@
+//! Checks calls to the commands declared by [`ExternalDefinitions`] for their number of arguments and the types of their literal arguments.
+
+use crate::prelude::generated::yarnspinnerparser::*;
+use crate::prelude::generated::yarnspinnerparservisitor::YarnSpinnerParserVisitorCompat;
+use crate::prelude::*;
+use antlr_rust::tree::{ParseTreeVisitorCompat, Tree};
+use std::collections::HashMap;
+use yarnspinner_core::types::{FunctionType, TypeFormat};
+
+/// Stands in for the value of a `{...}` expression in the text of a command, which is only known at runtime.
+const EXPRESSION_PLACEHOLDER: char = '\u{FFFC}';
+
+pub(crate) struct CommandCheckVisitor<'input> {
+    pub(crate) diagnostics: Vec<Diagnostic>,
+    commands: HashMap<String, FunctionType>,
+    file: FileParseResult<'input>,
+    _dummy: (),
+}
+
+impl<'input> CommandCheckVisitor<'input> {
+    pub(crate) fn new(file: FileParseResult<'input>, command_declarations: &[Declaration]) -> Self {
+        let commands = command_declarations
+            .iter()
+            .filter_map(|declaration| match &declaration.r#type {
+                Type::Function(function_type) => {
+                    Some((declaration.name.clone(), function_type.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        Self {
+            diagnostics: Default::default(),
+            commands,
+            file,
+            _dummy: (),
+        }
+    }
+
+    fn push_diagnostic(&mut self, message: String, ctx: &Command_statementContext<'input>) {
+        let diagnostic = Diagnostic::from_message(message)
+            .with_file_name(&self.file.name)
+            .with_parser_context(ctx, self.file.tokens())
+            .with_kind(DiagnosticKind::Type);
+        self.diagnostics.push(diagnostic);
+    }
+}
+
+impl<'input> ParseTreeVisitorCompat<'input> for CommandCheckVisitor<'input> {
+    type Node = YarnSpinnerParserContextType;
+    type Return = ();
+
+    fn temp_result(&mut self) -> &mut Self::Return {
+        &mut self._dummy
+    }
+}
+
+impl<'input> YarnSpinnerParserVisitorCompat<'input> for CommandCheckVisitor<'input> {
+    fn visit_command_statement(&mut self, ctx: &Command_statementContext<'input>) {
+        let Some(formatted_text) = ctx.command_formatted_text() else {
+            return;
+        };
+        let mut has_expressions = false;
+        let command_text: String = formatted_text
+            .get_children()
+            .map(|node| {
+                if node.get_child_count() == 0 {
+                    node.get_text()
+                } else {
+                    has_expressions = true;
+                    EXPRESSION_PLACEHOLDER.to_string()
+                }
+            })
+            .collect();
+
+        let mut components = split_command_text(&command_text).into_iter();
+        let Some(name) = components.next() else {
+            return;
+        };
+        let Some(command_type) = self.commands.get(&name).cloned() else {
+            return;
+        };
+        let arguments: Vec<_> = components.collect();
+        let expected_parameter_types = command_type.parameters;
+
+        // The value of an expression may be split into any number of arguments at runtime
+        if !has_expressions && arguments.len() != expected_parameter_types.len() {
+            let parameters = if expected_parameter_types.len() == 1 {
+                "parameter"
+            } else {
+                "parameters"
+            };
+            let message = format!(
+                "Command \"{}\" expects {} {}, but received {}",
+                name,
+                expected_parameter_types.len(),
+                parameters,
+                arguments.len()
+            );
+            self.push_diagnostic(message, ctx);
+            return;
+        }
+
+        // Arguments after an expression cannot be matched to their parameters
+        let literal_arguments = arguments
+            .iter()
+            .zip(expected_parameter_types.iter())
+            .enumerate()
+            .take_while(|(_, (argument, _))| !argument.contains(EXPRESSION_PLACEHOLDER));
+        for (i, (argument, expected_type)) in literal_arguments {
+            let is_valid = match expected_type {
+                Some(Type::Number) => argument.parse::<f32>().is_ok(),
+                Some(Type::Boolean) => argument.parse::<bool>().is_ok(),
+                _ => true,
+            };
+            if !is_valid {
+                let message = format!(
+                    "{} parameter {} expects a {}, not \"{}\"",
+                    name,
+                    i + 1,
+                    expected_type.format(),
+                    argument
+                );
+                self.push_diagnostic(message, ctx);
+                return;
+            }
+        }
+    }
+}
@@ -56,7 +56,8 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for ConstantValueVisitor<'in
             self.diagnostics.push(
                 Diagnostic::from_message(message)
                     .with_file_name(&self.file.name)
-                    .with_parser_context(ctx, self.file.tokens()),
+                    .with_parser_context(ctx, self.file.tokens())
+                    .with_kind(DiagnosticKind::Syntax),
             );
             // This default value seems very "JavaScript-y" with the pseudo-sensible default value on errors.
             // But this is not so! We just pushed an error diagnostic, so there will be no program emitted from this compilation attempt.
@@ -81,7 +82,8 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for ConstantValueVisitor<'in
         self.diagnostics.push(
             Diagnostic::from_message(message)
                 .with_file_name(&self.file.name)
-                .with_parser_context(ctx, self.file.tokens()),
+                .with_parser_context(ctx, self.file.tokens())
+                .with_kind(DiagnosticKind::Type),
         );
         ConstantValue::non_panicking_default()
     }
@@ -96,7 +98,8 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for ConstantValueVisitor<'in
         self.diagnostics.push(
             Diagnostic::from_message(message)
                 .with_file_name(&self.file.name)
-                .with_parser_context(ctx, self.file.tokens()),
+                .with_parser_context(ctx, self.file.tokens())
+                .with_kind(DiagnosticKind::Type),
         );
         ConstantValue::non_panicking_default()
     }
@@ -108,7 +111,8 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for ConstantValueVisitor<'in
         self.diagnostics.push(
             Diagnostic::from_message(message)
                 .with_file_name(&self.file.name)
-                .with_parser_context(ctx, self.file.tokens()),
+                .with_parser_context(ctx, self.file.tokens())
+                .with_kind(DiagnosticKind::Type),
         );
         ConstantValue::non_panicking_default()
     }
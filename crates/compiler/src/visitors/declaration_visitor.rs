@@ -4,7 +4,9 @@ use crate::prelude::generated::yarnspinnerparser::*;
 use crate::prelude::generated::yarnspinnerparservisitor::YarnSpinnerParserVisitorCompat;
 use crate::prelude::*;
 use crate::visitors::constant_value_visitor::ConstantValueVisitor;
+use antlr_rust::rule_context::CustomRuleContext;
 use antlr_rust::token::Token;
+use antlr_rust::token_factory::TokenFactory;
 use antlr_rust::tree::{ParseTree, ParseTreeVisitorCompat};
 use regex::Regex;
 use yarnspinner_core::prelude::*;
@@ -102,7 +104,8 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for DeclarationVisitor<'inpu
                 self.diagnostics.push(
                     Diagnostic::from_message(message)
                         .with_file_name(self.file.name.clone())
-                        .with_parser_context(header.as_ref(), self.file.tokens()),
+                        .with_parser_context(header.as_ref(), self.file.tokens())
+                        .with_kind(DiagnosticKind::Syntax),
                 );
             }
         }
@@ -121,23 +124,6 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for DeclarationVisitor<'inpu
             .declarations()
             .into_iter()
             .find(|d| !d.is_implicit && d.name == variable_name);
-        if let Some(existing_explicit_declaration) = existing_explicit_declaration {
-            // Then this is an error, because you can't have two explicit declarations for the same variable.
-            let line = existing_explicit_declaration
-                .source_file_line()
-                .map(|l| format!(", line: {l}"))
-                .unwrap_or_default();
-            let msg = format!(
-                "{} has already been declared in {}{line}",
-                existing_explicit_declaration.name, existing_explicit_declaration.source_file_name,
-            );
-            self.diagnostics.push(
-                Diagnostic::from_message(msg)
-                    .with_file_name(&self.file.name)
-                    .with_parser_context(ctx, self.file.tokens()),
-            );
-            return;
-        }
 
         // Figure out the value and its type
         let mut constant_value_visitor =
@@ -165,7 +151,8 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for DeclarationVisitor<'inpu
                         self.diagnostics.push(
                             Diagnostic::from_message(msg)
                                 .with_file_name(&self.file.name)
-                                .with_parser_context(ctx, self.file.tokens()),
+                                .with_parser_context(ctx, self.file.tokens())
+                                .with_kind(DiagnosticKind::Type),
                         );
                         return;
                     }
@@ -186,7 +173,8 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for DeclarationVisitor<'inpu
                     self.diagnostics.push(
                         Diagnostic::from_message(msg)
                             .with_file_name(&self.file.name)
-                            .with_parser_context(ctx, self.file.tokens()),
+                            .with_parser_context(ctx, self.file.tokens())
+                            .with_kind(DiagnosticKind::Type),
                     );
                     return;
                 }
@@ -195,6 +183,25 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for DeclarationVisitor<'inpu
         // We're done creating the declaration!
         let description = get_document_comments(self.file.tokens(), ctx);
         let description_as_option = (!description.is_empty()).then_some(description);
+        if let Some(existing_explicit_declaration) = existing_explicit_declaration {
+            // You can't have two explicit declarations for the same variable.
+            // ## Implementation note
+            // The original implementation always reports this as an error. We only do so if the declarations conflict,
+            // as identical declarations, e.g. the same one copied into multiple files, are harmless.
+            // A value that could not be evaluated is never identical.
+            let is_identical = value.as_ref().is_some_and(|value| {
+                existing_explicit_declaration.r#type == value.r#type
+                    && existing_explicit_declaration.default_value.as_ref()
+                        == Some(&value.raw_value)
+            });
+            self.diagnostics.push(redeclaration_diagnostic(
+                &existing_explicit_declaration,
+                is_identical,
+                ctx,
+                &self.file,
+            ));
+            return;
+        }
         if let Some(value) = value.as_ref() {
             let declaration = Declaration::new(variable_name, value.r#type.clone())
                 .with_default_value(value.raw_value.clone())
@@ -205,6 +212,159 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for DeclarationVisitor<'inpu
             self.new_declarations.push(declaration);
         }
     }
+
+    /// Constants are declared with `<<const $name = value>>`. Since the grammar has no `const` keyword, this is parsed as a command,
+    /// which is recognized here and skipped by the [`CodeGenerationVisitor`].
+    fn visit_command_statement(&mut self, ctx: &Command_statementContext<'input>) -> Self::Return {
+        let Some(formatted_text) = ctx.command_formatted_text() else {
+            return;
+        };
+        let command_text = formatted_text.get_text();
+        let Some((variable_name, value_text)) = parse_constant_declaration(&command_text) else {
+            return;
+        };
+        let Some(value) = parse_constant_value(value_text) else {
+            let message = format!(
+                "The value of constant {variable_name} must be a number, string or boolean literal, but is {value_text}"
+            );
+            self.diagnostics.push(
+                Diagnostic::from_message(message)
+                    .with_file_name(&self.file.name)
+                    .with_parser_context(ctx, self.file.tokens())
+                    .with_kind(DiagnosticKind::Type),
+            );
+            return;
+        };
+        let value_type = value.r#type();
+
+        let existing_explicit_declaration = self
+            .declarations()
+            .into_iter()
+            .find(|d| !d.is_implicit && d.name == variable_name);
+        if let Some(existing_explicit_declaration) = existing_explicit_declaration {
+            let is_identical = existing_explicit_declaration.is_constant
+                && existing_explicit_declaration.r#type == value_type
+                && existing_explicit_declaration.default_value.as_ref() == Some(&value);
+            self.diagnostics.push(redeclaration_diagnostic(
+                &existing_explicit_declaration,
+                is_identical,
+                ctx,
+                &self.file,
+            ));
+            return;
+        }
+
+        // The range refers to the variable only, like for `<<declare>>`
+        let text_start = formatted_text.range().start;
+        let name_offset = command_text[..command_text.find('$').unwrap()]
+            .chars()
+            .count();
+        let name_start = Position {
+            line: text_start.line,
+            character: text_start.character + name_offset,
+        };
+        let name_end = Position {
+            character: name_start.character + variable_name.chars().count(),
+            ..name_start
+        };
+        let description = get_document_comments(self.file.tokens(), ctx);
+        let description_as_option = (!description.is_empty()).then_some(description);
+        let declaration = Declaration::new(variable_name, value_type)
+            .with_default_value(value)
+            .with_description_optional(description_as_option)
+            .with_source_file_name(self.file.name.clone())
+            .with_source_node_name_optional(self.current_node_name.clone())
+            .with_constant()
+            .with_range(name_start..name_end);
+        self.new_declarations.push(declaration);
+    }
+}
+
+/// Splits the text of a `<<const $name = value>>` command into the name of the variable, including the `$`, and the text of the value.
+/// Returns [`None`] if the command is not a constant declaration.
+pub(crate) fn parse_constant_declaration(command_text: &str) -> Option<(&str, &str)> {
+    let declaration = command_text.trim().strip_prefix("const")?;
+    if !declaration.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let (variable_name, value_text) = declaration.split_once('=')?;
+    let variable_name = variable_name.trim();
+    let is_variable = variable_name.len() > 1
+        && variable_name.starts_with('$')
+        && !variable_name.contains(char::is_whitespace);
+    is_variable.then_some((variable_name, value_text.trim()))
+}
+
+fn parse_constant_value(value_text: &str) -> Option<YarnValue> {
+    match value_text {
+        "true" => Some(true.into()),
+        "false" => Some(false.into()),
+        _ => {
+            if let Some(string) = value_text
+                .strip_prefix('"')
+                .and_then(|text| text.strip_suffix('"'))
+            {
+                let unescaped = string.replace("\\\"", "\"").replace("\\\\", "\\");
+                return Some(unescaped.into());
+            }
+            // Infinity and NaN cannot be written as Yarn literals, even though Rust parses them
+            value_text
+                .parse::<f32>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .map(Into::into)
+        }
+    }
+}
+
+fn redeclaration_diagnostic<'input, T>(
+    existing_declaration: &Declaration,
+    is_identical: bool,
+    ctx: &T,
+    file: &FileParseResult<'input>,
+) -> Diagnostic
+where
+    T: ParserRuleContextExt<'input>,
+    <<<<T as CustomRuleContext<'input>>::TF as TokenFactory<'input>>::Inner as Token>::Data as ToOwned>::Owned:
+        Into<String>,
+{
+    let line = existing_declaration
+        .source_file_line()
+        .map(|l| format!(", line: {l}"))
+        .unwrap_or_default();
+    let (message, severity) = if is_identical {
+        (
+            format!(
+                "{} has already been declared identically in {}{line}",
+                existing_declaration.name, existing_declaration.source_file_name,
+            ),
+            DiagnosticSeverity::Warning,
+        )
+    } else {
+        (
+            format!(
+                "{} has already been declared in {}{line} with a different type or default value",
+                existing_declaration.name, existing_declaration.source_file_name,
+            ),
+            DiagnosticSeverity::Error,
+        )
+    };
+    let file_name = match &existing_declaration.source_file_name {
+        DeclarationSource::File(file_name) => Some(file_name.clone()),
+        DeclarationSource::External => None,
+    };
+    Diagnostic::from_message(message)
+        .with_file_name(&file.name)
+        .with_parser_context(ctx, file.tokens())
+        .with_severity(severity)
+        .with_related_information(RelatedInformation {
+            file_name,
+            range: existing_declaration.range.clone(),
+            message: "Originally declared here".to_owned(),
+        })
+        .with_kind(DiagnosticKind::DuplicateDeclaration {
+            variable: existing_declaration.name.clone(),
+        })
 }
 
 fn keyword_to_type(keyword: &str) -> Option<Type> {
@@ -238,6 +398,9 @@ mod tests {
             library: Default::default(),
             compilation_type: CompilationType::FullCompilation,
             variable_declarations: vec![],
+            line_id_strategy: Default::default(),
+            strip_line_text: false,
+            command_declarations: vec![],
         }
         .compile()
         .unwrap();
@@ -328,6 +491,9 @@ mod tests {
             library: Default::default(),
             compilation_type: CompilationType::FullCompilation,
             variable_declarations: vec![],
+            line_id_strategy: Default::default(),
+            strip_line_text: false,
+            command_declarations: vec![],
         }
         .compile();
 
@@ -347,6 +513,7 @@ mod tests {
                         character: 31,
                     }
                 )
+                .with_kind(DiagnosticKind::Type)
         );
         assert_eq!(
             diagnostics[1],
@@ -362,6 +529,9 @@ mod tests {
                         character: 14,
                     }
                 )
+                .with_kind(DiagnosticKind::UndeclaredVariable {
+                    variable: "$foo".to_owned()
+                })
         );
     }
 }
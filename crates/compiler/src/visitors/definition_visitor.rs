@@ -0,0 +1,106 @@
+//! Collects the places where nodes and variables are defined and where they are referenced.
+
+use crate::prelude::generated::yarnspinnerlexer;
+use crate::prelude::generated::yarnspinnerparser::*;
+use crate::prelude::generated::yarnspinnerparservisitor::YarnSpinnerParserVisitorCompat;
+use crate::prelude::*;
+use antlr_rust::parser_rule_context::ParserRuleContext;
+use antlr_rust::token::Token;
+use antlr_rust::tree::{ParseTree, ParseTreeVisitorCompat};
+use std::ops::Deref;
+
+#[derive(Default)]
+pub(crate) struct DefinitionVisitor {
+    /// The `title` headers of all nodes, in source order.
+    pub(crate) node_definitions: Vec<(String, Position)>,
+    /// The node names used by `<<jump>>` statements and by `visited` and `visited_count` calls with a string literal.
+    pub(crate) node_references: Vec<(String, Position)>,
+    /// The targets of `<<set>>` statements, in source order.
+    pub(crate) variable_assignments: Vec<(String, Position)>,
+    /// Every occurrence of a variable, including the ones in declarations and assignments.
+    pub(crate) variable_references: Vec<(String, Position)>,
+    _dummy: (),
+}
+
+impl DefinitionVisitor {
+    pub(crate) fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl ParseTreeVisitorCompat<'_> for DefinitionVisitor {
+    type Node = YarnSpinnerParserContextType;
+    type Return = ();
+
+    fn temp_result(&mut self) -> &mut Self::Return {
+        &mut self._dummy
+    }
+}
+
+impl<'input> YarnSpinnerParserVisitorCompat<'input> for DefinitionVisitor {
+    fn visit_header(&mut self, ctx: &HeaderContext<'input>) {
+        let Some(key) = ctx.header_key.as_ref() else {
+            return;
+        };
+        if key.get_text() != "title" {
+            return;
+        }
+        if let Some(value) = ctx.header_value.as_ref() {
+            self.node_definitions
+                .push((value.get_text().to_owned(), token_position(key.deref())));
+        }
+    }
+
+    fn visit_jumpToNodeName(&mut self, ctx: &JumpToNodeNameContext<'input>) {
+        if let Some(destination) = ctx.destination.as_ref() {
+            self.node_references.push((
+                destination.get_text().to_owned(),
+                token_position(destination.deref()),
+            ));
+        }
+    }
+
+    fn visit_function_call(&mut self, ctx: &Function_callContext<'input>) {
+        let function_name = ctx
+            .get_token(yarnspinnerlexer::FUNC_ID, 0)
+            .map(|token| token.get_text())
+            .unwrap_or_default();
+        if ["visited", "visited_count"].contains(&function_name.as_str()) {
+            if let Some(expression) = ctx.expression(0) {
+                let start = expression.start();
+                let is_string_literal = start.get_token_type() == yarnspinnerlexer::STRING
+                    && start.get_token_index() == expression.stop().get_token_index();
+                if is_string_literal {
+                    let node_name = start.get_text().trim_matches('"').to_owned();
+                    self.node_references
+                        .push((node_name, token_position(start.deref())));
+                }
+            }
+        }
+        ParseTreeVisitorCompat::visit_children(self, ctx);
+    }
+
+    fn visit_set_statement(&mut self, ctx: &Set_statementContext<'input>) {
+        if let Some(variable) = ctx.variable() {
+            self.variable_assignments.push((
+                variable.get_text(),
+                token_position(variable.start().deref()),
+            ));
+        }
+        ParseTreeVisitorCompat::visit_children(self, ctx);
+    }
+
+    fn visit_variable(&mut self, ctx: &VariableContext<'input>) {
+        if let Some(var_id) = ctx.get_token(yarnspinnerlexer::VAR_ID, 0) {
+            self.variable_references
+                .push((var_id.get_text(), token_position(var_id.symbol.deref())));
+        }
+    }
+}
+
+fn token_position(token: &(impl Token + ?Sized)) -> Position {
+    Position {
+        line: token.get_line_as_usize().saturating_sub(1),
+        character: token.get_column_as_usize(),
+    }
+}
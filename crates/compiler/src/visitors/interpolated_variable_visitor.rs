@@ -0,0 +1,77 @@
+//! Collects the variables used in the interpolations of lines and options, as well as the variables assigned anywhere.
+
+use crate::prelude::generated::yarnspinnerlexer;
+use crate::prelude::generated::yarnspinnerparser::*;
+use crate::prelude::generated::yarnspinnerparservisitor::YarnSpinnerParserVisitorCompat;
+use crate::prelude::*;
+use antlr_rust::parser_rule_context::ParserRuleContext;
+use antlr_rust::tree::{ParseTree, ParseTreeVisitorCompat};
+use std::collections::HashSet;
+
+pub(crate) struct InterpolatedVariableVisitor<'input> {
+    /// The variables found inside `{...}` expressions of lines and options,
+    /// together with a diagnostic pointing at the respective interpolation.
+    pub(crate) interpolated_variables: Vec<(String, Diagnostic)>,
+    /// The variables that are the target of a `<<set>>` statement.
+    pub(crate) assigned_variables: HashSet<String>,
+    is_in_line: bool,
+    file: FileParseResult<'input>,
+    _dummy: (),
+}
+
+impl<'input> InterpolatedVariableVisitor<'input> {
+    pub(crate) fn new(file: FileParseResult<'input>) -> Self {
+        Self {
+            interpolated_variables: Default::default(),
+            assigned_variables: Default::default(),
+            is_in_line: false,
+            file,
+            _dummy: (),
+        }
+    }
+}
+
+impl<'input> ParseTreeVisitorCompat<'input> for InterpolatedVariableVisitor<'input> {
+    type Node = YarnSpinnerParserContextType;
+    type Return = ();
+
+    fn temp_result(&mut self) -> &mut Self::Return {
+        &mut self._dummy
+    }
+}
+
+impl<'input> YarnSpinnerParserVisitorCompat<'input> for InterpolatedVariableVisitor<'input> {
+    fn visit_line_formatted_text(&mut self, ctx: &Line_formatted_textContext<'input>) {
+        self.is_in_line = true;
+        ParseTreeVisitorCompat::visit_children(self, ctx);
+        self.is_in_line = false;
+    }
+
+    fn visit_set_statement(&mut self, ctx: &Set_statementContext<'input>) {
+        if let Some(variable) = ctx.variable() {
+            self.assigned_variables.insert(variable.get_text());
+        }
+        ParseTreeVisitorCompat::visit_children(self, ctx);
+    }
+
+    fn visit_variable(&mut self, ctx: &VariableContext<'input>) {
+        if !self.is_in_line {
+            return;
+        }
+        let Some(var_id) = ctx.get_token(yarnspinnerlexer::VAR_ID, 0) else {
+            return;
+        };
+        let name = var_id.get_text();
+        let diagnostic = Diagnostic::from_message(format!(
+            "Variable {name} is interpolated here, but it is never declared with <<declare>> or assigned with <<set>>, \
+            so it will always have its default value. Is it misspelled?"
+        ))
+        .with_file_name(&self.file.name)
+        .with_parser_context(ctx, self.file.tokens())
+        .with_severity(DiagnosticSeverity::Warning)
+        .with_kind(DiagnosticKind::UndeclaredVariable {
+            variable: name.clone(),
+        });
+        self.interpolated_variables.push((name, diagnostic));
+    }
+}
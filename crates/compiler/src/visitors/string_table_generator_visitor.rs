@@ -2,8 +2,9 @@
 use crate::prelude::generated::{yarnspinnerparser::*, yarnspinnerparservisitor::*};
 use crate::prelude::*;
 use antlr_rust::parser_rule_context::ParserRuleContext;
-use antlr_rust::token::Token;
-use antlr_rust::tree::{ParseTree, ParseTreeVisitorCompat, Tree};
+use antlr_rust::token::{Token, TOKEN_HIDDEN_CHANNEL};
+use antlr_rust::tree::{ParseTree, ParseTreeVisitorCompat, TerminalNode, Tree};
+use better_any::TidExt;
 use std::rc::Rc;
 
 #[derive(Clone)]
@@ -15,6 +16,9 @@ use std::rc::Rc;
 pub(crate) struct StringTableGeneratorVisitor<'input> {
     pub(crate) diagnostics: Vec<Diagnostic>,
     current_node_name: String,
+    /// The number of lines visited so far in the current node.
+    line_index_in_node: usize,
+    line_id_strategy: LineIdStrategy,
     pub(crate) string_table_manager: StringTableManager,
     file: FileParseResult<'input>,
     _dummy: (),
@@ -24,17 +28,81 @@ impl<'input> StringTableGeneratorVisitor<'input> {
     pub(crate) fn new(
         string_table_manager: StringTableManager,
         file: FileParseResult<'input>,
+        line_id_strategy: LineIdStrategy,
     ) -> Self {
         Self {
             file,
             string_table_manager,
+            line_id_strategy,
             diagnostics: Default::default(),
             current_node_name: Default::default(),
+            line_index_in_node: Default::default(),
             _dummy: (),
         }
     }
 }
 
+impl<'input> StringTableGeneratorVisitor<'input> {
+    /// Reports malformed markup in the text of a line, pointing at the offending markers.
+    fn report_markup_issues(
+        &mut self,
+        ctx: &Line_statementContext<'input>,
+        formatted_text: &Line_formatted_textContext<'input>,
+    ) {
+        // The characters of the line together with their position in the source.
+        // Expressions are not markup, so each is represented by a single placeholder character.
+        let mut characters = Vec::new();
+        let mut positions = Vec::new();
+        for child in formatted_text.get_children() {
+            // Text and expression delimiters are terminals, which only know their position through their symbol.
+            let terminal = child
+                .clone()
+                .downcast_rc::<TerminalNode<'input, YarnSpinnerParserContextType>>()
+                .ok();
+            let Some(terminal) = terminal else {
+                let token = child.start();
+                characters.push('0');
+                positions.push(Position {
+                    line: token.get_line_as_usize().saturating_sub(1),
+                    character: token.get_column_as_usize(),
+                });
+                continue;
+            };
+            let token = &terminal.symbol;
+            let mut position = Position {
+                line: token.get_line_as_usize().saturating_sub(1),
+                character: token.get_column_as_usize(),
+            };
+            for character in terminal.get_text().chars() {
+                characters.push(character);
+                positions.push(position);
+                position.character += 1;
+            }
+        }
+        let end_of_line = positions.last().map(|position| Position {
+            character: position.character + 1,
+            ..*position
+        });
+
+        for issue in validate_markup(&characters) {
+            let start = positions[issue.range.start];
+            let end = positions
+                .get(issue.range.end)
+                .copied()
+                .or(end_of_line)
+                .unwrap_or(start);
+            self.diagnostics.push(
+                Diagnostic::from_message(issue.message)
+                    .with_parser_context(ctx, self.file.tokens())
+                    .with_range(start..end)
+                    .with_file_name(&self.file.name)
+                    .with_severity(issue.severity)
+                    .with_kind(DiagnosticKind::Markup),
+            );
+        }
+    }
+}
+
 impl<'input> ParseTreeVisitorCompat<'input> for StringTableGeneratorVisitor<'input> {
     type Node = YarnSpinnerParserContextType;
 
@@ -47,6 +115,7 @@ impl<'input> ParseTreeVisitorCompat<'input> for StringTableGeneratorVisitor<'inp
 
 impl<'input> YarnSpinnerParserVisitorCompat<'input> for StringTableGeneratorVisitor<'input> {
     fn visit_node(&mut self, ctx: &NodeContext<'input>) -> Self::Return {
+        self.line_index_in_node = 0;
         let mut tags = Vec::new();
         for header in ctx.header_all() {
             let header_key = header.header_key.as_ref().unwrap().get_text();
@@ -110,7 +179,10 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for StringTableGeneratorVisi
                 self.diagnostics.push(
                     Diagnostic::from_message(format!("Duplicate line ID {line_id}"))
                         .with_parser_context(diagnostic_context.as_ref(), self.file.tokens())
-                        .with_file_name(&self.file.name),
+                        .with_file_name(&self.file.name)
+                        .with_kind(DiagnosticKind::DuplicateLineId {
+                            line_id: line_id.to_owned(),
+                        }),
                 );
                 return;
             }
@@ -118,22 +190,43 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for StringTableGeneratorVisi
 
         let line_number = ctx.start().get_line_as_usize();
         let hashtag_texts = get_hashtag_texts(&hashtags);
+        if let Some(Err(weight)) = get_line_group_weight(&hashtag_texts) {
+            self.diagnostics.push(
+                Diagnostic::from_message(format!(
+                    "Invalid line group weight \"{weight}\": expected a positive number"
+                ))
+                .with_parser_context(ctx, self.file.tokens())
+                .with_file_name(&self.file.name)
+                .with_kind(DiagnosticKind::Syntax),
+            );
+        }
 
-        let composed_string = generate_formatted_text(&ctx.line_formatted_text().unwrap());
-
-        let string_id = self.string_table_manager.insert(
-            line_id.map(|t| t.get_text().into()),
-            StringInfo {
-                text: composed_string,
-                node_name: self.current_node_name.clone(),
-                line_number,
-                file_name: self.file.name.clone(),
-                metadata: hashtag_texts,
-                ..Default::default()
-            },
-        );
+        let formatted_text = ctx.line_formatted_text().unwrap();
+        self.report_markup_issues(ctx, &formatted_text);
+        let composed_string = generate_formatted_text(&formatted_text);
 
-        if line_id.is_none() {
+        let string_info = StringInfo {
+            text: composed_string,
+            node_name: self.current_node_name.clone(),
+            line_number,
+            file_name: self.file.name.clone(),
+            shadow_line_id: get_shadow_line_id(&hashtag_texts),
+            metadata: hashtag_texts,
+            ..Default::default()
+        };
+        // ## Implementation note
+        // The original always numbers implicit line IDs by their position in the whole string table, see `LineIdStrategy::Global`.
+        let index_in_node = self.line_index_in_node;
+        self.line_index_in_node += 1;
+        if let Some(line_id) = line_id {
+            self.string_table_manager
+                .insert(line_id.get_text().into(), string_info);
+        } else {
+            let string_id = self.string_table_manager.insert_implicit(
+                self.line_id_strategy,
+                index_in_node,
+                string_info,
+            );
             add_hashtag_child(ctx, string_id.0);
         }
     }
@@ -167,6 +260,41 @@ fn generate_formatted_text(ctx: &Line_formatted_textContext) -> String {
     composed_string.trim().to_owned()
 }
 
+/// Returns whether the line is a candidate of a line group, i.e. starts with a [`LINE_GROUP_ARROW`].
+pub(crate) fn is_line_group_candidate(
+    ctx: &Line_statementContext,
+    tokens: &ActualTokenStream,
+) -> bool {
+    tokens
+        .get_hidden_tokens_to_left(ctx.start().get_token_index(), TOKEN_HIDDEN_CHANNEL)
+        .iter()
+        .any(|token| token.get_token_type() == LINE_GROUP_ARROW)
+}
+
+/// Returns the weight given to a line group candidate by a `#weight:` hashtag, if there is one.
+/// A weight that is not a positive number is returned as the `Err` variant.
+pub(crate) fn get_line_group_weight(hashtag_texts: &[String]) -> Option<Result<f32, String>> {
+    hashtag_texts
+        .iter()
+        .find_map(|tag| tag.strip_prefix("weight:"))
+        .map(|weight| {
+            let weight = weight.trim();
+            weight
+                .parse::<f32>()
+                .ok()
+                .filter(|weight| weight.is_finite() && *weight > 0.0)
+                .ok_or_else(|| weight.to_owned())
+        })
+}
+
+/// Returns the ID of the line referenced by a `#shadow:` hashtag, if there is one.
+fn get_shadow_line_id(hashtag_texts: &[String]) -> Option<LineId> {
+    hashtag_texts
+        .iter()
+        .find_map(|tag| tag.strip_prefix("shadow:"))
+        .map(|shadowed_line| format!("line:{}", shadowed_line.trim()).into())
+}
+
 pub(crate) fn get_hashtag_texts(hashtags: &[Rc<HashtagContext>]) -> Vec<String> {
     hashtags
         .iter()
@@ -186,6 +314,7 @@ mod tests {
     use super::*;
     use antlr_rust::common_token_stream::CommonTokenStream;
     use antlr_rust::InputStream;
+    use std::collections::HashMap;
     use yarnspinner_core::prelude::Position;
 
     #[test]
@@ -260,6 +389,9 @@ a {1 + 3} cool expression
             library: Default::default(),
             compilation_type: CompilationType::FullCompilation,
             variable_declarations: vec![],
+            line_id_strategy: Default::default(),
+            strip_line_text: false,
+            command_declarations: vec![],
         }
         .compile()
         .unwrap();
@@ -275,6 +407,8 @@ a {1 + 3} cool expression
                 file_name: "test.yarn".to_string(),
                 is_implicit_tag: true,
                 metadata: vec![],
+                shadow_line_id: None,
+                is_text_stripped: false,
             }
         );
         assert_eq!(
@@ -286,6 +420,8 @@ a {1 + 3} cool expression
                 file_name: "test.yarn".to_string(),
                 is_implicit_tag: true,
                 metadata: vec![],
+                shadow_line_id: None,
+                is_text_stripped: false,
             }
         );
         assert_eq!(
@@ -297,10 +433,67 @@ a {1 + 3} cool expression
                 file_name: "test.yarn".to_string(),
                 is_implicit_tag: true,
                 metadata: vec![],
+                shadow_line_id: None,
+                is_text_stripped: false,
             }
         );
     }
 
+    #[test]
+    fn global_line_ids_are_numbered_across_nodes() {
+        let source = "title: Start\n---\nHello\n===\ntitle: Other\n---\nBye\n===\n";
+        assert_eq!(
+            LineId::from("line:test.yarn-Other-1"),
+            line_id_of("Bye", source, LineIdStrategy::default())
+        );
+    }
+
+    #[test]
+    fn sequential_line_ids_are_stable_across_unrelated_edits() {
+        let original = "title: Start\n---\nHello\nHow are you?\n===\ntitle: Other\n---\nBye\n===\n";
+        let edited = "title: Start\n---\nHello there\nHow are you?\n===\ntitle: Other\n---\nAnother line\nBye\n===\n";
+        let original_id = line_id_of("How are you?", original, LineIdStrategy::Sequential);
+        let edited_id = line_id_of("How are you?", edited, LineIdStrategy::Sequential);
+        assert_eq!(LineId::from("line:test.yarn-Start-1"), original_id);
+        assert_eq!(original_id, edited_id);
+    }
+
+    #[test]
+    fn content_hash_line_ids_are_stable_across_reorders() {
+        let original = "title: Start\n---\nHello\nHow are you?\nHello\n===\n";
+        let reordered = "title: Start\n---\nHow are you?\nHello\nA new line\nHello\n===\n";
+        let original_id = line_id_of("How are you?", original, LineIdStrategy::ContentHash);
+        let reordered_id = line_id_of("How are you?", reordered, LineIdStrategy::ContentHash);
+        assert_eq!(original_id, reordered_id);
+
+        // Lines with the same text still get unique IDs
+        let string_table = compile_strings(original, LineIdStrategy::ContentHash);
+        assert_eq!(3, string_table.len());
+    }
+
+    fn compile_strings(
+        source: &str,
+        line_id_strategy: LineIdStrategy,
+    ) -> HashMap<LineId, StringInfo> {
+        Compiler::new()
+            .add_file(File {
+                file_name: "test.yarn".to_string(),
+                source: source.to_string(),
+            })
+            .with_compilation_type(CompilationType::StringsOnly)
+            .with_line_id_strategy(line_id_strategy)
+            .compile()
+            .unwrap()
+            .string_table
+    }
+
+    fn line_id_of(text: &str, source: &str, line_id_strategy: LineIdStrategy) -> LineId {
+        compile_strings(source, line_id_strategy)
+            .into_iter()
+            .find_map(|(id, info)| (info.text == text).then_some(id))
+            .unwrap()
+    }
+
     #[test]
     fn catches_expression_errors() {
         let file = File {
@@ -318,6 +511,9 @@ a {very} cool expression
             library: Default::default(),
             compilation_type: CompilationType::FullCompilation,
             variable_declarations: vec![],
+            line_id_strategy: Default::default(),
+            strip_line_text: false,
+            command_declarations: vec![],
         }
         .compile();
 
@@ -338,7 +534,8 @@ a {very} cool expression
                 .with_range(range.clone())
                 .with_context(context.clone())
                 .with_start_line(4)
-                .with_severity(DiagnosticSeverity::Error);
+                .with_severity(DiagnosticSeverity::Error)
+                .with_kind(DiagnosticKind::Syntax);
 
         let second_expected =
             Diagnostic::from_message("mismatched input '}' expecting '('".to_string())
@@ -346,7 +543,8 @@ a {very} cool expression
                 .with_range(range)
                 .with_context(context)
                 .with_start_line(4)
-                .with_severity(DiagnosticSeverity::Error);
+                .with_severity(DiagnosticSeverity::Error)
+                .with_kind(DiagnosticKind::Syntax);
         if diagnostics[0] == first_expected {
             assert_eq!(diagnostics[1], second_expected);
         } else {
@@ -98,6 +98,22 @@ impl<'input> TypeCheckVisitor<'input> {
             .chain(self.new_declarations.iter())
     }
 
+    /// Points to the place where the variable `name` was explicitly declared, if there is such a place.
+    fn get_declaration_location(&self, name: &str) -> Option<RelatedInformation> {
+        let declaration = self
+            .declarations()
+            .find(|declaration| declaration.name == name && !declaration.is_implicit)?;
+        let file_name = match &declaration.source_file_name {
+            DeclarationSource::File(file_name) => Some(file_name.clone()),
+            DeclarationSource::External => None,
+        };
+        Some(RelatedInformation {
+            file_name,
+            range: declaration.range.clone(),
+            message: "Originally declared here".to_owned(),
+        })
+    }
+
     /// Gets the collection of all declarations mutably - both the ones we received
     /// at the start, and the new ones we've derived ourselves.
     pub(crate) fn declarations_mut(&mut self) -> impl Iterator<Item = &mut Declaration> + '_ {
@@ -252,7 +268,8 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for TypeCheckVisitor<'input>
         self.diagnostics.push(
             Diagnostic::from_message("Null is not a permitted type in Yarn Spinner 2.0 and later")
                 .with_file_name(&self.file.name)
-                .with_parser_context(ctx, self.file.tokens()),
+                .with_parser_context(ctx, self.file.tokens())
+                .with_kind(DiagnosticKind::Type),
         );
 
         None
@@ -338,7 +355,8 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for TypeCheckVisitor<'input>
                 supplied_parameters.len()
             ))
             .with_file_name(&self.file.name)
-            .with_parser_context(ctx, self.file.tokens());
+            .with_parser_context(ctx, self.file.tokens())
+            .with_kind(DiagnosticKind::Type);
             self.diagnostics.push(diagnostic);
             return *function_type.return_type;
         }
@@ -373,7 +391,8 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for TypeCheckVisitor<'input>
                     supplied_type.format()
                 ))
                 .with_file_name(&self.file.name)
-                .with_parser_context(ctx, self.file.tokens());
+                .with_parser_context(ctx, self.file.tokens())
+                .with_kind(DiagnosticKind::Type);
                 self.diagnostics.push(diagnostic);
                 return *function_type.return_type;
             }
@@ -412,7 +431,10 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for TypeCheckVisitor<'input>
         let diagnostic =
             Diagnostic::from_message(format_cannot_determine_variable_type_error(&name))
                 .with_file_name(&self.file.name)
-                .with_parser_context(ctx, self.file.tokens());
+                .with_parser_context(ctx, self.file.tokens())
+                .with_kind(DiagnosticKind::UndeclaredVariable {
+                    variable: name.clone(),
+                });
         self.deferred_types
             .push(DeferredTypeDiagnostic { name, diagnostic });
 
@@ -448,6 +470,23 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for TypeCheckVisitor<'input>
         }
         let mut expression_type = self.visit(expression_context.as_ref());
         let variable_name = variable_context.get_text();
+        let is_constant = self
+            .declarations()
+            .any(|declaration| declaration.name == variable_name && declaration.is_constant);
+        if is_constant {
+            let mut diagnostic = Diagnostic::from_message(format!(
+                "{variable_name} is a constant and cannot be assigned"
+            ))
+            .with_file_name(&self.file.name)
+            .with_parser_context(ctx, self.file.tokens())
+            .with_kind(DiagnosticKind::ConstantAssignment {
+                variable: variable_name.clone(),
+            });
+            if let Some(related_information) = self.get_declaration_location(&variable_name) {
+                diagnostic = diagnostic.with_related_information(related_information);
+            }
+            self.diagnostics.push(diagnostic);
+        }
         let terms: &[Term] = &[
             variable_context.clone().into(),
             expression_context.clone().into(),
@@ -462,13 +501,19 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for TypeCheckVisitor<'input>
                 // to the type of the variable.
                 match (variable_type.as_ref(), expression_type.as_ref()) {
                     (Some(variable_type), _) if !expression_type.is_sub_type_of(variable_type) => {
-                        let diagnostic = Diagnostic::from_message(format!(
+                        let mut diagnostic = Diagnostic::from_message(format!(
                             "{variable_name} ({}) cannot be assigned a {}",
                             variable_type.format(),
                             expression_type.format(),
                         ))
                         .with_file_name(&self.file.name)
-                        .with_parser_context(ctx, self.file.tokens());
+                        .with_parser_context(ctx, self.file.tokens())
+                        .with_kind(DiagnosticKind::Type);
+                        if let Some(related_information) =
+                            self.get_declaration_location(&variable_name)
+                        {
+                            diagnostic = diagnostic.with_related_information(related_information);
+                        }
                         self.diagnostics.push(diagnostic);
                     }
                     (None, Some(expression_type)) => {
@@ -498,7 +543,10 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for TypeCheckVisitor<'input>
                                     format_cannot_determine_variable_type_error(&variable_name),
                                 )
                                 .with_file_name(&self.file.name)
-                                .with_parser_context(ctx, self.file.tokens()),
+                                .with_parser_context(ctx, self.file.tokens())
+                                .with_kind(DiagnosticKind::UndeclaredVariable {
+                                    variable: variable_name,
+                                }),
                             )
                         }
                     }
@@ -540,7 +588,8 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for TypeCheckVisitor<'input>
                             Diagnostic::from_message(
                                 format!("Type of expression \"{}\" can't be determined without more context. Please declare one or more terms.", ctx.get_text_with_whitespace(self.file.tokens())))
                                 .with_file_name(&self.file.name)
-                                .with_parser_context(ctx, self.file.tokens()));
+                                .with_parser_context(ctx, self.file.tokens())
+                                .with_kind(DiagnosticKind::Type));
         }
         // at this point we have either fully resolved the type of the expression or been unable to do so
         // we return the type of the expression regardless and rely on either elements to catch the issue
@@ -629,6 +678,9 @@ mod tests {
             library: Default::default(),
             compilation_type: CompilationType::FullCompilation,
             variable_declarations: vec![],
+            line_id_strategy: Default::default(),
+            strip_line_text: false,
+            command_declarations: vec![],
         }
         .compile()
         .unwrap();
@@ -654,6 +706,9 @@ mod tests {
             library: Default::default(),
             compilation_type: CompilationType::FullCompilation,
             variable_declarations: vec![],
+            line_id_strategy: Default::default(),
+            strip_line_text: false,
+            command_declarations: vec![],
         }
         .compile();
 
@@ -707,6 +762,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn invalid_assignment_points_to_declaration() {
+        let file = File {
+            file_name: "test.yarn".to_string(),
+            source: "title: test
+---
+<<declare $foo to 1>>
+Some line
+<<set $foo to \"invalid\">>
+==="
+            .to_string(),
+        };
+        let result = Compiler {
+            files: vec![file],
+            library: Default::default(),
+            compilation_type: CompilationType::FullCompilation,
+            variable_declarations: vec![],
+            line_id_strategy: Default::default(),
+            strip_line_text: false,
+            command_declarations: vec![],
+        }
+        .compile();
+
+        let diagnostics = result.unwrap_err().0;
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(
+            "$foo (Number) cannot be assigned a String",
+            diagnostics[0].message
+        );
+        assert_eq!(
+            vec![RelatedInformation {
+                file_name: Some("test.yarn".to_owned()),
+                range: Some(
+                    Position {
+                        line: 2,
+                        character: 10,
+                    }..Position {
+                        line: 2,
+                        character: 14,
+                    }
+                ),
+                message: "Originally declared here".to_owned(),
+            }],
+            diagnostics[0].related_information
+        );
+    }
+
     fn assert_contains(diagnostics: &[Diagnostic], expected: &Diagnostic) {
         assert!(
             // Does not factor in context or start line because these are subject to frequent change
@@ -741,6 +843,9 @@ mod tests {
             library: Default::default(),
             compilation_type: CompilationType::FullCompilation,
             variable_declarations: vec![],
+            line_id_strategy: Default::default(),
+            strip_line_text: false,
+            command_declarations: vec![],
         }
         .compile()
         .unwrap();
@@ -764,6 +869,9 @@ mod tests {
             library: Default::default(),
             compilation_type: CompilationType::FullCompilation,
             variable_declarations: vec![],
+            line_id_strategy: Default::default(),
+            strip_line_text: false,
+            command_declarations: vec![],
         }
         .compile();
 
@@ -95,7 +95,8 @@ impl<'input> TypeCheckVisitor<'input> {
                     );
                         let diagnostic = Diagnostic::from_message(message)
                             .with_file_name(&self.file.name)
-                            .with_parser_context(context, self.file.tokens());
+                            .with_parser_context(context, self.file.tokens())
+                            .with_kind(DiagnosticKind::Type);
                         self.diagnostics.push(diagnostic);
                         return None;
                     }
@@ -107,7 +108,8 @@ impl<'input> TypeCheckVisitor<'input> {
                     );
                         let diagnostic = Diagnostic::from_message(message)
                             .with_file_name(&self.file.name)
-                            .with_parser_context(context, self.file.tokens());
+                            .with_parser_context(context, self.file.tokens())
+                            .with_kind(DiagnosticKind::Type);
                         self.diagnostics.push(diagnostic);
                         return None;
                     }
@@ -257,7 +259,8 @@ impl<'input> TypeCheckVisitor<'input> {
                     format_cannot_determine_variable_type_error(&var_name),
                 )
                 .with_file_name(&self.file.name)
-                .with_parser_context(undefined_variable_context.as_ref(), self.file.tokens());
+                .with_parser_context(undefined_variable_context.as_ref(), self.file.tokens())
+                .with_kind(DiagnosticKind::UndeclaredVariable { variable: var_name });
                 self.diagnostics.push(diagnostic);
                 continue;
             }
@@ -281,7 +284,8 @@ impl<'input> TypeCheckVisitor<'input> {
                 format!("All terms of {operation_description} must be the same, not {type_list}");
             let diagnostic = Diagnostic::from_message(message)
                 .with_file_name(&self.file.name)
-                .with_parser_context(context, self.file.tokens());
+                .with_parser_context(context, self.file.tokens())
+                .with_kind(DiagnosticKind::Type);
             self.diagnostics.push(diagnostic);
             return None;
         }
@@ -316,7 +320,8 @@ impl<'input> TypeCheckVisitor<'input> {
                 );
                 let diagnostic = Diagnostic::from_message(message)
                     .with_file_name(&self.file.name)
-                    .with_parser_context(context, self.file.tokens());
+                    .with_parser_context(context, self.file.tokens())
+                    .with_kind(DiagnosticKind::Type);
                 self.diagnostics.push(diagnostic);
                 return None;
             }
@@ -350,7 +355,8 @@ impl<'input> TypeCheckVisitor<'input> {
             );
             let diagnostic = Diagnostic::from_message(message)
                 .with_file_name(&self.file.name)
-                .with_parser_context(context, self.file.tokens());
+                .with_parser_context(context, self.file.tokens())
+                .with_kind(DiagnosticKind::Type);
             self.diagnostics.push(diagnostic);
             return None;
         }
@@ -376,7 +382,8 @@ impl<'input> TypeCheckVisitor<'input> {
             self.diagnostics.push(
                 Diagnostic::from_message(message)
                     .with_file_name(&self.file.name)
-                    .with_parser_context(context, self.file.tokens()),
+                    .with_parser_context(context, self.file.tokens())
+                    .with_kind(DiagnosticKind::Type),
             );
             return None;
         }
@@ -1,6 +1,7 @@
 //! Contains extensions to generated types that in the original implementation are sprinkled around the repo via partial classes
 
 use crate::prelude::*;
+use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::{Debug, Display};
 
@@ -112,6 +113,57 @@ impl Display for InvalidOpCodeError {
     }
 }
 
+/// The data of a [`Program`] that is specific to Yarn Spinner for Rust, stored in [`Program::extensions`].
+///
+/// This is not part of the upstream `yarn_spinner.proto`, so it is written by hand instead of being generated.
+/// The `generate_proto` binary of `yarnspinner_codegen` adds the field holding it to the generated [`Program`].
+/// Its tag of 100 keeps it clear of fields added upstream, and other runtimes skip it as an unknown field.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ProgramExtensions {
+    /// The names of the variables declared with `<<const $name = value>>`, including their `$`.
+    /// Running an instruction that assigns to one of them fails.
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[prost(string, repeated, tag = "1")]
+    pub constant_variables: Vec<String>,
+    /// The IDs of the lines whose metadata contains the [`ONCE_TAG`].
+    /// Options for these lines are no longer offered once they have been selected.
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[prost(string, repeated, tag = "2")]
+    pub once_lines: Vec<String>,
+    /// The IDs of the lines whose metadata contains the [`MULTISELECT_TAG`].
+    /// Options for these lines may be selected together.
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[prost(string, repeated, tag = "3")]
+    pub multiselect_lines: Vec<String>,
+    /// The IDs of the lines whose metadata contains the [`PINNED_TAG`].
+    /// Options for these lines keep their position when options are shuffled or sorted.
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[prost(string, repeated, tag = "4")]
+    pub pinned_lines: Vec<String>,
+    /// The values of the [`REQUIREMENT_TAG`] hashtags, keyed by the IDs of the lines carrying them.
+    /// They describe what is needed to select the options for these lines.
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[prost(map = "string, string", tag = "5")]
+    pub requirement_hints: HashMap<String, String>,
+}
+
+impl ProgramExtensions {
+    fn extend(&mut self, other: ProgramExtensions) {
+        self.constant_variables.extend(other.constant_variables);
+        self.once_lines.extend(other.once_lines);
+        self.multiselect_lines.extend(other.multiselect_lines);
+        self.pinned_lines.extend(other.pinned_lines);
+        self.requirement_hints.extend(other.requirement_hints);
+    }
+}
+
 impl Program {
     /// Creates a new Program by merging multiple Programs together.
     ///
@@ -131,6 +183,7 @@ impl Program {
                 output.nodes.insert(node_name, node);
             }
             output.initial_values.extend(program.initial_values);
+            output.extensions.extend(program.extensions);
         }
         Some(output)
     }
@@ -148,3 +201,22 @@ impl Instruction {
             .unwrap_or_else(|e| panic!("Failed to convert operand {index}: {e:?}",))
     }
 }
+
+impl Display for Instruction {
+    /// Formats the instruction as its opcode followed by its operands, e.g. `RunLine "line:0" 0`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match OpCode::try_from(self.opcode) {
+            Ok(opcode) => write!(f, "{opcode:?}")?,
+            Err(_) => write!(f, "{}", InvalidOpCodeError(self.opcode))?,
+        }
+        for operand in &self.operands {
+            match &operand.value {
+                Some(OperandValue::StringValue(s)) => write!(f, " {s:?}")?,
+                Some(OperandValue::FloatValue(n)) => write!(f, " {n}")?,
+                Some(OperandValue::BoolValue(b)) => write!(f, " {b}")?,
+                None => write!(f, " null")?,
+            }
+        }
+        Ok(())
+    }
+}
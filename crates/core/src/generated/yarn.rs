@@ -21,6 +21,10 @@ pub struct Program {
         ::prost::alloc::string::String,
         Operand,
     >,
+    /// Data that is specific to Yarn Spinner for Rust, see [`ProgramExtensions`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[prost(message, required, tag = "100")]
+    pub extensions: ProgramExtensions,
 }
 /// A collection of instructions
 use crate::prelude::*;
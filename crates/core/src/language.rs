@@ -0,0 +1,100 @@
+#[cfg(any(feature = "bevy", feature = "serde"))]
+use crate::prelude::*;
+use core::fmt::{self, Display};
+use core::str::FromStr;
+use icu_locid::LanguageIdentifier;
+use std::error::Error;
+
+/// IETF BCP 47 code.
+/// The default is "en-US".
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct Language(pub(crate) LanguageIdentifier);
+impl Language {
+    /// Creates a new `Language` from a string. Panics if the string is not a valid IETF BCP 47 code.
+    pub fn new(language: impl Into<String>) -> Self {
+        let language = language.into();
+        Self(language.parse().unwrap())
+    }
+
+    /// Creates a new `Language` from a string, returning an error if the string is not a valid IETF BCP 47 code.
+    pub fn parse(language: &str) -> std::result::Result<Self, LanguageTagError> {
+        language.parse().map(Self).map_err(|_| LanguageTagError {
+            tag: language.to_owned(),
+        })
+    }
+}
+
+impl FromStr for Language {
+    type Err = LanguageTagError;
+
+    fn from_str(language: &str) -> std::result::Result<Self, Self::Err> {
+        Self::parse(language)
+    }
+}
+
+/// The error returned by [`Language::parse`] when a string is not a valid IETF BCP 47 code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageTagError {
+    /// The string that failed to parse.
+    pub tag: String,
+}
+
+impl Error for LanguageTagError {}
+
+impl Display for LanguageTagError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "\"{}\" is not a valid IETF BCP 47 language tag",
+            self.tag
+        )
+    }
+}
+
+impl Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Default for Language {
+    fn default() -> Self {
+        "en-US".into()
+    }
+}
+
+impl From<Language> for LanguageIdentifier {
+    fn from(language: Language) -> Self {
+        language.0
+    }
+}
+
+impl<T> From<T> for Language
+where
+    String: From<T>,
+{
+    fn from(language: T) -> Self {
+        let language: String = language.into();
+        Self::new(language)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_strings() {
+        let language: Language = "de-CH".parse().unwrap();
+        assert_eq!("de-CH", language.to_string());
+        assert_eq!(Language::from("de-CH"), language);
+    }
+
+    #[test]
+    fn rejects_invalid_tags() {
+        let error = "not a language".parse::<Language>().unwrap_err();
+        assert_eq!("not a language", error.tag);
+    }
+}
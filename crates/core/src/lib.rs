@@ -6,13 +6,17 @@
 //! - If you wish to write an adapter crate for an engine yourself, use the [`yarnspinner`](https://crates.io/crates/yarnspinner) crate.
 
 #![warn(missing_docs, missing_debug_implementations)]
+mod command_text;
 mod feature_gates;
 mod generated;
 mod internal_value;
+mod language;
 mod library;
 mod line_id;
+pub mod markup;
 mod operator;
 mod position;
+mod program_verification;
 pub mod types;
 mod yarn_fn;
 mod yarn_value;
@@ -23,15 +27,18 @@ pub mod prelude {
     pub use crate::feature_gates::*;
 
     pub use crate::{
+        command_text::*,
         generated::{
             instruction::OpCode, operand::Value as OperandValue, Header, Instruction,
-            InvalidOpCodeError, Node, Operand, Program,
+            InvalidOpCodeError, Node, Operand, Program, ProgramExtensions,
         },
         internal_value::*,
+        language::*,
         library::*,
         line_id::*,
         operator::*,
         position::*,
+        program_verification::*,
         types::Type,
         yarn_fn::*,
         yarn_value::*,
@@ -1,8 +1,11 @@
 //! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner/Library.cs>
 
 use crate::prelude::*;
+use crate::types::FunctionType;
+use log::debug;
 use std::borrow::Cow;
 use std::collections::hash_map;
+use std::error::Error;
 use std::fmt::Display;
 
 /// A collection of functions that can be called from Yarn scripts.
@@ -11,12 +14,20 @@ use std::fmt::Display;
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Library(YarnFnRegistry);
 
+/// Adds the given functions to the library with "last wins" semantics:
+/// a function with the same name as one already present overwrites the existing one.
+/// Use [`Library::merge`] if name collisions should be treated as an error instead.
 impl Extend<<YarnFnRegistry as IntoIterator>::Item> for Library {
     fn extend<T: IntoIterator<Item = (Cow<'static, str>, Box<dyn UntypedYarnFn>)>>(
         &mut self,
         iter: T,
     ) {
-        self.0.extend(iter);
+        for (name, function) in iter {
+            if self.0.contains_function(&name) {
+                debug!("Overwriting existing function \"{name}\" in library");
+            }
+            self.0.add_boxed(name, function);
+        }
     }
 }
 
@@ -46,11 +57,44 @@ impl Library {
         self.0.extend(other.0 .0);
     }
 
+    /// Combines two libraries into one.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`LibraryError::FunctionConflict`] if a function with the same name is present in both libraries.
+    /// If overwriting such functions is intended, use [`Library::import`] or [`Extend::extend`] instead.
+    ///
+    /// ## Implementation Notes
+    ///
+    /// This mirrors the behavior of the original `ImportLibrary`, which throws on duplicate names.
+    pub fn merge(a: Self, b: Self) -> Result<Self, LibraryError> {
+        if let Some(name) = b.names().find(|name| a.contains_function(name)) {
+            return Err(LibraryError::FunctionConflict(name.to_owned()));
+        }
+        let mut merged = a;
+        merged.0.extend(b.0 .0);
+        Ok(merged)
+    }
+
     /// Iterates over the names and functions in the library.
     pub fn iter(&self) -> impl Iterator<Item = (&str, &(dyn UntypedYarnFn))> {
         self.0.iter()
     }
 
+    /// Iterates over the names of all functions in the library together with their signatures,
+    /// e.g. for listing the functions that are available to Yarn scripts.
+    /// This includes the operators registered by [`Library::standard_library`], which are named like `Number.Add`.
+    pub fn signatures(&self) -> impl Iterator<Item = (&str, FunctionType)> {
+        self.iter().map(|(name, function)| {
+            let mut function_type = FunctionType::default();
+            for parameter in function.parameter_types() {
+                function_type.add_parameter(Type::try_from(parameter).ok());
+            }
+            function_type.set_return_type(Type::try_from(function.return_type()).ok());
+            (name, function_type)
+        })
+    }
+
     /// Gets a function by name.
     pub fn get(&self, name: &str) -> Option<&(dyn UntypedYarnFn)> {
         self.0.get(name)
@@ -64,17 +108,47 @@ impl Library {
         format!("$Yarn.Internal.Visiting.{node_name}")
     }
 
+    /// Generates the tracking variable name that counts how often the option with the given line ID was selected.
+    pub fn generate_unique_visited_variable_for_option(line_id: &str) -> String {
+        format!("$Yarn.Internal.OptionVisiting.{line_id}")
+    }
+
+    /// The name of the function that picks which line of a line group is run.
+    ///
+    /// Each candidate of the group is passed as two parameters: its weight, followed by the name of the variable
+    /// counting how often it was run, see [`Library::generate_unique_once_variable_for_line_group_candidate`].
+    /// The variable name is empty for candidates that may be run any number of times.
+    /// The function returns the 0-based index of the picked candidate, or -1 if no candidate is left.
+    ///
+    /// Since the number of parameters depends on the group and picking a candidate needs the dialogue's random number generator,
+    /// this function is not part of any [`Library`]. Calls to it are handled by the virtual machine itself.
+    pub const LINE_GROUP_SELECTION_FUNCTION_NAME: &'static str = "Yarn.Internal.select_line_group";
+
+    /// Generates the variable name that stores which candidate of the line group with the given index in a node was picked.
+    pub fn generate_unique_line_group_variable(node_name: &str, group_index: usize) -> String {
+        format!("$Yarn.Internal.LineGroup.{node_name}.{group_index}")
+    }
+
+    /// Generates the variable name that counts how often the line group candidate with the given line ID and a `#once` hashtag was run.
+    pub fn generate_unique_once_variable_for_line_group_candidate(line_id: &str) -> String {
+        format!("$Yarn.Internal.LineGroupCandidate.{line_id}")
+    }
+
     /// Creates a [`Library`] with the standard functions that are included in Yarn Spinner.
     /// These are:
     /// - `string`: Converts a value to a string.
     /// - `number`: Converts a value to a number.
     /// - `bool`: Converts a value to a boolean.
+    /// - `format_time`: Formats a number of seconds as `mm:ss`, or as `h:mm:ss` if it is at least an hour.
+    /// - `format_duration`: Formats a number of seconds as e.g. `2 minutes 5 seconds`, see [`format_duration`].
     /// - Comparison operators for numbers, strings, and booleans. (`==`, `!=`, `<`, `<=`, `>`, `>=`)
     pub fn standard_library() -> Self {
         let mut library = yarn_library!(
             "string" => <String as From<YarnValue >>::from,
             "number" => |value: YarnValue| f32::try_from(value).expect("Failed to convert a Yarn value to a number"),
             "bool" => |value: YarnValue| bool::try_from(value).expect("Failed to convert a Yarn value to a bool"),
+            "format_time" => format_time,
+            "format_duration" => |seconds: f32| format_duration(seconds, |count| count == 1),
         );
         for r#type in [Type::Number, Type::String, Type::Boolean] {
             library.add_methods(r#type);
@@ -137,6 +211,25 @@ impl Library {
         self.0.functions()
     }
 
+    /// Makes the `==` and `!=` operators consider two numbers equal if they differ by at most `relative_tolerance` times the larger of their magnitudes,
+    /// e.g. so that `0.1 + 0.2 == 0.3` holds despite rounding errors.
+    /// A tolerance of 0, which [`Library::standard_library`] uses, compares numbers exactly.
+    ///
+    /// ## Implementation notes
+    ///
+    /// This method is not present in the original implementation, which always compares numbers exactly.
+    pub fn set_number_equality_tolerance(&mut self, relative_tolerance: f32) -> &mut Self {
+        let name =
+            |operator: Operator| Type::Number.get_canonical_name_for_method(&operator.to_string());
+        let are_equal = move |lhs: f32, rhs: f32| {
+            lhs == rhs || (lhs - rhs).abs() <= relative_tolerance * lhs.abs().max(rhs.abs())
+        };
+        self.add_function(name(Operator::EqualTo), are_equal)
+            .add_function(name(Operator::NotEqualTo), move |lhs: f32, rhs: f32| {
+                !are_equal(lhs, rhs)
+            })
+    }
+
     /// Registers the methods found inside a type.
     fn add_methods(&mut self, r#type: Type) {
         for (name, function) in r#type.methods().into_iter() {
@@ -159,6 +252,26 @@ impl Display for Library {
     }
 }
 
+/// An error that occurs when combining [`Library`]s.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LibraryError {
+    FunctionConflict(String),
+}
+
+impl Error for LibraryError {}
+
+impl Display for LibraryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LibraryError::FunctionConflict(name) => write!(
+                f,
+                "Cannot merge libraries: both contain a function named \"{name}\""
+            ),
+        }
+    }
+}
+
 /// Create a [`Library`] from a list of named functions.
 ///
 /// ## Example
@@ -188,3 +301,117 @@ macro_rules! yarn_library {
     };
 }
 pub use yarn_library;
+
+/// Negative and non-finite durations are treated as zero, fractions of a second are cut off.
+fn format_time(seconds: f32) -> String {
+    let total_seconds = if seconds.is_finite() {
+        seconds.max(0.0) as u64
+    } else {
+        0
+    };
+    let hours = total_seconds / 3600;
+    let minutes = total_seconds % 3600 / 60;
+    let seconds = total_seconds % 60;
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes:02}:{seconds:02}")
+    }
+}
+
+/// Formats a number of seconds as e.g. "1 hour 2 minutes 5 seconds", leaving out units that are zero.
+/// `is_singular` decides whether a unit with the given count is named in singular, which depends on the plural rules of a language.
+/// The `format_duration` of [`Library::standard_library`] uses the English rule, i.e. only a count of 1 is singular.
+///
+/// Negative and non-finite durations are treated as zero, fractions of a second are cut off.
+/// The unit names are always English.
+pub fn format_duration(seconds: f32, is_singular: impl Fn(u64) -> bool) -> String {
+    let total_seconds = if seconds.is_finite() {
+        seconds.max(0.0) as u64
+    } else {
+        0
+    };
+    let units = [
+        (total_seconds / 3600, "hour", "hours"),
+        (total_seconds % 3600 / 60, "minute", "minutes"),
+        (total_seconds % 60, "second", "seconds"),
+    ];
+    let format_unit = |(count, singular, plural): (u64, &str, &str)| {
+        let name = if is_singular(count) { singular } else { plural };
+        format!("{count} {name}")
+    };
+    let parts: Vec<_> = units
+        .into_iter()
+        .filter(|(count, _, _)| *count > 0)
+        .map(format_unit)
+        .collect();
+    if parts.is_empty() {
+        format_unit(units[2])
+    } else {
+        parts.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_libraries_without_conflicts() {
+        let a = yarn_library! {
+            "one" => || 1,
+        };
+        let b = yarn_library! {
+            "two" => || 2,
+        };
+        let merged = Library::merge(a, b).unwrap();
+        assert!(merged.contains_function("one"));
+        assert!(merged.contains_function("two"));
+    }
+
+    #[test]
+    fn detects_conflicts_when_merging() {
+        let a = yarn_library! {
+            "one" => || 1,
+        };
+        let b = yarn_library! {
+            "one" => || true,
+        };
+        assert_eq!(
+            Err(LibraryError::FunctionConflict("one".to_owned())),
+            Library::merge(a, b)
+        );
+    }
+
+    #[test]
+    fn extend_overwrites_existing_functions() {
+        let mut library = yarn_library! {
+            "one" => || 1,
+        };
+        library.extend(yarn_library! {
+            "one" => || true,
+        });
+        let function = library.get("one").unwrap();
+        assert_eq!(std::any::TypeId::of::<bool>(), function.return_type());
+    }
+
+    #[test]
+    fn formats_time() {
+        assert_eq!("00:00", format_time(0.0));
+        assert_eq!("01:05", format_time(65.0));
+        assert_eq!("59:59", format_time(3599.9));
+        assert_eq!("1:00:00", format_time(3600.0));
+        assert_eq!("12:03:04", format_time(43384.0));
+        assert_eq!("00:00", format_time(-5.0));
+    }
+
+    #[test]
+    fn formats_duration() {
+        let is_singular = |count| count == 1;
+        assert_eq!("0 seconds", format_duration(0.0, is_singular));
+        assert_eq!("1 second", format_duration(1.5, is_singular));
+        assert_eq!("1 minute 5 seconds", format_duration(65.0, is_singular));
+        assert_eq!("2 hours 1 second", format_duration(7201.0, is_singular));
+        assert_eq!("0 seconds", format_duration(f32::NAN, is_singular));
+    }
+}
@@ -37,3 +37,54 @@ impl Display for LineId {
         self.0.fmt(f)
     }
 }
+
+/// The key of the hashtag that describes what is needed to select an option, e.g.
+/// ```text
+/// -> Bribe the guard <<if $gold >= 50>> #requirement:50_gold
+/// ```
+///
+/// The value is everything after the `:`. It cannot contain spaces, since a hashtag ends at the first whitespace:
+/// `#requirement:Needs 50 gold` would have the value `Needs`, with `50` and `gold` becoming part of the line.
+/// Use a short key like the one above instead and look up the text to display, e.g. in a localization table.
+///
+/// The compiler lists the values by line ID in [`ProgramExtensions::requirement_hints`](crate::prelude::ProgramExtensions::requirement_hints),
+/// which the runtime's `Dialogue` registers when the program is loaded. They are then delivered in `DialogueOption::requirement_hint`.
+pub const REQUIREMENT_TAG: &str = "requirement";
+
+/// The hashtag that marks an option as only being offered until it has been selected once, e.g.
+/// ```text
+/// -> Ask about the map #once
+/// -> Leave
+/// ```
+///
+/// The compiler lists the IDs of lines carrying this tag in [`ProgramExtensions::once_lines`](crate::prelude::ProgramExtensions::once_lines),
+/// which the runtime's `Dialogue` registers when the program is loaded.
+/// Selections of these options are counted in the variable storage under [`Library::generate_unique_visited_variable_for_option`](crate::prelude::Library::generate_unique_visited_variable_for_option),
+/// so they are forgotten when the storage is cleared.
+pub const ONCE_TAG: &str = "once";
+
+/// The hashtag that marks an option as belonging to a "select all that apply" style option group, e.g.
+/// ```text
+/// -> Bread #multiselect
+/// -> Cheese #multiselect
+/// ```
+///
+/// The compiler lists the IDs of lines carrying this tag in [`ProgramExtensions::multiselect_lines`](crate::prelude::ProgramExtensions::multiselect_lines),
+/// which the runtime's `Dialogue` registers when the program is loaded.
+/// If every option presented at once is marked like this, several of them can be selected with `Dialogue::set_selected_options`.
+/// Since the selection is stored as the line IDs joined by [`MULTISELECT_SEPARATOR`], the compiler rejects tagged lines whose ID contains it.
+pub const MULTISELECT_TAG: &str = "multiselect";
+
+/// The hashtag that marks an option as keeping its position when options are shuffled or sorted by the runtime's `Dialogue`, e.g.
+/// ```text
+/// -> Paris
+/// -> London
+/// -> Never mind #pinned
+/// ```
+///
+/// The compiler lists the IDs of lines carrying this tag in [`ProgramExtensions::pinned_lines`](crate::prelude::ProgramExtensions::pinned_lines),
+/// which the runtime's `Dialogue` registers when the program is loaded.
+pub const PINNED_TAG: &str = "pinned";
+
+/// The separator between the line IDs of the options selected together via the runtime's `Dialogue::set_selected_options`.
+pub const MULTISELECT_SEPARATOR: &str = ",";
@@ -0,0 +1,16 @@
+//! Types handling the parsing of Yarn markup, shared by the runtime, which parses the markup of lines as they are run,
+//! and the compiler, which checks it ahead of time.
+//! Yarn markup looks like this:
+//! ```text
+//! Mae: [shout]I'm a cat![/shout]!
+//! Greg: You're a [size=12]cat[/size]!
+//! ```
+//! The parsing extracts the information that "Mae" and "Greg" are characters, that "shout" and "size" are attributes, and that "size" has a value of "12".
+mod attribute_marker_processor;
+mod line_parser;
+mod markup_parse_error;
+mod parsed_markup;
+
+pub use self::{
+    attribute_marker_processor::*, line_parser::*, markup_parse_error::*, parsed_markup::*,
+};
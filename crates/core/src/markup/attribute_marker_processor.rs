@@ -1,15 +1,14 @@
 //! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner/YarnSpinner.Markup/IAttributeMarkerProcessor.cs>
 
-pub(crate) use self::{dialogue_text_processor::*, no_markup_text_processor::*};
+pub use self::no_markup_text_processor::*;
 use crate::markup::MarkupAttributeMarker;
 use crate::prelude::Language;
 use core::fmt::Debug;
 
-mod dialogue_text_processor;
 mod no_markup_text_processor;
 
 /// Provides a mechanism for producing replacement text for a marker.
-pub(crate) trait AttributeMarkerProcessor: Debug + Send + Sync {
+pub trait AttributeMarkerProcessor: Debug + Send + Sync {
     /// Produces the replacement text that should be inserted into a parse
     /// result for a given attribute.
     ///
@@ -17,7 +16,9 @@ pub(crate) trait AttributeMarkerProcessor: Debug + Send + Sync {
     /// position to its corresponding closing marker is provided as a string
     /// property called `contents`.
     fn replacement_text_for_marker(&self, marker: &MarkupAttributeMarker) -> String;
+    /// Sets the language that replacement text is produced for, e.g. to pick the plural forms of that language.
     fn set_language_code(&mut self, language_code: Option<Language>);
+    /// Clones the processor into a new box, so that [`LineParser`](crate::markup::LineParser)s holding processors can be cloned.
     fn clone_box(&self) -> Box<dyn AttributeMarkerProcessor>;
 }
 
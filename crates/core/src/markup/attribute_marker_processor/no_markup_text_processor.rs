@@ -7,10 +7,11 @@ use crate::prelude::Language;
 
 /// A markup text processor that implements the `[nomarkup]` attribute's behaviour.
 #[derive(Default, Debug, Clone)]
-pub(crate) struct NoMarkupTextProcessor;
+pub struct NoMarkupTextProcessor;
 
 impl NoMarkupTextProcessor {
-    pub(crate) fn new() -> Self {
+    /// Creates a new processor for the `[nomarkup]` attribute.
+    pub fn new() -> Self {
         Self
     }
 }
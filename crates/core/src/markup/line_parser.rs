@@ -9,9 +9,11 @@ use crate::prelude::*;
 use once_cell::sync::Lazy;
 use regex::Regex;
 use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
 use unicode_normalization::UnicodeNormalization;
 use unicode_segmentation::UnicodeSegmentation;
 
+/// The result of parsing the markup of a line.
 pub type Result<T> = std::result::Result<T, MarkupParseError>;
 
 #[derive(Debug, Clone)]
@@ -22,7 +24,8 @@ pub type Result<T> = std::result::Result<T, MarkupParseError>;
     all(feature = "bevy", feature = "serde"),
     reflect(Serialize, Deserialize)
 )]
-pub(crate) struct LineParser {
+/// Parses the markup of a line, e.g. `[b]Hello[/b]`, into its plain text and [`MarkupAttribute`]s.
+pub struct LineParser {
     // ## Implementation notes
     // We don't port `stringReader` because [`BufReader`] is not [`Clone`]
     /// A map for the names of attributes to an object that can generate replacement text for those attributes.
@@ -35,24 +38,67 @@ pub(crate) struct LineParser {
     source_position: usize,
     /// The current position of the string reader in the plain text, measured in text elements.
     position: usize,
+    unclosed_attribute_policy: UnclosedAttributePolicy,
+}
+
+/// What happens to attributes that are still open at the end of a line, e.g. the `b` in `[b]Hello`,
+/// set via `Dialogue::with_unclosed_attribute_policy` in the runtime.
+/// Attributes can be closed explicitly by their close marker, e.g. `[/b]`, or by `[/]`, which closes the innermost open attribute.
+/// For example, the first `[/]` in `[b][i]text[/][/]` closes `i` and the second one closes `b`.
+///
+/// ## Implementation notes
+///
+/// This enum is not present in the original implementation, which drops such attributes.
+///
+/// Unlike the original implementation, in which `[/]` closes every open attribute at once,
+/// `[/]` closes only the innermost one, as in newer versions of Yarn Spinner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq, Hash, Default))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+pub enum UnclosedAttributePolicy {
+    /// The attributes are left out of the parsed line, as in the original implementation.
+    #[default]
+    Drop,
+    /// The attributes are closed at the end of the line, as if the line ended with a `[/]` for each of them.
+    AutoClose,
+    /// Parsing the line fails with [`MarkupParseError::UnclosedAttribute`].
+    Error,
+}
+
+/// A mistake in the markup of a line, found by [`LineParser::find_markup_issues`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct MarkupIssue {
+    /// The error that parsing the line fails with.
+    /// [`MarkupParseError::UnclosedAttribute`] only makes parsing fail with [`UnclosedAttributePolicy::Error`].
+    pub error: MarkupParseError,
+    /// The characters of the line that the mistake was found at.
+    /// The indices refer to the line in Unicode normalization form C, which is what the parser works on.
+    pub source_range: Range<usize>,
 }
 
 impl Default for LineParser {
     fn default() -> Self {
         Self {
             marker_processors: HashMap::from([(
-                "nomarkup".to_string(),
+                NO_MARKUP_ATTRIBUTE.to_string(),
                 Box::new(NoMarkupTextProcessor::new()) as Box<dyn AttributeMarkerProcessor>,
             )]),
             input: Default::default(),
             source_position: Default::default(),
             position: Default::default(),
+            unclosed_attribute_policy: Default::default(),
         }
     }
 }
 
 impl LineParser {
-    pub(crate) fn new() -> Self {
+    /// Creates a parser that only handles the `[nomarkup]` replacement marker.
+    pub fn new() -> Self {
         Self::default()
     }
 
@@ -64,9 +110,9 @@ impl LineParser {
     /// text. This allows users of the [`LineParser`]
     /// to dynamically replace text in a line. The `nomarkup` tag is
     /// implemented in this way by the [`LineParser`]
-    /// directly; the [`Dialogue`] uses this mechanism
+    /// directly; the runtime's `Dialogue` uses this mechanism
     /// to implement the `select`, `plural` and `ordinal` markers.
-    pub(crate) fn register_marker_processor(
+    pub fn register_marker_processor(
         mut self,
         attribute_name: impl Into<String>,
         processor: Box<dyn AttributeMarkerProcessor>,
@@ -81,12 +127,20 @@ impl LineParser {
         self
     }
 
+    /// Sets what happens to attributes that are still open at the end of a line.
+    pub fn set_unclosed_attribute_policy(&mut self, policy: UnclosedAttributePolicy) {
+        self.unclosed_attribute_policy = policy;
+    }
+
     /// Parses a line of text, and produces a [`ParsedMarkup`] containing the processed text
     ///
     /// ## Implementation notes
     ///
     /// The original does not reset the internal `source_position`. This was likely a bug.
-    pub(crate) fn parse_markup(&mut self, input: &str) -> Result<ParsedMarkup> {
+    ///
+    /// The original only treats `\[` and `\]` as escape sequences. Here, `\\` is an escaped backslash as well,
+    /// so that text can end in a literal backslash without escaping a following bracket.
+    pub fn parse_markup(&mut self, input: &str) -> Result<ParsedMarkup> {
         if input.is_empty() {
             // We got a null input; return an empty markup parse result
             return Ok(ParsedMarkup::new());
@@ -95,6 +149,84 @@ impl LineParser {
         self.input = normalize(input);
         self.source_position = 0;
 
+        let (text, markers) = self.parse_markers().map_err(|issue| issue.error)?;
+        let end_position = text.as_str().graphemes(true).count();
+        let mut attributes = self.build_attributes_from_markers(markers, end_position)?;
+        let character_attribute_is_present = attributes
+            .iter()
+            .any(|attr| attr.name == CHARACTER_ATTRIBUTE);
+        if character_attribute_is_present {
+            return Ok(ParsedMarkup { text, attributes });
+        }
+
+        // Attempt to generate a character attribute from the start
+        // of the string to the first colon
+        // ## Implementation note
+        // The original searches the raw input, which includes markup like `[b]Boss[/b]: Hello`.
+        // We search the plain text instead, so that the name does not contain markup
+        // and the attribute's length is measured in the same characters as every other attribute.
+        let Some(match_) = END_OF_CHARACTER_MARKER.find(&text) else {
+            return Ok(ParsedMarkup { text, attributes });
+        };
+
+        let character_name = text[..match_.start()].to_string();
+
+        let character_attribute = MarkupAttribute {
+            name: CHARACTER_ATTRIBUTE.to_string(),
+            position: 0,
+            length: text[..match_.end()].chars().count(),
+            properties: HashMap::from([(
+                CHARACTER_ATTRIBUTE_NAME_PROPERTY.to_string(),
+                character_name.into(),
+            )]),
+            source_position: 0,
+        };
+
+        attributes.push(character_attribute);
+        Ok(ParsedMarkup { text, attributes })
+    }
+
+    /// Finds the mistakes in the markup of a line, and where in the line they are.
+    ///
+    /// Like [`LineParser::parse_markup`], this stops at the first error. Every attribute that is still open at the end of the line
+    /// is reported as a [`MarkupParseError::UnclosedAttribute`], whatever the [`UnclosedAttributePolicy`].
+    ///
+    /// ## Implementation notes
+    ///
+    /// It allows the compiler to check markup with the same rules the runtime uses to parse it.
+    pub fn find_markup_issues(&mut self, input: &str) -> Vec<MarkupIssue> {
+        if input.is_empty() {
+            return Vec::new();
+        }
+
+        self.input = normalize(input);
+        self.source_position = 0;
+
+        let markers = match self.parse_markers() {
+            Ok((_text, markers)) => markers,
+            Err(issue) => return vec![issue],
+        };
+        match Self::pair_markers(markers) {
+            Ok((_attributes, unclosed_markers)) => unclosed_markers
+                .into_iter()
+                // The innermost marker is at the front
+                .rev()
+                .map(|open_marker| MarkupIssue {
+                    error: self.unclosed_attribute_error(&open_marker),
+                    source_range: open_marker.source_position..open_marker.source_end,
+                })
+                .collect(),
+            Err(close_marker) => vec![MarkupIssue {
+                source_range: close_marker.source_position..close_marker.source_end,
+                error: self.unmatched_close_marker_error(close_marker),
+            }],
+        }
+    }
+
+    /// Reads the entire input, separating the plain text from the markers in it.
+    fn parse_markers(
+        &mut self,
+    ) -> std::result::Result<(String, Vec<MarkupAttributeMarker>), MarkupIssue> {
         let mut text = String::new();
         let mut markers = Vec::new();
         let mut last_character = 0 as char;
@@ -103,70 +235,25 @@ impl LineParser {
         while let Some(character) = self.read_next() {
             match character {
                 '\\' => {
-                    // This may be the start of an escaped bracket ("\[" or "\]"). Peek ahead to see if it is.
+                    // This may be the start of an escaped bracket ("\[" or "\]") or backslash ("\\"). Peek ahead to see if it is.
                     if let Some(next_character) = self.peek_next() {
-                        if next_character == '[' || next_character == ']' {
+                        if next_character == '[' || next_character == ']' || next_character == '\\'
+                        {
                             // It is! We'll discard this '\', and read the next character as plain text.
                             let character = self.read_next().unwrap();
                             text.push(character);
                             continue;
                         }
                     }
-                    // It wasn't an escaped bracket. Continue on, and parse the '\' as a normal character.
+                    // It wasn't an escaped bracket or backslash. Continue on, and parse the '\' as a normal character.
                     text.push(character);
                 }
                 '[' => {
-                    // How long is our current string, in text elements (i.e. visible glyphs)?
-                    self.position = text.as_str().graphemes(true).count();
-
                     // The start of a marker!
-                    let mut marker = self.parse_attribute_marker()?;
-
-                    let had_preceding_whitespace_or_line_start =
-                        self.source_position == 0 || last_character.is_whitespace();
-
-                    // Is this a replacement marker?
-                    let was_replacement_marker = marker
-                        .name
-                        .as_ref()
-                        .map(|name| self.marker_processors.contains_key(name))
-                        .unwrap_or_default();
-                    if was_replacement_marker {
-                        // Process it and get the replacement text!
-                        let replacement_text = self.process_replacement_marker(&mut marker)?;
-
-                        // Insert it into our final string and update our position accordingly
-                        text.push_str(&replacement_text);
-                    }
-                    let mut trim_whitespace_if_able = false;
-                    if had_preceding_whitespace_or_line_start {
-                        // By default, self-closing markers will trim a single trailing whitespace after it if there was preceding whitespace.
-                        // This doesn't happen if the marker was a replacement marker, or it has a property "trimwhitespace" (which must be boolean) set to false.
-                        // All markers can opt-in to trailing whitespace trimming by having a 'trimwhitespace' property set to true.
-                        if marker.tag_type == TagType::SelfClosing {
-                            trim_whitespace_if_able = !was_replacement_marker;
-                        }
-                        if let Some(prop) = marker.properties.get(TRIM_WHITESPACE_PROPERTY) {
-                            let MarkupValue::Bool(trim_whitespace) = prop else {
-                                return Err(
-                                    MarkupParseError::TrimWhitespaceAttributeIsNotBoolean {
-                                        input: self.input.clone(),
-                                        name: marker.name,
-                                        position: self.position,
-                                        type_: prop.type_name().to_lowercase(),
-                                    },
-                                );
-                            };
-                            trim_whitespace_if_able = *trim_whitespace;
-                        }
-                    }
-                    if trim_whitespace_if_able {
-                        // If there's trailing whitespace, and we want to remove it, do so
-                        if let Some(true) = self.peek_whitespace() {
-                            // Consume the single trailing whitespace character (and don't update position)
-                            self.read_next();
-                        }
-                    }
+                    let marker_start = self.source_position - 1;
+                    let marker = self
+                        .parse_marker(&mut text, last_character)
+                        .map_err(|error| self.issue_since(marker_start, error))?;
                     markers.push(marker);
                 }
                 _ => {
@@ -179,38 +266,77 @@ impl LineParser {
             last_character = character;
         }
 
-        let mut attributes = self.build_attributes_from_markers(markers)?;
-        let character_attribute_is_present = attributes
-            .iter()
-            .any(|attr| attr.name == CHARACTER_ATTRIBUTE);
-        if character_attribute_is_present {
-            return Ok(ParsedMarkup { text, attributes });
-        }
-
-        // Attempt to generate a character attribute from the start
-        // of the string to the first colon
-        let Some(match_) = END_OF_CHARACTER_MARKER.find(&self.input) else {
-            return Ok(ParsedMarkup { text, attributes });
-        };
-
-        let character_name = self.input[..match_.start()].to_string();
+        Ok((text, markers))
+    }
 
-        let character_attribute = MarkupAttribute {
-            name: CHARACTER_ATTRIBUTE.to_string(),
-            position: 0,
-            length: match_.end(),
-            properties: HashMap::from([(
-                CHARACTER_ATTRIBUTE_NAME_PROPERTY.to_string(),
-                character_name.into(),
-            )]),
-            source_position: 0,
-        };
+    /// Parses the marker whose `[` was just read, pushing its replacement text, if any, onto `text`.
+    fn parse_marker(
+        &mut self,
+        text: &mut String,
+        last_character: char,
+    ) -> Result<MarkupAttributeMarker> {
+        // How long is our current string, in text elements (i.e. visible glyphs)?
+        self.position = text.as_str().graphemes(true).count();
+
+        let mut marker = self.parse_attribute_marker()?;
+
+        let had_preceding_whitespace_or_line_start =
+            self.source_position == 0 || last_character.is_whitespace();
+
+        // Is this a replacement marker?
+        let was_replacement_marker = marker
+            .name
+            .as_ref()
+            .map(|name| self.marker_processors.contains_key(name))
+            .unwrap_or_default();
+        if was_replacement_marker {
+            // Process it and get the replacement text!
+            let replacement_text = self.process_replacement_marker(&mut marker)?;
+
+            // Insert it into our final string and update our position accordingly
+            text.push_str(&replacement_text);
+        }
+        let mut trim_whitespace_if_able = false;
+        if had_preceding_whitespace_or_line_start {
+            // By default, self-closing markers will trim a single trailing whitespace after it if there was preceding whitespace.
+            // This doesn't happen if the marker was a replacement marker, or it has a property "trimwhitespace" (which must be boolean) set to false.
+            // All markers can opt-in to trailing whitespace trimming by having a 'trimwhitespace' property set to true.
+            if marker.tag_type == TagType::SelfClosing {
+                trim_whitespace_if_able = !was_replacement_marker;
+            }
+            if let Some(prop) = marker.properties.get(TRIM_WHITESPACE_PROPERTY) {
+                let MarkupValue::Bool(trim_whitespace) = prop else {
+                    return Err(MarkupParseError::TrimWhitespaceAttributeIsNotBoolean {
+                        input: self.input.clone(),
+                        name: marker.name,
+                        position: self.position,
+                        type_: prop.type_name().to_lowercase(),
+                    });
+                };
+                trim_whitespace_if_able = *trim_whitespace;
+            }
+        }
+        if trim_whitespace_if_able {
+            // If there's trailing whitespace, and we want to remove it, do so
+            if let Some(true) = self.peek_whitespace() {
+                // Consume the single trailing whitespace character (and don't update position)
+                self.read_next();
+            }
+        }
+        Ok(marker)
+    }
 
-        attributes.push(character_attribute);
-        Ok(ParsedMarkup { text, attributes })
+    /// Creates an issue for an error found while parsing the marker that starts at `marker_start`.
+    fn issue_since(&self, marker_start: usize, error: MarkupParseError) -> MarkupIssue {
+        let end = self.source_position.min(self.input.chars().count());
+        MarkupIssue {
+            error,
+            source_range: marker_start..end,
+        }
     }
 
-    pub(crate) fn set_language_code(&mut self, language_code: impl Into<Option<Language>>) {
+    /// Sets the language that the registered marker processors produce replacement text for.
+    pub fn set_language_code(&mut self, language_code: impl Into<Option<Language>>) {
         let language_code = language_code.into();
         for processor in self.marker_processors.values_mut() {
             processor.set_language_code(language_code.clone());
@@ -241,6 +367,7 @@ impl LineParser {
                     properties: HashMap::new(),
                     position: self.position,
                     source_position: source_position_at_marker_start,
+                    source_end: self.source_position,
                 });
             }
             // It's a named closing tag!
@@ -252,6 +379,7 @@ impl LineParser {
                 properties: HashMap::new(),
                 position: self.position,
                 source_position: source_position_at_marker_start,
+                source_end: self.source_position,
             });
         }
 
@@ -298,6 +426,7 @@ impl LineParser {
                         properties,
                         position: self.position,
                         source_position: source_position_at_marker_start,
+                        source_end: self.source_position,
                     });
                 }
                 '/' => {
@@ -310,6 +439,7 @@ impl LineParser {
                         properties,
                         position: self.position,
                         source_position: source_position_at_marker_start,
+                        source_end: self.source_position,
                     });
                 }
                 _ => {
@@ -376,11 +506,49 @@ impl LineParser {
     ///
     /// ## Retuns
     ///
-    /// Returns an `Err` when a close marker is encountered, but no corresponding open marker for it exists.
+    /// Returns an `Err` when a close marker is encountered, but no corresponding open marker for it exists,
+    /// or when a marker is left open at `end_position` and the [`UnclosedAttributePolicy`] is [`UnclosedAttributePolicy::Error`].
     fn build_attributes_from_markers(
         &self,
         markers: Vec<MarkupAttributeMarker>,
+        end_position: usize,
     ) -> Result<Vec<MarkupAttribute>> {
+        let (mut attributes, unclosed_markers) = Self::pair_markers(markers)
+            .map_err(|close_marker| self.unmatched_close_marker_error(close_marker))?;
+
+        match self.unclosed_attribute_policy {
+            UnclosedAttributePolicy::Drop => {}
+            UnclosedAttributePolicy::AutoClose => {
+                let attributes_to_add = unclosed_markers.into_iter().map(|open_marker| {
+                    let length = end_position - open_marker.position;
+                    MarkupAttribute::from_marker(open_marker, length)
+                });
+                attributes.extend(attributes_to_add);
+            }
+            UnclosedAttributePolicy::Error => {
+                // The innermost marker is at the front
+                if let Some(open_marker) = unclosed_markers.front() {
+                    return Err(self.unclosed_attribute_error(open_marker));
+                }
+            }
+        }
+
+        attributes.sort_by_key(|attribute| attribute.source_position);
+        Ok(attributes)
+    }
+
+    /// Pairs the open markers with the markers closing them, creating a [`MarkupAttribute`] for every pair and self-closing marker.
+    ///
+    /// ## Retuns
+    ///
+    /// The attributes and the markers that are still open, with the innermost one at the front,
+    /// or the first close marker for which no corresponding open marker exists.
+    fn pair_markers(
+        markers: Vec<MarkupAttributeMarker>,
+    ) -> std::result::Result<
+        (Vec<MarkupAttribute>, VecDeque<MarkupAttributeMarker>),
+        MarkupAttributeMarker,
+    > {
         let mut unclosed_markers = VecDeque::new();
         let mut attributes = Vec::with_capacity(markers.len());
         for marker in markers {
@@ -396,14 +564,12 @@ impl LineParser {
                     // unclosed stack to find the most recent
                     // marker of the same type to find its pair.
                     assert!(marker.name.is_some());
-                    let matched_open_marker_index = unclosed_markers
+                    let Some(matched_open_marker_index) = unclosed_markers
                         .iter()
                         .position(|open_marker| open_marker.name == marker.name)
-                        .ok_or_else(|| MarkupParseError::UnmatchedCloseMarker {
-                            input: self.input.clone(),
-                            name: marker.name.unwrap(),
-                            position: marker.position,
-                        })?;
+                    else {
+                        return Err(marker);
+                    };
 
                     // This attribute is now closed, so we can
                     // remove the marker from the unmatched list
@@ -422,26 +588,38 @@ impl LineParser {
                     attributes.push(attribute);
                 }
                 TagType::CloseAll => {
-                    // Close all currently open markers
-
-                    // For each marker that we currently have open,
-                    // this marker has closed it, so create an
-                    // attribute for it
-                    let attributes_to_add = unclosed_markers.iter().map(|open_marker| {
+                    // Close the innermost open marker, which is at
+                    // the front of the unclosed list. If nothing is
+                    // open, there is nothing to close.
+                    if let Some(open_marker) = unclosed_markers.pop_front() {
                         let length = marker.position - open_marker.position;
-                        MarkupAttribute::from_marker(open_marker.clone(), length)
-                    });
-                    attributes.extend(attributes_to_add);
-
-                    // We've now closed all markers, so we can
-                    // clear the unclosed list now
-                    unclosed_markers.clear();
+                        let attribute = MarkupAttribute::from_marker(open_marker, length);
+                        attributes.push(attribute);
+                    }
                 }
             }
         }
 
-        attributes.sort_by_key(|attribute| attribute.source_position);
-        Ok(attributes)
+        Ok((attributes, unclosed_markers))
+    }
+
+    fn unmatched_close_marker_error(
+        &self,
+        close_marker: MarkupAttributeMarker,
+    ) -> MarkupParseError {
+        MarkupParseError::UnmatchedCloseMarker {
+            input: self.input.clone(),
+            name: close_marker.name.unwrap(),
+            position: close_marker.position,
+        }
+    }
+
+    fn unclosed_attribute_error(&self, open_marker: &MarkupAttributeMarker) -> MarkupParseError {
+        MarkupParseError::UnclosedAttribute {
+            input: self.input.clone(),
+            name: open_marker.name.clone().unwrap_or_default(),
+            position: open_marker.position,
+        }
     }
 
     fn read_next(&mut self) -> Option<char> {
@@ -721,12 +899,15 @@ impl LineParser {
 }
 
 /// Returns a new string whose textual value is the same as this string, but whose binary representation is in Unicode normalization form C.
-pub(crate) fn normalize(string: &str) -> String {
+pub fn normalize(string: &str) -> String {
     string.nfc().to_string()
 }
 
 /// The name of the property in replacement attributes that contains the text of the attribute.
-pub(crate) const REPLACEMENT_MARKER_CONTENTS: &str = "contents";
+pub const REPLACEMENT_MARKER_CONTENTS: &str = "contents";
+
+/// The name of the attribute whose contents are not parsed for markup.
+pub const NO_MARKUP_ATTRIBUTE: &str = "nomarkup";
 
 /// The name of the implicitly-generated `character` attribute.
 pub const CHARACTER_ATTRIBUTE: &str = "character";
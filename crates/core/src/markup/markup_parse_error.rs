@@ -49,6 +49,11 @@ pub enum MarkupParseError {
         name: String,
         position: usize,
     },
+    UnclosedAttribute {
+        input: String,
+        name: String,
+        position: usize,
+    },
 }
 
 impl Error for MarkupParseError {}
@@ -79,6 +84,11 @@ impl fmt::Display for MarkupParseError {
                 name,
                 position,
             } => write!(f, "Unterminated marker {name} in line {input} at position {position}"),
+            UnclosedAttribute {
+                input,
+                name,
+                position,
+            } => write!(f, "Attribute {name} opened at position {position} is never closed in line {input}"),
         }
     }
 }
@@ -10,10 +10,10 @@ use std::collections::HashMap;
 /// Represents a range of text in a marked-up string.
 ///
 /// You do not create instances of this struct yourself.
-/// It is created by [`Dialogue`] in [`Dialogue::continue_`] and passed to you through a [`DialogueEvent`].
+/// It is created by the runtime's `Dialogue` in `Dialogue::continue_` and passed to you through a `DialogueEvent`.
 ///
 /// ## See also
-/// - [`Line`]
+/// - The runtime's `Line`
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "bevy", derive(Reflect))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
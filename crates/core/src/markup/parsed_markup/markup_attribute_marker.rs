@@ -7,18 +7,20 @@ use std::collections::HashMap;
 /// Represents a marker (e.g. `[a]`) in line of marked up text.
 ///
 /// You do not create instances of this struct yourself. It is created
-/// by objects that can parse markup, such as [`Dialogue`].
+/// by objects that can parse markup, such as the [`LineParser`](crate::markup::LineParser).
 #[derive(Debug, Clone, PartialEq)]
-pub(crate) struct MarkupAttributeMarker {
+pub struct MarkupAttributeMarker {
     /// The name of the marker.
     /// For example, the marker `[wave]` has the name `wave`.
-    pub(crate) name: Option<String>,
+    pub name: Option<String>,
     /// The position of the marker in the plain text.
-    pub(crate) position: usize,
+    pub position: usize,
     /// The list of properties associated with this marker.
-    pub(crate) properties: HashMap<String, MarkupValue>,
+    pub properties: HashMap<String, MarkupValue>,
     /// The type of marker that this is.
-    pub(crate) tag_type: TagType,
+    pub tag_type: TagType,
     /// The position of this marker in the original source text.
-    pub(crate) source_position: usize,
+    pub source_position: usize,
+    /// The position in the original source text right after this marker.
+    pub source_end: usize,
 }
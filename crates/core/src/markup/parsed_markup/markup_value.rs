@@ -8,7 +8,7 @@ use core::fmt::Display;
 /// A value associated with a markup name.
 ///
 /// You do not create instances of this struct yourself. It is created
-/// by objects that can parse markup, such as [`Dialogue`]
+/// by objects that can parse markup, such as the runtime's `Dialogue`
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "bevy", derive(Reflect))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -1,15 +1,15 @@
 //! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner/YarnSpinner.Markup/MarkupParseResult.cs>
 //! which was split into multiple files.
 
-/// A type of [`MarkupAttributeMarker`].
+/// A type of [`MarkupAttributeMarker`](crate::markup::MarkupAttributeMarker).
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
-pub(crate) enum TagType {
+pub enum TagType {
     /// An open marker. For example, `[a]`.
     Open,
     /// A closing marker. For example, `[/a]`.
     Close,
     /// A self-closing marker. For example, `[a/]`.
     SelfClosing,
-    /// The close-all marker, `[/]`.
+    /// The close marker without a name, `[/]`, which closes the innermost open marker.
     CloseAll,
 }
@@ -2,7 +2,9 @@
 use crate::prelude::*;
 
 /// Represents a position in a multi-line string.
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+///
+/// Positions are ordered by [`Position::line`] first and [`Position::character`] second, i.e. in the order they appear in the string.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
 #[cfg_attr(feature = "bevy", derive(Reflect))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "bevy", reflect(Debug, PartialEq, Hash))]
@@ -18,3 +20,31 @@ pub struct Position {
     /// Careful: This represents a unicode code point, not a byte, i.e. what you'd get with `string.chars().nth(character)`.
     pub character: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sorts_by_line_then_character() {
+        let position = |line, character| Position { line, character };
+        let mut positions = vec![
+            position(2, 0),
+            position(0, 7),
+            position(1, 3),
+            position(0, 2),
+            position(1, 0),
+        ];
+        positions.sort();
+        assert_eq!(
+            vec![
+                position(0, 2),
+                position(0, 7),
+                position(1, 0),
+                position(1, 3),
+                position(2, 0),
+            ],
+            positions
+        );
+    }
+}
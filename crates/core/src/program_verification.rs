@@ -0,0 +1,329 @@
+//! Consistency checks for compiled [`Program`]s.
+
+use crate::prelude::*;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+impl Program {
+    /// Checks the program for internal inconsistencies and returns every one found. An empty vector means the program is consistent.
+    ///
+    /// The following is checked:
+    /// - Every label of a node points to an instruction index within the node.
+    /// - Every label referenced by an instruction exists in the node.
+    /// - Every node name that is pushed right before a [`OpCode::RunNode`] refers to a node of this program.
+    /// - Every instruction has a valid opcode and the number and types of operands its opcode expects.
+    ///
+    /// Except for [`VerificationError::UnknownNode`], these errors can only be caused by a bug in the compiler.
+    /// Unknown nodes can also be legitimate when the missing node is added to the dialogue from another program later on.
+    pub fn verify(&self) -> Vec<VerificationError> {
+        let mut node_names: Vec<_> = self.nodes.keys().collect();
+        // Sort for deterministic output
+        node_names.sort();
+        node_names
+            .into_iter()
+            .flat_map(|node_name| self.verify_node(&self.nodes[node_name]))
+            .collect()
+    }
+
+    fn verify_node(&self, node: &Node) -> Vec<VerificationError> {
+        let mut errors = Vec::new();
+        let mut labels: Vec<_> = node.labels.iter().collect();
+        labels.sort();
+        for (label, &index) in labels {
+            let is_in_bounds = usize::try_from(index)
+                .map(|index| index <= node.instructions.len())
+                .unwrap_or_default();
+            if !is_in_bounds {
+                errors.push(VerificationError::LabelOutOfBounds {
+                    node_name: node.name.clone(),
+                    label: label.clone(),
+                    index,
+                });
+            }
+        }
+
+        for (instruction_index, instruction) in node.instructions.iter().enumerate() {
+            let Ok(opcode) = OpCode::try_from(instruction.opcode) else {
+                errors.push(VerificationError::InvalidOpCode {
+                    node_name: node.name.clone(),
+                    instruction_index,
+                    opcode: InvalidOpCodeError(instruction.opcode),
+                });
+                continue;
+            };
+            if !has_expected_operands(opcode, &instruction.operands) {
+                errors.push(VerificationError::InvalidOperands {
+                    node_name: node.name.clone(),
+                    instruction_index,
+                    opcode,
+                    operands: instruction.operands.clone(),
+                });
+                continue;
+            }
+            let referenced_label = match opcode {
+                OpCode::JumpTo | OpCode::JumpIfFalse => Some(&instruction.operands[0]),
+                OpCode::AddOption => Some(&instruction.operands[1]),
+                _ => None,
+            };
+            if let Some(Some(OperandValue::StringValue(label))) =
+                referenced_label.map(|operand| &operand.value)
+            {
+                if !node.labels.contains_key(label) {
+                    errors.push(VerificationError::UnknownLabel {
+                        node_name: node.name.clone(),
+                        instruction_index,
+                        label: label.clone(),
+                    });
+                }
+            }
+            if opcode == OpCode::RunNode {
+                let previous_instruction = instruction_index
+                    .checked_sub(1)
+                    .map(|index| &node.instructions[index]);
+                if let Some(target_node) = previous_instruction
+                    .filter(|previous| previous.opcode == OpCode::PushString as i32)
+                    .and_then(|previous| previous.operands.first())
+                    .and_then(|operand| match &operand.value {
+                        Some(OperandValue::StringValue(target_node)) => Some(target_node),
+                        _ => None,
+                    })
+                {
+                    if !self.nodes.contains_key(target_node) {
+                        errors.push(VerificationError::UnknownNode {
+                            node_name: node.name.clone(),
+                            instruction_index,
+                            target_node: target_node.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        errors
+    }
+}
+
+/// Returns whether the operands match the ones emitted by the compiler for the given opcode.
+/// See the documentation of [`OpCode`] for the meaning of each operand.
+fn has_expected_operands(opcode: OpCode, operands: &[Operand]) -> bool {
+    use OperandValue::*;
+    let operand_values: Vec<_> = operands.iter().map(|operand| &operand.value).collect();
+    match opcode {
+        OpCode::Jump
+        | OpCode::ShowOptions
+        | OpCode::PushNull
+        | OpCode::Pop
+        | OpCode::Stop
+        | OpCode::RunNode => operand_values.is_empty(),
+        OpCode::JumpTo
+        | OpCode::JumpIfFalse
+        | OpCode::PushString
+        | OpCode::CallFunc
+        | OpCode::PushVariable
+        | OpCode::StoreVariable => matches!(operand_values[..], [Some(StringValue(_))]),
+        OpCode::PushFloat => matches!(operand_values[..], [Some(FloatValue(_))]),
+        OpCode::PushBool => matches!(operand_values[..], [Some(BoolValue(_))]),
+        OpCode::RunLine | OpCode::RunCommand => matches!(
+            operand_values[..],
+            [Some(StringValue(_)), Some(FloatValue(_))]
+        ),
+        OpCode::AddOption => matches!(
+            operand_values[..],
+            [
+                Some(StringValue(_)),
+                Some(StringValue(_)),
+                Some(FloatValue(_)),
+                Some(BoolValue(_))
+            ]
+        ),
+    }
+}
+
+/// An inconsistency found by [`Program::verify`].
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerificationError {
+    /// A label of a node points outside of the node's instructions.
+    LabelOutOfBounds {
+        node_name: String,
+        label: String,
+        index: i32,
+    },
+    /// An instruction jumps to a label that does not exist in its node.
+    UnknownLabel {
+        node_name: String,
+        instruction_index: usize,
+        label: String,
+    },
+    /// An instruction runs a node that does not exist in the program.
+    UnknownNode {
+        node_name: String,
+        instruction_index: usize,
+        target_node: String,
+    },
+    /// An instruction's opcode is not a valid [`OpCode`].
+    InvalidOpCode {
+        node_name: String,
+        instruction_index: usize,
+        opcode: InvalidOpCodeError,
+    },
+    /// An instruction's operands do not match the ones its [`OpCode`] expects.
+    InvalidOperands {
+        node_name: String,
+        instruction_index: usize,
+        opcode: OpCode,
+        operands: Vec<Operand>,
+    },
+}
+
+impl VerificationError {
+    /// Returns `true` if this error can only be caused by a bug in the compiler,
+    /// i.e. everything except [`VerificationError::UnknownNode`].
+    pub fn indicates_compiler_bug(&self) -> bool {
+        !matches!(self, VerificationError::UnknownNode { .. })
+    }
+}
+
+impl Error for VerificationError {}
+
+impl Display for VerificationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerificationError::LabelOutOfBounds {
+                node_name,
+                label,
+                index,
+            } => write!(
+                f,
+                "Label {label} in node {node_name} points to instruction {index}, which is out of bounds"
+            ),
+            VerificationError::UnknownLabel {
+                node_name,
+                instruction_index,
+                label,
+            } => write!(
+                f,
+                "Instruction {instruction_index} in node {node_name} references unknown label {label}"
+            ),
+            VerificationError::UnknownNode {
+                node_name,
+                instruction_index,
+                target_node,
+            } => write!(
+                f,
+                "Instruction {instruction_index} in node {node_name} runs unknown node {target_node}"
+            ),
+            VerificationError::InvalidOpCode {
+                node_name,
+                instruction_index,
+                opcode,
+            } => write!(f, "Instruction {instruction_index} in node {node_name}: {opcode}"),
+            VerificationError::InvalidOperands {
+                node_name,
+                instruction_index,
+                opcode,
+                operands,
+            } => write!(
+                f,
+                "Instruction {instruction_index} in node {node_name} has invalid operands for {}: {operands:?}",
+                opcode.as_str_name()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn accepts_consistent_program() {
+        let program = program_with_node(
+            vec![
+                instruction(OpCode::JumpTo, vec!["end".to_owned().into()]),
+                instruction(
+                    OpCode::RunLine,
+                    vec!["line:0".to_owned().into(), 0_usize.into()],
+                ),
+                instruction(OpCode::PushString, vec!["Start".to_owned().into()]),
+                instruction(OpCode::RunNode, vec![]),
+                instruction(OpCode::Stop, vec![]),
+            ],
+            [("end".to_owned(), 4)],
+        );
+        assert_eq!(Vec::<VerificationError>::new(), program.verify());
+    }
+
+    #[test]
+    fn finds_inconsistencies() {
+        let program = program_with_node(
+            vec![
+                instruction(OpCode::JumpTo, vec!["nowhere".to_owned().into()]),
+                instruction(OpCode::RunLine, vec!["line:0".to_owned().into()]),
+                instruction(OpCode::PushString, vec!["Missing".to_owned().into()]),
+                instruction(OpCode::RunNode, vec![]),
+                Instruction {
+                    opcode: 1000,
+                    operands: vec![],
+                },
+            ],
+            [("end".to_owned(), 6)],
+        );
+        let errors = program.verify();
+        assert_eq!(
+            vec![
+                VerificationError::LabelOutOfBounds {
+                    node_name: "Start".to_owned(),
+                    label: "end".to_owned(),
+                    index: 6,
+                },
+                VerificationError::UnknownLabel {
+                    node_name: "Start".to_owned(),
+                    instruction_index: 0,
+                    label: "nowhere".to_owned(),
+                },
+                VerificationError::InvalidOperands {
+                    node_name: "Start".to_owned(),
+                    instruction_index: 1,
+                    opcode: OpCode::RunLine,
+                    operands: vec!["line:0".to_owned().into()],
+                },
+                VerificationError::UnknownNode {
+                    node_name: "Start".to_owned(),
+                    instruction_index: 3,
+                    target_node: "Missing".to_owned(),
+                },
+                VerificationError::InvalidOpCode {
+                    node_name: "Start".to_owned(),
+                    instruction_index: 4,
+                    opcode: InvalidOpCodeError(1000),
+                },
+            ],
+            errors
+        );
+        assert!(!errors[3].indicates_compiler_bug());
+    }
+
+    fn instruction(opcode: OpCode, operands: Vec<Operand>) -> Instruction {
+        Instruction {
+            opcode: opcode.into(),
+            operands,
+        }
+    }
+
+    fn program_with_node(
+        instructions: Vec<Instruction>,
+        labels: impl IntoIterator<Item = (String, i32)>,
+    ) -> Program {
+        let node = Node {
+            name: "Start".to_owned(),
+            instructions,
+            labels: labels.into_iter().collect(),
+            ..Default::default()
+        };
+        Program {
+            nodes: HashMap::from([("Start".to_owned(), node)]),
+            ..Default::default()
+        }
+    }
+}
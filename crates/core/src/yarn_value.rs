@@ -1,8 +1,15 @@
 //! Implements a subset of dotnet's [`Convert`](https://learn.microsoft.com/en-us/dotnet/api/system.convert?view=net-8.0) type.
+use crate::prelude::Operator;
 #[cfg(any(feature = "bevy", feature = "serde"))]
 use crate::prelude::*;
+use crate::types::{Type, TypedValue};
+use fixed_decimal::FixedDecimal;
+use icu_decimal::FixedDecimalFormatter;
+use icu_locid::LanguageIdentifier;
+use std::convert::Infallible;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 /// Represents a Yarn value. The chosen variant corresponds to the last assignment of the value,
 /// with the type being inferred from the type checker.
@@ -49,6 +56,111 @@ impl YarnValue {
             (a, b) => a == b,
         }
     }
+
+    /// Converts the value to a string for display to a player using the conventions of the given language,
+    /// which is expected to be an IETF BCP 47 code such as `"de-DE"`.
+    ///
+    /// [`YarnValue::Number`]s use the decimal and grouping separators of the language as found in the CLDR,
+    /// e.g. `1500.5` is formatted as `"1.500,5"` in German and as `"1’500.5"` in Swiss German.
+    /// The digits are the same as those of the [`Display`] implementation.
+    /// All other values, and numbers in languages that cannot be parsed, are formatted the same way as their [`Display`] implementation.
+    ///
+    /// ## Implementation Notes
+    ///
+    /// The original formats numbers via `ToString(CultureInfo)`, which does not group thousands.
+    /// We group them as the locale usually does, so that e.g. `"10000"` becomes `"10,000"` in English.
+    pub fn format_with_locale(&self, language_code: &str) -> String {
+        let text = self.to_string();
+        let Self::Number(value) = self else {
+            return text;
+        };
+        if !value.is_finite() {
+            return text;
+        }
+        let Ok(language) = language_code
+            .replace('_', "-")
+            .parse::<LanguageIdentifier>()
+        else {
+            return text;
+        };
+        let (Ok(formatter), Ok(decimal)) = (
+            FixedDecimalFormatter::try_new(&language.into(), Default::default()),
+            text.parse::<FixedDecimal>(),
+        ) else {
+            return text;
+        };
+        formatter.format_to_string(&decimal)
+    }
+
+    /// Returns the number if this is a [`YarnValue::Number`]. Unlike [`f32::try_from`], other variants are not converted.
+    pub fn as_number(&self) -> Option<f32> {
+        match self {
+            Self::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the string if this is a [`YarnValue::String`]. Unlike [`String::from`], other variants are not converted.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the boolean if this is a [`YarnValue::Boolean`]. Unlike [`bool::try_from`], other variants are not converted.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Adds two [`YarnValue::Number`]s or concatenates two [`YarnValue::String`]s, the same way `+` does in Yarn.
+    /// Returns an error for all other combinations of variants.
+    pub fn checked_add(&self, other: &Self) -> Result<Self, YarnValueTypeError> {
+        match (self, other) {
+            (Self::String(lhs), Self::String(rhs)) => Ok(Self::String(format!("{lhs}{rhs}"))),
+            _ => self.number_operation(other, Operator::Add, |lhs, rhs| lhs + rhs),
+        }
+    }
+
+    /// Subtracts a [`YarnValue::Number`] from another. Returns an error if either value is not a number.
+    pub fn checked_sub(&self, other: &Self) -> Result<Self, YarnValueTypeError> {
+        self.number_operation(other, Operator::Subtract, |lhs, rhs| lhs - rhs)
+    }
+
+    /// Multiplies two [`YarnValue::Number`]s. Returns an error if either value is not a number.
+    pub fn checked_mul(&self, other: &Self) -> Result<Self, YarnValueTypeError> {
+        self.number_operation(other, Operator::Multiply, |lhs, rhs| lhs * rhs)
+    }
+
+    /// Divides a [`YarnValue::Number`] by another. Returns an error if either value is not a number.
+    /// Like in Yarn, dividing by zero is not an error but results in an infinite or NaN number.
+    pub fn checked_div(&self, other: &Self) -> Result<Self, YarnValueTypeError> {
+        self.number_operation(other, Operator::Divide, |lhs, rhs| lhs / rhs)
+    }
+
+    /// Calculates the remainder of dividing a [`YarnValue::Number`] by another. Returns an error if either value is not a number.
+    pub fn checked_rem(&self, other: &Self) -> Result<Self, YarnValueTypeError> {
+        self.number_operation(other, Operator::Modulo, |lhs, rhs| lhs % rhs)
+    }
+
+    fn number_operation(
+        &self,
+        other: &Self,
+        operator: Operator,
+        operation: impl FnOnce(f32, f32) -> f32,
+    ) -> Result<Self, YarnValueTypeError> {
+        match (self.as_number(), other.as_number()) {
+            (Some(lhs), Some(rhs)) => Ok(Self::Number(operation(lhs, rhs))),
+            _ => Err(YarnValueTypeError {
+                operator,
+                lhs: self.r#type(),
+                rhs: other.r#type(),
+            }),
+        }
+    }
 }
 
 impl<T> From<&T> for YarnValue
@@ -251,6 +363,33 @@ impl From<std::str::ParseBoolError> for YarnValueCastError {
     }
 }
 
+/// Represents an arithmetic operation on two [`YarnValue`]s whose variants the operation is not defined for,
+/// e.g. adding a [`YarnValue::Number`] to a [`YarnValue::String`] via [`YarnValue::checked_add`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YarnValueTypeError {
+    /// The operation that was attempted.
+    pub operator: Operator,
+    /// The type of the left operand.
+    pub lhs: Type,
+    /// The type of the right operand.
+    pub rhs: Type,
+}
+
+impl Error for YarnValueTypeError {}
+
+impl Display for YarnValueTypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Cannot apply operator {} to values of type {} and {}",
+            self.operator, self.lhs, self.rhs
+        )
+    }
+}
+
+/// Formats the value for developer-facing output such as logs, e.g. `3` or `1.5` for numbers, `true` or `false` for booleans
+/// and the raw text for strings. Integral numbers are written without a trailing `.0`.
+/// Unlike [`YarnValue::format_with_locale`], this does not depend on the locale.
 impl Display for YarnValue {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -260,3 +399,138 @@ impl Display for YarnValue {
         }
     }
 }
+
+/// Infers the variant from the text, e.g. for input from debug consoles or config files:
+/// - Text that parses to a finite number becomes a [`YarnValue::Number`], e.g. `42` or `-1.5`.
+/// - `true` and `false` become a [`YarnValue::Boolean`].
+/// - Everything else becomes a [`YarnValue::String`].
+///
+/// To force a string, wrap the text in double quotes, e.g. `"42"`. The quotes are not part of the resulting string.
+impl FromStr for YarnValue {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(quoted) = s.strip_prefix('"').and_then(|rest| rest.strip_suffix('"')) {
+            return Ok(Self::String(quoted.to_owned()));
+        }
+        if let Some(number) = s.parse::<f32>().ok().filter(|number| number.is_finite()) {
+            return Ok(Self::Number(number));
+        }
+        if let Ok(boolean) = s.parse::<bool>() {
+            return Ok(Self::Boolean(boolean));
+        }
+        Ok(Self::String(s.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_numbers_with_locale_decimal_separator() {
+        let value = YarnValue::Number(1.5);
+        assert_eq!("1.5", value.format_with_locale("en-US"));
+        assert_eq!("1,5", value.format_with_locale("de-DE"));
+        assert_eq!("1,5", value.format_with_locale("pt_BR"));
+        assert_eq!("1.5", value.format_with_locale("de-CH"));
+        assert_eq!("0,1", YarnValue::Number(0.1).format_with_locale("fr"));
+    }
+
+    #[test]
+    fn formats_numbers_with_locale_grouping_separator() {
+        let value = YarnValue::Number(1500.5);
+        assert_eq!("1,500.5", value.format_with_locale("en-US"));
+        assert_eq!("1.500,5", value.format_with_locale("de-DE"));
+        assert_eq!("1’500.5", value.format_with_locale("de-CH"));
+        assert_eq!(
+            "-10,000",
+            YarnValue::Number(-10000.0).format_with_locale("en")
+        );
+    }
+
+    #[test]
+    fn formats_numbers_in_unparsable_languages_without_locale() {
+        let value = YarnValue::Number(1500.5);
+        assert_eq!("1500.5", value.format_with_locale("not a language"));
+    }
+
+    #[test]
+    fn formats_non_numbers_independent_of_locale() {
+        assert_eq!("a.b", YarnValue::from("a.b").format_with_locale("de-DE"));
+        assert_eq!("true", YarnValue::from(true).format_with_locale("de-DE"));
+    }
+
+    #[test]
+    fn displays_integral_number_without_fraction() {
+        assert_eq!("42", YarnValue::Number(42.0).to_string());
+        assert_eq!("-3", YarnValue::Number(-3.0).to_string());
+    }
+
+    #[test]
+    fn displays_fractional_number() {
+        assert_eq!("1.5", YarnValue::Number(1.5).to_string());
+    }
+
+    #[test]
+    fn displays_boolean() {
+        assert_eq!("true", YarnValue::Boolean(true).to_string());
+        assert_eq!("false", YarnValue::Boolean(false).to_string());
+    }
+
+    #[test]
+    fn displays_raw_string() {
+        assert_eq!(
+            "Hello, \"world\"",
+            YarnValue::from("Hello, \"world\"").to_string()
+        );
+    }
+
+    #[test]
+    fn adds_numbers() {
+        let sum = YarnValue::from(1.5).checked_add(&YarnValue::from(2));
+        assert_eq!(Ok(YarnValue::Number(3.5)), sum);
+        assert_eq!(Some(3.5), sum.unwrap().as_number());
+    }
+
+    #[test]
+    fn rejects_adding_number_and_string() {
+        let error = YarnValue::from(1)
+            .checked_add(&YarnValue::from("one"))
+            .unwrap_err();
+        assert_eq!(
+            YarnValueTypeError {
+                operator: Operator::Add,
+                lhs: Type::Number,
+                rhs: Type::String,
+            },
+            error
+        );
+    }
+
+    #[test]
+    fn parses_numbers() {
+        assert_eq!(Ok(YarnValue::Number(42.0)), "42".parse());
+        assert_eq!(Ok(YarnValue::Number(-1.5)), "-1.5".parse());
+    }
+
+    #[test]
+    fn parses_booleans() {
+        assert_eq!(Ok(YarnValue::Boolean(true)), "true".parse());
+        assert_eq!(Ok(YarnValue::Boolean(false)), "false".parse());
+    }
+
+    #[test]
+    fn parses_other_text_as_string() {
+        assert_eq!(Ok(YarnValue::from("hello")), "hello".parse());
+        assert_eq!(Ok(YarnValue::from("NaN")), "NaN".parse());
+        assert_eq!(Ok(YarnValue::from("")), "".parse());
+    }
+
+    #[test]
+    fn parses_quoted_text_as_string() {
+        assert_eq!(Ok(YarnValue::from("42")), "\"42\"".parse());
+        assert_eq!(Ok(YarnValue::from("true")), "\"true\"".parse());
+        assert_eq!(Ok(YarnValue::from("\"")), "\"".parse());
+    }
+}
@@ -24,7 +24,8 @@
 //! ```
 //!
 //! This crate also exposes the [`SpeakerChangeEvent`] which you can use to animate characters while they are speaking,
-//! as the text is written out over a few seconds.
+//! as the text is written out over a few seconds, and the [`TypewriterMarkerReachedEvent`] which you can use to trigger effects
+//! when the text reaches a markup attribute like `[shake]`.
 //!
 //! ## Inputs
 //!
@@ -43,20 +44,23 @@
 use bevy::prelude::*;
 use bevy_yarnspinner::prelude::YarnSpinnerPlugin;
 pub use setup::UiRootNode;
+pub use typewriter::{TypewriterMarkerReachedEvent, TypewriterMode};
 pub use updating::SpeakerChangeEvent;
 
 pub mod prelude {
     //! Everything you need to get starting using this example Yarn Spinner dialogue view.
     pub use crate::{
         ExampleYarnSpinnerDialogueViewPlugin, ExampleYarnSpinnerDialogueViewSystemSet,
-        SpeakerChangeEvent,
+        SpeakerChangeEvent, TypewriterMarkerReachedEvent, TypewriterMode,
     };
 }
 
 /// The plugin registering all systems of the dialogue view.
 #[derive(Debug, Default)]
 #[non_exhaustive]
-pub struct ExampleYarnSpinnerDialogueViewPlugin;
+pub struct ExampleYarnSpinnerDialogueViewPlugin {
+    typewriter_mode: TypewriterMode,
+}
 
 /// The [`SystemSet`] containing all systems added by the [`ExampleYarnSpinnerDialogueViewPlugin`].
 /// Is run after the [`YarnSpinnerSystemSet`](bevy_yarnspinner::prelude::YarnSpinnerSystemSet).
@@ -68,6 +72,13 @@ impl ExampleYarnSpinnerDialogueViewPlugin {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Sets how fast the text of lines is revealed. Defaults to [`TypewriterMode::FixedSpeed`].
+    #[must_use]
+    pub fn with_typewriter_mode(mut self, mode: TypewriterMode) -> Self {
+        self.typewriter_mode = mode;
+        self
+    }
 }
 
 mod assets;
@@ -82,7 +93,9 @@ impl Plugin for ExampleYarnSpinnerDialogueViewPlugin {
             app.is_plugin_added::<YarnSpinnerPlugin>(),
             "YarnSpinnerPlugin must be added before ExampleYarnSpinnerDialogueViewPlugin"
         );
-        app.add_plugins(assets::ui_assets_plugin)
+        app.insert_resource(self.typewriter_mode)
+            .register_type::<TypewriterMode>()
+            .add_plugins(assets::ui_assets_plugin)
             .add_plugins(setup::ui_setup_plugin)
             .add_plugins(updating::ui_updating_plugin)
             .add_plugins(typewriter::typewriter_plugin)
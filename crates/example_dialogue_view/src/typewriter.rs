@@ -5,6 +5,8 @@ use crate::ExampleYarnSpinnerDialogueViewSystemSet;
 use bevy::prelude::*;
 use bevy::utils::Instant;
 use bevy_yarnspinner::{events::*, prelude::*};
+use std::collections::HashMap;
+use std::time::Duration;
 use unicode_segmentation::UnicodeSegmentation;
 
 pub(crate) fn typewriter_plugin(app: &mut App) {
@@ -22,12 +24,40 @@ pub(crate) fn typewriter_plugin(app: &mut App) {
             .after(YarnSpinnerSystemSet)
             .in_set(ExampleYarnSpinnerDialogueViewSystemSet),
     )
-    .add_event::<TypewriterFinishedEvent>();
+    .add_event::<TypewriterFinishedEvent>()
+    .add_event::<TypewriterMarkerReachedEvent>()
+    .register_type::<TypewriterMarkerReachedEvent>();
 }
 
 #[derive(Debug, Eq, PartialEq, Hash, Reflect, Event)]
 pub(crate) struct TypewriterFinishedEvent;
 
+/// Signals that the typewriter revealed the text at which a markup attribute starts, e.g. the "there" in `Hello [shake]there[/shake]!`.
+/// Useful for triggering effects like a screen shake at a specific word.
+/// Every attribute of a line, except for the character name, is reached exactly once, even when the text is fast-forwarded.
+#[derive(Debug, Clone, PartialEq, Eq, Reflect, Event)]
+#[reflect(Debug, PartialEq)]
+#[non_exhaustive]
+pub struct TypewriterMarkerReachedEvent {
+    /// The name of the attribute, e.g. `shake` for `[shake]`.
+    pub name: String,
+    /// The properties of the attribute, e.g. `strength` with the value `2` for `[shake strength=2]`.
+    pub properties: HashMap<String, String>,
+}
+
+/// How fast the text of a line is revealed. Set it with [`ExampleYarnSpinnerDialogueViewPlugin::with_typewriter_mode`](crate::ExampleYarnSpinnerDialogueViewPlugin::with_typewriter_mode)
+/// or change it at runtime by modifying this resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Reflect, Resource)]
+#[reflect(Debug, PartialEq, Hash, Default, Resource)]
+pub enum TypewriterMode {
+    /// Reveal the text at a fixed speed, which can be adjusted per line with the `#pacing:` metadata.
+    #[default]
+    FixedSpeed,
+    /// Reveal the text so that it is complete right when the line's voice-over ends, see [`LocalizedLine::audio_duration`].
+    /// Lines without a known audio duration are revealed like in [`TypewriterMode::FixedSpeed`].
+    MatchAudio,
+}
+
 #[derive(Debug, Clone, PartialEq, Resource)]
 pub(crate) struct Typewriter {
     pub(crate) character_name: Option<String>,
@@ -37,6 +67,14 @@ pub(crate) struct Typewriter {
     elapsed: f32,
     start: Instant,
     fast_typing: bool,
+    /// Multiplier for the typing speed, read from the `#pacing:` metadata of the line.
+    pacing: f32,
+    /// Seconds to wait after the text is fully typed, read from the `#pause_after:` metadata of the line.
+    pause_left: f32,
+    /// The typing speed that makes the text finish together with the line's audio, if [`TypewriterMode::MatchAudio`] is used.
+    audio_synced_graphemes_per_second: Option<f32>,
+    /// The attributes of the line that were not reached yet, with the position of their first grapheme, ordered by position.
+    markers_left: Vec<(usize, TypewriterMarkerReachedEvent)>,
 }
 
 impl Default for Typewriter {
@@ -49,62 +87,143 @@ impl Default for Typewriter {
             elapsed: default(),
             start: Instant::now(),
             fast_typing: default(),
+            pacing: 1.0,
+            pause_left: default(),
+            audio_synced_graphemes_per_second: default(),
+            markers_left: default(),
         }
     }
 }
 
 impl Typewriter {
-    pub(crate) fn set_line(&mut self, line: &LocalizedLine) {
+    pub(crate) fn set_line(&mut self, line: &LocalizedLine, mode: TypewriterMode) {
+        let line_without_character_name = line.without_character_name();
+        let mut markers_left: Vec<_> = line_without_character_name
+            .attributes
+            .iter()
+            .map(|attribute| {
+                let properties = attribute
+                    .properties
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.to_string()))
+                    .collect();
+                let event = TypewriterMarkerReachedEvent {
+                    name: attribute.name.clone(),
+                    properties,
+                };
+                (attribute.position, event)
+            })
+            .collect();
+        markers_left.sort_by_key(|(position, _)| *position);
+        let graphemes_left: Vec<_> = line_without_character_name
+            .text
+            .graphemes(true)
+            .map(|s| s.to_string())
+            .collect();
+        let audio_synced_graphemes_per_second = match (mode, line.audio_duration()) {
+            (TypewriterMode::MatchAudio, Some(duration)) => {
+                graphemes_per_second_to_match(graphemes_left.len(), duration)
+            }
+            _ => None,
+        };
         *self = Self {
             character_name: line.character_name().map(|s| s.to_string()),
             current_text: String::new(),
-            graphemes_left: line
-                .text_without_character_name()
-                .graphemes(true)
-                .map(|s| s.to_string())
-                .collect(),
+            graphemes_left,
+            markers_left,
+            audio_synced_graphemes_per_second,
             last_before_options: line.is_last_line_before_options(),
+            pacing: match line.metadata_value("pacing") {
+                Some("slow") => 0.5,
+                Some("fast") => 2.0,
+                _ => 1.0,
+            },
+            pause_left: line
+                .metadata_value("pause_after")
+                .and_then(|seconds| seconds.parse::<f32>().ok())
+                .filter(|seconds| seconds.is_finite())
+                .unwrap_or_default()
+                .max(0.0),
             ..default()
         };
     }
 
     pub(crate) fn is_finished(&self) -> bool {
-        self.graphemes_left.is_empty() && !self.current_text.is_empty()
+        self.graphemes_left.is_empty() && !self.current_text.is_empty() && self.pause_left <= 0.0
+    }
+
+    /// The whole text of the line, including the graphemes that were not revealed yet, e.g. for measuring the layout in advance.
+    pub(crate) fn full_text(&self) -> String {
+        self.current_text.clone() + &self.remaining_text()
+    }
+
+    /// The text of the line that was not revealed yet.
+    pub(crate) fn remaining_text(&self) -> String {
+        self.graphemes_left.concat()
     }
 
     pub(crate) fn fast_forward(&mut self) {
         self.fast_typing = true;
+        self.pause_left = 0.0;
     }
 
-    fn update_current_text(&mut self) {
+    /// Reveals the graphemes due since the last update and returns the markers that were reached by that.
+    fn update_current_text(&mut self) -> Vec<TypewriterMarkerReachedEvent> {
         if self.is_finished() {
-            return;
+            return Vec::new();
         }
         self.elapsed += self.start.elapsed().as_secs_f32();
         self.start = Instant::now();
+        if self.graphemes_left.is_empty() && !self.current_text.is_empty() {
+            self.pause_left -= self.elapsed;
+            self.elapsed = 0.0;
+            return Vec::new();
+        }
         let calculated_graphemes = (self.graphemes_per_second() * self.elapsed).floor() as usize;
         let graphemes_left = self.graphemes_left.len();
         let grapheme_length_to_take = (calculated_graphemes).min(graphemes_left);
         self.elapsed -= grapheme_length_to_take as f32 / self.graphemes_per_second();
         let graphemes_to_take = self.graphemes_left.drain(..grapheme_length_to_take);
         self.current_text.extend(graphemes_to_take);
+
+        // A marker is reached when its first grapheme is revealed. Markers at the very end of the text have no grapheme, so they are reached when the text is complete.
+        let revealed_graphemes = self.current_text.graphemes(true).count();
+        let is_complete = self.graphemes_left.is_empty();
+        let reached_count = self
+            .markers_left
+            .iter()
+            .take_while(|(position, _)| *position < revealed_graphemes || is_complete)
+            .count();
+        self.markers_left
+            .drain(..reached_count)
+            .map(|(_, event)| event)
+            .collect()
     }
 
     fn graphemes_per_second(&self) -> f32 {
         if self.fast_typing {
             120.0
+        } else if let Some(graphemes_per_second) = self.audio_synced_graphemes_per_second {
+            graphemes_per_second
         } else {
-            40.0
+            40.0 * self.pacing
         }
     }
 }
 
+/// The typing speed at which `grapheme_count` graphemes are revealed in exactly `duration`.
+fn graphemes_per_second_to_match(grapheme_count: usize, duration: Duration) -> Option<f32> {
+    (grapheme_count > 0 && !duration.is_zero())
+        .then(|| grapheme_count as f32 / duration.as_secs_f32())
+}
+
 fn write_text(
     mut commands: Commands,
     mut text: Query<Entity, With<DialogueNode>>,
     mut typewriter: ResMut<Typewriter>,
     option_selection: Option<Res<OptionSelection>>,
     mut speaker_change_events: EventWriter<SpeakerChangeEvent>,
+    mut marker_reached_events: EventWriter<TypewriterMarkerReachedEvent>,
     mut root_visibility: Query<&mut Visibility, With<UiRootNode>>,
 ) {
     let mut text_entity = commands.entity(text.single_mut());
@@ -119,7 +238,7 @@ fn write_text(
         *root_visibility.single_mut() = Visibility::Inherited;
         // If this is last before options, the `OptionSelection` will make the visibility inherited as soon as it's ready instead
     }
-    typewriter.update_current_text();
+    marker_reached_events.send_batch(typewriter.update_current_text());
     if typewriter.is_finished() {
         if let Some(name) = typewriter.character_name.as_deref() {
             speaker_change_events.send(SpeakerChangeEvent {
@@ -130,7 +249,7 @@ fn write_text(
     }
 
     let current_text = &typewriter.current_text;
-    let rest = typewriter.graphemes_left.join("");
+    let rest = typewriter.remaining_text();
     let spans = create_dialog_text(current_text, rest);
     text_entity.despawn_descendants().with_children(|parent| {
         parent.spawn(spans[0].clone());
@@ -185,3 +304,103 @@ fn send_finished_event(
         *last_finished = true;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_yarnspinner::UnderlyingYarnLine;
+
+    #[test]
+    fn matches_typing_speed_to_audio_duration() {
+        let mut line = LocalizedLine::from_yarn_line(
+            UnderlyingYarnLine {
+                id: "line:1".into(),
+                text: "Alice: Hello there".to_owned(),
+                attributes: vec![],
+            },
+            default(),
+            vec![],
+        )
+        .with_audio_duration(Some(Duration::from_millis(2500)));
+        let mut typewriter = Typewriter::default();
+        typewriter.set_line(&line, TypewriterMode::MatchAudio);
+        // "Alice: Hello there" has 18 graphemes, as there is no character attribute in this line
+        assert_eq!(18.0 / 2.5, typewriter.graphemes_per_second());
+
+        typewriter.set_line(&line, TypewriterMode::FixedSpeed);
+        assert_eq!(40.0, typewriter.graphemes_per_second());
+
+        line = line.with_audio_duration(None);
+        typewriter.set_line(&line, TypewriterMode::MatchAudio);
+        assert_eq!(40.0, typewriter.graphemes_per_second());
+    }
+
+    #[test]
+    fn reports_reaching_markers_once() {
+        let line = LocalizedLine::from_yarn_line(
+            UnderlyingYarnLine {
+                id: "line:1".into(),
+                text: "Hello there!".to_owned(),
+                attributes: vec![MarkupAttribute {
+                    name: "shake".to_owned(),
+                    position: 6,
+                    length: 5,
+                    properties: [("strength".to_owned(), MarkupValue::Integer(2))].into(),
+                    source_position: 6,
+                }],
+            },
+            default(),
+            vec![],
+        );
+        let mut typewriter = Typewriter::default();
+        typewriter.set_line(&line, TypewriterMode::FixedSpeed);
+
+        // At 40 graphemes per second, this reveals "Hell"
+        typewriter.elapsed = 0.11;
+        assert!(typewriter.update_current_text().is_empty());
+
+        // This reveals "o t", including the first grapheme of the marker
+        typewriter.elapsed += 0.08;
+        let events = typewriter.update_current_text();
+        assert_eq!("Hello t", typewriter.current_text);
+        assert_eq!(
+            vec![TypewriterMarkerReachedEvent {
+                name: "shake".to_owned(),
+                properties: [("strength".to_owned(), "2".to_owned())].into(),
+            }],
+            events
+        );
+
+        typewriter.elapsed += 1.0;
+        assert!(typewriter.update_current_text().is_empty());
+        assert_eq!("Hello there!", typewriter.current_text);
+    }
+
+    #[test]
+    fn full_text_stays_constant_while_revealing() {
+        let line = LocalizedLine::from_yarn_line(
+            UnderlyingYarnLine {
+                id: "line:1".into(),
+                text: "Grüße, Welt!".to_owned(),
+                attributes: vec![],
+            },
+            default(),
+            vec![],
+        );
+        let mut typewriter = Typewriter::default();
+        typewriter.set_line(&line, TypewriterMode::FixedSpeed);
+        assert_eq!("Grüße, Welt!", typewriter.full_text());
+        assert_eq!("Grüße, Welt!", typewriter.remaining_text());
+
+        // At 40 graphemes per second, this reveals "Grüß"
+        typewriter.elapsed = 0.11;
+        typewriter.update_current_text();
+        assert_eq!("Grüße, Welt!", typewriter.full_text());
+        assert_eq!("e, Welt!", typewriter.remaining_text());
+
+        typewriter.elapsed += 1.0;
+        typewriter.update_current_text();
+        assert_eq!("Grüße, Welt!", typewriter.full_text());
+        assert!(typewriter.remaining_text().is_empty());
+    }
+}
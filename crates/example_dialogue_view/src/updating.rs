@@ -1,6 +1,6 @@
 use crate::option_selection::OptionSelection;
 use crate::setup::{DialogueContinueNode, DialogueNameNode, UiRootNode};
-use crate::typewriter::{self, Typewriter};
+use crate::typewriter::{self, Typewriter, TypewriterMode};
 use crate::ExampleYarnSpinnerDialogueViewSystemSet;
 use bevy::prelude::*;
 use bevy_yarnspinner::{events::*, prelude::*};
@@ -55,6 +55,7 @@ fn present_line(
     mut line_events: EventReader<PresentLineEvent>,
     mut speaker_change_events: EventWriter<SpeakerChangeEvent>,
     mut typewriter: ResMut<Typewriter>,
+    typewriter_mode: Res<TypewriterMode>,
     mut name_node: Query<Entity, With<DialogueNameNode>>,
     mut text_writer: TextUiWriter,
 ) {
@@ -69,7 +70,7 @@ fn present_line(
             String::new()
         };
         *text_writer.text(name_node.single_mut(), 0) = name;
-        typewriter.set_line(&event.line);
+        typewriter.set_line(&event.line, *typewriter_mode);
     }
 }
 
@@ -43,6 +43,18 @@ impl Context {
         context
     }
 
+    /// Adds an analyser that follows the possible paths through the program, including jumps between nodes, to find
+    /// - variables without an initial value that may be read before they are assigned, and
+    /// - declared default values that are always overwritten before they could be read.
+    ///
+    /// Nodes that are never jumped to are assumed to be where dialogue starts.
+    /// Both cases are reported as a [`DiagnosisSeverity::Warning`] with a [`DiagnosisKind`].
+    /// This analyser is not part of [`Context::default_analysers`].
+    #[must_use]
+    pub fn with_variable_liveness_analysis(self) -> Self {
+        self.add_analyser(Box::new(VariableLivenessChecker::new()))
+    }
+
     /// Adds an analyser to the [`Context`].
     #[must_use]
     pub fn add_analyser(mut self, analyser: Box<dyn CompiledProgramAnalyser>) -> Self {
@@ -1,8 +1,10 @@
+pub(crate) use self::variable_liveness_checker::*;
 use self::{unused_variable_checker::*, variable_lister::*};
 use crate::prelude::*;
 
 mod unused_variable_checker;
 mod variable_lister;
+mod variable_liveness_checker;
 
 macro_rules! boxes {
     ($($x:ident),*) => {
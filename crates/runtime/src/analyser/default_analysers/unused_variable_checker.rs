@@ -53,6 +53,9 @@ impl CompiledProgramAnalyser for UnusedVariableChecker {
                     DiagnosisSeverity::Warning,
                     format!("Variable {variable} is assigned, but never read from"),
                 )
+                .with_kind(DiagnosisKind::WriteOnly {
+                    variable: variable.clone(),
+                })
             })
             .collect()
     }
@@ -0,0 +1,226 @@
+//! Finds variables that may be read before they are written, as well as declared default values that can never be read.
+
+use crate::prelude::*;
+use std::collections::{HashMap, HashSet};
+use yarnspinner_core::prelude::*;
+
+#[derive(Debug, Default)]
+pub(crate) struct VariableLivenessChecker {
+    /// Variables without an initial value that may be read before being written, with the nodes in which that happens,
+    /// mapped to the first instruction in the node that reads them.
+    uninitialized_reads: HashMap<(String, String), usize>,
+    /// Variables with an initial value that is overwritten on every path before it could be read,
+    /// mapped to the node and instruction of the first write.
+    overwritten_defaults: HashMap<String, (String, usize)>,
+}
+
+impl VariableLivenessChecker {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// A position in the program, i.e. a node name and an instruction index in that node.
+type ProgramPoint<'a> = (&'a str, usize);
+
+impl CompiledProgramAnalyser for VariableLivenessChecker {
+    fn diagnose(&mut self, program: &Program) {
+        let successors = successors(program);
+
+        // Nodes that are never jumped to are where dialogue starts.
+        // If every node is jumped to, e.g. because they form a loop, any of them could be the start.
+        let jump_targets: HashSet<_> = successors
+            .iter()
+            .filter(|((node_name, index), _)| {
+                program.nodes[*node_name].instructions[*index].opcode() == OpCode::RunNode
+            })
+            .flat_map(|(_, destinations)| destinations)
+            .map(|(node_name, _)| *node_name)
+            .collect();
+        let mut entry_points: Vec<_> = program
+            .nodes
+            .keys()
+            .map(String::as_str)
+            .filter(|node_name| !jump_targets.contains(node_name))
+            .collect();
+        if entry_points.is_empty() {
+            entry_points = program.nodes.keys().map(String::as_str).collect();
+        }
+
+        // Forward dataflow analysis: for every point, the variables that have not been written on at least one path to it.
+        let all_variables: HashSet<String> = program
+            .nodes
+            .values()
+            .flat_map(|node| &node.instructions)
+            .filter_map(variable_name_if_accessed)
+            .collect();
+        let mut unwritten: HashMap<ProgramPoint, HashSet<String>> = HashMap::new();
+        let mut worklist: Vec<ProgramPoint> = Vec::new();
+        for node_name in entry_points {
+            if !program.nodes[node_name].instructions.is_empty() {
+                unwritten.insert((node_name, 0), all_variables.clone());
+                worklist.push((node_name, 0));
+            }
+        }
+        while let Some(point) = worklist.pop() {
+            let (node_name, index) = point;
+            let instruction = &program.nodes[node_name].instructions[index];
+            let mut state = unwritten[&point].clone();
+            if instruction.opcode() == OpCode::StoreVariable {
+                state.remove(&variable_name(instruction));
+            }
+            for successor in successors.get(&point).into_iter().flatten() {
+                let is_first_visit = !unwritten.contains_key(successor);
+                let successor_state = unwritten.entry(*successor).or_default();
+                let previous_len = successor_state.len();
+                successor_state.extend(state.iter().cloned());
+                if is_first_visit || successor_state.len() != previous_len {
+                    worklist.push(*successor);
+                }
+            }
+        }
+        // Points that were never reached have no entry, so these are not considered.
+        let mut unwritten_reads = HashSet::new();
+        let mut reads = HashSet::new();
+        let mut writes: HashMap<String, ProgramPoint> = HashMap::new();
+        for ((node_name, index), state) in &unwritten {
+            let instruction = &program.nodes[*node_name].instructions[*index];
+            let variable = variable_name_if_accessed(instruction);
+            match (instruction.opcode(), variable) {
+                (OpCode::PushVariable, Some(variable)) => {
+                    if state.contains(&variable) {
+                        unwritten_reads.insert(variable.clone());
+                        if !program.initial_values.contains_key(&variable) {
+                            let first_read = self
+                                .uninitialized_reads
+                                .entry((variable.clone(), node_name.to_string()))
+                                .or_insert(*index);
+                            *first_read = (*first_read).min(*index);
+                        }
+                    }
+                    reads.insert(variable);
+                }
+                (OpCode::StoreVariable, Some(variable)) => {
+                    let first_write = writes.entry(variable).or_insert((*node_name, *index));
+                    *first_write = (*first_write).min((*node_name, *index));
+                }
+                _ => {}
+            }
+        }
+        let overwritten_defaults = program
+            .initial_values
+            .keys()
+            .filter(|variable| !is_internal_variable(variable))
+            .filter(|variable| reads.contains(*variable) && !unwritten_reads.contains(*variable))
+            .filter_map(|variable| {
+                let (node_name, index) = writes.get(variable)?;
+                Some((variable.clone(), (node_name.to_string(), *index)))
+            });
+        self.overwritten_defaults.extend(overwritten_defaults);
+    }
+
+    fn collect_diagnoses(&self) -> Vec<Diagnosis> {
+        let uninitialized_reads = self
+            .uninitialized_reads
+            .iter()
+            .filter(|((variable, _), _)| !is_internal_variable(variable))
+            .map(|((variable, node_name), index)| {
+                Diagnosis::new(
+                    DiagnosisSeverity::Warning,
+                    format!("Variable {variable} may be read before it is assigned, and has no initial value"),
+                )
+                .with_node_name(node_name)
+                .with_instruction_index(*index)
+                .with_kind(DiagnosisKind::UninitializedRead {
+                    variable: variable.clone(),
+                })
+            });
+        let overwritten_defaults =
+            self.overwritten_defaults
+                .iter()
+                .map(|(variable, (node_name, index))| {
+            Diagnosis::new(
+                DiagnosisSeverity::Warning,
+                format!("The default value of variable {variable} is always overwritten before it is read"),
+            )
+            .with_node_name(node_name)
+            .with_instruction_index(*index)
+            .with_kind(DiagnosisKind::OverwrittenDefault {
+                variable: variable.clone(),
+            })
+        });
+        uninitialized_reads.chain(overwritten_defaults).collect()
+    }
+}
+
+/// Maps every point in the program to the points that execution can continue at from there.
+/// Jumps whose destination is only known at runtime are assumed to be able to go anywhere they could possibly go.
+fn successors(program: &Program) -> HashMap<ProgramPoint<'_>, Vec<ProgramPoint<'_>>> {
+    let mut successors = HashMap::new();
+    for (node_name, node) in &program.nodes {
+        let node_name = node_name.as_str();
+        let label = |label_name: &str| {
+            node.labels
+                .get(label_name)
+                .and_then(|&index| usize::try_from(index).ok())
+                .map(|index| (node_name, index))
+        };
+        for (index, instruction) in node.instructions.iter().enumerate() {
+            let next = (index + 1 < node.instructions.len()).then_some((node_name, index + 1));
+            let point_successors: Vec<_> = match instruction.opcode() {
+                OpCode::JumpTo => label(&string_operand(instruction)).into_iter().collect(),
+                OpCode::JumpIfFalse => next
+                    .into_iter()
+                    .chain(label(&string_operand(instruction)))
+                    .collect(),
+                // The destination of an option is on the stack, so it could be any label of the node.
+                OpCode::Jump => node.labels.keys().filter_map(|l| label(l)).collect(),
+                OpCode::Stop => vec![],
+                OpCode::RunNode => {
+                    let destination = index
+                        .checked_sub(1)
+                        .map(|previous| &node.instructions[previous])
+                        .filter(|previous| previous.opcode() == OpCode::PushString)
+                        .map(string_operand)
+                        .and_then(|destination| program.nodes.get_key_value(&destination));
+                    match destination {
+                        Some((destination, _)) => vec![(destination.as_str(), 0)],
+                        None => program
+                            .nodes
+                            .keys()
+                            .map(|destination| (destination.as_str(), 0))
+                            .collect(),
+                    }
+                }
+                _ => next.into_iter().collect(),
+            };
+            let point_successors = point_successors
+                .into_iter()
+                .filter(|(name, index)| *index < program.nodes[*name].instructions.len())
+                .collect();
+            successors.insert((node_name, index), point_successors);
+        }
+    }
+    successors
+}
+
+fn string_operand(instruction: &Instruction) -> String {
+    instruction.operands[0].clone().try_into().unwrap()
+}
+
+fn variable_name(instruction: &Instruction) -> String {
+    string_operand(instruction)
+}
+
+fn variable_name_if_accessed(instruction: &Instruction) -> Option<String> {
+    matches!(
+        instruction.opcode(),
+        OpCode::PushVariable | OpCode::StoreVariable
+    )
+    .then(|| variable_name(instruction))
+}
+
+/// Variables generated by the compiler, e.g. for tracking node visits, are not the user's responsibility.
+fn is_internal_variable(variable: &str) -> bool {
+    variable.starts_with("$Yarn.Internal.")
+}
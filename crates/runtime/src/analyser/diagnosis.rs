@@ -27,6 +27,40 @@ pub struct Diagnosis {
     pub line: Option<usize>,
     /// The 1-indexed column number, i.e. the character index in the line, of the node that caused the diagnosis, if any.
     pub column: Option<usize>,
+    /// What kind of problem was found, for diagnoses that are meant to be processed by tools. See [`DiagnosisKind`].
+    pub kind: Option<DiagnosisKind>,
+    /// The index of the instruction in the node named [`Diagnosis::node_name`] that caused the diagnosis, if any.
+    /// Compiled programs carry no source positions, so pass this to the `DebugInfo` of the node,
+    /// which is part of the compilation result, to find the position in the Yarn file.
+    pub instruction_index: Option<usize>,
+}
+
+/// The problem found by an analyser, in a structured form. Set on [`Diagnosis::kind`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq, Hash))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+pub enum DiagnosisKind {
+    /// The variable has no initial value and may be read before it is assigned.
+    UninitializedRead {
+        /// The name of the variable, including the `$`.
+        variable: String,
+    },
+    /// The variable is assigned, but never read.
+    WriteOnly {
+        /// The name of the variable, including the `$`.
+        variable: String,
+    },
+    /// The declared default value of the variable is always overwritten before it could be read.
+    OverwrittenDefault {
+        /// The name of the variable, including the `$`.
+        variable: String,
+    },
 }
 
 /// The severity of a [`Diagnosis`], as reported by a [`CompiledProgramAnalyser`].
@@ -67,6 +101,8 @@ impl Diagnosis {
             node_name: Default::default(),
             line: Default::default(),
             column: Default::default(),
+            kind: Default::default(),
+            instruction_index: Default::default(),
         }
     }
 
@@ -90,6 +126,20 @@ impl Diagnosis {
         self.column = Some(column);
         self
     }
+
+    /// Sets the index of the instruction the diagnosis is associated with. By default, this is `None`.
+    #[must_use]
+    pub fn with_instruction_index(mut self, instruction_index: usize) -> Self {
+        self.instruction_index = Some(instruction_index);
+        self
+    }
+
+    /// Sets the structured [`DiagnosisKind`] of the diagnosis. By default, this is `None`.
+    #[must_use]
+    pub fn with_kind(mut self, kind: DiagnosisKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
 }
 
 impl Display for Diagnosis {
@@ -3,10 +3,12 @@
 //! ## Implementation notes
 //! The original delegates command parsing to the Unity plugin, but we think it's foundational enough to do it directly in the runtime.
 
-use crate::markup::normalize;
 #[cfg(any(feature = "bevy", feature = "serde"))]
 use crate::prelude::*;
-use yarnspinner_core::prelude::YarnValue;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use yarnspinner_core::prelude::{split_command_text, YarnValue};
 
 /// A custom command found in a Yarn file within the `<<` and `>>` characters.
 #[derive(Debug, Clone, PartialEq)]
@@ -59,91 +61,97 @@ impl Command {
             raw: input,
         }
     }
+
+    /// Returns the name of the command with the given text as it appears in the compiled program,
+    /// or [`None`] if the name is substituted at runtime.
+    pub(crate) fn name_of(command_text: &str) -> Option<String> {
+        split_command_text(command_text)
+            .into_iter()
+            .next()
+            .filter(|name| !name.contains('{'))
+    }
 }
 
-/// Splits input into a number of non-empty sub-strings, separated
-/// by whitespace, and grouping double-quoted strings into a single
-/// sub-string.
+/// The result of handling a [`Command`], to be passed back to the [`Dialogue`] via [`Dialogue::apply_command_result`].
+///
+/// A command handler typically cannot mutate the [`Dialogue`] that emitted the command, since the dialogue is borrowed while
+/// its events are being handled. Instead, the handler can return the variables it wants to write,
+/// which are then applied before the dialogue continues.
 ///
-/// This method behaves similarly to the [`String::split`] method with
-/// the empty results filtered out, with the following differences:
+/// ## Examples
 ///
-/// - Text that appears inside a pair of double-quote characters will not be split.
-/// - Text that appears after a double-quote character and
-///     before the end of the input will not be split (that is, an
-///     unterminated double-quoted string will be treated as though it
-///     had been terminated at the end of the input.)
-/// - When inside a pair of double-quote characters, the string
-///     `\\` will be converted to `\`, and the string `\"` will be converted to `"`.
-fn split_command_text(input: &str) -> Vec<String> {
-    let input = normalize(input);
-    let mut chars = input.chars().peekable();
-    let mut results = Vec::new();
-    let mut current_component = String::new();
-    while let Some(mut char) = chars.next() {
-        match char {
-            _ if char.is_whitespace() => {
-                if !current_component.is_empty() {
-                    // We've reached the end of a run of visible
-                    // characters. Add this run to the result list and
-                    // prepare for the next one.
-                    results.push(std::mem::take(&mut current_component));
-                } else {
-                    // We encountered a whitespace character, but
-                    // didn't have any characters queued up. Skip this
-                    // character.
-                }
-            }
-            '\"' => {
-                // We've entered a quoted string!
-                loop {
-                    char = match chars.next() {
-                        Some(c) => c,
-                        None => {
-                            // Oops, we ended the input while parsing a
-                            // quoted string! Dump our current word
-                            // immediately and return.
-                            results.push(current_component);
-                            return results;
-                        }
-                    };
-                    match char {
-                        '\\' => {
-                            // Possibly an escaped character!
-                            match chars.peek() {
-                                Some('\\') | Some('\"') => {
-                                    // It's an escaped character! Consume it and add it to the current component.
-                                    let next = chars.next().unwrap();
-                                    current_component.push(next);
-                                }
-                                _ => {
-                                    // Oops, an invalid escape. Add the \ and
-                                    // whatever is after it.
-                                    current_component.push(char);
-                                }
-                            }
-                        }
-                        '\"' => {
-                            // The end of a string!
-                            break;
-                        }
-                        _ => {
-                            // Any other character. Add it to the buffer.
-                            current_component.push(char);
-                        }
-                    }
-                }
-                results.push(std::mem::take(&mut current_component));
-            }
-            _ => {
-                current_component.push(char);
-            }
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// # use yarnspinner_core::prelude::*;
+/// let result = CommandResult::Continue.with_variable_set("$gold", 50);
+/// assert_eq!(result.variable_writes(), &[("$gold".to_string(), YarnValue::from(50))]);
+/// ```
+///
+/// [`Dialogue`]: crate::prelude::Dialogue
+/// [`Dialogue::apply_command_result`]: crate::prelude::Dialogue::apply_command_result
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq, Default))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+pub enum CommandResult {
+    /// The dialogue should continue without any side effects.
+    #[default]
+    Continue,
+    /// The dialogue should continue after the contained variables have been written, in order.
+    ContinueWithVariables(Vec<(String, YarnValue)>),
+}
+
+impl CommandResult {
+    /// Adds a write of `value` to the variable `name`. Writes are applied in the order they were added.
+    #[must_use]
+    pub fn with_variable_set(self, name: impl Into<String>, value: impl Into<YarnValue>) -> Self {
+        let mut variable_writes = match self {
+            Self::Continue => Vec::new(),
+            Self::ContinueWithVariables(variable_writes) => variable_writes,
+        };
+        variable_writes.push((name.into(), value.into()));
+        Self::ContinueWithVariables(variable_writes)
+    }
+
+    /// The variable writes contained in this result, in the order they will be applied.
+    pub fn variable_writes(&self) -> &[(String, YarnValue)] {
+        match self {
+            Self::Continue => &[],
+            Self::ContinueWithVariables(variable_writes) => variable_writes,
         }
     }
-    if !current_component.is_empty() {
-        results.push(current_component);
+}
+
+type CommandHandler = Arc<dyn Fn(Vec<YarnValue>) -> CommandResult + Send + Sync>;
+
+/// The handlers registered with [`Dialogue::with_command`](crate::prelude::Dialogue::with_command), by command name.
+#[derive(Clone, Default)]
+pub(crate) struct CommandHandlers(HashMap<String, CommandHandler>);
+
+impl CommandHandlers {
+    pub(crate) fn insert(
+        &mut self,
+        name: impl Into<String>,
+        handler: impl Fn(Vec<YarnValue>) -> CommandResult + Send + Sync + 'static,
+    ) {
+        self.0.insert(name.into(), Arc::new(handler));
+    }
+
+    /// Runs the handler registered for the command, if any.
+    pub(crate) fn run(&self, command: &Command) -> Option<CommandResult> {
+        let handler = self.0.get(&command.name)?;
+        Some(handler(command.parameters.clone()))
+    }
+}
+
+impl Debug for CommandHandlers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
     }
-    results
 }
 
 #[cfg(test)]
@@ -151,36 +159,6 @@ mod tests {
     //! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner-Unity/blob/5944b0e03d319303cd185b08140772a5804a2762/Tests/Runtime/DialogueRunnerTests/DialogueRunnerTests.cs#L465>
     use super::*;
 
-    #[test]
-    fn split_command_text_splits_text_correctly() {
-        for (input, expected_components) in [
-            ("one two three four", vec!["one", "two", "three", "four"]),
-            ("one \"two three\" four", vec!["one", "two three", "four"]),
-            ("one \"two three four", vec!["one", "two three four"]),
-            (
-                "one \"two \\\"three\" four",
-                vec!["one", "two \"three", "four"],
-            ),
-            (
-                "one \\two three four",
-                vec!["one", "\\two", "three", "four"],
-            ),
-            (
-                "one \"two \\\\ three\" four",
-                vec!["one", "two \\ three", "four"],
-            ),
-            (
-                "one \"two \\1 three\" four",
-                vec!["one", "two \\1 three", "four"],
-            ),
-            ("one      two", vec!["one", "two"]),
-        ] {
-            let parsed_components = split_command_text(input);
-
-            assert_eq!(expected_components, parsed_components);
-        }
-    }
-
     #[test]
     fn parses_command() {
         for (input, expected_command) in [
@@ -1,11 +1,14 @@
 //! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner/Dialogue.cs>
 
-use crate::markup::{DialogueTextProcessor, LineParser, MarkupParseError};
+use crate::markup::{DialogueTextProcessor, LineParser, MarkupParseError, UnclosedAttributePolicy};
 use crate::prelude::*;
-use log::error;
-use std::collections::HashMap;
+use icu_plurals::PluralCategory;
+use log::{debug, error};
+use rand::{rngs::StdRng, SeedableRng};
+use std::collections::{BTreeSet, HashMap};
 use std::error::Error;
 use std::fmt::{self, Debug, Display};
+use std::sync::Arc;
 use yarnspinner_core::prelude::*;
 
 /// Co-ordinates the execution of Yarn programs.
@@ -15,6 +18,7 @@ use yarnspinner_core::prelude::*;
 pub struct Dialogue {
     vm: VirtualMachine,
     language_code: Option<Language>,
+    format_duration_overridden: bool,
 }
 
 #[allow(missing_docs)]
@@ -33,6 +37,8 @@ pub enum DialogueError {
         max_id: usize,
     },
     UnexpectedOptionSelectionError,
+    UnexpectedMultiselectError,
+    NoOptionSelectedError,
     ContinueOnOptionSelectionError,
     NoNodeSelectedOnContinue,
     NoProgramLoaded,
@@ -44,6 +50,18 @@ pub enum DialogueError {
         function_name: String,
         library: Library,
     },
+    StepLimitExceeded {
+        max_steps: usize,
+    },
+    ConstantAssignment {
+        variable_name: String,
+    },
+    UnloadingActiveNode {
+        node_name: String,
+    },
+    UnavailableOptionSelected {
+        selected_option_id: OptionId,
+    },
 }
 
 impl Error for DialogueError {
@@ -65,12 +83,18 @@ impl Display for DialogueError {
             LineProviderError { id, language_code } => write!(f, "Line ID \"{id}\" not found in line provider with language code {language_code:?}"),
             InvalidOptionIdError { selected_option_id, max_id } => write!(f, "{selected_option_id:?} is not a valid option ID (expected a number between 0 and {max_id}."),
             UnexpectedOptionSelectionError => f.write_str("An option was selected, but the dialogue wasn't waiting for a selection. This method should only be called after the Dialogue is waiting for the user to select an option."),
+            UnexpectedMultiselectError => f.write_str("Multiple options were selected, but the options the dialogue is waiting on are not all marked as multi-select. Use `Dialogue::set_selected_option` to select a single option instead."),
+            NoOptionSelectedError => f.write_str("An empty set of options was selected. At least one option must be selected."),
             ContinueOnOptionSelectionError => f.write_str("Dialogue was asked to continue running, but it is waiting for the user to select an option first."),
             NoNodeSelectedOnContinue => f.write_str("Cannot continue running dialogue. No node has been selected."),
             NoProgramLoaded => f.write_str("No program has been loaded. Cannot continue running dialogue."),
             InvalidNode { node_name } => write!(f, "No node named \"{node_name}\" has been loaded."),
             VariableStorageError(e) => Display::fmt(e, f),
             FunctionNotFound { function_name, library } => write!(f, "Function \"{function_name}\" not found in library: {library}"),
+            StepLimitExceeded { max_steps } => write!(f, "Dialogue ran {max_steps} instructions without producing a line, options, or a command and was stopped. The script probably contains an infinite loop."),
+            ConstantAssignment { variable_name } => write!(f, "Cannot assign to {variable_name}, because it is a constant."),
+            UnloadingActiveNode { node_name } => write!(f, "Cannot unload node \"{node_name}\" while it is running. Stop the dialogue first."),
+            UnavailableOptionSelected { selected_option_id } => write!(f, "{selected_option_id:?} is not available, because its line condition failed."),
         }
     }
 }
@@ -87,7 +111,43 @@ impl From<VariableStorageError> for DialogueError {
     }
 }
 
+/// The callback registered with [`Dialogue::with_error_handler`], if any.
+#[derive(Clone, Default)]
+pub(crate) struct ErrorHandler(Option<ErrorCallback>);
+
+type ErrorCallback = Arc<dyn Fn(&DialogueError) + Send + Sync>;
+
+impl ErrorHandler {
+    pub(crate) fn set(&mut self, handler: impl Fn(&DialogueError) + Send + Sync + 'static) {
+        self.0 = Some(Arc::new(handler));
+    }
+
+    /// Passes the error to the registered callback, if any.
+    pub(crate) fn report(&self, error: &DialogueError) {
+        if let Some(handler) = self.0.as_ref() {
+            handler(error);
+        }
+    }
+}
+
+impl Debug for ErrorHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ErrorHandler")
+            .field(&self.0.is_some())
+            .finish()
+    }
+}
+
 impl Dialogue {
+    /// The name of the node that [`Dialogue::set_node_to_start`] starts at unless overridden with [`Dialogue::with_default_start_node`].
+    pub const DEFAULT_START_NODE_NAME: &'static str = "Start";
+
+    /// The number of nodes [`Dialogue::jump_history`] remembers unless overridden with [`Dialogue::with_history_capacity`].
+    pub const DEFAULT_HISTORY_CAPACITY: usize = 100;
+
+    /// The number of instructions a single call to [`Dialogue::continue_`] may run unless overridden with [`Dialogue::with_max_steps_per_next`].
+    pub const DEFAULT_MAX_STEPS_PER_NEXT: usize = 100_000;
+
     /// Creates a new [`Dialogue`] instance with the given [`VariableStorage`] and [`TextProvider`].
     /// - The [`TextProvider`] is used to retrieve the text of lines and options.
     /// - The [`VariableStorage`] is used to store and retrieve variables.
@@ -98,10 +158,7 @@ impl Dialogue {
         variable_storage: Box<dyn VariableStorage>,
         text_provider: Box<dyn TextProvider>,
     ) -> Self {
-        let mut library = Library::standard_library();
-        library
-            .add_function("visited", visited(variable_storage.clone()))
-            .add_function("visited_count", visited_count(variable_storage.clone()));
+        let library = Library::standard_library();
 
         let dialogue_text_processor = Box::new(DialogueTextProcessor::new());
         let line_parser = LineParser::new()
@@ -109,10 +166,68 @@ impl Dialogue {
             .register_marker_processor("plural", dialogue_text_processor.clone())
             .register_marker_processor("ordinal", dialogue_text_processor);
 
-        Self {
+        let mut dialogue = Self {
             vm: VirtualMachine::new(library, variable_storage, line_parser, text_provider),
             language_code: Default::default(),
+            format_duration_overridden: false,
+        };
+        dialogue.register_bound_functions();
+        dialogue
+    }
+
+    /// Creates a copy of this [`Dialogue`] that can be run without affecting the original, e.g. for a "preview from here" feature in an editor.
+    ///
+    /// The copy shares the loaded [`Program`] and the [`TextProvider`] with the original, but has its own execution state
+    /// and a fresh [`MemoryVariableStorage`] that is seeded with the current variables of the original.
+    /// Running the copy will thus neither advance the original nor change its variables.
+    #[must_use]
+    pub fn clone_for_preview(&self) -> Self {
+        let mut variable_storage: Box<dyn VariableStorage> = Box::new(MemoryVariableStorage::new());
+        if let Err(e) = VariableStorage::extend(
+            variable_storage.as_mut(),
+            self.variable_storage().variables(),
+        ) {
+            error!("Failed to copy variables into the preview's VariableStorage: {e}");
+        }
+
+        let mut preview = self.clone();
+        preview.bind_variable_storage(variable_storage);
+        preview
+    }
+
+    /// Creates a new [`Dialogue`] from this one, which serves as a template, e.g. for running many independent conversations on a game server.
+    ///
+    /// The new instance has the same [`Library`], language, settings and loaded [`Program`] as this one, but uses the given `variable_storage`,
+    /// which is populated with the initial values of the program's variables. It starts out stopped, i.e. [`Dialogue::set_node`] needs to be called before running it.
+    /// The template is not affected by running the new instance.
+    ///
+    /// The program is shared with the template instead of being copied, since it is never modified after being loaded.
+    /// The [`TextProvider`] is copied with [`TextProvider::clone_shallow`], so depending on its implementation, it may share its state,
+    /// e.g. the selected language, with the template.
+    #[must_use]
+    pub fn template_clone(&self, variable_storage: Box<dyn VariableStorage>) -> Self {
+        let mut instance = self.clone();
+        // Stopping returns the events of the template that have not been consumed yet, which the instance should not deliver.
+        let _ = instance.vm.stop();
+        instance.bind_variable_storage(variable_storage);
+        if let Some(program) = self.vm.program.clone() {
+            instance.extend_variable_storage_from(&program);
         }
+        instance
+    }
+
+    fn bind_variable_storage(&mut self, variable_storage: Box<dyn VariableStorage>) {
+        self.vm.variable_storage = variable_storage;
+        self.register_bound_functions();
+    }
+
+    /// (Re-)registers the functions that look up the visited state of nodes, which need to read from the current [`VariableStorage`].
+    fn register_bound_functions(&mut self) {
+        let variable_storage = self.vm.variable_storage.clone();
+        self.vm
+            .library
+            .add_function("visited", visited(variable_storage.clone()))
+            .add_function("visited_count", visited_count(variable_storage));
     }
 }
 
@@ -138,10 +253,27 @@ fn visited_count(storage: Box<dyn VariableStorage>) -> yarn_fn_type! { impl Fn(S
     }
 }
 
+/// The `format_duration` of the standard library, but with the plural rules of the given language.
+/// The rules are looked up once per language, not on every call.
+fn localized_format_duration(language: Language) -> yarn_fn_type! { impl Fn(f32) -> String } {
+    move |seconds: f32| {
+        let pluralization = Pluralization::cached(language.clone());
+        format_duration(seconds, |count| {
+            pluralization.get_cardinal_plural_case(count as f32) == PluralCategory::One
+        })
+    }
+}
+
 impl Iterator for Dialogue {
     type Item = Vec<DialogueEvent>;
 
     /// Panicking version of [`Dialogue::continue_`].
+    ///
+    /// ## Implementation notes
+    ///
+    /// Does not panic when [`DialogueError::StepLimitExceeded`] is encountered, but returns the events of stopping the dialogue,
+    /// which end with [`DialogueEvent::DialogueComplete`], so that the next call returns [`None`].
+    /// The error is logged and passed to the handler set with [`Dialogue::with_error_handler`].
     #[must_use = "All dialogue events that are returned by the dialogue must be handled or explicitly ignored"]
     fn next(&mut self) -> Option<Self::Item> {
         self.vm.next()
@@ -168,15 +300,37 @@ impl Dialogue {
 
     /// Sets the [`Dialogue`]'s language. A value of `None` means that you are using the base language, i.e. the one the Yarn files are written in.
     /// Returns the last language code.
+    ///
+    /// The `format_duration` function of the standard library is replaced by one using the plural rules of the new language.
+    /// A `format_duration` registered with [`Dialogue::set_format_duration`] is kept as is.
     pub fn set_language_code(
         &mut self,
         language_code: impl Into<Option<Language>>,
     ) -> Option<Language> {
         let language_code = language_code.into();
         self.vm.set_language_code(language_code.clone());
+        if !self.format_duration_overridden {
+            self.vm.library.add_function(
+                "format_duration",
+                localized_format_duration(language_code.clone().unwrap_or_default()),
+            );
+        }
         std::mem::replace(&mut self.language_code, language_code)
     }
 
+    /// Registers a `format_duration` function that replaces the one of the standard library.
+    /// Unlike a function registered through [`Dialogue::library_mut`], it is kept when [`Dialogue::set_language_code`] is called.
+    pub fn set_format_duration<Marker, F>(&mut self, function: F) -> &mut Self
+    where
+        Marker: 'static,
+        F: YarnFn<Marker> + 'static + Clone,
+        F::Out: IntoYarnValueFromNonYarnValue + 'static + Clone,
+    {
+        self.vm.library.add_function("format_duration", function);
+        self.format_duration_overridden = true;
+        self
+    }
+
     /// Gets the [`Library`] that this Dialogue uses to locate functions.
     ///
     /// When the Dialogue is constructed, the Library is initialized with
@@ -187,6 +341,8 @@ impl Dialogue {
     }
 
     /// See [`Dialogue::library`].
+    ///
+    /// Use [`Dialogue::set_format_duration`] instead to replace `format_duration`, so that it survives a change of the language.
     #[must_use]
     pub fn library_mut(&mut self) -> &mut Library {
         &mut self.vm.library
@@ -206,6 +362,16 @@ impl Dialogue {
         self
     }
 
+    /// Sets whether [`DialogueEvent::LineHints`] are sent again after every branch decision, e.g. an `<<if>>` or a selected option,
+    /// whenever this narrows down the lines that can still be reached in the current node.
+    /// This lets streaming asset systems unload the assets of lines that can no longer appear.
+    /// Only has an effect if line hints are enabled via [`Dialogue::set_line_hints_enabled`]. The default is `false`.
+    #[must_use]
+    pub fn with_continuous_line_hints(mut self, enabled: bool) -> Self {
+        self.vm.continuous_line_hints = enabled;
+        self
+    }
+
     /// Gets the currently registered [`TextProvider`].
     pub fn text_provider(&self) -> &dyn TextProvider {
         self.vm.text_provider()
@@ -225,6 +391,65 @@ impl Dialogue {
     pub fn variable_storage_mut(&mut self) -> &mut dyn VariableStorage {
         self.vm.variable_storage_mut()
     }
+
+    /// Gets the values of all variables, falling back to the initial values of the loaded [`Program`] for variables that were not set yet.
+    #[must_use]
+    pub fn current_variables(&self) -> HashMap<String, YarnValue> {
+        let mut variables: HashMap<String, YarnValue> = self
+            .vm
+            .program
+            .iter()
+            .flat_map(|program| program.initial_values.iter())
+            .map(|(name, value)| (name.clone(), value.clone().into()))
+            .collect();
+        variables.extend(self.variable_storage().variables());
+        variables
+    }
+
+    /// Runs instructions that only push values, i.e. the code the compiler generates for an expression, and returns the resulting stack.
+    /// Variables are read like [`Dialogue::current_variables`] and functions are called from the [`Library`].
+    /// Does not change the state of the [`Dialogue`].
+    ///
+    /// Returns [`None`] if the instructions contain anything else or a variable or function cannot be found.
+    #[must_use]
+    pub fn evaluate_instructions(&self, instructions: &[Instruction]) -> Option<Vec<YarnValue>> {
+        self.vm.evaluate_instructions(instructions)
+    }
+
+    /// Resets how often each node was visited, so that `visited` returns `false` and `visited_count` returns `0` for all nodes,
+    /// e.g. when starting a new game plus. All other variables keep their values.
+    pub fn reset_visited(&mut self) -> &mut Self {
+        let prefix = Library::generate_unique_visited_variable_for_node("");
+        let visit_variables: Vec<_> = self
+            .variable_storage()
+            .variables()
+            .into_keys()
+            .filter(|name| name.starts_with(&prefix))
+            .collect();
+        for name in visit_variables {
+            self.reset_visit_variable(name);
+        }
+        self
+    }
+
+    /// Resets how often the node `node_name` was visited, like [`Dialogue::reset_visited`] does for all nodes.
+    pub fn reset_visited_for(&mut self, node_name: &str) -> &mut Self {
+        let name = Library::generate_unique_visited_variable_for_node(node_name);
+        if self.variable_storage().contains(&name) {
+            self.reset_visit_variable(name);
+        }
+        self
+    }
+
+    fn reset_visit_variable(&mut self, name: String) {
+        if let Err(e) = self
+            .variable_storage_mut()
+            .set(name, YarnValue::Number(0.0))
+        {
+            error!("Failed to reset visit count: {e}");
+            self.vm.error_handler.report(&e.into());
+        }
+    }
 }
 
 // VM proxy
@@ -252,6 +477,10 @@ impl Dialogue {
     }
 
     fn extend_variable_storage_from(&mut self, program: &Program) {
+        self.vm
+            .constant_variables
+            .extend(program.extensions.constant_variables.iter().cloned());
+
         let initial: HashMap<String, YarnValue> = program
             .initial_values
             .iter()
@@ -267,24 +496,54 @@ impl Dialogue {
         }
     }
 
+    /// Registers the lines that the compiler found to be tagged with option hashtags, e.g. [`ONCE_TAG`], and their requirement hints.
+    fn register_option_lines_from(&mut self, program: &Program) {
+        self.vm
+            .once_line_ids
+            .extend(program.extensions.once_lines.iter().cloned().map(LineId));
+        self.vm.multiselect_line_ids.extend(
+            program
+                .extensions
+                .multiselect_lines
+                .iter()
+                .cloned()
+                .map(LineId),
+        );
+        self.vm
+            .pinned_line_ids
+            .extend(program.extensions.pinned_lines.iter().cloned().map(LineId));
+        self.vm.requirement_hints.extend(
+            program
+                .extensions
+                .requirement_hints
+                .iter()
+                .map(|(line_id, hint)| (LineId(line_id.clone()), hint.clone())),
+        );
+    }
+
     /// Sets or replaces the [`Dialogue`]'s current [`Program`]. The program is replaced, all current state is reset.
     pub fn replace_program(&mut self, program: Program) -> &mut Self {
-        self.vm.program.replace(program.clone());
+        self.vm.program.replace(Arc::new(program.clone()));
         self.vm.reset_state();
+        self.vm.markup_cache.clear();
         self.extend_variable_storage_from(&program);
+        self.register_option_lines_from(&program);
         self
     }
 
     /// Merges the currently set [`Program`] with the given one. If there is no program set, the given one is set.
     pub fn add_program(&mut self, program: Program) -> &mut Self {
         if let Some(existing_program) = self.vm.program.as_mut() {
-            *existing_program =
-                Program::combine(vec![existing_program.clone(), program.clone()]).unwrap();
+            *existing_program = Arc::new(
+                Program::combine(vec![existing_program.as_ref().clone(), program.clone()]).unwrap(),
+            );
         } else {
-            self.vm.program.replace(program.clone());
+            self.vm.program.replace(Arc::new(program.clone()));
             self.vm.reset_state();
         }
+        self.vm.markup_cache.clear();
         self.extend_variable_storage_from(&program);
+        self.register_option_lines_from(&program);
 
         self
     }
@@ -304,6 +563,129 @@ impl Dialogue {
         Ok(self)
     }
 
+    /// Builder version of [`Dialogue::set_node`], e.g. to configure the starting node along with the rest of the [`Dialogue`].
+    ///
+    /// ## Panics
+    ///
+    /// In debug builds, panics if no node with the value of `node_name` has been loaded.
+    /// In release builds, the error is logged instead and no node is set, so that a misconfigured node name does not crash a shipped game.
+    #[must_use]
+    pub fn with_node_at(mut self, node_name: impl Into<String>) -> Self {
+        if let Err(e) = self.set_node(node_name) {
+            if cfg!(debug_assertions) {
+                panic!("Failed to set node: {e}");
+            }
+            error!("Failed to set node: {e}");
+        }
+        self
+    }
+
+    /// Starts the [`Dialogue`] at its default start node, i.e. the one set with [`Dialogue::with_default_start_node`] or,
+    /// if none was set, [`Dialogue::DEFAULT_START_NODE_NAME`]. See [`Dialogue::set_node`] for details.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if no node with the default start node's name has been loaded.
+    ///
+    /// ## Implementation notes
+    ///
+    /// The original implementation provides this as a default argument to `SetNode`.
+    pub fn set_node_to_start(&mut self) -> Result<&mut Self> {
+        let start_node = self.default_start_node().to_owned();
+        self.set_node(start_node)
+    }
+
+    /// Overrides the node that [`Dialogue::set_node_to_start`] starts at, for projects that don't use [`Dialogue::DEFAULT_START_NODE_NAME`].
+    #[must_use]
+    pub fn with_default_start_node(mut self, node_name: impl Into<String>) -> Self {
+        self.vm.default_start_node_name = Some(node_name.into());
+        self
+    }
+
+    /// Sets how many lines the [`Dialogue`] remembers the parsed markup of, so that lines delivered repeatedly
+    /// don't have to be parsed again. The cache is cleared whenever the program or the language changes.
+    /// Defaults to [`DEFAULT_MARKUP_CACHE_CAPACITY`](crate::markup::DEFAULT_MARKUP_CACHE_CAPACITY). A capacity of 0 disables the cache.
+    #[must_use]
+    pub fn with_markup_cache_capacity(mut self, capacity: usize) -> Self {
+        self.vm.markup_cache.set_capacity(capacity);
+        self
+    }
+
+    /// Registers a function that can rewrite every [`Line`] before it is delivered in a [`DialogueEvent::Line`],
+    /// e.g. for filtering profanity or inserting names. Transformers run after substitutions were inserted and markup was parsed,
+    /// in the order they were registered. A transformer that changes [`Line::text`] is responsible for keeping
+    /// the [`Line::attributes`] consistent with it. The lines of options are not passed to transformers.
+    #[must_use]
+    pub fn with_line_transformer(
+        mut self,
+        transformer: impl Fn(&mut Line) + Send + Sync + 'static,
+    ) -> Self {
+        self.vm.line_transformers.push(transformer);
+        self
+    }
+
+    /// Sets whether markup in the values of substitutions, e.g. `[b]Sally[/b]` stored in `$name` for the line `{$name}: Hi!`,
+    /// is parsed like the markup written in the line itself. Substitutions are inserted before the markup is parsed,
+    /// so the [`MarkupAttribute`](crate::markup::MarkupAttribute)s of the resulting [`Line`] cover the substituted text in either case.
+    ///
+    /// Defaults to `false`, which treats substituted values as plain text. Only enable this if the values are trusted,
+    /// since a value entered by a player could otherwise inject markup, such as a character name, into the line.
+    ///
+    /// ## Implementation notes
+    ///
+    /// This method is not present in the original implementation, which always parses the markup of substituted values.
+    #[must_use]
+    pub fn with_substitution_markup(mut self, enabled: bool) -> Self {
+        self.vm.substitution_markup = enabled;
+        self
+    }
+
+    /// Sets how whitespace in the text of delivered lines is normalized, e.g. to collapse doubled spaces
+    /// or line breaks introduced by substitutions or localized text. Defaults to [`WhitespaceMode::Preserve`].
+    ///
+    /// Whitespace is normalized after markup is parsed, so markup and the text inside `[nomarkup]` attributes are left untouched.
+    /// The positions of [`MarkupAttribute`](crate::markup::MarkupAttribute)s are moved to refer to the normalized text.
+    #[must_use]
+    pub fn with_whitespace_collapse(mut self, mode: WhitespaceMode) -> Self {
+        self.vm.whitespace_mode = mode;
+        self.vm.markup_cache.clear();
+        self
+    }
+
+    /// Sets what happens to markup attributes that are still open at the end of a line, e.g. the `b` in `[b]Hello`.
+    /// By default, they are dropped like in the original implementation, see [`UnclosedAttributePolicy`].
+    #[must_use]
+    pub fn with_unclosed_attribute_policy(mut self, policy: UnclosedAttributePolicy) -> Self {
+        self.vm.line_parser.set_unclosed_attribute_policy(policy);
+        self.vm.markup_cache.clear();
+        self
+    }
+
+    /// Registers a handler for the command with the given name, e.g. `set_sprite` for `<<set_sprite ship "happy">>`, so that
+    /// commands can be handled without a game engine integration. The handler receives the [`Command::parameters`]
+    /// and runs as soon as the command is reached. The variable writes in the returned [`CommandResult`] are applied right away
+    /// and the dialogue continues without emitting a [`DialogueEvent::Command`].
+    /// Commands without a registered handler are still emitted as [`DialogueEvent::Command`]s for the caller to handle.
+    /// Registering a handler for a name that already has one replaces it.
+    #[must_use]
+    pub fn with_command(
+        mut self,
+        name: impl Into<String>,
+        handler: impl Fn(Vec<YarnValue>) -> CommandResult + Send + Sync + 'static,
+    ) -> Self {
+        self.vm.command_handlers.insert(name, handler);
+        self
+    }
+
+    /// Gets the name of the node that [`Dialogue::set_node_to_start`] starts at.
+    #[must_use]
+    pub fn default_start_node(&self) -> &str {
+        self.vm
+            .default_start_node_name
+            .as_deref()
+            .unwrap_or(Self::DEFAULT_START_NODE_NAME)
+    }
+
     /// Attempts to pop the line hints that were generated by the last [`Dialogue::set_node`] call.
     ///
     /// Panics if [`Dialogue::line_hints_enabled`] is `false`.
@@ -318,6 +700,10 @@ impl Dialogue {
     /// Immediately stops the [`Dialogue`]
     ///
     /// Returns unfinished [`DialogueEvent`]s that should be handled by the caller. The last is guaranteed to be [`DialogueEvent::DialogueComplete`].
+    ///
+    /// Scripts can end the dialogue themselves with a `<<stop>>` command. Unlike this method, which is meant for interrupting
+    /// the dialogue from the outside, `<<stop>>` finishes the current node regularly: the next call to [`Dialogue::continue_`]
+    /// returns a [`DialogueEvent::NodeComplete`] followed by [`DialogueEvent::DialogueComplete`], and the jump history is kept.
     pub fn stop(&mut self) -> Vec<DialogueEvent> {
         self.vm.stop()
     }
@@ -327,6 +713,24 @@ impl Dialogue {
         self.vm.unload_programs()
     }
 
+    /// Removes the node `node_name` from the loaded [`Program`], e.g. to replace a single node shipped in a DLC via [`Dialogue::add_program`].
+    /// Returns whether the node was loaded. Afterwards, [`Dialogue::set_node`] and jumps to the node fail with [`DialogueError::InvalidNode`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`DialogueError::UnloadingActiveNode`] if the node is currently running.
+    pub fn unload_node(&mut self, node_name: &str) -> Result<bool> {
+        if self.is_active() && self.vm.current_node().as_deref() == Some(node_name) {
+            return Err(DialogueError::UnloadingActiveNode {
+                node_name: node_name.to_owned(),
+            });
+        }
+        let Some(program) = self.vm.program.as_mut() else {
+            return Ok(false);
+        };
+        Ok(Arc::make_mut(program).nodes.remove(node_name).is_some())
+    }
+
     /// Gets the names of the nodes in the currently loaded Program, if there is one.
     #[must_use]
     pub fn node_names(&self) -> Option<impl Iterator<Item = &str>> {
@@ -342,13 +746,17 @@ impl Dialogue {
     /// A node's source text will only be present in the string table if its
     /// `tags` header contains `rawText`.
     ///
+    /// The returned ID follows the convention `line:<node_name>`, e.g. `line:Start`, which is the ID the compiler uses for a node's source text.
+    ///
+    /// Returns [`None`] if the node is not present in the program or is not tagged with `rawText`.
     /// Because the [`Dialogue`] API is designed to be unaware
     /// of the contents of the string table, this method does not test to
     /// see if the string table contains an entry with the line ID. You will
-    /// need to test for that yourself.
+    /// need to test for that yourself, e.g. via [`TextProvider::get_text`].
     #[must_use]
     pub fn get_line_id_for_node(&self, node_name: &str) -> Option<LineId> {
         self.get_node_logging_errors(node_name)
+            .filter(|node| node.tags.iter().any(|tag| tag == "rawText"))
             .map(|_| format!("line:{node_name}").into())
     }
 
@@ -369,6 +777,11 @@ impl Dialogue {
     /// The headers are all the key-value pairs defined in the node's source code
     /// including the `tags` and `title` headers.
     ///
+    /// A header's value is everything after the first `:` of its line up to the end of the line,
+    /// so it may contain further colons, e.g. in URLs or structured metadata like `meta: {"time": "12:30"}`.
+    /// Values cannot span multiple lines, since every line before the `---` that starts the body is read as a header of its own.
+    /// If a key is used more than once, the value of its last occurrence is returned.
+    ///
     /// Returns [`None`] if the node is not present in the program.
     #[must_use]
     pub fn get_headers_for_node(&self, node_name: &str) -> Option<HashMap<String, String>> {
@@ -380,18 +793,134 @@ impl Dialogue {
         })
     }
 
+    /// Returns the coordinates stored in the `position` header of the node `node_name`, as written by graph editors
+    /// in the format `position: x,y`. Whitespace around the coordinates is ignored.
+    ///
+    /// Returns [`None`] if the node is not present in the program, has no `position` header, or the header is malformed.
+    #[must_use]
+    pub fn get_node_position(&self, node_name: &str) -> Option<(i32, i32)> {
+        let headers = self.get_headers_for_node(node_name)?;
+        let (x, y) = headers.get("position")?.split_once(',')?;
+        Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+    }
+
+    /// Evaluates the expressions that are interpolated into the line or option `line_id`, e.g. `{$name}`,
+    /// against the current variables and returns them in order, formatted for display.
+    /// The result corresponds to the `{0}`, `{1}`, etc. markers in the line's text in the string table.
+    ///
+    /// This does not run or otherwise change the dialogue, so it can be used by tools that render lines outside of it.
+    ///
+    /// Returns [`None`] if no program is loaded, if no node contains the line,
+    /// or if the line's expressions call a function that is not in the [`Library`].
+    #[must_use]
+    pub fn substitutions_for_line(&self, line_id: &LineId) -> Option<Vec<String>> {
+        self.vm.substitutions_for_line(line_id)
+    }
+
+    /// Returns a human-readable listing of the instructions of the node `node_name`, one entry per instruction or label.
+    /// Each instruction is listed with its index, opcode and operands, e.g. `   3 RunLine "line:0" 0`,
+    /// and each label is listed as `label_name:` right before the instruction it points to.
+    /// This is useful for diagnosing compiler issues.
+    ///
+    /// Returns [`None`] if the node is not present in the program.
+    ///
+    /// See [`Dialogue::disassemble_node_with_positions`] for a version that annotates the instructions with their source positions.
+    ///
+    /// ## Implementation notes
+    ///
+    /// This method is not present in the original implementation, but mirrors the output of the `ydisasm` tool.
+    #[must_use]
+    pub fn disassemble_node(&self, node_name: &str) -> Option<Vec<String>> {
+        self.disassemble_node_with_positions(node_name, &HashMap::new())
+    }
+
+    /// Like [`Dialogue::disassemble_node`], but annotates every instruction with the source position
+    /// found in `line_positions`, which maps instruction indices to positions.
+    /// The compiler provides this mapping in the `line_positions` of the `DebugInfo` of each node.
+    #[must_use]
+    pub fn disassemble_node_with_positions(
+        &self,
+        node_name: &str,
+        line_positions: &HashMap<usize, Option<Position>>,
+    ) -> Option<Vec<String>> {
+        let node = self.get_node_logging_errors(node_name)?;
+        let mut labels: Vec<_> = node.labels.iter().collect();
+        labels.sort_by_key(|(name, index)| (**index, *name));
+        let mut labels = labels.into_iter().peekable();
+        let mut listing = Vec::new();
+        for (index, instruction) in node.instructions.iter().enumerate() {
+            while let Some((label, _)) =
+                labels.next_if(|(_, &label_index)| label_index as usize == index)
+            {
+                listing.push(format!("{label}:"));
+            }
+            let entry = format!("{index:>4} {instruction}");
+            let entry = match line_positions.get(&index).copied().flatten() {
+                Some(position) => format!(
+                    "{entry:<40} ; line {}, column {}",
+                    position.line + 1,
+                    position.character + 1
+                ),
+                None => entry,
+            };
+            listing.push(entry);
+        }
+        // Labels pointing to the end of the node
+        listing.extend(labels.map(|(label, _)| format!("{label}:")));
+        Some(listing)
+    }
+
     /// Gets a value indicating whether a specified node exists in the [`Program`].
+    ///
+    /// Returns `false` both if no program is loaded and if the loaded program does not contain the node.
+    /// Use [`Dialogue::has_program`] to tell these cases apart.
     #[must_use]
     pub fn node_exists(&self, node_name: &str) -> bool {
         // Not calling `get_node_logging_errors` because this method does not write errors when there are no nodes.
         if let Some(program) = self.vm.program.as_ref() {
             program.nodes.contains_key(node_name)
         } else {
-            error!("Tried to call NodeExists, but no program has been loaded");
+            debug!("Tried to call NodeExists, but no program has been loaded");
             false
         }
     }
 
+    /// Returns the nodes of the loaded [`Program`] and the jumps between them, e.g. to document the structure of a story
+    /// or to find nodes that can never be reached. See [`NodeGraph::to_dot`] for rendering it.
+    ///
+    /// Jumps whose destination is only known at runtime lead to [`NodeEdgeDestination::Unknown`].
+    /// Returns an empty graph if no program is loaded.
+    #[must_use]
+    pub fn node_dependency_graph(&self) -> NodeGraph {
+        self.vm
+            .program
+            .as_ref()
+            .map(|program| NodeGraph::from_program(program))
+            .unwrap_or_default()
+    }
+
+    /// Returns the names of all commands that the loaded [`Program`] can run, e.g. to warn about commands without a handler.
+    ///
+    /// Commands whose name is only known at runtime, such as `<<{$action} door>>`, are not included.
+    /// Returns an empty set if no program is loaded.
+    #[must_use]
+    pub fn referenced_command_names(&self) -> BTreeSet<String> {
+        self.vm
+            .program
+            .iter()
+            .flat_map(|program| program.nodes.values())
+            .flat_map(|node| &node.instructions)
+            .filter(|instruction| instruction.opcode() == OpCode::RunCommand)
+            .filter_map(|instruction| Command::name_of(&instruction.read_operand::<String>(0)))
+            .collect()
+    }
+
+    /// Gets a value indicating whether a [`Program`] has been loaded via [`Dialogue::add_program`] or [`Dialogue::replace_program`].
+    #[must_use]
+    pub fn has_program(&self) -> bool {
+        self.vm.program.is_some()
+    }
+
     /// Gets the name of the node that this Dialogue is currently executing.
     ///
     /// If [`Dialogue::next`] has never been called, this value will be [`None`].
@@ -400,6 +929,144 @@ impl Dialogue {
         self.vm.current_node()
     }
 
+    /// Returns the tags of the node that this Dialogue is currently executing,
+    /// i.e. the equivalent of calling [`Dialogue::get_tags_for_node`] with [`Dialogue::current_node`], but without the chance of the node changing in between.
+    ///
+    /// If [`Dialogue::next`] has never been called, this value will be [`None`].
+    #[must_use]
+    pub fn current_node_tags(&self) -> Option<Vec<String>> {
+        self.vm.current_node_tags()
+    }
+
+    /// Returns the name and parameters of the command that the dialogue is waiting on, e.g. for a debug overlay.
+    /// This is the command of the last [`DialogueEvent::Command`] returned by [`Dialogue::continue_`],
+    /// until the dialogue is continued or stopped. Returns [`None`] if the dialogue is not waiting on a command.
+    ///
+    /// Commands with a handler registered via [`Dialogue::with_command`] are never pending, since they are run without waiting.
+    #[must_use]
+    pub fn pending_command(&self) -> Option<(String, Vec<YarnValue>)> {
+        self.vm
+            .pending_command()
+            .map(|command| (command.name.clone(), command.parameters.clone()))
+    }
+
+    /// Gets the names of the nodes that were entered, with the most recent one last.
+    /// Every time the current node changes, e.g. through [`Dialogue::set_node`] or a `<<jump>>`, its name is appended.
+    ///
+    /// Only the last [`Dialogue::DEFAULT_HISTORY_CAPACITY`] nodes are kept, see [`Dialogue::with_history_capacity`].
+    /// The history is cleared by [`Dialogue::stop`].
+    #[must_use]
+    pub fn jump_history(&self) -> &[String] {
+        &self.vm.jump_history
+    }
+
+    /// Gets the instructions that were run, with the most recent one last.
+    /// Empty unless recording was enabled with [`Dialogue::with_instruction_trace`].
+    ///
+    /// Unlike [`Dialogue::jump_history`], the trace is not cleared by [`Dialogue::stop`],
+    /// so it can be inspected after a dialogue ended unexpectedly.
+    pub fn instruction_trace(&self) -> impl ExactSizeIterator<Item = &TraceEntry> + '_ {
+        self.vm.instruction_trace.iter()
+    }
+
+    /// Sets whether the options of a [`DialogueEvent::Options`] are presented in a random order.
+    /// Options tagged with [`PINNED_TAG`] or registered via [`Dialogue::add_pinned_lines`] keep their position, e.g. so that a "Cancel" option stays last.
+    /// The [`OptionId`]s are not changed by shuffling, so selecting an option works the same either way.
+    ///
+    /// The order is drawn from the random number generator seeded by [`Dialogue::with_rng_seed`].
+    /// Defaults to `false`.
+    #[must_use]
+    pub fn with_shuffle_options(mut self, shuffle_options: bool) -> Self {
+        self.vm.shuffle_options = shuffle_options;
+        self
+    }
+
+    /// Sets what happens when an option whose [`DialogueOption::is_available`] is `false` is passed to [`Dialogue::set_selected_option`]
+    /// or [`Dialogue::set_selected_options`], see [`UnavailableSelectionPolicy`].
+    /// Defaults to [`UnavailableSelectionPolicy::Reject`].
+    #[must_use]
+    pub fn with_unavailable_selection_policy(mut self, policy: UnavailableSelectionPolicy) -> Self {
+        self.vm.unavailable_selection_policy = policy;
+        self
+    }
+
+    /// Sets the order in which the options of a [`DialogueEvent::Options`] are presented, see [`OptionSort`].
+    /// Like with [`Dialogue::with_shuffle_options`], pinned options keep their position and the [`OptionId`]s are not changed.
+    /// When combined with shuffling, the sort order takes precedence.
+    /// Defaults to [`OptionSort::SourceOrder`].
+    #[must_use]
+    pub fn with_option_sort(mut self, option_sort: OptionSort) -> Self {
+        self.vm.option_sort = option_sort;
+        self
+    }
+
+    /// Seeds the random number generator used by the [`Dialogue`], e.g. for [`Dialogue::with_shuffle_options`] and for picking lines of line groups,
+    /// so that runs are reproducible on every platform. Without a seed, the generator is seeded randomly.
+    #[must_use]
+    pub fn with_rng_seed(mut self, seed: u64) -> Self {
+        self.vm.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// Makes the `==` and `!=` operators in Yarn scripts consider two numbers equal if they differ by at most `epsilon` times the larger of their magnitudes,
+    /// so that e.g. `0.1 + 0.2 == 0.3` holds despite rounding errors. See [`Library::set_number_equality_tolerance`].
+    /// By default, numbers are compared exactly, so that scripts behave the same as in other Yarn Spinner runtimes.
+    /// A relative tolerance like `1e-6` is enough to absorb the rounding errors of typical dialogue math.
+    ///
+    /// ## Implementation notes
+    ///
+    /// This method is not present in the original implementation, which always compares numbers exactly.
+    #[must_use]
+    pub fn with_float_epsilon(mut self, epsilon: f32) -> Self {
+        self.vm.library.set_number_equality_tolerance(epsilon);
+        self
+    }
+
+    /// Sets how many instructions a single call to [`Dialogue::continue_`] may run before giving up.
+    /// When the limit is exceeded, the dialogue is stopped and [`DialogueError::StepLimitExceeded`] is returned,
+    /// so that a script stuck in an infinite loop, e.g. a node that unconditionally jumps to itself, cannot hang the game.
+    /// The events of stopping the dialogue are only delivered by [`Dialogue::next`], which reports the error to the [`Dialogue::with_error_handler`] instead.
+    /// Defaults to [`Dialogue::DEFAULT_MAX_STEPS_PER_NEXT`].
+    #[must_use]
+    pub fn with_max_steps_per_next(mut self, max_steps: usize) -> Self {
+        self.vm.max_steps_per_next = max_steps;
+        self
+    }
+
+    /// Registers a callback that is called with every error the [`Dialogue`] encounters while running, e.g. for reporting them to telemetry.
+    /// This includes all errors returned by [`Dialogue::continue_`], which are still returned as usual after the callback ran,
+    /// as well as errors that are otherwise only logged, like a failure to reset a visit count in the [`VariableStorage`].
+    /// The callback only gets to see the error and cannot access the [`Dialogue`] itself.
+    /// Registering a callback replaces the previous one.
+    #[must_use]
+    pub fn with_error_handler(
+        mut self,
+        handler: impl Fn(&DialogueError) + Send + Sync + 'static,
+    ) -> Self {
+        self.vm.error_handler.set(handler);
+        self
+    }
+
+    /// Sets how many nodes [`Dialogue::jump_history`] remembers. A capacity of 0 disables the history.
+    #[must_use]
+    pub fn with_history_capacity(mut self, capacity: usize) -> Self {
+        self.vm.history_capacity = capacity;
+        let overflow = self.vm.jump_history.len().saturating_sub(capacity);
+        self.vm.jump_history.drain(..overflow);
+        self
+    }
+
+    /// Records the last `capacity` instructions that were run, readable via [`Dialogue::instruction_trace`].
+    /// Intended for debugging scripts that behave unexpectedly. A capacity of 0 disables the trace, which is the default,
+    /// in which case nothing is recorded.
+    #[must_use]
+    pub fn with_instruction_trace(mut self, capacity: usize) -> Self {
+        self.vm.instruction_trace_capacity = capacity;
+        let overflow = self.vm.instruction_trace.len().saturating_sub(capacity);
+        self.vm.instruction_trace.drain(..overflow);
+        self
+    }
+
     /// Analyses the currently loaded Yarn program with the given [`Context`]. Call [`Context::finish_analysis`] afterwards to get the results.
     pub fn analyse(&self, context: &mut Context) -> &Self {
         let program = self
@@ -423,7 +1090,7 @@ impl Dialogue {
                 None
             }
         } else {
-            error!("No program is loaded");
+            debug!("No program is loaded");
             None
         }
     }
@@ -439,6 +1106,12 @@ impl Dialogue {
     /// - If the Dialogue is not expecting an option to be selected.
     /// - If the option ID is not found in the vector of [`DialogueOption`] provided by [`DialogueEvent::Options`].
     ///
+    /// ## Errors
+    /// - If the option is not available, depending on [`Dialogue::with_unavailable_selection_policy`].
+    ///
+    /// With [`UnavailableSelectionPolicy::TreatAsNoOp`], selecting an unavailable option is ignored without an error instead,
+    /// so the dialogue is still waiting for an option selection afterwards.
+    ///
     /// ## See Also
     /// - [`Dialogue::continue_`]
     pub fn set_selected_option(&mut self, selected_option_id: OptionId) -> Result<&mut Self> {
@@ -446,6 +1119,102 @@ impl Dialogue {
         Ok(self)
     }
 
+    /// Signals to the [`Dialogue`] that the user has selected several [`DialogueOption`]s of a multi-select option group at once.
+    ///
+    /// This is only valid if every option of the last [`DialogueEvent::Options`] belongs to a line tagged with [`MULTISELECT_TAG`] or registered with [`Dialogue::add_multiselect_lines`].
+    /// The [`LineId`]s of the selected options are stored in the variable [`MULTISELECT_VARIABLE_NAME`], separated by [`MULTISELECT_SEPARATOR`].
+    /// Afterwards, the dialogue continues with the first selected option.
+    ///
+    /// ## Errors
+    /// - If the Dialogue is not expecting an option to be selected.
+    /// - If the options are not all marked as multi-select.
+    /// - If `selected_option_ids` is empty or contains an ID not found in the vector of [`DialogueOption`] provided by [`DialogueEvent::Options`].
+    /// - If one of the options is not available, depending on [`Dialogue::with_unavailable_selection_policy`].
+    pub fn set_selected_options(&mut self, selected_option_ids: &[OptionId]) -> Result<&mut Self> {
+        self.vm.set_selected_options(selected_option_ids)?;
+        Ok(self)
+    }
+
+    /// Applies the side effects a command handler requested via [`CommandResult`], i.e. writes its variables into the [`VariableStorage`].
+    /// Call this after handling a [`DialogueEvent::Command`] and before calling [`Dialogue::continue_`] again,
+    /// so that subsequent instructions, e.g. an `<<if>>`, see the new values.
+    ///
+    /// ## Errors
+    /// - If one of the variables is a constant of a loaded program, see [`DialogueError::ConstantAssignment`].
+    /// - If the [`VariableStorage`] rejects one of the writes.
+    ///
+    /// Writes before the failing one remain applied.
+    pub fn apply_command_result(&mut self, result: CommandResult) -> Result<&mut Self> {
+        let CommandResult::ContinueWithVariables(variable_writes) = result else {
+            return Ok(self);
+        };
+        for (name, value) in variable_writes {
+            self.vm.set_variable_checked(name, value)?;
+        }
+        Ok(self)
+    }
+
+    /// Marks the lines with the given IDs as options that may be selected together via [`Dialogue::set_selected_options`].
+    /// The lines tagged with [`MULTISELECT_TAG`] in a [`Program`] are marked automatically by [`Dialogue::add_program`] and [`Dialogue::replace_program`],
+    /// so this is only needed for programs that were not compiled by this crate's compiler or for lines that should behave like this without the tag.
+    pub fn add_multiselect_lines(
+        &mut self,
+        line_ids: impl IntoIterator<Item = LineId>,
+    ) -> &mut Self {
+        self.vm.multiselect_line_ids.extend(line_ids);
+        self
+    }
+
+    /// Marks the lines with the given IDs as options that are no longer offered once they have been selected.
+    /// The lines tagged with [`ONCE_TAG`] in a [`Program`] are marked automatically by [`Dialogue::add_program`] and [`Dialogue::replace_program`],
+    /// so this is only needed for programs that were not compiled by this crate's compiler or for lines that should behave like this without the tag.
+    pub fn add_once_lines(&mut self, line_ids: impl IntoIterator<Item = LineId>) -> &mut Self {
+        self.vm.once_line_ids.extend(line_ids);
+        self
+    }
+
+    /// Marks the lines with the given IDs as options that keep their position when options are shuffled, see [`Dialogue::with_shuffle_options`].
+    /// The lines tagged with [`PINNED_TAG`] in a [`Program`] are marked automatically by [`Dialogue::add_program`] and [`Dialogue::replace_program`],
+    /// so this is only needed for programs that were not compiled by this crate's compiler or for lines that should behave like this without the tag.
+    pub fn add_pinned_lines(&mut self, line_ids: impl IntoIterator<Item = LineId>) -> &mut Self {
+        self.vm.pinned_line_ids.extend(line_ids);
+        self
+    }
+
+    /// Registers the requirement hints of options, delivered in [`DialogueOption::requirement_hint`].
+    /// The values of the [`REQUIREMENT_TAG`] hashtags in a [`Program`], e.g. `50_gold` for `#requirement:50_gold`, are registered automatically
+    /// by [`Dialogue::add_program`] and [`Dialogue::replace_program`], so this is only needed for programs that were not compiled by this crate's compiler
+    /// or for hints that cannot be written as a hashtag. Registering a hint for a line that already has one replaces it.
+    pub fn add_requirement_hints(
+        &mut self,
+        hints: impl IntoIterator<Item = (LineId, String)>,
+    ) -> &mut Self {
+        self.vm.requirement_hints.extend(hints);
+        self
+    }
+
+    /// Marks the variables with the given names as constants, so that running a program that assigns to them fails with [`DialogueError::ConstantAssignment`].
+    /// The constants declared with `<<const $name = value>>` in a [`Program`] are marked automatically by [`Dialogue::add_program`] and [`Dialogue::replace_program`],
+    /// so this is only needed for variables that should be constant without being declared as such, e.g. ones set by the game.
+    /// The compiler already rejects assignments to constants in the same compilation, so this guards against programs compiled separately.
+    ///
+    /// Only assignments made by running a program are refused. Setting a constant through [`Dialogue::variable_storage_mut`] or the [`VariableStorage`] itself,
+    /// e.g. to restore a saved game, is still possible.
+    pub fn add_constant_variables(
+        &mut self,
+        variable_names: impl IntoIterator<Item = String>,
+    ) -> &mut Self {
+        self.vm.constant_variables.extend(variable_names);
+        self
+    }
+
+    /// Returns `true` if the dialogue is waiting for an option selection and all presented options are marked as multi-select,
+    /// meaning that [`Dialogue::set_selected_options`] may be called.
+    #[must_use]
+    pub fn is_waiting_for_multiselect(&self) -> bool {
+        self.vm.is_waiting_for_multiselect()
+    }
+
     /// Gets a value indicating whether the Dialogue is currently executing Yarn instructions.
     #[must_use]
     pub fn is_active(&self) -> bool {
@@ -473,4 +1242,57 @@ mod tests {
     }
 
     fn accept_send_sync(_: impl Send + Sync) {}
+
+    #[test]
+    fn runs_registered_command_handlers() {
+        let received_parameters = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let handler_parameters = received_parameters.clone();
+        let mut dialogue = Dialogue::new(
+            Box::new(MemoryVariableStorage::new()),
+            Box::new(StringTableTextProvider::new()),
+        )
+        .with_command("greet", move |parameters| {
+            handler_parameters.lock().unwrap().extend(parameters);
+            CommandResult::Continue.with_variable_set("$greeted", true)
+        });
+        let command = |text: &str| Instruction {
+            opcode: OpCode::RunCommand.into(),
+            operands: vec![text.to_owned().into(), 0_usize.into()],
+        };
+        let node = Node {
+            name: "Start".to_owned(),
+            instructions: vec![
+                command("greet Sally"),
+                command("shrug"),
+                Instruction {
+                    opcode: OpCode::Stop.into(),
+                    operands: vec![],
+                },
+            ],
+            ..Default::default()
+        };
+        dialogue.replace_program(Program {
+            nodes: HashMap::from([("Start".to_owned(), node)]),
+            ..Default::default()
+        });
+        dialogue.set_node("Start").unwrap();
+
+        let events = dialogue.continue_().unwrap();
+        let commands: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                DialogueEvent::Command(command) => Some(command.name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec!["shrug"], commands);
+        assert_eq!(
+            vec![YarnValue::from("Sally")],
+            *received_parameters.lock().unwrap()
+        );
+        assert_eq!(
+            YarnValue::from(true),
+            dialogue.variable_storage().get("$greeted").unwrap()
+        );
+    }
 }
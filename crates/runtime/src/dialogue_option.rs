@@ -3,6 +3,65 @@
 use crate::prelude::*;
 use std::fmt::Display;
 
+pub use yarnspinner_core::prelude::{
+    MULTISELECT_SEPARATOR, MULTISELECT_TAG, ONCE_TAG, PINNED_TAG, REQUIREMENT_TAG,
+};
+
+/// The variable in which [`Dialogue::set_selected_options`] stores the selected options.
+/// Like the visit counters, it lives in the `$Yarn.Internal.` namespace, so it cannot clash with a variable of a Yarn script.
+/// Read it from the [`VariableStorage`] after the selection.
+///
+/// Since a [`YarnValue`] cannot hold a list, the value is a [`YarnValue::String`] holding the [`LineId`]s of the selected options,
+/// separated by [`MULTISELECT_SEPARATOR`], e.g. `line:bread,line:wine`. The compiler rejects lines tagged with [`MULTISELECT_TAG`]
+/// whose ID contains the separator, so splitting the value at it recovers the IDs.
+/// Lines registered via [`Dialogue::add_multiselect_lines`] are not checked.
+pub const MULTISELECT_VARIABLE_NAME: &str = "$Yarn.Internal.SelectedOptions";
+
+/// The order in which the options of a [`DialogueEvent::Options`] are presented, set via [`Dialogue::with_option_sort`].
+/// Options tagged with [`PINNED_TAG`] or registered via [`Dialogue::add_pinned_lines`] keep their position regardless of the order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq, Hash, Default))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+pub enum OptionSort {
+    /// The options are presented in the order in which they appear in the Yarn script.
+    #[default]
+    SourceOrder,
+    /// The options are sorted alphabetically by their text, using the collation rules of the [`Dialogue::language_code`],
+    /// so that e.g. accented characters are sorted next to their unaccented counterparts.
+    /// Without a language code, the language-neutral root collation is used.
+    LocaleAlphabetical,
+}
+
+/// What happens when an option whose [`DialogueOption::is_available`] is `false` is selected,
+/// e.g. because of stale input, set via [`Dialogue::with_unavailable_selection_policy`].
+///
+/// ## Implementation notes
+///
+/// This enum is not present in the original implementation, which accepts the selection of unavailable options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq, Hash, Default))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+pub enum UnavailableSelectionPolicy {
+    /// The selection returns [`DialogueError::UnavailableOptionSelected`] and the dialogue keeps waiting for a valid selection.
+    #[default]
+    Reject,
+    /// The selection is ignored without an error and the dialogue keeps waiting for a valid selection.
+    TreatAsNoOp,
+    /// The selection returns [`DialogueError::UnavailableOptionSelected`] and the dialogue is stopped,
+    /// for games that consider selecting an unavailable option a bug.
+    Error,
+}
+
 /// An option to be presented to the user.
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "bevy", derive(Reflect))]
@@ -35,6 +94,27 @@ pub struct DialogueOption {
     /// This is intended for situations where games wish to show options that the player _could_ have taken,
     /// if some other condition had been met (e.g. having enough "charisma" points).
     pub is_available: bool,
+
+    /// A description of what is needed to select this option, e.g. `50_gold`, so that it can be shown next to the option when it is not available.
+    /// Set for lines tagged with [`REQUIREMENT_TAG`] or registered via [`Dialogue::add_requirement_hints`], regardless of whether the option is currently available.
+    pub requirement_hint: Option<String>,
+}
+
+/// Convenience methods for the options delivered by [`DialogueEvent::Options`], which correspond to the original `OptionSet`.
+///
+/// ## Implementation notes
+///
+/// This trait is not present in the original implementation, since there, these options are a dedicated `OptionSet` type.
+pub trait OptionSetExt {
+    /// Returns the [`LineId`]s of all options in the order in which they are presented, e.g. so that a view can preload the voice-over of all visible options
+    /// before one of them is selected.
+    fn line_ids(&self) -> Vec<LineId>;
+}
+
+impl OptionSetExt for [DialogueOption] {
+    fn line_ids(&self) -> Vec<LineId> {
+        self.iter().map(|option| option.line.id.clone()).collect()
+    }
 }
 
 /// The identifying number for an option. You should not need to create these yourself, since you get them from [`DialogueOption`]s.
@@ -2,7 +2,7 @@
 //!
 //! ## Implementation notes
 //!
-//! - `OptionSet` was replaced by a simple `Vec<DialogueOption>`
+//! - `OptionSet` was replaced by a simple `Vec<DialogueOption>`, whose convenience methods are provided by [`OptionSetExt`]
 //! - Additional newtypes were introduced for strings.
 
 use crate::prelude::*;
@@ -26,6 +26,7 @@ pub enum DialogueEvent {
     Line(Line),
     /// A list of [`DialogueOption`]s should be presented to the user, who in turns must select one of them.
     /// The selected option must be communicated to the [`Dialogue`] via [`Dialogue::set_selected_option`] before calling [`Dialogue::continue_`] again.
+    /// See [`OptionSetExt`] for convenience methods on the options.
     Options(Vec<DialogueOption>),
     /// A [`Command`] should be executed.
     ///
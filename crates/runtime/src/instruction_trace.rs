@@ -0,0 +1,22 @@
+//! Contains [`TraceEntry`], the record of a single instruction run by a [`Dialogue`] with an instruction trace enabled.
+
+use crate::prelude::*;
+
+/// A single instruction that was run by a [`Dialogue`], as recorded by [`Dialogue::with_instruction_trace`].
+/// Read the recorded entries with [`Dialogue::instruction_trace`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct TraceEntry {
+    /// The name of the node the instruction belongs to.
+    pub node_name: String,
+    /// The index of the instruction in the node.
+    pub instruction_index: usize,
+    /// The value on top of the stack after the instruction ran or failed, or [`None`] if the stack was empty.
+    pub stack_top: Option<YarnValue>,
+}
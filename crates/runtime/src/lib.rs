@@ -11,9 +11,10 @@ mod command;
 mod dialogue;
 mod dialogue_option;
 mod events;
-mod language;
+mod instruction_trace;
 mod line;
 pub mod markup;
+mod node_graph;
 mod pluralization;
 mod text_provider;
 mod variable_storage;
@@ -29,12 +30,14 @@ pub mod prelude {
         dialogue::{Dialogue, DialogueError},
         dialogue_option::*,
         events::*,
-        language::*,
+        instruction_trace::*,
         line::*,
-        markup::MarkupParseError,
+        markup::{MarkupParseError, UnclosedAttributePolicy},
+        node_graph::*,
         text_provider::*,
         variable_storage::*,
     };
     pub(crate) use crate::{pluralization::*, virtual_machine::*};
     pub(crate) use yarnspinner_core::prelude::*;
+    pub use yarnspinner_core::prelude::{Language, LanguageTagError};
 }
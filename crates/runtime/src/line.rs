@@ -4,9 +4,14 @@
 //! Introduced `LineId` newtype for better type safety
 
 use crate::markup::{
-    MarkupAttribute, MarkupValue, CHARACTER_ATTRIBUTE, CHARACTER_ATTRIBUTE_NAME_PROPERTY,
+    MarkupAttribute, MarkupValue, ParsedMarkup, CHARACTER_ATTRIBUTE,
+    CHARACTER_ATTRIBUTE_NAME_PROPERTY, NO_MARKUP_ATTRIBUTE,
 };
 use crate::prelude::*;
+use std::fmt::{Debug, Formatter};
+use std::ops::Range;
+use std::sync::Arc;
+use unicode_segmentation::UnicodeSegmentation;
 
 /// A line of dialogue, sent from the [`Dialogue`] to the game.
 ///
@@ -50,6 +55,26 @@ impl Line {
         self.attributes.iter().find(|attr| attr.name == name)
     }
 
+    /// Returns the attributes that apply to any part of the text in `grapheme_range`, in the order they appear in [`Line::attributes`].
+    /// The range is measured in grapheme clusters, just like [`MarkupAttribute::position`] and [`MarkupAttribute::length`].
+    ///
+    /// Useful for typewriter-style views that reveal a line piece by piece and need to know which effects to apply to the newly revealed text.
+    /// Self-closing attributes like `[pause/]` have no length and are returned if their position lies within the range.
+    pub fn active_attributes_in(&self, grapheme_range: Range<usize>) -> Vec<&MarkupAttribute> {
+        self.attributes
+            .iter()
+            .filter(|attribute| {
+                let start = attribute.position;
+                let end = attribute.position + attribute.length;
+                if attribute.length == 0 {
+                    grapheme_range.contains(&start)
+                } else {
+                    start < grapheme_range.end && end > grapheme_range.start
+                }
+            })
+            .collect()
+    }
+
     /// The name of the character, if present.
     /// ## Examples
     /// When there is a name:
@@ -139,10 +164,17 @@ impl Line {
     /// assert_eq!("Great, thanks", line.text);
     /// assert_eq!("Great, thanks", &line.text_without_character_name());
     pub fn text_without_character_name(&self) -> String {
+        self.without_character_name().text
+    }
+
+    /// Returns a copy of this line with the text covered by the `character` attribute removed,
+    /// like [`Line::text_without_character_name`], but also keeps the other attributes,
+    /// with their positions shifted to account for the removed name. See [`Line::delete_range`] for details.
+    pub fn without_character_name(&self) -> Self {
         if let Some(attribute) = self.attribute(CHARACTER_ATTRIBUTE) {
-            self.delete_range(attribute).text
+            self.delete_range(attribute)
         } else {
-            self.text.to_owned()
+            self.clone()
         }
     }
 
@@ -279,3 +311,100 @@ impl Line {
         }
     }
 }
+
+/// The functions registered with [`Dialogue::with_line_transformer`], applied in registration order.
+#[derive(Clone, Default)]
+pub(crate) struct LineTransformers(Vec<LineTransformer>);
+
+type LineTransformer = Arc<dyn Fn(&mut Line) + Send + Sync>;
+
+impl LineTransformers {
+    pub(crate) fn push(&mut self, transformer: impl Fn(&mut Line) + Send + Sync + 'static) {
+        self.0.push(Arc::new(transformer));
+    }
+
+    pub(crate) fn apply(&self, line: &mut Line) {
+        for transformer in &self.0 {
+            transformer(line);
+        }
+    }
+}
+
+impl Debug for LineTransformers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("LineTransformers")
+            .field(&format!("{} transformers", self.0.len()))
+            .finish()
+    }
+}
+
+/// How whitespace in the text of a [`Line`] is normalized after its markup is parsed, set via [`Dialogue::with_whitespace_collapse`].
+/// Text inside a `[nomarkup]` attribute is always preserved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq, Hash, Default))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+pub enum WhitespaceMode {
+    /// The text is delivered as written.
+    #[default]
+    Preserve,
+    /// Runs of whitespace other than line breaks, e.g. doubled spaces, are replaced by a single space.
+    CollapseRuns,
+    /// Like [`WhitespaceMode::CollapseRuns`], but line breaks are treated as spaces as well, so that the line has no line breaks.
+    SingleLine,
+}
+
+impl WhitespaceMode {
+    /// Normalizes the whitespace of the parsed text, moving the attributes so that they still cover the same text.
+    pub(crate) fn apply(self, markup: &mut ParsedMarkup) {
+        if self == WhitespaceMode::Preserve {
+            return;
+        }
+        let graphemes: Vec<_> = markup.text.graphemes(true).collect();
+        let mut is_preserved = vec![false; graphemes.len()];
+        for attribute in &markup.attributes {
+            if attribute.name == NO_MARKUP_ATTRIBUTE {
+                let end = (attribute.position + attribute.length).min(graphemes.len());
+                is_preserved[attribute.position.min(end)..end].fill(true);
+            }
+        }
+
+        let mut text = String::with_capacity(markup.text.len());
+        // Maps every grapheme position of the original text to its position in the normalized text.
+        let mut new_positions = Vec::with_capacity(graphemes.len() + 1);
+        let mut length = 0;
+        let mut previous_was_space = false;
+        for (grapheme, is_preserved) in graphemes.into_iter().zip(is_preserved) {
+            new_positions.push(length);
+            let is_space = !is_preserved && self.is_collapsible(grapheme);
+            if !is_space {
+                text.push_str(grapheme);
+                length += 1;
+            } else if !previous_was_space {
+                text.push(' ');
+                length += 1;
+            }
+            previous_was_space = is_space;
+        }
+        new_positions.push(length);
+
+        for attribute in &mut markup.attributes {
+            let start = new_positions[attribute.position.min(new_positions.len() - 1)];
+            let end =
+                new_positions[(attribute.position + attribute.length).min(new_positions.len() - 1)];
+            attribute.position = start;
+            attribute.length = end - start;
+        }
+        markup.text = text;
+    }
+
+    fn is_collapsible(self, grapheme: &str) -> bool {
+        let is_line_break = grapheme.contains(['\n', '\r']);
+        grapheme.chars().all(char::is_whitespace)
+            && (!is_line_break || self == WhitespaceMode::SingleLine)
+    }
+}
@@ -5,16 +5,23 @@
 //! Greg: You're a [size=12]cat[/size]!
 //! ```
 //! The parsing extracts the information that "Mae" and "Greg" are characters, that "shout" and "size" are attributes, and that "size" has a value of "12".
-mod attribute_marker_processor;
-mod line_parser;
-mod markup_parse_error;
-mod parsed_markup;
-
-pub use self::line_parser::{
-    CHARACTER_ATTRIBUTE, CHARACTER_ATTRIBUTE_NAME_PROPERTY, TRIM_WHITESPACE_PROPERTY,
+//!
+//! The parser itself lives in [`yarnspinner_core::markup`], so that the compiler can check markup with the same rules.
+mod dialogue_text_processor;
+mod markup_builder;
+mod markup_cache;
+
+pub use self::markup_builder::MarkupBuilder;
+pub use self::markup_cache::DEFAULT_MARKUP_CACHE_CAPACITY;
+pub(crate) use self::{dialogue_text_processor::*, markup_cache::*};
+pub(crate) use yarnspinner_core::markup::{
+    AttributeMarkerProcessor, LineParser, MarkupAttributeMarker, ParsedMarkup, Result,
+    NO_MARKUP_ATTRIBUTE,
+};
+pub use yarnspinner_core::markup::{
+    MarkupAttribute, MarkupParseError, MarkupValue, UnclosedAttributePolicy, CHARACTER_ATTRIBUTE,
+    CHARACTER_ATTRIBUTE_NAME_PROPERTY, TRIM_WHITESPACE_PROPERTY,
 };
-pub(crate) use self::{attribute_marker_processor::*, line_parser::*};
-pub use self::{markup_parse_error::*, parsed_markup::*};
 
 #[cfg(test)]
 mod tests {
@@ -107,6 +114,26 @@ mod tests {
         assert!(markup.get_attribute("c").is_none());
     }
 
+    #[test]
+    fn test_finding_active_attributes_in_range() {
+        let line = "[a]A [b]B[/a] [pause/]C[/b] D";
+        let markup = line_parser().parse_markup(line).unwrap();
+        let line = markup.as_line();
+        assert_eq!("A B C D", line.text);
+
+        let names = |range| -> Vec<_> {
+            line.active_attributes_in(range)
+                .into_iter()
+                .map(|attribute| attribute.name.as_str())
+                .collect()
+        };
+        assert_eq!(vec!["a"], names(0..2));
+        assert_eq!(vec!["a", "b"], names(1..3));
+        assert_eq!(vec!["b", "pause"], names(3..5));
+        assert_eq!(vec!["b"], names(3..4));
+        assert!(names(5..7).is_empty());
+    }
+
     #[test]
     fn test_multibyte_character_parsing() {
         for input in [
@@ -199,7 +226,7 @@ mod tests {
         for input in [
             "A [b]B [c]C[/c][/b] D", // attributes can be closed
             "A [b]B [c]C[/b][/c] D", // attributes can be closed out of order
-            "A [b]B [c]C[/] D",      // "[/]" closes all open attributes
+            "A [b]B [c]C[/][/] D",   // "[/]" closes the innermost open attribute
         ] {
             let markup = line_parser().parse_markup(input).unwrap();
 
@@ -219,6 +246,58 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_close_all_marker() {
+        let markup = line_parser().parse_markup("[b]text[/]").unwrap();
+        assert_eq!("text", markup.text);
+        assert_eq!(1, markup.attributes.len());
+        assert_eq!("b", markup.attributes[0].name);
+        assert_eq!(4, markup.attributes[0].length);
+
+        // The first close marker closes `i`, the second one `b`
+        let markup = line_parser().parse_markup("[b][i]te[/]xt[/]").unwrap();
+        assert_eq!("text", markup.text);
+        assert_eq!(2, markup.attributes.len());
+        assert_eq!("b", markup.attributes[0].name);
+        assert_eq!(4, markup.attributes[0].length);
+        assert_eq!("i", markup.attributes[1].name);
+        assert_eq!(2, markup.attributes[1].length);
+
+        // Like the original, `b` is dropped by default since nothing closes it
+        let markup = line_parser().parse_markup("[b][i]text[/]").unwrap();
+        assert_eq!(1, markup.attributes.len());
+        assert_eq!("i", markup.attributes[0].name);
+    }
+
+    #[test]
+    fn test_unclosed_attributes_are_closed_at_end_of_line() {
+        let line = "A [b]B [i]C";
+        // Like the original, unclosed attributes are dropped by default
+        let markup = line_parser().parse_markup(line).unwrap();
+        assert_eq!("A B C", markup.text);
+        assert!(markup.attributes.is_empty());
+
+        let mut auto_closing_parser = line_parser();
+        auto_closing_parser.set_unclosed_attribute_policy(UnclosedAttributePolicy::AutoClose);
+        let markup = auto_closing_parser.parse_markup(line).unwrap();
+        assert_eq!("A B C", markup.text);
+        assert_eq!(2, markup.attributes.len());
+        assert_eq!("b", markup.attributes[0].name);
+        assert_eq!(2, markup.attributes[0].position);
+        assert_eq!(3, markup.attributes[0].length);
+        assert_eq!("i", markup.attributes[1].name);
+        assert_eq!(4, markup.attributes[1].position);
+        assert_eq!(1, markup.attributes[1].length);
+
+        let mut line_parser = line_parser();
+        line_parser.set_unclosed_attribute_policy(UnclosedAttributePolicy::Error);
+        assert!(matches!(
+            line_parser.parse_markup(line),
+            Err(MarkupParseError::UnclosedAttribute { name, position: 4, .. }) if name == "i"
+        ));
+        assert!(line_parser.parse_markup("A [b]B[/b]").is_ok());
+    }
+
     #[test]
     fn test_self_closing_attributes() {
         let line = "A [a/] B";
@@ -276,6 +355,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_implicit_character_attribute_ignores_markup_in_name() {
+        let line = "[b]Boss[/b]: [i]Hello[/i]";
+        let markup = line_parser().parse_markup(line).unwrap().as_line();
+
+        assert_eq!("Boss: Hello", markup.text);
+        assert_eq!(Some("Boss"), markup.character_name());
+        let character_attribute = markup.attribute("character").unwrap();
+        assert_eq!(0, character_attribute.position);
+        assert_eq!(6, character_attribute.length);
+
+        let without_name = markup.without_character_name();
+        assert_eq!("Hello", without_name.text);
+        assert_eq!("Hello", markup.text_without_character_name());
+        assert_eq!(1, without_name.attributes.len());
+        assert_eq!("i", without_name.attributes[0].name);
+        assert_eq!(0, without_name.attributes[0].position);
+        assert_eq!(5, without_name.attributes[0].length);
+    }
+
     #[test]
     fn test_no_markup_mode_parsing() {
         let line = "S [a]S[/a] [nomarkup][a]S;][/a][/nomarkup]";
@@ -0,0 +1,166 @@
+use crate::markup::{MarkupAttribute, MarkupValue};
+use crate::prelude::*;
+use std::collections::HashMap;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Builds a [`Line`] with [`MarkupAttribute`]s from code, e.g. for procedurally generated dialogue,
+/// without writing markup like `[b]...[/b]` and parsing it again.
+///
+/// Text is appended with [`MarkupBuilder::text`]. Attributes are opened with [`MarkupBuilder::push_attribute`] and cover all text
+/// appended until the matching [`MarkupBuilder::pop_attribute`]. Attributes can be nested, and any that are still open
+/// when calling [`MarkupBuilder::build`] cover the rest of the text. Positions and lengths are counted in grapheme clusters, just like for parsed markup.
+///
+/// ## Examples
+///
+/// ```rust
+/// # use yarnspinner_runtime::markup::MarkupBuilder;
+/// let line = MarkupBuilder::new()
+///     .text("Hello ")
+///     .push_attribute("wave")
+///     .text("there")
+///     .pop_attribute()
+///     .text("!")
+///     .build("line:greeting");
+///
+/// assert_eq!("Hello there!", line.text);
+/// assert_eq!("there", line.text_for_attribute(&line.attributes[0]));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MarkupBuilder {
+    text: String,
+    attributes: Vec<MarkupAttribute>,
+    /// Indices into `attributes` of the attributes that were pushed but not popped yet, innermost last.
+    open_attributes: Vec<usize>,
+}
+
+impl MarkupBuilder {
+    /// Creates a new [`MarkupBuilder`] without any text.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends plain text, which is covered by all currently open attributes.
+    #[must_use]
+    pub fn text(mut self, text: impl AsRef<str>) -> Self {
+        self.text.push_str(text.as_ref());
+        self
+    }
+
+    /// Opens an attribute that starts at the end of the text appended so far.
+    #[must_use]
+    pub fn push_attribute(mut self, name: impl Into<String>) -> Self {
+        let position = self.grapheme_count();
+        self.open_attributes.push(self.attributes.len());
+        self.attributes.push(MarkupAttribute {
+            name: name.into(),
+            position,
+            length: 0,
+            properties: HashMap::new(),
+            // There is no source text, so the best we can do is to point to where the marker would be.
+            source_position: position,
+        });
+        self
+    }
+
+    /// Sets a property of the innermost open attribute, e.g. `color` for `[color=red]`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if no attribute is open.
+    #[must_use]
+    pub fn with_property(mut self, name: impl Into<String>, value: impl Into<MarkupValue>) -> Self {
+        let index = *self
+            .open_attributes
+            .last()
+            .expect("Cannot set a markup property: no attribute is open");
+        self.attributes[index]
+            .properties
+            .insert(name.into(), value.into());
+        self
+    }
+
+    /// Closes the innermost open attribute, which then covers all text appended since it was pushed.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if no attribute is open.
+    #[must_use]
+    pub fn pop_attribute(mut self) -> Self {
+        let index = self
+            .open_attributes
+            .pop()
+            .expect("Cannot pop a markup attribute: no attribute is open");
+        self.close(index);
+        self
+    }
+
+    /// Closes all open attributes and returns the built [`Line`] with the given [`LineId`].
+    pub fn build(mut self, line_id: impl Into<LineId>) -> Line {
+        while let Some(index) = self.open_attributes.pop() {
+            self.close(index);
+        }
+        Line {
+            id: line_id.into(),
+            text: self.text,
+            attributes: self.attributes,
+        }
+    }
+
+    fn close(&mut self, index: usize) {
+        let end = self.grapheme_count();
+        let attribute = &mut self.attributes[index];
+        attribute.length = end - attribute.position;
+    }
+
+    fn grapheme_count(&self) -> usize {
+        self.text.graphemes(true).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_nested_attributes() {
+        let line = MarkupBuilder::new()
+            .text("A ")
+            .push_attribute("color")
+            .with_property("color", "red")
+            .text("red ")
+            .push_attribute("b")
+            .text("bold")
+            .pop_attribute()
+            .text(" word")
+            .pop_attribute()
+            .text("!")
+            .build("line:test");
+
+        assert_eq!("A red bold word!", line.text);
+        assert_eq!(2, line.attributes.len());
+
+        let color = &line.attributes[0];
+        assert_eq!("color", color.name);
+        assert_eq!(2, color.position);
+        assert_eq!(13, color.length);
+        assert_eq!(Some(&MarkupValue::from("red")), color.property("color"));
+        assert_eq!("red bold word", line.text_for_attribute(color));
+
+        let bold = &line.attributes[1];
+        assert_eq!("b", bold.name);
+        assert_eq!(6, bold.position);
+        assert_eq!(4, bold.length);
+        assert_eq!("bold", line.text_for_attribute(bold));
+    }
+
+    #[test]
+    fn closes_open_attributes_at_end_of_text() {
+        let line = MarkupBuilder::new()
+            .push_attribute("shake")
+            .text("Oh no")
+            .build("line:test");
+
+        assert_eq!(0, line.attributes[0].position);
+        assert_eq!(5, line.attributes[0].length);
+    }
+}
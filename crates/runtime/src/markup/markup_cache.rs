@@ -0,0 +1,192 @@
+use crate::markup::ParsedMarkup;
+use crate::prelude::*;
+use std::collections::{HashMap, VecDeque};
+
+/// The number of lines whose parsed markup a [`Dialogue`] remembers by default.
+/// See [`Dialogue::with_markup_cache_capacity`].
+pub const DEFAULT_MARKUP_CACHE_CAPACITY: usize = 64;
+
+type MarkupCacheKey = (LineId, Option<Language>);
+
+/// A least-recently-used cache for the markup of lines, so that lines shown repeatedly are not parsed again.
+///
+/// Entries are keyed by the [`LineId`] and the active language. Since the same line can have different text
+/// depending on its substitutions, an entry is only used if the text it was parsed from matches as well.
+#[derive(Debug, Clone)]
+pub(crate) struct MarkupCache {
+    capacity: usize,
+    entries: HashMap<MarkupCacheKey, (String, ParsedMarkup)>,
+    /// The keys of `entries`, from least to most recently used.
+    recency: VecDeque<MarkupCacheKey>,
+    /// How many lookups were answered from the cache.
+    pub(crate) hits: usize,
+    /// How many lookups had to be parsed anew.
+    pub(crate) misses: usize,
+}
+
+impl Default for MarkupCache {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_MARKUP_CACHE_CAPACITY)
+    }
+}
+
+impl MarkupCache {
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Default::default(),
+            recency: Default::default(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.recency.len() > capacity {
+            self.evict_least_recently_used();
+        }
+    }
+
+    /// Returns the cached markup of the line, or parses `text` with `parse` and caches the result.
+    pub(crate) fn get_or_parse(
+        &mut self,
+        line_id: &LineId,
+        language: Option<&Language>,
+        text: &str,
+        parse: impl FnOnce(&str) -> crate::markup::Result<ParsedMarkup>,
+    ) -> crate::markup::Result<ParsedMarkup> {
+        let key = (line_id.clone(), language.cloned());
+        if let Some((cached_text, markup)) = self.entries.get(&key) {
+            if cached_text == text {
+                let markup = markup.clone();
+                self.hits += 1;
+                self.mark_as_recently_used(&key);
+                return Ok(markup);
+            }
+        }
+        self.misses += 1;
+        let markup = parse(text)?;
+        if self.capacity > 0 {
+            if self.entries.contains_key(&key) {
+                self.mark_as_recently_used(&key);
+            } else {
+                if self.recency.len() >= self.capacity {
+                    self.evict_least_recently_used();
+                }
+                self.recency.push_back(key.clone());
+            }
+            self.entries.insert(key, (text.to_owned(), markup.clone()));
+        }
+        Ok(markup)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+
+    fn mark_as_recently_used(&mut self, key: &MarkupCacheKey) {
+        if let Some(index) = self.recency.iter().position(|k| k == key) {
+            let key = self.recency.remove(index).unwrap();
+            self.recency.push_back(key);
+        }
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        if let Some(key) = self.recency.pop_front() {
+            self.entries.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_line_only_once() {
+        let mut cache = MarkupCache::with_capacity(2);
+        let mut parse_count = 0;
+        for _ in 0..3 {
+            cache
+                .get_or_parse(&"line:a".into(), None, "A", |text| {
+                    parse_count += 1;
+                    Ok(markup(text))
+                })
+                .unwrap();
+        }
+        assert_eq!(1, parse_count);
+        assert_eq!(2, cache.hits);
+        assert_eq!(1, cache.misses);
+    }
+
+    #[test]
+    fn parses_again_when_text_or_language_differs() {
+        let mut cache = MarkupCache::with_capacity(4);
+        let line_id = "line:a".into();
+        let german = Language::from("de-DE");
+        cache.get_or_parse(&line_id, None, "1", parse).unwrap();
+        cache.get_or_parse(&line_id, None, "2", parse).unwrap();
+        cache
+            .get_or_parse(&line_id, Some(&german), "2", parse)
+            .unwrap();
+        assert_eq!(0, cache.hits);
+
+        let parsed = cache.get_or_parse(&line_id, None, "2", parse).unwrap();
+        assert_eq!("2", parsed.text);
+        assert_eq!(1, cache.hits);
+    }
+
+    #[test]
+    fn evicts_least_recently_used_line() {
+        let mut cache = MarkupCache::with_capacity(2);
+        cache
+            .get_or_parse(&"line:a".into(), None, "A", parse)
+            .unwrap();
+        cache
+            .get_or_parse(&"line:b".into(), None, "B", parse)
+            .unwrap();
+        // Use "a" so that "b" becomes the least recently used entry
+        cache
+            .get_or_parse(&"line:a".into(), None, "A", parse)
+            .unwrap();
+        cache
+            .get_or_parse(&"line:c".into(), None, "C", parse)
+            .unwrap();
+        assert_eq!(1, cache.hits);
+
+        cache
+            .get_or_parse(&"line:a".into(), None, "A", parse)
+            .unwrap();
+        assert_eq!(2, cache.hits);
+        cache
+            .get_or_parse(&"line:b".into(), None, "B", parse)
+            .unwrap();
+        assert_eq!(2, cache.hits);
+    }
+
+    #[test]
+    fn zero_capacity_disables_cache() {
+        let mut cache = MarkupCache::with_capacity(0);
+        cache
+            .get_or_parse(&"line:a".into(), None, "A", parse)
+            .unwrap();
+        cache
+            .get_or_parse(&"line:a".into(), None, "A", parse)
+            .unwrap();
+        assert_eq!(0, cache.hits);
+        assert_eq!(2, cache.misses);
+    }
+
+    fn parse(text: &str) -> crate::markup::Result<ParsedMarkup> {
+        Ok(markup(text))
+    }
+
+    fn markup(text: &str) -> ParsedMarkup {
+        ParsedMarkup {
+            text: text.to_owned(),
+            attributes: vec![],
+        }
+    }
+}
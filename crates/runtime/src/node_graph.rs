@@ -0,0 +1,148 @@
+//! Contains the [`NodeGraph`] returned by [`Dialogue::node_dependency_graph`].
+
+use crate::prelude::*;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// The nodes of a [`Program`] and the ways execution can move from one to another, e.g. for documentation or for finding unreachable nodes.
+/// Can be rendered in the [DOT language](https://graphviz.org/doc/info/lang.html) via [`NodeGraph::to_dot`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq, Hash, Default))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct NodeGraph {
+    /// The names of all nodes in the program, sorted alphabetically.
+    pub nodes: Vec<String>,
+    /// The unique edges between the nodes, sorted by their source, then their destination.
+    pub edges: Vec<NodeEdge>,
+}
+
+/// A directed edge of a [`NodeGraph`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq, Hash))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct NodeEdge {
+    /// The name of the node the edge starts in.
+    pub source: String,
+    /// Where the edge leads.
+    pub destination: NodeEdgeDestination,
+    /// How execution moves along the edge.
+    pub kind: NodeEdgeKind,
+}
+
+/// The end of a [`NodeEdge`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq, Hash))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+pub enum NodeEdgeDestination {
+    /// The node with the given name.
+    Node(String),
+    /// A node that is only known at runtime, e.g. for `<<jump {$destination}>>`.
+    Unknown,
+}
+
+/// How execution moves along a [`NodeEdge`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq, Hash))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+#[non_exhaustive]
+pub enum NodeEdgeKind {
+    /// A `<<jump>>` statement, which leaves the source node for good.
+    Jump,
+}
+
+impl NodeEdgeKind {
+    fn label(self) -> &'static str {
+        match self {
+            NodeEdgeKind::Jump => "jump",
+        }
+    }
+}
+
+impl NodeGraph {
+    pub(crate) fn from_program(program: &Program) -> Self {
+        let mut nodes: Vec<_> = program.nodes.keys().cloned().collect();
+        nodes.sort_unstable();
+        let mut edges = BTreeSet::new();
+        for (node_name, node) in &program.nodes {
+            for (index, instruction) in node.instructions.iter().enumerate() {
+                if instruction.opcode() != OpCode::RunNode {
+                    continue;
+                }
+                // A jump to a fixed node pushes its name right before running it.
+                let destination = index
+                    .checked_sub(1)
+                    .map(|previous| &node.instructions[previous])
+                    .filter(|previous| previous.opcode() == OpCode::PushString)
+                    .map(|previous| previous.read_operand::<String>(0))
+                    .map_or(NodeEdgeDestination::Unknown, NodeEdgeDestination::Node);
+                edges.insert(NodeEdge {
+                    source: node_name.clone(),
+                    destination,
+                    kind: NodeEdgeKind::Jump,
+                });
+            }
+        }
+        Self {
+            nodes,
+            edges: edges.into_iter().collect(),
+        }
+    }
+
+    /// Renders the graph in the [DOT language](https://graphviz.org/doc/info/lang.html), e.g. for Graphviz.
+    /// Edges are labeled with their [`NodeEdgeKind`]. Edges with a [`NodeEdgeDestination::Unknown`] all lead to a single node labeled `?`.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        const UNKNOWN_NODE: &str = "\"<unknown>\"";
+        let mut dot = String::from("digraph {\n");
+        for node in &self.nodes {
+            writeln!(dot, "    {};", quoted(node)).unwrap();
+        }
+        let has_unknown_destination = self
+            .edges
+            .iter()
+            .any(|edge| edge.destination == NodeEdgeDestination::Unknown);
+        if has_unknown_destination {
+            writeln!(dot, "    {UNKNOWN_NODE} [label=\"?\", shape=diamond];").unwrap();
+        }
+        for edge in &self.edges {
+            let destination = match &edge.destination {
+                NodeEdgeDestination::Node(name) => quoted(name),
+                NodeEdgeDestination::Unknown => UNKNOWN_NODE.to_owned(),
+            };
+            writeln!(
+                dot,
+                "    {} -> {destination} [label=\"{}\"];",
+                quoted(&edge.source),
+                edge.kind.label()
+            )
+            .unwrap();
+        }
+        dot.push('}');
+        dot.push('\n');
+        dot
+    }
+}
+
+fn quoted(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
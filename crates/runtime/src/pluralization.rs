@@ -1,7 +1,15 @@
 use crate::prelude::Language;
 use fixed_decimal::{DoublePrecision, FixedDecimal};
+use icu_locid::LanguageIdentifier;
 use icu_plurals::{PluralCategory, PluralRuleType};
 use icu_plurals::{PluralOperands, PluralRules};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+thread_local! {
+    static CACHE: RefCell<HashMap<Language, Rc<Pluralization>>> = RefCell::default();
+}
 
 #[derive(Debug)]
 pub(crate) struct Pluralization {
@@ -11,8 +19,8 @@ pub(crate) struct Pluralization {
 
 impl Pluralization {
     pub(crate) fn new(language: impl Into<Language>) -> Self {
-        let language = language.into();
-        let locale = language.0.into();
+        let language: Language = language.into();
+        let locale = LanguageIdentifier::from(language).into();
         let cardinal_rules = PluralRules::try_new(&locale, PluralRuleType::Cardinal).unwrap();
         let ordinal_rules = PluralRules::try_new(&locale, PluralRuleType::Ordinal).unwrap();
         Self {
@@ -21,6 +29,18 @@ impl Pluralization {
         }
     }
 
+    /// Like [`Pluralization::new`], but reuses the rules of a language once they have been looked up on the current thread.
+    pub(crate) fn cached(language: impl Into<Language>) -> Rc<Self> {
+        let language = language.into();
+        CACHE.with(|cache| {
+            cache
+                .borrow_mut()
+                .entry(language.clone())
+                .or_insert_with(|| Rc::new(Self::new(language)))
+                .clone()
+        })
+    }
+
     pub(crate) fn get_cardinal_plural_case(&self, value: f32) -> PluralCategory {
         let value = get_into_plural_operand(value);
         self.cardinal_rules.category_for(value)
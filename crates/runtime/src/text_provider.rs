@@ -21,6 +21,16 @@ pub trait TextProvider: Debug + Send + Sync {
     fn accept_line_hints(&mut self, line_ids: &[LineId]);
     /// Returns the text for the given [`LineId`]. Will only be called if [`TextProvider::are_lines_available`] returns `true`.
     fn get_text(&self, id: &LineId) -> Option<String>;
+    /// Returns the texts for all given [`LineId`]s at once, e.g. for pre-rendering subtitles or exporting a script.
+    /// [`LineId`]s without a text are not contained in the returned map.
+    ///
+    /// The default implementation calls [`TextProvider::get_text`] for every [`LineId`].
+    /// Implementations can override this to retrieve the texts more efficiently.
+    fn get_texts(&self, ids: &[LineId]) -> HashMap<LineId, String> {
+        ids.iter()
+            .filter_map(|id| Some((id.clone(), self.get_text(id)?)))
+            .collect()
+    }
     /// Sets the current language. If `None` is passed, the base language will be used.
     fn set_language(&mut self, language: Option<Language>);
     /// Returns the current language. If `None` is returned, the base language is used.
@@ -52,6 +62,8 @@ pub struct StringTableTextProvider {
     translation_table: Option<(Language, StringTable)>,
     /// Set to `None` to select base language.
     translation_language: Option<Language>,
+    /// Maps shadow lines to the lines whose text they reuse.
+    shadow_lines: HashMap<LineId, LineId>,
 }
 
 impl StringTableTextProvider {
@@ -61,6 +73,11 @@ impl StringTableTextProvider {
     }
 
     /// Adds strings for the base language, i.e. the language that the Yarn files are written in.
+    ///
+    /// When adding the string table of a compilation made with `Compiler::with_strip_line_text`, leave out the lines whose text was stripped,
+    /// i.e. whose `StringInfo::is_text_stripped` is set. Running such a line then fails with
+    /// [`DialogueError::LineProviderError`](crate::prelude::DialogueError::LineProviderError) unless its text is added by other means,
+    /// instead of silently showing an empty line.
     pub fn extend_base_language(&mut self, string_table: HashMap<LineId, String>) {
         self.base_language_table.extend(string_table);
     }
@@ -80,6 +97,12 @@ impl StringTableTextProvider {
         }
         self.translation_table.replace((language, string_table));
     }
+
+    /// Registers shadow lines, i.e. lines tagged with `#shadow:`, by mapping their [`LineId`]s to the [`LineId`]s of the lines they shadow.
+    /// The text of a shadow line is then always looked up under the [`LineId`] of the line it shadows, so shadow lines don't need their own translations.
+    pub fn extend_shadow_lines(&mut self, shadow_lines: HashMap<LineId, LineId>) {
+        self.shadow_lines.extend(shadow_lines);
+    }
 }
 
 impl TextProvider for StringTableTextProvider {
@@ -92,6 +115,7 @@ impl TextProvider for StringTableTextProvider {
     }
 
     fn get_text(&self, id: &LineId) -> Option<String> {
+        let id = self.shadow_lines.get(id).unwrap_or(id);
         if let Some(language) = self.translation_language.as_ref() {
             if let Some((registered_language, translation_table)) = self.translation_table.as_ref()
             {
@@ -107,6 +131,30 @@ impl TextProvider for StringTableTextProvider {
         self.base_language_table.get(id).cloned()
     }
 
+    fn get_texts(&self, ids: &[LineId]) -> HashMap<LineId, String> {
+        // Look up the translation once instead of for every line
+        let translation_table = self.translation_language.as_ref().and_then(|language| {
+            let table = self
+                .translation_table
+                .as_ref()
+                .filter(|(registered_language, _)| registered_language == language)
+                .map(|(_, translation_table)| translation_table);
+            if table.is_none() {
+                error!("Didn't find language {language} in translations, falling back to base language.");
+            }
+            table
+        });
+        ids.iter()
+            .filter_map(|id| {
+                let source_id = self.shadow_lines.get(id).unwrap_or(id);
+                let text = translation_table
+                    .and_then(|translation_table| translation_table.get(source_id))
+                    .or_else(|| self.base_language_table.get(source_id))?;
+                Some((id.clone(), text.clone()))
+            })
+            .collect()
+    }
+
     fn set_language(&mut self, language_code: Option<Language>) {
         self.translation_language = language_code;
     }
@@ -134,3 +182,41 @@ impl TextProvider for StringTableTextProvider {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gets_texts_of_existing_lines() {
+        let mut text_provider = StringTableTextProvider::new();
+        text_provider.extend_base_language(HashMap::from([
+            (LineId::from("line:1"), "Hello".to_owned()),
+            (LineId::from("line:2"), "Hi".to_owned()),
+            (LineId::from("line:3"), "Hey".to_owned()),
+        ]));
+        text_provider.extend_translation(
+            "de",
+            HashMap::from([(LineId::from("line:1"), "Hallo".to_owned())]),
+        );
+        text_provider.set_language(Some("de".into()));
+
+        let ids: [LineId; 3] = ["line:1".into(), "line:2".into(), "line:missing".into()];
+        let texts = text_provider.get_texts(&ids);
+        assert_eq!(
+            HashMap::from([
+                (LineId::from("line:1"), "Hallo".to_owned()),
+                (LineId::from("line:2"), "Hi".to_owned()),
+            ]),
+            texts
+        );
+        assert!(!texts.contains_key(&LineId::from("line:missing")));
+
+        // The default implementation behaves the same
+        let texts_one_by_one: HashMap<_, _> = ids
+            .iter()
+            .filter_map(|id| Some((id.clone(), text_provider.get_text(id)?)))
+            .collect();
+        assert_eq!(texts_one_by_one, texts);
+    }
+}
@@ -36,7 +36,8 @@ pub trait VariableStorage: Debug + Send + Sync {
     fn extend(&mut self, values: HashMap<String, YarnValue>) -> Result<()>;
     /// Returns a map of all variables in this variable storage.
     fn variables(&self) -> HashMap<String, YarnValue>;
-    /// Clears all variables in this variable storage.
+    /// Clears all variables in this variable storage, e.g. when starting a new game.
+    /// This includes the internal variables used to track node visits, so `visited` and `visited_count` will treat every node as unvisited afterwards.
     fn clear(&mut self);
     /// Gets the [`VariableStorage`] as a trait object.
     /// This allows retrieving the concrete type by downcasting, using the `downcast_ref` method available through the `Any` trait.
@@ -59,6 +60,7 @@ impl Extend<(String, YarnValue)> for Box<dyn VariableStorage> {
 pub enum VariableStorageError {
     InvalidVariableName { name: String },
     VariableNotFound { name: String },
+    CapacityExceeded { name: String, capacity: usize },
     InternalError { error: Box<dyn Error + Send + Sync> },
 }
 
@@ -70,6 +72,7 @@ impl Display for VariableStorageError {
         match self {
             InvalidVariableName { name } => write!(f, "{name} is not a valid variable name: Variable names must start with a \'$\'. (Did you mean to use \'${name}\'?)"),
             VariableNotFound { name } => write!(f, "Variable name {name} is not defined"),
+            CapacityExceeded { name, capacity } => write!(f, "Cannot store variable {name}: The variable storage is limited to {capacity} variables"),
             InternalError { error } => write!(f, "Internal variable storage error: {error}"),
         }
     }
@@ -83,13 +86,42 @@ impl Clone for Box<dyn VariableStorage> {
 
 /// A simple concrete implementation of [`VariableStorage`] that keeps all variables in memory.
 #[derive(Debug, Clone, Default)]
-pub struct MemoryVariableStorage(Arc<RwLock<HashMap<String, YarnValue>>>);
+pub struct MemoryVariableStorage {
+    variables: Arc<RwLock<HashMap<String, YarnValue>>>,
+    capacity_limit: Option<usize>,
+}
 
 impl MemoryVariableStorage {
     /// Creates a new empty `MemoryVariableStorage`.
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Creates a new empty `MemoryVariableStorage` that holds at most `max` variables.
+    /// Storing a new variable beyond that fails with [`VariableStorageError::CapacityExceeded`], while overwriting existing variables is still possible.
+    pub fn with_capacity_limit(max: usize) -> Self {
+        Self {
+            capacity_limit: Some(max),
+            ..Self::default()
+        }
+    }
+
+    /// Returns the number of variables stored.
+    pub fn len(&self) -> usize {
+        self.variables.read().unwrap().len()
+    }
+
+    /// Returns `true` if no variables are stored.
+    pub fn is_empty(&self) -> bool {
+        self.variables.read().unwrap().is_empty()
+    }
+
+    /// Returns `true` if a capacity limit was set via [`MemoryVariableStorage::with_capacity_limit`] and it has been reached,
+    /// i.e. no new variables can be stored.
+    pub fn is_at_capacity(&self) -> bool {
+        self.capacity_limit
+            .is_some_and(|capacity_limit| self.len() >= capacity_limit)
+    }
 }
 
 impl VariableStorage for MemoryVariableStorage {
@@ -99,33 +131,51 @@ impl VariableStorage for MemoryVariableStorage {
 
     fn set(&mut self, name: String, value: YarnValue) -> Result<()> {
         Self::validate_name(&name)?;
-        self.0.write().unwrap().insert(name, value);
+        let mut variables = self.variables.write().unwrap();
+        if let Some(capacity) = self.capacity_limit {
+            if !variables.contains_key(&name) && variables.len() >= capacity {
+                return Err(VariableStorageError::CapacityExceeded { name, capacity });
+            }
+        }
+        variables.insert(name, value);
         Ok(())
     }
 
     fn get(&self, name: &str) -> Result<YarnValue> {
         Self::validate_name(name)?;
-        self.0.read().unwrap().get(name).cloned().ok_or_else(|| {
-            VariableStorageError::VariableNotFound {
+        self.variables
+            .read()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| VariableStorageError::VariableNotFound {
                 name: name.to_string(),
-            }
-        })
+            })
     }
 
     fn extend(&mut self, values: HashMap<String, YarnValue>) -> Result<()> {
         for name in values.keys() {
             Self::validate_name(name)?;
         }
-        self.0.write().unwrap().extend(values);
+        let mut variables = self.variables.write().unwrap();
+        if let Some(capacity) = self.capacity_limit {
+            let mut new_names = values.keys().filter(|name| !variables.contains_key(*name));
+            let new_count = new_names.clone().count();
+            if variables.len() + new_count > capacity {
+                let name = new_names.next().unwrap().clone();
+                return Err(VariableStorageError::CapacityExceeded { name, capacity });
+            }
+        }
+        variables.extend(values);
         Ok(())
     }
 
     fn variables(&self) -> HashMap<String, YarnValue> {
-        self.0.read().unwrap().clone()
+        self.variables.read().unwrap().clone()
     }
 
     fn clear(&mut self) {
-        self.0.write().unwrap().clear();
+        self.variables.write().unwrap().clear();
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -149,3 +199,46 @@ impl MemoryVariableStorage {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capacity_limit_rejects_new_variables_but_allows_overwrites() {
+        let mut storage = MemoryVariableStorage::with_capacity_limit(2);
+        assert!(storage.is_empty());
+        storage.set("$a".to_owned(), 1.into()).unwrap();
+        storage.set("$b".to_owned(), 2.into()).unwrap();
+        assert_eq!(2, storage.len());
+        assert!(storage.is_at_capacity());
+
+        let result = storage.set("$c".to_owned(), 3.into());
+        assert!(matches!(
+            result,
+            Err(VariableStorageError::CapacityExceeded { name, capacity: 2 }) if name == "$c"
+        ));
+        storage.set("$a".to_owned(), 10.into()).unwrap();
+        assert_eq!(YarnValue::from(10), storage.get("$a").unwrap());
+
+        let result = VariableStorage::extend(
+            &mut storage,
+            HashMap::from([("$b".to_owned(), 20.into()), ("$d".to_owned(), 4.into())]),
+        );
+        assert!(matches!(
+            result,
+            Err(VariableStorageError::CapacityExceeded { .. })
+        ));
+        assert_eq!(YarnValue::from(2), storage.get("$b").unwrap());
+    }
+
+    #[test]
+    fn storage_without_capacity_limit_is_never_at_capacity() {
+        let mut storage = MemoryVariableStorage::new();
+        for i in 0..100 {
+            storage.set(format!("$var{i}"), i.into()).unwrap();
+        }
+        assert_eq!(100, storage.len());
+        assert!(!storage.is_at_capacity());
+    }
+}
@@ -4,11 +4,20 @@
 //! The `Operand` extensions and the `Operator` enum were moved into upstream crates to make them not depend on the runtime.
 
 pub(crate) use self::{execution_state::*, state::*};
-use crate::markup::{LineParser, ParsedMarkup};
+use crate::dialogue::ErrorHandler;
+use crate::markup::{LineParser, MarkupCache};
 use crate::prelude::*;
 use crate::Result;
+use icu_collator::{Collator, CollatorOptions};
+use icu_locid::LanguageIdentifier;
 use log::*;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::Debug;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
 use yarnspinner_core::prelude::OpCode;
 use yarnspinner_core::prelude::*;
 
@@ -18,15 +27,42 @@ mod state;
 #[derive(Debug, Clone)]
 pub(crate) struct VirtualMachine {
     pub(crate) library: Library,
-    pub(crate) program: Option<Program>,
+    pub(crate) program: Option<Arc<Program>>,
     pub(crate) variable_storage: Box<dyn VariableStorage>,
     pub(crate) line_hints_enabled: bool,
+    pub(crate) continuous_line_hints: bool,
+    /// The line IDs of the last [`DialogueEvent::LineHints`], used to only send hints again when they narrow down.
+    last_line_hints: Vec<LineId>,
+    pub(crate) multiselect_line_ids: HashSet<LineId>,
+    pub(crate) once_line_ids: HashSet<LineId>,
+    pub(crate) pinned_line_ids: HashSet<LineId>,
+    pub(crate) requirement_hints: HashMap<LineId, String>,
+    pub(crate) constant_variables: HashSet<String>,
+    pub(crate) shuffle_options: bool,
+    pub(crate) option_sort: OptionSort,
+    pub(crate) unavailable_selection_policy: UnavailableSelectionPolicy,
+    pub(crate) rng: StdRng,
+    pub(crate) default_start_node_name: Option<String>,
+    pub(crate) jump_history: Vec<String>,
+    pub(crate) history_capacity: usize,
+    pub(crate) max_steps_per_next: usize,
+    /// The last instructions that were run, at most [`VirtualMachine::instruction_trace_capacity`] of them.
+    pub(crate) instruction_trace: VecDeque<TraceEntry>,
+    pub(crate) instruction_trace_capacity: usize,
     current_node_name: Option<String>,
     state: State,
     execution_state: ExecutionState,
     current_node: Option<Node>,
     batched_events: Vec<DialogueEvent>,
-    line_parser: LineParser,
+    /// The command of the last [`DialogueEvent::Command`] until the dialogue continues.
+    pending_command: Option<Command>,
+    pub(crate) line_parser: LineParser,
+    pub(crate) markup_cache: MarkupCache,
+    pub(crate) line_transformers: LineTransformers,
+    pub(crate) substitution_markup: bool,
+    pub(crate) whitespace_mode: WhitespaceMode,
+    pub(crate) command_handlers: CommandHandlers,
+    pub(crate) error_handler: ErrorHandler,
     text_provider: Box<dyn TextProvider>,
     language_code: Option<Language>,
 }
@@ -35,9 +71,16 @@ impl Iterator for VirtualMachine {
     type Item = Vec<DialogueEvent>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.assert_can_continue().is_ok().then(||
-            self.continue_()
-            .unwrap_or_else(|e| panic!("Encountered error while running dialogue through its `Iterator` implementation: {e}")))
+        if self.assert_can_continue().is_err() {
+            return None;
+        }
+        match self.continue_or_stop() {
+            Ok(events) => Some(events),
+            // The dialogue was stopped and the error was passed to the error handler,
+            // so the events of stopping it, which end with `DialogueComplete`, are the last ones.
+            Err((DialogueError::StepLimitExceeded { .. }, stop_events)) => Some(stop_events),
+            Err((e, _)) => panic!("Encountered error while running dialogue through its `Iterator` implementation: {e}"),
+        }
     }
 }
 
@@ -52,6 +95,12 @@ impl VirtualMachine {
             library,
             variable_storage,
             line_parser,
+            markup_cache: Default::default(),
+            line_transformers: Default::default(),
+            substitution_markup: Default::default(),
+            whitespace_mode: Default::default(),
+            command_handlers: Default::default(),
+            error_handler: Default::default(),
             text_provider,
             language_code: Default::default(),
             program: Default::default(),
@@ -60,7 +109,25 @@ impl VirtualMachine {
             execution_state: Default::default(),
             current_node: Default::default(),
             batched_events: Default::default(),
+            pending_command: Default::default(),
             line_hints_enabled: Default::default(),
+            continuous_line_hints: Default::default(),
+            last_line_hints: Default::default(),
+            multiselect_line_ids: Default::default(),
+            once_line_ids: Default::default(),
+            pinned_line_ids: Default::default(),
+            requirement_hints: Default::default(),
+            constant_variables: Default::default(),
+            shuffle_options: Default::default(),
+            option_sort: Default::default(),
+            unavailable_selection_policy: Default::default(),
+            rng: unseeded_rng(),
+            default_start_node_name: Default::default(),
+            jump_history: Default::default(),
+            history_capacity: Dialogue::DEFAULT_HISTORY_CAPACITY,
+            max_steps_per_next: Dialogue::DEFAULT_MAX_STEPS_PER_NEXT,
+            instruction_trace: Default::default(),
+            instruction_trace_capacity: Default::default(),
         }
     }
 
@@ -80,16 +147,32 @@ impl VirtualMachine {
         self.variable_storage.as_mut()
     }
 
+    /// Writes a variable into the [`VariableStorage`] on behalf of the running program or a command handler,
+    /// refusing to overwrite a constant of a loaded program.
+    pub(crate) fn set_variable_checked(
+        &mut self,
+        variable_name: String,
+        value: YarnValue,
+    ) -> Result<()> {
+        if self.constant_variables.contains(&variable_name) {
+            return Err(DialogueError::ConstantAssignment { variable_name });
+        }
+        self.variable_storage.set(variable_name, value)?;
+        Ok(())
+    }
+
     pub(crate) fn set_language_code(&mut self, language_code: impl Into<Option<Language>>) {
         let language_code = language_code.into();
         self.language_code.clone_from(&language_code);
         self.line_parser.set_language_code(language_code.clone());
         self.text_provider.set_language(language_code);
+        self.markup_cache.clear();
     }
 
     pub(crate) fn reset_state(&mut self) {
         self.state = State::default();
         self.current_node_name = None;
+        self.pending_command = None;
     }
 
     pub(crate) fn set_execution_state(&mut self, execution_state: ExecutionState) -> &mut Self {
@@ -104,6 +187,7 @@ impl VirtualMachine {
     /// The original does not reset the state upon calling this. I suspect that's a bug.
     pub(crate) fn stop(&mut self) -> Vec<DialogueEvent> {
         self.set_execution_state(ExecutionState::Stopped);
+        self.jump_history.clear();
         self.batched_events.push(DialogueEvent::DialogueComplete);
         std::mem::take(&mut self.batched_events)
     }
@@ -117,6 +201,7 @@ impl VirtualMachine {
         self.reset_state();
 
         self.current_node_name = Some(node_name.clone());
+        self.record_jump(&node_name);
 
         self.batched_events
             .push(DialogueEvent::NodeStart(node_name));
@@ -127,22 +212,73 @@ impl VirtualMachine {
         Ok(())
     }
 
+    fn record_jump(&mut self, node_name: &str) {
+        if self.history_capacity == 0 {
+            return;
+        }
+        if self.jump_history.len() >= self.history_capacity {
+            let overflow = self.jump_history.len() + 1 - self.history_capacity;
+            self.jump_history.drain(..overflow);
+        }
+        self.jump_history.push(node_name.to_owned());
+    }
+
+    fn record_trace(&mut self, node_name: &str, instruction_index: usize) {
+        if self.instruction_trace.len() >= self.instruction_trace_capacity {
+            self.instruction_trace.pop_front();
+        }
+        self.instruction_trace.push_back(TraceEntry {
+            node_name: node_name.to_owned(),
+            instruction_index,
+            stack_top: self.state.stack.last().map(|value| value.raw_value.clone()),
+        });
+    }
+
     fn send_line_hints(&mut self) {
+        let string_ids = self.reachable_line_ids(0);
+        self.text_provider.accept_line_hints(&string_ids);
+        self.last_line_hints.clone_from(&string_ids);
+        self.batched_events
+            .push(DialogueEvent::LineHints(string_ids));
+    }
+
+    /// Sends the lines that can still be reached from the current instruction,
+    /// if a branch decision changed them from the ones previously hinted.
+    ///
+    /// ## Implementation notes
+    ///
+    /// This method is not present in the original implementation, which only hints lines when a node is set.
+    fn send_narrowed_line_hints(&mut self) {
+        if !(self.line_hints_enabled && self.continuous_line_hints) {
+            return;
+        }
+        let string_ids = self.reachable_line_ids(self.state.program_counter);
+        let reachable: HashSet<_> = string_ids.iter().collect();
+        let last_hinted: HashSet<_> = self.last_line_hints.iter().collect();
+        if reachable == last_hinted {
+            return;
+        }
+        self.text_provider.accept_line_hints(&string_ids);
+        self.last_line_hints.clone_from(&string_ids);
+        self.batched_events
+            .push(DialogueEvent::LineHints(string_ids));
+    }
+
+    fn reachable_line_ids(&self, start: usize) -> Vec<LineId> {
         // Create a list; we will never have more lines and options
         // than total instructions, so that's a decent capacity for
         // the list
         // [sic] TODO: maybe this list could be reused to save on allocations?
 
-        let string_ids: Vec<_> = self
-            .current_node
-            .as_ref()
-            .unwrap()
-            .instructions
+        let node = self.current_node.as_ref().unwrap();
+        node.instructions
             .iter()
+            .zip(reachable_instructions(node, start))
             // Loop over every instruction and find the ones that run a
             // line or add an option; these are the two instructions
             // that will signal a line can appear to the player
-            .filter_map(|instruction| {
+            .filter(|(_, is_reachable)| *is_reachable)
+            .filter_map(|(instruction, _)| {
                 let opcode: OpCode = instruction.opcode.try_into().unwrap();
                 [OpCode::RunLine, OpCode::AddOption]
                     .contains(&opcode)
@@ -154,10 +290,7 @@ impl VirtualMachine {
                         LineId(id)
                     })
             })
-            .collect();
-        self.text_provider.accept_line_hints(&string_ids);
-        self.batched_events
-            .push(DialogueEvent::LineHints(string_ids));
+            .collect()
     }
 
     pub(crate) fn pop_line_hints(&mut self) -> Option<Vec<LineId>> {
@@ -195,13 +328,50 @@ impl VirtualMachine {
     /// Exposed via the more idiomatic [`Iterator::next`] implementation.
     ///
     pub(crate) fn continue_(&mut self) -> crate::Result<Vec<DialogueEvent>> {
+        self.continue_or_stop().map_err(|(error, _)| error)
+    }
+
+    /// Like [`VirtualMachine::continue_`], but if the error stopped the dialogue, the events of stopping it are returned alongside it.
+    fn continue_or_stop(
+        &mut self,
+    ) -> std::result::Result<Vec<DialogueEvent>, (DialogueError, Vec<DialogueEvent>)> {
+        self.run_until_yield().map_err(|error| {
+            self.error_handler.report(&error);
+            let stop_events = if matches!(error, DialogueError::StepLimitExceeded { .. }) {
+                error!("Stopping dialogue: {error}");
+                self.stop()
+            } else {
+                Vec::new()
+            };
+            (error, stop_events)
+        })
+    }
+
+    fn run_until_yield(&mut self) -> crate::Result<Vec<DialogueEvent>> {
         self.assert_can_continue()?;
         self.set_execution_state(ExecutionState::Running);
+        self.pending_command = None;
 
+        let mut steps = 0;
         while self.execution_state == ExecutionState::Running {
+            // ## Implementation note
+            // The original has no step limit, so a script that loops without ever yielding control would hang.
+            steps += 1;
+            if steps > self.max_steps_per_next {
+                // The dialogue is stopped by the caller, see `VirtualMachine::continue_or_stop`
+                return Err(DialogueError::StepLimitExceeded {
+                    max_steps: self.max_steps_per_next,
+                });
+            }
             let current_node = self.current_node.clone().unwrap();
-            let current_instruction = &current_node.instructions[self.state.program_counter];
-            self.run_instruction(current_instruction)?;
+            let instruction_index = self.state.program_counter;
+            let current_instruction = &current_node.instructions[instruction_index];
+            let result = self.run_instruction(current_instruction);
+            // Recorded even if the instruction failed, since that is the one most worth inspecting
+            if self.instruction_trace_capacity > 0 {
+                self.record_trace(&current_node.name, instruction_index);
+            }
+            result?;
             // ## Implementation note
             // The original increments the program counter here, but that leads to intentional underflow on [`OpCode::RunNode`],
             // so we do the incrementation in [`VirtualMachine::run_instruction`] instead.
@@ -219,10 +389,6 @@ impl VirtualMachine {
         Ok(std::mem::take(&mut self.batched_events))
     }
 
-    pub(crate) fn parse_markup(&mut self, line: &str) -> crate::markup::Result<ParsedMarkup> {
-        self.line_parser.parse_markup(line)
-    }
-
     /// Runs a series of tests to see if the [`VirtualMachine`] is in a state where [`VirtualMachine::r#continue`] can be called. Panics if it can't.
     fn assert_can_continue(&self) -> crate::Result<()> {
         if self.current_node.is_none() || self.current_node_name.is_none() {
@@ -237,7 +403,8 @@ impl VirtualMachine {
     }
 
     pub(crate) fn unload_programs(&mut self) {
-        self.program = None
+        self.program = None;
+        self.markup_cache.clear();
     }
 
     pub(crate) fn set_selected_option(&mut self, selected_option_id: OptionId) -> Result<()> {
@@ -250,6 +417,15 @@ impl VirtualMachine {
                 max_id: self.state.current_options.len().saturating_sub(1),
             });
         }
+        if !self.accepts_selection_of(&[selected_option_id])? {
+            return Ok(());
+        }
+
+        let line_id = self.state.current_options[selected_option_id.0]
+            .line
+            .id
+            .clone();
+        self.record_option_selection(&line_id)?;
 
         // We now know what number option was selected; push the
         // corresponding node name to the stack.
@@ -267,6 +443,144 @@ impl VirtualMachine {
         Ok(())
     }
 
+    pub(crate) fn set_selected_options(&mut self, selected_option_ids: &[OptionId]) -> Result<()> {
+        if self.execution_state != ExecutionState::WaitingOnOptionSelection {
+            return Err(DialogueError::UnexpectedOptionSelectionError);
+        }
+        if !self.is_waiting_for_multiselect() {
+            return Err(DialogueError::UnexpectedMultiselectError);
+        }
+        let Some(&first_option_id) = selected_option_ids.first() else {
+            return Err(DialogueError::NoOptionSelectedError);
+        };
+        let max_id = self.state.current_options.len().saturating_sub(1);
+        if let Some(&selected_option_id) = selected_option_ids
+            .iter()
+            .find(|id| id.0 >= self.state.current_options.len())
+        {
+            return Err(DialogueError::InvalidOptionIdError {
+                selected_option_id,
+                max_id,
+            });
+        }
+        if !self.accepts_selection_of(selected_option_ids)? {
+            return Ok(());
+        }
+
+        let mut selected_line_ids: Vec<&str> = Vec::with_capacity(selected_option_ids.len());
+        for id in selected_option_ids {
+            let line_id = self.state.current_options[id.0].line.id.0.as_str();
+            if !selected_line_ids.contains(&line_id) {
+                selected_line_ids.push(line_id);
+            }
+        }
+        let selection = selected_line_ids.join(MULTISELECT_SEPARATOR);
+        let selected_line_ids: Vec<LineId> = selected_line_ids
+            .into_iter()
+            .map(|line_id| line_id.to_owned().into())
+            .collect();
+        for line_id in &selected_line_ids {
+            self.record_option_selection(line_id)?;
+        }
+        self.variable_storage
+            .set(MULTISELECT_VARIABLE_NAME.to_owned(), selection.into())?;
+
+        // The dialogue can only follow one path, so we continue with the first selected option.
+        let destination_node = self.state.current_options[first_option_id.0]
+            .destination_node
+            .clone();
+        self.state.push(destination_node);
+        self.state.current_options.clear();
+        self.set_execution_state(ExecutionState::WaitingForContinue);
+        Ok(())
+    }
+
+    /// Applies the [`UnavailableSelectionPolicy`] if any of the selected options is not available.
+    /// Returns whether the selection should be carried out.
+    fn accepts_selection_of(&mut self, selected_option_ids: &[OptionId]) -> Result<bool> {
+        let Some(&selected_option_id) = selected_option_ids
+            .iter()
+            .find(|id| !self.state.current_options[id.0].is_available)
+        else {
+            return Ok(true);
+        };
+        match self.unavailable_selection_policy {
+            UnavailableSelectionPolicy::Reject => {
+                Err(DialogueError::UnavailableOptionSelected { selected_option_id })
+            }
+            UnavailableSelectionPolicy::TreatAsNoOp => {
+                debug!("Ignoring the selection of unavailable option {selected_option_id}");
+                Ok(false)
+            }
+            UnavailableSelectionPolicy::Error => {
+                self.set_execution_state(ExecutionState::Stopped);
+                self.batched_events.clear();
+                Err(DialogueError::UnavailableOptionSelected { selected_option_id })
+            }
+        }
+    }
+
+    /// Counts the selection of an option if it is only offered once, so that it is left out the next time.
+    fn record_option_selection(&mut self, line_id: &LineId) -> Result<()> {
+        if !self.once_line_ids.contains(line_id) {
+            return Ok(());
+        }
+        let visit_count = self.option_visit_count(line_id);
+        let name = Library::generate_unique_visited_variable_for_option(&line_id.0);
+        self.variable_storage
+            .set(name, (visit_count + 1.0).into())?;
+        Ok(())
+    }
+
+    fn option_visit_count(&self, line_id: &LineId) -> f32 {
+        let name = Library::generate_unique_visited_variable_for_option(&line_id.0);
+        if let Ok(YarnValue::Number(count)) = self.variable_storage.get(&name) {
+            count
+        } else {
+            0.0
+        }
+    }
+
+    pub(crate) fn is_waiting_for_multiselect(&self) -> bool {
+        self.is_waiting_for_option_selection()
+            && !self.state.current_options.is_empty()
+            && self
+                .state
+                .current_options
+                .iter()
+                .all(|option| self.multiselect_line_ids.contains(&option.line.id))
+    }
+
+    /// Shuffles the order of the options that are not pinned, leaving the pinned ones where they are.
+    /// The [`OptionId`]s are left untouched, so they still refer to the options in [`State::current_options`].
+    fn shuffle_unpinned_options(&mut self, options: &mut [DialogueOption]) {
+        reorder_unpinned_options(&self.pinned_line_ids, options, |unpinned_options| {
+            unpinned_options.shuffle(&mut self.rng);
+        });
+    }
+
+    /// Sorts the options that are not pinned by their text according to the collation rules of the current language, see [`OptionSort::LocaleAlphabetical`].
+    fn sort_unpinned_options_alphabetically(&self, options: &mut [DialogueOption]) {
+        let language = self
+            .language_code
+            .clone()
+            .map(LanguageIdentifier::from)
+            .unwrap_or(LanguageIdentifier::UND);
+        match Collator::try_new(&(&language).into(), CollatorOptions::new()) {
+            Ok(collator) => {
+                reorder_unpinned_options(&self.pinned_line_ids, options, |unpinned_options| {
+                    unpinned_options.sort_by(|a, b| collator.compare(&a.line.text, &b.line.text));
+                })
+            }
+            Err(e) => {
+                warn!("Failed to load the collation rules for {language}, sorting options by their code points instead: {e}");
+                reorder_unpinned_options(&self.pinned_line_ids, options, |unpinned_options| {
+                    unpinned_options.sort_by(|a, b| a.line.text.cmp(&b.line.text));
+                });
+            }
+        }
+    }
+
     pub(crate) fn is_active(&self) -> bool {
         self.execution_state != ExecutionState::Stopped
     }
@@ -275,10 +589,65 @@ impl VirtualMachine {
         self.execution_state == ExecutionState::WaitingOnOptionSelection
     }
 
+    pub(crate) fn pending_command(&self) -> Option<&Command> {
+        self.pending_command.as_ref()
+    }
+
     pub(crate) fn current_node(&self) -> Option<String> {
         self.current_node_name.clone()
     }
 
+    pub(crate) fn current_node_tags(&self) -> Option<Vec<String>> {
+        // `current_node` is not reset when stopping, so the name decides whether a node is running.
+        self.current_node_name.as_ref()?;
+        self.current_node.as_ref().map(|node| node.tags.clone())
+    }
+
+    pub(crate) fn substitutions_for_line(&self, line_id: &LineId) -> Option<Vec<String>> {
+        let program = self.program.as_ref()?;
+        let (instructions, line_index) = program.nodes.values().find_map(|node| {
+            node.instructions
+                .iter()
+                .position(|instruction| {
+                    matches!(instruction.opcode(), OpCode::RunLine | OpCode::AddOption)
+                        && instruction.read_operand::<String>(0) == line_id.0
+                })
+                .map(|index| (&node.instructions, index))
+        })?;
+        let line_instruction = &instructions[line_index];
+        let count_operand = match line_instruction.opcode() {
+            OpCode::RunLine => 1,
+            _ => 2,
+        };
+        let expression_count: usize = line_instruction.read_operand(count_operand);
+
+        // The substitutions are the topmost values on the stack, so walk back until the instructions pushing them are found.
+        let mut values_needed = expression_count;
+        let mut start = line_index;
+        while values_needed > 0 {
+            start = start.checked_sub(1)?;
+            let instruction = &instructions[start];
+            values_needed = match instruction.opcode() {
+                OpCode::PushString
+                | OpCode::PushFloat
+                | OpCode::PushBool
+                | OpCode::PushVariable => values_needed - 1,
+                // Pops the parameter count and the parameters, then pushes the return value
+                OpCode::CallFunc => {
+                    let parameter_count: usize = instructions
+                        .get(start.checked_sub(1)?)
+                        .filter(|previous| previous.opcode() == OpCode::PushFloat)?
+                        .read_operand(0);
+                    values_needed + parameter_count
+                }
+                _ => return None,
+            };
+        }
+
+        let stack = self.evaluate_instructions(&instructions[start..line_index])?;
+        Some(self.format_for_display(stack))
+    }
+
     /// ## Implementation note
     ///
     /// Increments the program counter here instead of in `continue_` for cleaner code
@@ -295,6 +664,7 @@ impl VirtualMachine {
                 let jump_destination: String = self.state.peek();
                 self.state.program_counter =
                     self.find_instruction_point_for_label(&jump_destination);
+                self.send_narrowed_line_hints();
             }
             OpCode::RunLine => {
                 // Looks up a string from the string table and passes it to the client as a line
@@ -310,7 +680,9 @@ impl VirtualMachine {
                 assert_up_to_date_compiler(instruction.operands.len() >= 2);
 
                 let substitutions = self.pop_substitutions_with_count_at_operand(instruction, 1);
-                let line = self.prepare_line(string_id, &substitutions)?;
+                let substitutions = self.format_for_display(substitutions);
+                let mut line = self.prepare_line(string_id, &substitutions)?;
+                self.line_transformers.apply(&mut line);
 
                 self.batched_events.push(DialogueEvent::Line(line));
 
@@ -331,10 +703,23 @@ impl VirtualMachine {
                     .into_iter()
                     .enumerate()
                     .fold(command_text, |command_text, (i, substitution)| {
-                        command_text.replace(&format!("{{{i}}}"), &substitution)
+                        // Commands are not localized, so their values are not formatted for display.
+                        command_text.replace(&format!("{{{i}}}"), &String::from(substitution))
                     });
                 let command = Command::parse(command_text);
 
+                // ## Implementation note
+                // Commands with a handler registered via `Dialogue::with_command` are run right here,
+                // so the dialogue continues without waiting for the caller.
+                if let Some(result) = self.command_handlers.run(&command) {
+                    for (name, value) in result.variable_writes() {
+                        self.set_variable_checked(name.clone(), value.clone())?;
+                    }
+                    self.state.program_counter += 1;
+                    return Ok(());
+                }
+
+                self.pending_command = Some(command.clone());
                 self.batched_events.push(DialogueEvent::Command(command));
 
                 // Implementation note:
@@ -351,6 +736,7 @@ impl VirtualMachine {
                 let string_id: LineId = string_id.into();
                 assert_up_to_date_compiler(instruction.operands.len() >= 4);
                 let substitutions = self.pop_substitutions_with_count_at_operand(instruction, 2);
+                let substitutions = self.format_for_display(substitutions);
                 let line = self.prepare_line(string_id, &substitutions)?;
 
                 // Indicates whether the VM believes that the
@@ -368,16 +754,26 @@ impl VirtualMachine {
                     true
                 };
 
+                // ## Implementation note:
+                // Options marked as only being offered once are left out after they have been selected.
+                if self.once_line_ids.contains(&line.id) && self.option_visit_count(&line.id) > 0.0
+                {
+                    self.state.program_counter += 1;
+                    return Ok(());
+                }
+
                 let index = self.state.current_options.len();
                 let node_name = instruction.read_operand(1);
                 // ## Implementation note:
                 // The original calculates the ID in the `ShowOptions` opcode,
                 // but this way is cleaner because it allows us to store a `DialogueOption` instead of a bunch of values in a big tuple.
+                let requirement_hint = self.requirement_hints.get(&line.id).cloned();
                 self.state.current_options.push(DialogueOption {
                     line,
                     id: OptionId(index),
                     destination_node: node_name,
                     is_available: line_condition_passed,
+                    requirement_hint,
                 });
                 self.state.program_counter += 1;
             }
@@ -396,7 +792,13 @@ impl VirtualMachine {
                 // Pass the options set to the client, as well as a
                 // delegate for them to call when the user has made
                 // a selection
-                let current_options = self.state.current_options.clone();
+                let mut current_options = self.state.current_options.clone();
+                if self.shuffle_options {
+                    self.shuffle_unpinned_options(&mut current_options);
+                }
+                if self.option_sort == OptionSort::LocaleAlphabetical {
+                    self.sort_unpinned_options_alphabetically(&mut current_options);
+                }
                 self.batched_events
                     .push(DialogueEvent::Options(current_options));
 
@@ -436,6 +838,7 @@ impl VirtualMachine {
                 } else {
                     self.state.program_counter += 1;
                 }
+                self.send_narrowed_line_hints();
             }
             OpCode::Pop => {
                 // Pops a value from the stack.
@@ -456,6 +859,12 @@ impl VirtualMachine {
 
                 // Call a function, whose parameters are expected to be on the stack. Pushes the function's return value, if it returns one.
                 let function_name: String = instruction.read_operand(0);
+                if function_name == Library::LINE_GROUP_SELECTION_FUNCTION_NAME {
+                    let candidate_index = self.select_line_group_candidate(parameters);
+                    self.state.push(candidate_index);
+                    self.state.program_counter += 1;
+                    return Ok(());
+                }
                 let function =
                     self.library
                         .get(&function_name)
@@ -525,7 +934,7 @@ impl VirtualMachine {
                 // Store the top value on the stack in a variable.
                 let top_value = self.state.peek_value().clone();
                 let variable_name: String = instruction.read_operand(0);
-                self.variable_storage.set(variable_name, top_value.into())?;
+                self.set_variable_checked(variable_name, top_value.into())?;
                 self.state.program_counter += 1;
             }
             OpCode::Stop => {
@@ -561,10 +970,26 @@ impl VirtualMachine {
                 language_code: self.language_code.clone(),
             }
         })?;
-        let substituted_text = expand_substitutions(&line_text, substitutions);
-        let markup = self
-            .parse_markup(&substituted_text)
+        let substituted_text = if self.substitution_markup {
+            expand_substitutions(&line_text, substitutions)
+        } else {
+            let substitutions: Vec<_> = substitutions
+                .iter()
+                .map(|substitution| escape_markup(substitution))
+                .collect();
+            expand_substitutions(&line_text, &substitutions)
+        };
+        let line_parser = &mut self.line_parser;
+        let mut markup = self
+            .markup_cache
+            .get_or_parse(
+                &string_id,
+                self.language_code.as_ref(),
+                &substituted_text,
+                |text| line_parser.parse_markup(text),
+            )
             .map_err(DialogueError::MarkupParseError)?;
+        self.whitespace_mode.apply(&mut markup);
         let line = Line {
             id: string_id,
             text: markup.text,
@@ -602,7 +1027,7 @@ impl VirtualMachine {
         &mut self,
         instruction: &Instruction,
         index: usize,
-    ) -> Vec<String> {
+    ) -> Vec<YarnValue> {
         let expression_count: usize = instruction.operands[index].clone().try_into().unwrap();
         let mut values: Vec<_> = (0..expression_count)
             .rev()
@@ -611,6 +1036,98 @@ impl VirtualMachine {
         values.reverse();
         values
     }
+
+    /// Runs instructions that only push values, i.e. the code generated for expressions, and returns the resulting stack.
+    /// Returns [`None`] if the instructions contain anything else or a variable or function cannot be found.
+    /// Does not modify any state.
+    pub(crate) fn evaluate_instructions(
+        &self,
+        instructions: &[Instruction],
+    ) -> Option<Vec<YarnValue>> {
+        let mut stack: Vec<YarnValue> = Vec::new();
+        for instruction in instructions {
+            match instruction.opcode() {
+                OpCode::PushString => stack.push(instruction.read_operand::<String>(0).into()),
+                OpCode::PushFloat => stack.push(instruction.read_operand::<f32>(0).into()),
+                OpCode::PushBool => stack.push(instruction.read_operand::<bool>(0).into()),
+                OpCode::PushVariable => {
+                    let variable_name: String = instruction.read_operand(0);
+                    let value = self.variable_storage.get(&variable_name).ok().or_else(|| {
+                        self.program
+                            .as_ref()?
+                            .initial_values
+                            .get(&variable_name)
+                            .map(|value| value.clone().into())
+                    })?;
+                    stack.push(value);
+                }
+                OpCode::CallFunc => {
+                    let parameter_count = usize::try_from(stack.pop()?).ok()?;
+                    let parameters = stack.split_off(stack.len().checked_sub(parameter_count)?);
+                    let function_name: String = instruction.read_operand(0);
+                    let function = self.library.get(&function_name)?;
+                    stack.push(function.call(parameters));
+                }
+                _ => return None,
+            }
+        }
+        Some(stack)
+    }
+
+    /// Picks a candidate of a line group for a call to [`Library::LINE_GROUP_SELECTION_FUNCTION_NAME`] and returns its index,
+    /// or `-1` if no candidate is left.
+    /// Candidates are picked with a probability proportional to their weight, leaving out those that may only run once and already did.
+    fn select_line_group_candidate(&mut self, parameters: Vec<YarnValue>) -> f32 {
+        let candidates: Vec<(f32, String)> = parameters
+            .chunks_exact(2)
+            .map(|candidate| {
+                let weight = f32::try_from(candidate[0].clone()).unwrap_or(1.0);
+                (weight, String::from(&candidate[1]))
+            })
+            .collect();
+        let run_count = |once_variable: &str| match self.variable_storage.get(once_variable) {
+            Ok(YarnValue::Number(count)) => count,
+            _ => 0.0,
+        };
+        let weights = candidates.iter().map(|(weight, once_variable)| {
+            let is_used_up = !once_variable.is_empty() && run_count(once_variable) > 0.0;
+            if is_used_up {
+                0.0
+            } else {
+                *weight
+            }
+        });
+        let Ok(distribution) = WeightedIndex::new(weights) else {
+            // No candidate is left
+            return -1.0;
+        };
+        let index = distribution.sample(&mut self.rng);
+        let once_variable = &candidates[index].1;
+        if !once_variable.is_empty() {
+            let count = run_count(once_variable) + 1.0;
+            if let Err(e) = self
+                .variable_storage
+                .set(once_variable.clone(), YarnValue::Number(count))
+            {
+                error!("Failed to mark line group candidate as run: {e}");
+                self.error_handler.report(&e.into());
+            }
+        }
+        index as f32
+    }
+
+    /// Converts values that are substituted into lines and options to strings using the conventions of the current language.
+    /// Without a language set, the locale-agnostic representation is used.
+    fn format_for_display(&self, values: Vec<YarnValue>) -> Vec<String> {
+        let language_code = self.language_code.as_ref().map(ToString::to_string);
+        values
+            .into_iter()
+            .map(|value| match language_code.as_deref() {
+                Some(language_code) => value.format_with_locale(language_code),
+                None => value.into(),
+            })
+            .collect()
+    }
 }
 
 fn assert_up_to_date_compiler(predicate: bool) {
@@ -637,3 +1154,96 @@ fn expand_substitutions(text: &str, substitutions: &[String]) -> String {
             text.replace(&format!("{{{i}}}",), substitution)
         })
 }
+
+/// Applies `reorder` to the options that are not pinned and puts them back in the positions of unpinned options.
+fn reorder_unpinned_options(
+    pinned_line_ids: &HashSet<LineId>,
+    options: &mut [DialogueOption],
+    reorder: impl FnOnce(&mut [DialogueOption]),
+) {
+    let unpinned_indices: Vec<_> = options
+        .iter()
+        .enumerate()
+        .filter(|(_, option)| !pinned_line_ids.contains(&option.line.id))
+        .map(|(index, _)| index)
+        .collect();
+    let mut unpinned_options: Vec<_> = unpinned_indices
+        .iter()
+        .map(|&index| options[index].clone())
+        .collect();
+    reorder(&mut unpinned_options);
+    for (index, option) in unpinned_indices.into_iter().zip(unpinned_options) {
+        options[index] = option;
+    }
+}
+
+/// Creates a random number generator for when no seed was set with [`Dialogue::with_rng_seed`].
+/// The seed is taken from the randomly keyed [`RandomState`] of the standard library
+/// instead of the operating system's entropy, which is not available on every platform, e.g. `wasm32-unknown-unknown`.
+fn unseeded_rng() -> StdRng {
+    StdRng::seed_from_u64(RandomState::new().build_hasher().finish())
+}
+
+/// Escapes the square brackets and backslashes in `text` so that the markup parser treats them as plain text.
+///
+/// ## Implementation notes
+///
+/// This function is not present in the original implementation, which always parses the markup of substitutions.
+fn escape_markup(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+}
+
+/// Returns for every instruction of the node whether it can be executed when starting at the instruction `start`.
+/// Branches guarded by a constant condition, e.g. `<<if false>>`, are known to never be taken.
+/// All other branches are assumed to be possible.
+///
+/// ## Implementation notes
+///
+/// This function is not present in the original implementation, which hints every line of the node.
+fn reachable_instructions(node: &Node, start: usize) -> Vec<bool> {
+    let label = |label_name: String| {
+        node.labels
+            .get(&label_name)
+            .and_then(|&index| usize::try_from(index).ok())
+    };
+    let mut is_reachable = vec![false; node.instructions.len()];
+    let mut worklist = vec![start];
+    while let Some(index) = worklist.pop() {
+        if index >= node.instructions.len() || is_reachable[index] {
+            continue;
+        }
+        is_reachable[index] = true;
+        let instruction = &node.instructions[index];
+        let next = index + 1;
+        match instruction.opcode() {
+            OpCode::JumpTo => worklist.extend(label(instruction.read_operand(0))),
+            OpCode::JumpIfFalse => {
+                // If a label points at this instruction, the condition may have been pushed somewhere else
+                let is_jumped_to = node.labels.values().any(|&label| label as usize == index);
+                let constant_condition = index
+                    .checked_sub(1)
+                    .filter(|_| !is_jumped_to)
+                    .map(|previous| &node.instructions[previous])
+                    .filter(|previous| previous.opcode() == OpCode::PushBool)
+                    .map(|previous| previous.read_operand::<bool>(0));
+                if constant_condition != Some(true) {
+                    worklist.extend(label(instruction.read_operand(0)));
+                }
+                if constant_condition != Some(false) {
+                    worklist.push(next);
+                }
+            }
+            // The destination of an option is on the stack, so it could be any label of the node.
+            OpCode::Jump => worklist.extend(
+                node.labels
+                    .values()
+                    .filter_map(|&index| usize::try_from(index).ok()),
+            ),
+            OpCode::Stop | OpCode::RunNode => {}
+            _ => worklist.push(next),
+        }
+    }
+    is_reachable
+}
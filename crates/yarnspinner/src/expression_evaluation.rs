@@ -0,0 +1,132 @@
+//! Evaluating Yarn expressions outside of a script, e.g. for a debug console.
+
+use crate::compiler::{Compiler, CompilerError, Declaration, File};
+use crate::core::YarnValue;
+use crate::runtime::Dialogue;
+use std::error::Error;
+use std::fmt::{self, Display};
+use yarnspinner_core::prelude::OpCode;
+use yarnspinner_core::types::TypedValue;
+
+/// The node that the expression is compiled into.
+const EVALUATION_NODE_NAME: &str = "Evaluation";
+
+/// The variable that the compiled expression is assigned to.
+const EVALUATION_VARIABLE_NAME: &str = "$__yarn_internal_evaluation";
+
+/// An error returned by [`EvaluateExpression::evaluate_expression`].
+#[allow(missing_docs)]
+#[derive(Debug)]
+pub enum EvalError {
+    /// The source tried to change a variable, e.g. `set $gold = 0`.
+    WriteNotAllowed,
+    /// The source contains something other than a single expression, e.g. a command or several lines.
+    NotAnExpression,
+    /// The expression could not be compiled, e.g. because of a syntax or type error.
+    CompilerError(CompilerError),
+    /// The expression compiled, but could not be evaluated, e.g. because it calls a function that is not in the [`Library`](crate::core::Library).
+    EvaluationFailed { expression: String },
+}
+
+impl Error for EvalError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EvalError::CompilerError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use EvalError::*;
+        match self {
+            WriteNotAllowed => f.write_str("Evaluated expressions may not change variables."),
+            NotAnExpression => f.write_str("Expected a single expression, but found a statement or several lines."),
+            CompilerError(e) => Display::fmt(e, f),
+            EvaluationFailed { expression } => write!(f, "Failed to evaluate \"{expression}\". Are all functions it calls registered in the library?"),
+        }
+    }
+}
+
+impl From<CompilerError> for EvalError {
+    fn from(source: CompilerError) -> Self {
+        EvalError::CompilerError(source)
+    }
+}
+
+/// Evaluates Yarn expressions outside of a script. Implemented for [`Dialogue`].
+///
+/// This lives in this crate rather than in the runtime, since compiling the expression needs the compiler.
+pub trait EvaluateExpression {
+    /// Evaluates a Yarn expression such as `$gold > 100 and visited("Shop")` against the current variables and the [`Library`](crate::core::Library), e.g. for a debug console.
+    ///
+    /// The expression is compiled on its own with the same rules as expressions in Yarn scripts, using the types of the current variables and
+    /// the signatures of the functions in the [`Library`](crate::core::Library). Evaluating it does not change the state of the [`Dialogue`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`EvalError::WriteNotAllowed`] for statements like `set $gold = 0`,
+    /// [`EvalError::CompilerError`] if the expression does not compile, e.g. because of a type error,
+    /// and [`EvalError::EvaluationFailed`] if it calls a function that is declared but missing from the [`Library`](crate::core::Library).
+    fn evaluate_expression(&self, source: &str) -> Result<YarnValue, EvalError>;
+}
+
+impl EvaluateExpression for Dialogue {
+    fn evaluate_expression(&self, source: &str) -> Result<YarnValue, EvalError> {
+        let statement = source.trim().trim_start_matches("<<").trim_start();
+        let is_set_statement = statement
+            .strip_prefix("set")
+            .is_some_and(|rest| rest.starts_with(char::is_whitespace));
+        if is_set_statement {
+            return Err(EvalError::WriteNotAllowed);
+        }
+        if source.contains("<<") || source.contains(">>") || source.contains('\n') {
+            return Err(EvalError::NotAnExpression);
+        }
+
+        let mut compiler = Compiler::new();
+        compiler.with_library(self.library());
+        for (name, value) in self.current_variables() {
+            compiler
+                .declare_variable(Declaration::new(name, value.r#type()).with_default_value(value));
+        }
+        let compilation = compiler
+            .add_file(File {
+                file_name: "<expression>".to_owned(),
+                source: format!(
+                    "title: {EVALUATION_NODE_NAME}\n---\n<<set {EVALUATION_VARIABLE_NAME} = ({source})>>\n==="
+                ),
+            })
+            .compile()?;
+
+        let evaluation_failed = || EvalError::EvaluationFailed {
+            expression: source.to_owned(),
+        };
+        let node = compilation
+            .program
+            .as_ref()
+            .and_then(|program| program.nodes.get(EVALUATION_NODE_NAME))
+            .ok_or_else(evaluation_failed)?;
+        let stores: Vec<_> = node
+            .instructions
+            .iter()
+            .enumerate()
+            .filter(|(_, instruction)| instruction.opcode() == OpCode::StoreVariable)
+            .collect();
+        let [(store_index, store)] = stores[..] else {
+            return Err(EvalError::WriteNotAllowed);
+        };
+        if store.read_operand::<String>(0) != EVALUATION_VARIABLE_NAME {
+            return Err(EvalError::WriteNotAllowed);
+        }
+
+        let mut stack = self
+            .evaluate_instructions(&node.instructions[..store_index])
+            .ok_or_else(evaluation_failed)?;
+        match (stack.pop(), stack.is_empty()) {
+            (Some(value), true) => Ok(value),
+            _ => Err(evaluation_failed()),
+        }
+    }
+}
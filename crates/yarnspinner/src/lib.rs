@@ -7,6 +7,8 @@
 
 pub use log;
 
+mod expression_evaluation;
+
 pub mod prelude {
     //! Everything you need to get started using Yarn Spinner.
     pub use crate::compiler::{
@@ -18,10 +20,11 @@ pub mod prelude {
         Program as YarnProgram, YarnFn, YarnValue,
     };
     pub use crate::runtime::{
-        Command as YarnCommand, CompiledProgramAnalyser as YarnAnalyser,
+        Command as YarnCommand, CommandResult, CompiledProgramAnalyser as YarnAnalyser,
         Context as YarnAnalysisContext, Dialogue, DialogueError, DialogueEvent, DialogueOption,
-        Language, Line as YarnLine, MarkupAttribute, MarkupValue, OptionId,
-        Result as YarnRuntimeResult, StringTable, TextProvider, VariableStorage,
+        EvalError, EvaluateExpression, Language, Line as YarnLine, MarkupAttribute, MarkupValue,
+        OptionId, Result as YarnRuntimeResult, StringTable, TextProvider, TraceEntry,
+        VariableStorage,
     };
 }
 
@@ -30,9 +33,11 @@ pub mod core {
     pub use yarnspinner_core::prelude::{
         optionality, yarn_fn_type, yarn_library, Header, Instruction,
         IntoYarnValueFromNonYarnValue, InvalidOpCodeError, Library, LineId, Node, Position,
-        Program, Type, UntypedYarnFn, YarnFn, YarnFnParam, YarnFnParamItem, YarnValue,
-        YarnValueCastError, YarnValueWrapper, YarnValueWrapperIter,
+        Program, ProgramExtensions, Type, UntypedYarnFn, VerificationError, YarnFn, YarnFnParam,
+        YarnFnParamItem, YarnValue, YarnValueCastError, YarnValueTypeError, YarnValueWrapper,
+        YarnValueWrapperIter,
     };
+    pub use yarnspinner_core::types::FunctionType;
 }
 pub mod compiler {
     //! Types and traits used by the compiler, in particular the [`Compiler`] struct.
@@ -42,8 +47,9 @@ pub mod compiler {
 
 pub mod runtime {
     //! Types and traits used by the runtime, in particular the [`Dialogue`] struct.
+    pub use crate::expression_evaluation::{EvalError, EvaluateExpression};
     pub use yarnspinner_runtime::markup::{
-        MarkupAttribute, MarkupParseError, MarkupValue, CHARACTER_ATTRIBUTE,
+        MarkupAttribute, MarkupBuilder, MarkupParseError, MarkupValue, CHARACTER_ATTRIBUTE,
         CHARACTER_ATTRIBUTE_NAME_PROPERTY, TRIM_WHITESPACE_PROPERTY,
     };
     pub use yarnspinner_runtime::prelude::*;
@@ -3,10 +3,13 @@
 //! ## Implementation notes
 //! `TestDumpingCode` was not ported because `GetByteCode` is not used by a user directly and thus was not implemented at all.
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 use test_base::prelude::*;
 use yarnspinner::compiler::*;
+use yarnspinner::core::*;
 use yarnspinner::runtime::*;
+use yarnspinner_core::prelude::OpCode;
 
 mod test_base;
 
@@ -146,6 +149,8 @@ fn test_getting_raw_source() {
     let source = test_base.string_table.get_text(&source_id).unwrap();
 
     assert_eq!(source, "A: HAHAHA\n");
+    assert_eq!("line:LearnMore", source_id.0);
+    assert!(dialogue.get_line_id_for_node("NonexistentNode").is_none());
 }
 
 #[test]
@@ -163,6 +168,72 @@ fn test_getting_tags() {
     assert_eq!(tags, vec!["rawText"]);
 }
 
+#[test]
+fn test_continuous_line_hints_narrow_after_branches() {
+    let result = Compiler::from_test_source(
+        "<<declare $flag = true>>\n\
+        <<if $flag>>\n\
+        Taken #line:taken\n\
+        <<else>>\n\
+        Skipped #line:skipped\n\
+        <<endif>>\n\
+        Always #line:always",
+    )
+    .compile()
+    .unwrap();
+
+    let mut dialogue = TestBase::new()
+        .with_compilation(result)
+        .dialogue
+        .with_continuous_line_hints(true);
+    dialogue
+        .set_line_hints_enabled(true)
+        .set_node("Start")
+        .unwrap();
+
+    let hints: Vec<_> = dialogue
+        .continue_()
+        .unwrap()
+        .into_iter()
+        .filter_map(|event| match event {
+            DialogueEvent::LineHints(lines) => Some(lines),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            vec![
+                LineId::from("line:taken"),
+                "line:skipped".into(),
+                "line:always".into()
+            ],
+            vec![LineId::from("line:taken"), "line:always".into()],
+        ],
+        hints
+    );
+}
+
+#[test]
+fn test_getting_line_id_for_node() {
+    let result = Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source: "title: Start\n---\nHello\n===\n\
+                title: Notes\ntags: draft rawText\n---\nSome notes\n===\n"
+                .to_owned(),
+        })
+        .compile()
+        .unwrap();
+    let dialogue = TestBase::new().with_compilation(result).dialogue;
+
+    assert_eq!(
+        Some(LineId::from("line:Notes")),
+        dialogue.get_line_id_for_node("Notes")
+    );
+    assert_eq!(None, dialogue.get_line_id_for_node("Start"));
+    assert_eq!(None, dialogue.get_line_id_for_node("NotANode"));
+}
+
 #[test]
 fn test_getting_headers() {
     let path = test_data_path().join("Example.yarn");
@@ -184,6 +255,110 @@ fn test_getting_headers() {
     assert_eq!(headers, expected_headers);
 }
 
+#[test]
+fn test_header_values_may_contain_colons() {
+    let result = Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source: "title: Start\n\
+                metadata: {\"speaker\": \"Alice\", \"time\": \"12:30\"}\n\
+                source: https://example.com/docs?page=1\n\
+                note: before --- after\n\
+                ---\nHello\n===\n"
+                .to_owned(),
+        })
+        .compile()
+        .unwrap();
+    let test_base = TestBase::new().with_program(result.program.unwrap());
+
+    let headers = test_base.dialogue.get_headers_for_node("Start").unwrap();
+    assert_eq!(
+        "{\"speaker\": \"Alice\", \"time\": \"12:30\"}",
+        headers["metadata"]
+    );
+    assert_eq!("https://example.com/docs?page=1", headers["source"]);
+    assert_eq!("before --- after", headers["note"]);
+    assert_eq!(4, headers.len());
+}
+
+#[test]
+fn test_getting_node_position() {
+    let result = Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source: "title: Start\nposition: 100, 200\n---\nHello\n===\n\
+                title: Unplaced\nposition: left\n---\nHi\n===\n"
+                .to_owned(),
+        })
+        .compile()
+        .unwrap();
+    let test_base = TestBase::new().with_compilation(result);
+    let dialogue = &test_base.dialogue;
+
+    assert_eq!(Some((100, 200)), dialogue.get_node_position("Start"));
+    assert_eq!(None, dialogue.get_node_position("Unplaced"));
+    assert_eq!(None, dialogue.get_node_position("NonexistentNode"));
+}
+
+#[test]
+fn test_resetting_visit_counts_keeps_other_variables() {
+    let result = Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source: "title: Start\n---\n<<declare $gold = 0>>\n<<set $gold to 5>>\n\
+                <<if visited(\"Shop\") or visited(\"Inn\")>>\nWelcome back\n<<endif>>\n<<jump Shop>>\n===\n\
+                title: Shop\n---\n<<jump Inn>>\n===\n\
+                title: Inn\n---\nGood night\n===\n"
+                .to_owned(),
+        })
+        .compile()
+        .unwrap();
+    let mut dialogue = TestBase::new().with_compilation(result).dialogue;
+    dialogue.run_node("Start");
+
+    let visit_count = |dialogue: &Dialogue, node_name: &str| {
+        dialogue
+            .variable_storage()
+            .get(&Library::generate_unique_visited_variable_for_node(
+                node_name,
+            ))
+            .unwrap()
+    };
+    assert_eq!(YarnValue::Number(1.0), visit_count(&dialogue, "Shop"));
+    assert_eq!(YarnValue::Number(1.0), visit_count(&dialogue, "Inn"));
+
+    dialogue.reset_visited_for("Shop");
+    assert_eq!(YarnValue::Number(0.0), visit_count(&dialogue, "Shop"));
+    assert_eq!(YarnValue::Number(1.0), visit_count(&dialogue, "Inn"));
+
+    dialogue.reset_visited();
+    assert_eq!(YarnValue::Number(0.0), visit_count(&dialogue, "Inn"));
+    assert_eq!(
+        YarnValue::Number(5.0),
+        dialogue.variable_storage().get("$gold").unwrap()
+    );
+}
+
+#[test]
+fn test_getting_current_node_tags() {
+    let result = Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source: "title: Start\ntags: music:calm rain\n---\nHello\n===\n".to_owned(),
+        })
+        .compile()
+        .unwrap();
+    let mut dialogue = TestBase::new().with_compilation(result).dialogue;
+    assert_eq!(None, dialogue.current_node_tags());
+
+    dialogue.set_node("Start").unwrap();
+    dialogue.next().unwrap();
+    assert_eq!(
+        Some(vec!["music:calm".to_owned(), "rain".to_owned()]),
+        dialogue.current_node_tags()
+    );
+}
+
 /// ## Implementation note
 /// Corresponds to `TestPrepareForLine`
 #[test]
@@ -218,6 +393,50 @@ fn test_line_hints() {
     assert!(line_hints_were_sent);
 }
 
+#[test]
+fn test_line_hints_skip_constant_false_branches() {
+    let result = Compiler::from_test_source(
+        "<<declare $maybe = false>>\n\
+        Always #line:always\n\
+        <<if false>>\n\
+        Never #line:never\n\
+        <<elseif true>>\n\
+        Instead #line:instead\n\
+        <<else>>\n\
+        Otherwise #line:otherwise\n\
+        <<endif>>\n\
+        <<if $maybe>>\n\
+        Maybe #line:maybe\n\
+        <<endif>>",
+    )
+    .compile()
+    .unwrap();
+
+    let mut dialogue = TestBase::new().with_compilation(result).dialogue;
+    dialogue
+        .set_line_hints_enabled(true)
+        .set_node("Start")
+        .unwrap();
+
+    let lines = dialogue
+        .next()
+        .unwrap()
+        .into_iter()
+        .find_map(|event| match event {
+            DialogueEvent::LineHints(lines) => Some(lines),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(
+        vec![
+            LineId::from("line:always"),
+            "line:instead".into(),
+            "line:maybe".into()
+        ],
+        lines
+    );
+}
+
 #[test]
 fn test_function_argument_type_inference() {
     let test_base = TestBase::new().extend_library(|library| {
@@ -327,3 +546,1306 @@ fn test_selecting_option_from_inside_option_callback() {
         }
     }
 }
+
+#[test]
+fn test_selecting_multiple_options_from_multiselect_group() {
+    let result = Compiler::from_test_source(
+        "-> Bread #multiselect\n-> Cheese #multiselect\n-> Wine #multiselect\nDone\n",
+    )
+    .compile()
+    .unwrap();
+    let line_id_for_text = |text: &str| {
+        result
+            .string_table
+            .iter()
+            .find_map(|(id, info)| (info.text == text).then(|| id.clone()))
+            .unwrap()
+    };
+    let bread = line_id_for_text("Bread");
+    let wine = line_id_for_text("Wine");
+    let multiselect_lines: Vec<_> = result
+        .string_table
+        .iter()
+        .filter(|(_, info)| info.metadata.contains(&MULTISELECT_TAG.to_owned()))
+        .map(|(id, _)| id.0.clone())
+        .collect();
+    assert_eq!(3, multiselect_lines.len());
+    assert_eq!(
+        multiselect_lines.len(),
+        result
+            .program
+            .as_ref()
+            .unwrap()
+            .extensions
+            .multiselect_lines
+            .len()
+    );
+
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_node("Start").unwrap();
+
+    let mut saw_options = false;
+    while let Some(events) = test_base.dialogue.next() {
+        for event in events {
+            if let DialogueEvent::Options(options) = event {
+                assert_eq!(3, options.len());
+                assert!(test_base.dialogue.is_waiting_for_multiselect());
+                test_base
+                    .dialogue
+                    .set_selected_options(&[OptionId(0), OptionId(2)])
+                    .unwrap();
+                saw_options = true;
+            }
+        }
+    }
+    assert!(saw_options);
+
+    let selection: String = test_base
+        .variable_storage
+        .get(MULTISELECT_VARIABLE_NAME)
+        .unwrap()
+        .into();
+    assert_eq!(format!("{bread}{MULTISELECT_SEPARATOR}{wine}"), selection);
+}
+
+#[test]
+fn test_shuffled_options_are_reproducible_and_keep_pinned_options() {
+    let result = Compiler::from_test_source(
+        "-> Paris\n    Bonjour!\n-> London\n-> Berlin\n-> Rome\n-> Never mind #pinned\n",
+    )
+    .compile()
+    .unwrap();
+    let pinned_lines: Vec<_> = result
+        .string_table
+        .iter()
+        .filter(|(_, info)| info.metadata.contains(&PINNED_TAG.to_owned()))
+        .map(|(id, _)| id.0.clone())
+        .collect();
+    assert_eq!(1, pinned_lines.len());
+    assert_eq!(
+        pinned_lines,
+        result.program.as_ref().unwrap().extensions.pinned_lines
+    );
+
+    let present_options = |seed| {
+        let mut test_base = TestBase::new().with_compilation(result.clone());
+        test_base.dialogue = test_base
+            .dialogue
+            .with_shuffle_options(true)
+            .with_rng_seed(seed);
+        test_base.dialogue.set_node("Start").unwrap();
+        let options = test_base
+            .dialogue
+            .continue_()
+            .unwrap()
+            .into_iter()
+            .find_map(|event| match event {
+                DialogueEvent::Options(options) => Some(options),
+                _ => None,
+            })
+            .unwrap();
+        (test_base, options)
+    };
+
+    let (mut test_base, options) = present_options(42);
+    let (_, options_with_same_seed) = present_options(42);
+    assert_eq!(options, options_with_same_seed);
+
+    let texts: Vec<_> = options
+        .iter()
+        .map(|option| option.line.text.as_str())
+        .collect();
+    // The order is the same on every platform, the pinned option stays last
+    assert_eq!(
+        vec!["Rome", "London", "Paris", "Berlin", "Never mind"],
+        texts
+    );
+
+    // Option IDs are not affected by shuffling
+    let paris = options
+        .iter()
+        .find(|option| option.line.text == "Paris")
+        .unwrap();
+    assert_eq!(OptionId(0), paris.id);
+    test_base.dialogue.set_selected_option(paris.id).unwrap();
+    let events = test_base.dialogue.continue_().unwrap();
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, DialogueEvent::Line(line) if line.text == "Bonjour!")));
+}
+
+#[test]
+fn test_unavailable_selection_policies() {
+    let result =
+        Compiler::from_test_source("-> Bribe the guard <<if false>>\n-> Leave\n    Goodbye\n")
+            .compile()
+            .unwrap();
+    let present_options = |policy| {
+        let mut test_base = TestBase::new().with_compilation(result.clone());
+        test_base.dialogue = test_base.dialogue.with_unavailable_selection_policy(policy);
+        test_base.dialogue.set_node("Start").unwrap();
+        let options = test_base
+            .dialogue
+            .continue_()
+            .unwrap()
+            .into_iter()
+            .find_map(|event| match event {
+                DialogueEvent::Options(options) => Some(options),
+                _ => None,
+            })
+            .unwrap();
+        assert!(!options[0].is_available);
+        test_base
+    };
+
+    // Reject: the selection fails, but a valid selection can still be made
+    let mut test_base = present_options(UnavailableSelectionPolicy::Reject);
+    assert!(matches!(
+        test_base.dialogue.set_selected_option(OptionId(0)),
+        Err(DialogueError::UnavailableOptionSelected {
+            selected_option_id: OptionId(0)
+        })
+    ));
+    assert!(test_base.dialogue.is_waiting_for_option_selection());
+    test_base.dialogue.set_selected_option(OptionId(1)).unwrap();
+
+    // TreatAsNoOp: the selection is silently ignored
+    let mut test_base = present_options(UnavailableSelectionPolicy::TreatAsNoOp);
+    test_base.dialogue.set_selected_option(OptionId(0)).unwrap();
+    assert!(test_base.dialogue.is_waiting_for_option_selection());
+    test_base.dialogue.set_selected_option(OptionId(1)).unwrap();
+    let events = test_base.dialogue.continue_().unwrap();
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, DialogueEvent::Line(line) if line.text == "Goodbye")));
+
+    // Error: the selection fails and the dialogue is stopped
+    let mut test_base = present_options(UnavailableSelectionPolicy::Error);
+    assert!(matches!(
+        test_base.dialogue.set_selected_option(OptionId(0)),
+        Err(DialogueError::UnavailableOptionSelected { .. })
+    ));
+    assert!(!test_base.dialogue.is_active());
+}
+
+#[test]
+fn test_options_expose_their_line_ids() {
+    let result = Compiler::from_test_source("-> Yes #line:yes\n-> No\n-> Maybe\n")
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_node("Start").unwrap();
+    let options = test_base
+        .dialogue
+        .continue_()
+        .unwrap()
+        .into_iter()
+        .find_map(|event| match event {
+            DialogueEvent::Options(options) => Some(options),
+            _ => None,
+        })
+        .unwrap();
+
+    let line_ids = options.line_ids();
+    assert_eq!(3, line_ids.len());
+    assert_eq!(LineId::from("line:yes"), line_ids[0]);
+    let distinct_line_ids: HashSet<_> = line_ids.iter().collect();
+    assert_eq!(3, distinct_line_ids.len());
+    for (option, line_id) in options.iter().zip(&line_ids) {
+        assert_eq!(&option.line.id, line_id);
+    }
+}
+
+#[test]
+fn test_options_are_sorted_by_locale() {
+    let result = Compiler::from_test_source("-> Zebra\n-> Ödla\n-> Éclair\n-> Apple\n-> eagle\n")
+        .compile()
+        .unwrap();
+
+    let present_options = |option_sort, language: &str| {
+        let mut test_base = TestBase::new().with_compilation(result.clone());
+        test_base.dialogue = test_base.dialogue.with_option_sort(option_sort);
+        test_base
+            .dialogue
+            .set_language_code(Language::new(language));
+        test_base.dialogue.set_node("Start").unwrap();
+        test_base
+            .dialogue
+            .continue_()
+            .unwrap()
+            .into_iter()
+            .find_map(|event| match event {
+                DialogueEvent::Options(options) => Some(options),
+                _ => None,
+            })
+            .unwrap()
+            .into_iter()
+            .map(|option| option.line.text)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        vec!["Zebra", "Ödla", "Éclair", "Apple", "eagle"],
+        present_options(OptionSort::SourceOrder, "de")
+    );
+    assert_eq!(
+        vec!["Apple", "eagle", "Éclair", "Ödla", "Zebra"],
+        present_options(OptionSort::LocaleAlphabetical, "de")
+    );
+    // Swedish sorts Ö after Z
+    assert_eq!(
+        vec!["Apple", "eagle", "Éclair", "Zebra", "Ödla"],
+        present_options(OptionSort::LocaleAlphabetical, "sv")
+    );
+}
+
+#[test]
+fn test_once_options_are_omitted_after_selection() {
+    let result = Compiler::from_test_source(
+        "-> Ask about the map #once\n    Here is the map.\n    <<jump Start>>\n-> Leave\n",
+    )
+    .compile()
+    .unwrap();
+    let once_lines: Vec<_> = result
+        .string_table
+        .iter()
+        .filter(|(_, info)| info.metadata.contains(&ONCE_TAG.to_owned()))
+        .map(|(id, _)| id.0.clone())
+        .collect();
+    assert_eq!(1, once_lines.len());
+    assert_eq!(
+        once_lines,
+        result.program.as_ref().unwrap().extensions.once_lines
+    );
+
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_node("Start").unwrap();
+
+    let mut offered_options = Vec::new();
+    while let Some(events) = test_base.dialogue.next() {
+        for event in events {
+            if let DialogueEvent::Options(options) = event {
+                let texts: Vec<_> = options
+                    .iter()
+                    .map(|option| option.line.text.clone())
+                    .collect();
+                offered_options.push(texts);
+                test_base.dialogue.set_selected_option(OptionId(0)).unwrap();
+            }
+        }
+    }
+    assert_eq!(
+        vec![
+            vec!["Ask about the map".to_owned(), "Leave".to_owned()],
+            vec!["Leave".to_owned()],
+        ],
+        offered_options
+    );
+}
+
+#[test]
+fn test_line_groups_pick_weighted_lines_reproducibly() {
+    let result = Compiler::from_test_source(
+        "=> Hello! #weight:8\n=> Hi! #weight:1\n=> Hey! #weight:1\nBye.\n",
+    )
+    .compile()
+    .unwrap();
+
+    let run_lines = |seed| {
+        let mut test_base = TestBase::new().with_compilation(result.clone());
+        test_base.dialogue = test_base.dialogue.with_rng_seed(seed);
+        let mut greetings = Vec::new();
+        for _ in 0..1000 {
+            let mut lines: Vec<_> = test_base
+                .dialogue
+                .run_node("Start")
+                .into_iter()
+                .map(|line| line.text)
+                .collect();
+            // Exactly one line of the group is run, followed by the rest of the node
+            assert_eq!(2, lines.len());
+            assert_eq!("Bye.", lines[1]);
+            greetings.push(lines.swap_remove(0));
+        }
+        greetings
+    };
+
+    let greetings = run_lines(42);
+    assert_eq!(greetings, run_lines(42));
+
+    let count = |text: &str| {
+        greetings
+            .iter()
+            .filter(|greeting| *greeting == text)
+            .count()
+    };
+    assert_eq!(1000, count("Hello!") + count("Hi!") + count("Hey!"));
+    assert!(count("Hello!") > 700, "{}", count("Hello!"));
+    assert!(count("Hi!") > 50, "{}", count("Hi!"));
+    assert!(count("Hey!") > 50, "{}", count("Hey!"));
+}
+
+#[test]
+fn test_line_group_once_candidates_run_only_once() {
+    let result = Compiler::from_test_source("=> First #once\n=> Second #once\n")
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    let mut lines: Vec<_> = (0..3)
+        .flat_map(|_| test_base.dialogue.run_node("Start"))
+        .map(|line| line.text)
+        .collect();
+    lines.sort_unstable();
+    assert_eq!(vec!["First".to_owned(), "Second".to_owned()], lines);
+}
+
+#[test]
+fn test_line_group_arrow_can_be_escaped() {
+    let result = Compiler::from_test_source("=\\> Not a candidate\n=> Only candidate\n")
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    let lines: Vec<_> = test_base
+        .dialogue
+        .run_node("Start")
+        .into_iter()
+        .map(|line| line.text)
+        .collect();
+    assert_eq!(vec!["=> Not a candidate", "Only candidate"], lines);
+}
+
+#[test]
+fn test_line_group_reports_failure_to_mark_once_candidate_as_run() {
+    let result = Compiler::from_test_source("=> Only once #once\n")
+        .compile()
+        .unwrap();
+    // The storage is full, so only the already existing variable storing the picked candidate can be written
+    let mut variable_storage = MemoryVariableStorage::with_capacity_limit(1);
+    variable_storage
+        .set(
+            Library::generate_unique_line_group_variable("Start", 0),
+            YarnValue::Number(0.0),
+        )
+        .unwrap();
+    let mut text_provider = StringTableTextProvider::new();
+    text_provider.extend_base_language(
+        result
+            .string_table
+            .into_iter()
+            .map(|(id, info)| (id, info.text))
+            .collect(),
+    );
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let mut dialogue = Dialogue::new(Box::new(variable_storage), Box::new(text_provider))
+        .with_error_handler({
+            let errors = errors.clone();
+            move |error| errors.lock().unwrap().push(error.to_string())
+        });
+    dialogue.add_program(result.program.unwrap());
+
+    assert_eq!(1, dialogue.run_node("Start").len());
+    let errors = errors.lock().unwrap();
+    assert_eq!(1, errors.len());
+    assert!(
+        errors[0].contains("limited to 1 variables"),
+        "{}",
+        errors[0]
+    );
+}
+
+#[test]
+fn test_evaluate_expression_against_current_state() {
+    let result = Compiler::from_test_source(
+        "<<declare $gold = 150>>\n<<if visited(\"Start\")>>\nWelcome back\n<<endif>>\n",
+    )
+    .compile()
+    .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.run_node("Start");
+
+    let value = test_base
+        .dialogue
+        .evaluate_expression(r#"$gold > 100 and visited("Start")"#)
+        .unwrap();
+    assert_eq!(YarnValue::Boolean(true), value);
+
+    assert!(matches!(
+        test_base.dialogue.evaluate_expression("set $gold = 0"),
+        Err(EvalError::WriteNotAllowed)
+    ));
+    assert_eq!(
+        YarnValue::Number(150.0),
+        test_base.dialogue.variable_storage().get("$gold").unwrap()
+    );
+}
+
+#[test]
+fn test_evaluate_expression_reports_type_errors() {
+    let result = Compiler::from_test_source("<<declare $gold = 150>>\n")
+        .compile()
+        .unwrap();
+    let test_base = TestBase::new().with_compilation(result);
+
+    let result = test_base.dialogue.evaluate_expression("$gold + true");
+    assert!(matches!(result, Err(EvalError::CompilerError(_))));
+}
+
+#[test]
+fn test_selecting_multiple_options_requires_multiselect_group() {
+    let result = Compiler::from_test_source("-> option 1\n-> option 2\n")
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_node("Start").unwrap();
+    let _events = test_base.dialogue.continue_().unwrap();
+
+    assert!(test_base.dialogue.is_waiting_for_option_selection());
+    assert!(!test_base.dialogue.is_waiting_for_multiselect());
+    assert!(matches!(
+        test_base
+            .dialogue
+            .set_selected_options(&[OptionId(0), OptionId(1)]),
+        Err(DialogueError::UnexpectedMultiselectError)
+    ));
+}
+
+#[test]
+fn test_preview_does_not_affect_original_dialogue() {
+    let result = Compiler::from_test_source("<<declare $gold = 0>>\n<<set $gold to 5>>\nDone\n")
+        .compile()
+        .unwrap();
+    let test_base = TestBase::new().with_compilation(result);
+    let dialogue = test_base.dialogue;
+
+    let mut preview = dialogue.clone_for_preview();
+    let preview_lines: Vec<_> = preview
+        .run_node("Start")
+        .into_iter()
+        .map(|line| line.text)
+        .collect();
+    assert_eq!(vec!["Done".to_owned()], preview_lines);
+    let preview_gold: f32 = preview
+        .variable_storage()
+        .get("$gold")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(5.0, preview_gold);
+
+    assert_eq!(None, dialogue.current_node());
+    assert!(!dialogue.is_active());
+    let original_gold: f32 = dialogue
+        .variable_storage()
+        .get("$gold")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(0.0, original_gold);
+}
+
+#[test]
+fn test_template_clones_run_independently() {
+    let result = Compiler::from_test_source(
+        "<<declare $count = 0>>\n<<set $count to $count + 1>>\n<<if visited(\"Start\")>>\nAgain\n<<endif>>\nCount {$count}\n",
+    )
+    .compile()
+    .unwrap();
+    let template = TestBase::new().with_compilation(result).dialogue;
+
+    fn run(dialogue: &mut Dialogue) -> Vec<String> {
+        let lines = dialogue.run_node("Start");
+        lines.into_iter().map(|line| line.text).collect()
+    }
+    let mut first = template.template_clone(Box::new(MemoryVariableStorage::new()));
+    let mut second = template.template_clone(Box::new(MemoryVariableStorage::new()));
+
+    assert_eq!(vec!["Count 1"], run(&mut first));
+    assert_eq!(vec!["Again", "Count 2"], run(&mut first));
+    // The second instance has its own variables and visit counts
+    assert_eq!(vec!["Count 1"], run(&mut second));
+
+    assert_eq!(
+        YarnValue::Number(0.0),
+        template.variable_storage().get("$count").unwrap()
+    );
+}
+
+#[test]
+fn test_command_result_sets_variable_read_by_subsequent_if() {
+    let result = Compiler::from_test_source(
+        "<<declare $gold = 0>>\n<<giveGold 50>>\n<<if $gold >= 50>>\nRich\n<<else>>\nPoor\n<<endif>>\n",
+    )
+    .compile()
+    .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_node("Start").unwrap();
+
+    let mut lines = Vec::new();
+    while let Some(events) = test_base.dialogue.next() {
+        for event in events {
+            match event {
+                DialogueEvent::Command(command) => {
+                    assert_eq!("giveGold", command.name);
+                    let amount: f32 = command.parameters[0].clone().try_into().unwrap();
+                    let gold: f32 = test_base
+                        .dialogue
+                        .variable_storage()
+                        .get("$gold")
+                        .unwrap()
+                        .try_into()
+                        .unwrap();
+                    let result = CommandResult::Continue.with_variable_set("$gold", gold + amount);
+                    test_base.dialogue.apply_command_result(result).unwrap();
+                }
+                DialogueEvent::Line(line) => lines.push(line.text),
+                _ => {}
+            }
+        }
+    }
+    assert_eq!(vec!["Rich".to_owned()], lines);
+}
+
+#[test]
+fn test_disassembling_node() {
+    let result = Compiler::from_test_source("Hello\n-> Option A\n-> Option B\n")
+        .compile()
+        .unwrap();
+    let debug_info = result.debug_info["Start"].clone();
+    let test_base = TestBase::new().with_compilation(result);
+
+    let listing = test_base.dialogue.disassemble_node("Start").unwrap();
+    assert!(listing.iter().any(|entry| entry.contains("RunLine")));
+    assert!(listing.iter().any(|entry| entry.contains("AddOption")));
+    assert!(listing.iter().any(|entry| entry.ends_with(" Jump")));
+    assert!(listing.iter().any(|entry| entry.ends_with(':')));
+    assert!(test_base.dialogue.disassemble_node("Missing").is_none());
+
+    let annotated_listing = test_base
+        .dialogue
+        .disassemble_node_with_positions("Start", &debug_info.line_positions)
+        .unwrap();
+    assert_eq!(listing.len(), annotated_listing.len());
+    assert!(annotated_listing
+        .iter()
+        .any(|entry| entry.contains("RunLine") && entry.contains("; line 3")));
+}
+
+#[test]
+fn test_clearing_variable_storage_resets_variables_and_visits() {
+    let result = Compiler::from_test_source(
+        "<<declare $gold = 0>>\n<<set $gold to 5>>\n<<if visited(\"Start\")>>\nAgain\n<<else>>\nFirst\n<<endif>>\n",
+    )
+    .compile()
+    .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    let run_start = |dialogue: &mut Dialogue| -> Vec<_> {
+        dialogue
+            .run_node("Start")
+            .into_iter()
+            .map(|line| line.text)
+            .collect()
+    };
+    assert_eq!(vec!["First".to_owned()], run_start(&mut test_base.dialogue));
+    assert_eq!(vec!["Again".to_owned()], run_start(&mut test_base.dialogue));
+
+    test_base.dialogue.variable_storage_mut().clear();
+    assert!(test_base.dialogue.variable_storage().variables().is_empty());
+    assert!(!test_base.dialogue.variable_storage().contains("$gold"));
+
+    assert_eq!(vec!["First".to_owned()], run_start(&mut test_base.dialogue));
+}
+
+#[test]
+fn test_setting_node_to_default_start_node() {
+    let result = Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source: "title: Intro\n---\nWelcome\n===\n".to_owned(),
+        })
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    assert_eq!(
+        Dialogue::DEFAULT_START_NODE_NAME,
+        test_base.dialogue.default_start_node()
+    );
+    assert!(test_base.dialogue.set_node_to_start().is_err());
+
+    test_base.dialogue = test_base.dialogue.with_default_start_node("Intro");
+    test_base.dialogue.set_node_to_start().unwrap();
+    assert_eq!(Some("Intro".to_owned()), test_base.dialogue.current_node());
+}
+
+#[test]
+fn test_node_dependency_graph() {
+    let result = Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source: "title: Start\n---\n<<if true>>\n<<jump Shop>>\n<<else>>\n<<jump Inn>>\n<<endif>>\n===\n\
+                title: Shop\n---\n<<jump Inn>>\n===\n\
+                title: Inn\n---\n<<declare $next = \"Start\">>\n<<jump {$next}>>\n===\n"
+                .to_owned(),
+        })
+        .compile()
+        .unwrap();
+    let test_base = TestBase::new().with_compilation(result);
+    let graph = test_base.dialogue.node_dependency_graph();
+
+    assert_eq!(vec!["Inn", "Shop", "Start"], graph.nodes);
+    let jump = |source: &str, destination: NodeEdgeDestination| NodeEdge {
+        source: source.to_owned(),
+        destination,
+        kind: NodeEdgeKind::Jump,
+    };
+    let node = |name: &str| NodeEdgeDestination::Node(name.to_owned());
+    assert_eq!(
+        vec![
+            jump("Inn", NodeEdgeDestination::Unknown),
+            jump("Shop", node("Inn")),
+            jump("Start", node("Inn")),
+            jump("Start", node("Shop")),
+        ],
+        graph.edges
+    );
+
+    let dot = graph.to_dot();
+    assert!(dot.contains("\"Start\" -> \"Shop\" [label=\"jump\"];"));
+    assert!(dot.contains("\"Inn\" -> \"<unknown>\" [label=\"jump\"];"));
+}
+
+#[test]
+fn test_number_equality_uses_float_epsilon() {
+    let result = Compiler::from_test_source(
+        "<<if 0.1 + 0.2 == 0.3 and 1000000 == 1000000.5 and 0.3 - 0.1 != 0.25>>\nEqual\n<<else>>\nUnequal\n<<endif>>",
+    )
+    .compile()
+    .unwrap();
+    let run_start = |dialogue: &mut Dialogue| -> Vec<_> {
+        dialogue
+            .run_node("Start")
+            .into_iter()
+            .map(|line| line.text)
+            .collect()
+    };
+
+    // Numbers are compared exactly by default
+    let mut test_base = TestBase::new().with_compilation(result);
+    assert_eq!(
+        vec!["Unequal".to_owned()],
+        run_start(&mut test_base.dialogue)
+    );
+
+    // The epsilon is relative to the magnitude of the numbers
+    test_base.dialogue = test_base.dialogue.with_float_epsilon(1e-5);
+    assert_eq!(vec!["Equal".to_owned()], run_start(&mut test_base.dialogue));
+}
+
+#[test]
+fn test_step_limit_stops_infinite_loop() {
+    let result = Compiler::from_test_source("<<jump Start>>")
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue = test_base.dialogue.with_max_steps_per_next(100);
+    test_base.dialogue.set_node_to_start().unwrap();
+
+    let error = test_base.dialogue.continue_().unwrap_err();
+    assert!(matches!(
+        error,
+        DialogueError::StepLimitExceeded { max_steps: 100 }
+    ));
+    assert!(!test_base.dialogue.is_active());
+}
+
+#[test]
+fn test_step_limit_ends_iteration() {
+    let result = Compiler::from_test_source("<<jump Start>>")
+        .compile()
+        .unwrap();
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue = test_base
+        .dialogue
+        .with_max_steps_per_next(100)
+        .with_error_handler({
+            let errors = errors.clone();
+            move |error| errors.lock().unwrap().push(error.to_string())
+        });
+    test_base.dialogue.set_node_to_start().unwrap();
+
+    // The events of stopping the dialogue are delivered before the iteration ends
+    let events = test_base.dialogue.next().unwrap();
+    assert_eq!(Some(&DialogueEvent::DialogueComplete), events.last());
+    assert!(test_base.dialogue.next().is_none());
+    assert!(test_base.dialogue.current_node().is_none());
+    assert!(test_base.dialogue.jump_history().is_empty());
+
+    let errors = errors.lock().unwrap();
+    assert_eq!(1, errors.len());
+    assert!(errors[0].contains("100 instructions"));
+}
+
+#[test]
+fn test_error_handler_receives_errors() {
+    let result = Compiler::from_test_source("<<jump Start>>")
+        .compile()
+        .unwrap();
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue = test_base
+        .dialogue
+        .with_max_steps_per_next(100)
+        .with_error_handler({
+            let errors = errors.clone();
+            move |error| errors.lock().unwrap().push(error.to_string())
+        });
+    test_base.dialogue.set_node_to_start().unwrap();
+
+    // The error is still returned
+    let error = test_base.dialogue.continue_().unwrap_err();
+    assert_eq!(vec![error.to_string()], *errors.lock().unwrap());
+}
+
+#[test]
+fn test_assigning_to_constant_of_separately_compiled_program_fails() {
+    let constants = Compiler::new()
+        .add_file(File {
+            file_name: "constants.yarn".to_owned(),
+            source: "title: Constants\n---\n<<const $max_hp = 100>>\n===\n".to_owned(),
+        })
+        .compile()
+        .unwrap();
+    let result = Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source: "title: Start\n---\n<<set $max_hp to 50>>\n===\n".to_owned(),
+        })
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base
+        .dialogue
+        .add_program(constants.program.unwrap())
+        .set_node_to_start()
+        .unwrap();
+
+    let error = test_base.dialogue.continue_().unwrap_err();
+    assert!(matches!(
+        error,
+        DialogueError::ConstantAssignment { variable_name } if variable_name == "$max_hp"
+    ));
+    assert_eq!(
+        Some(YarnValue::from(100.0)),
+        test_base.dialogue.variable_storage().get("$max_hp").ok()
+    );
+}
+
+#[test]
+fn test_command_handlers_cannot_overwrite_constants() {
+    let result = Compiler::from_test_source("<<const $max_hp = 100>>\n<<heal>>")
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue = test_base.dialogue.with_command("heal", |_| {
+        CommandResult::Continue.with_variable_set("$max_hp", 200)
+    });
+    test_base.dialogue.set_node_to_start().unwrap();
+
+    // Handlers registered on the dialogue are run by the VM
+    let error = test_base.dialogue.continue_().unwrap_err();
+    assert!(matches!(
+        error,
+        DialogueError::ConstantAssignment { variable_name } if variable_name == "$max_hp"
+    ));
+
+    // Results of handlers run by the caller are applied afterwards
+    let error = test_base
+        .dialogue
+        .apply_command_result(CommandResult::Continue.with_variable_set("$max_hp", 300))
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        DialogueError::ConstantAssignment { variable_name } if variable_name == "$max_hp"
+    ));
+    assert_eq!(
+        Some(YarnValue::from(100.0)),
+        test_base.dialogue.variable_storage().get("$max_hp").ok()
+    );
+}
+
+#[test]
+fn test_unloading_single_node() {
+    let result = Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source: "title: Start\n---\nHello\n<<jump Quest>>\n===\n\
+                title: Quest\n---\nOld quest\n===\n\
+                title: Shop\n---\nWelcome\n===\n"
+                .to_owned(),
+        })
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    // A running node cannot be unloaded
+    test_base.dialogue.set_node("Quest").unwrap();
+    test_base.dialogue.continue_().unwrap();
+    assert!(matches!(
+        test_base.dialogue.unload_node("Quest"),
+        Err(DialogueError::UnloadingActiveNode { .. })
+    ));
+    test_base.dialogue.stop();
+
+    assert!(test_base.dialogue.unload_node("Quest").unwrap());
+    assert!(!test_base.dialogue.unload_node("Quest").unwrap());
+    assert!(!test_base.dialogue.node_exists("Quest"));
+    assert!(matches!(
+        test_base.dialogue.set_node("Quest"),
+        Err(DialogueError::InvalidNode { .. })
+    ));
+
+    // Other nodes still run
+    test_base.dialogue.set_node("Shop").unwrap();
+    let events = test_base.dialogue.continue_().unwrap();
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, DialogueEvent::Line(line) if line.text == "Welcome")));
+
+    // Jumps into the removed node fail
+    test_base.dialogue.stop();
+    test_base.dialogue.set_node("Start").unwrap();
+    test_base.dialogue.continue_().unwrap();
+    assert!(matches!(
+        test_base.dialogue.continue_(),
+        Err(DialogueError::InvalidNode { .. })
+    ));
+}
+
+#[test]
+fn test_node_exists_without_program() {
+    let test_base = TestBase::new();
+    assert!(!test_base.dialogue.has_program());
+    assert!(!test_base.dialogue.node_exists("Start"));
+    assert_eq!(None, test_base.dialogue.get_tags_for_node("Start"));
+
+    let result = Compiler::from_test_source("Hello").compile().unwrap();
+    let test_base = TestBase::new().with_compilation(result);
+    assert!(test_base.dialogue.has_program());
+    assert!(test_base.dialogue.node_exists("Start"));
+    assert!(!test_base.dialogue.node_exists("Missing"));
+}
+
+#[test]
+fn test_jump_history_records_traversal() {
+    let source = "\
+title: Start
+---
+<<jump Forest>>
+===
+title: Forest
+---
+Trees everywhere.
+<<jump River>>
+===
+title: River
+---
+<<jump Forest2>>
+===
+title: Forest2
+---
+More trees.
+===
+";
+    let result = Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source: source.to_owned(),
+        })
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.run_node("Start");
+    assert_eq!(
+        ["Start", "Forest", "River", "Forest2"].as_slice(),
+        test_base.dialogue.jump_history()
+    );
+
+    test_base.dialogue = test_base.dialogue.with_history_capacity(2);
+    assert_eq!(
+        ["River", "Forest2"].as_slice(),
+        test_base.dialogue.jump_history()
+    );
+
+    test_base.dialogue.stop();
+    assert!(test_base.dialogue.jump_history().is_empty());
+}
+
+#[test]
+fn test_with_node_at_sets_node() {
+    let result = Compiler::from_test_source("Hello").compile().unwrap();
+    let test_base = TestBase::new().with_compilation(result);
+    let dialogue = test_base.dialogue.with_node_at("Start");
+    assert_eq!(Some("Start".to_owned()), dialogue.current_node());
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "Failed to set node")]
+fn test_with_node_at_panics_on_missing_node_in_debug() {
+    let result = Compiler::from_test_source("Hello").compile().unwrap();
+    let test_base = TestBase::new().with_compilation(result);
+    let _dialogue = test_base.dialogue.with_node_at("Missing");
+}
+
+#[test]
+fn test_variable_liveness_analysis() {
+    let source = "\
+title: Start
+---
+<<declare $gold = 10>>
+<<declare $name = \"Sam\">>
+<<set $gold to 5>>
+You have {$gold} gold.
+<<jump Shop>>
+===
+title: Shop
+---
+Welcome, {$name}!
+<<set $name to \"Customer\">>
+===
+";
+    let result = Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source: source.to_owned(),
+        })
+        .compile()
+        .unwrap();
+    let mut program = result.program.clone().unwrap();
+
+    let mut context = Context::empty().with_variable_liveness_analysis();
+    TestBase::new()
+        .with_program(program.clone())
+        .dialogue
+        .analyse(&mut context);
+    let diagnoses = context.finish_analysis();
+    let kinds: Vec<_> = diagnoses
+        .iter()
+        .filter_map(|diagnosis| diagnosis.kind.clone())
+        .collect();
+    assert_eq!(
+        vec![DiagnosisKind::OverwrittenDefault {
+            variable: "$gold".to_owned()
+        }],
+        kinds
+    );
+    // The location is the `<<set $gold to 5>>` that overwrites the default
+    let overwritten_default = &diagnoses[0];
+    assert_eq!(Some("Start"), overwritten_default.node_name.as_deref());
+    let line_info =
+        result.debug_info["Start"].get_line_info(overwritten_default.instruction_index.unwrap());
+    assert_eq!(4, line_info.position.unwrap().line);
+
+    // Programs compiled from Yarn always have initial values, so remove one manually
+    program.initial_values.remove("$name");
+    let mut context = Context::empty().with_variable_liveness_analysis();
+    TestBase::new()
+        .with_program(program)
+        .dialogue
+        .analyse(&mut context);
+    let diagnoses = context.finish_analysis();
+    let uninitialized_read = diagnoses
+        .iter()
+        .find(|diagnosis| {
+            diagnosis.kind
+                == Some(DiagnosisKind::UninitializedRead {
+                    variable: "$name".to_owned(),
+                })
+        })
+        .unwrap();
+    assert_eq!(Some("Shop".to_owned()), uninitialized_read.node_name);
+    let line_info =
+        result.debug_info["Shop"].get_line_info(uninitialized_read.instruction_index.unwrap());
+    assert_eq!(10, line_info.position.unwrap().line);
+}
+
+#[test]
+fn test_formatting_time_and_durations() {
+    let mut test_base = TestBase::new();
+    let result = Compiler::from_test_source(
+        "{format_time(65)}\n{format_time(3725)}\n{format_duration(60)}\n{format_duration(125)}\n{format_duration(0)}",
+    )
+    .extend_library(test_base.dialogue.library().clone())
+    .compile()
+    .unwrap();
+    test_base = test_base.with_compilation(result);
+
+    let lines: Vec<_> = test_base
+        .dialogue
+        .run_node("Start")
+        .into_iter()
+        .map(|line| line.text)
+        .collect();
+    assert_eq!(
+        vec![
+            "01:05",
+            "1:02:05",
+            "1 minute",
+            "2 minutes 5 seconds",
+            "0 seconds"
+        ],
+        lines
+    );
+}
+
+#[test]
+fn test_setting_language_keeps_user_format_duration() {
+    let mut test_base = TestBase::new();
+    test_base
+        .dialogue
+        .set_format_duration(|seconds: f32| format!("{seconds}s"));
+    let result = Compiler::from_test_source("{format_duration(125)}")
+        .extend_library(test_base.dialogue.library().clone())
+        .compile()
+        .unwrap();
+    test_base = test_base.with_compilation(result);
+
+    for language in [Some(Language::from("de-CH")), None] {
+        test_base.dialogue.set_language_code(language);
+        let lines: Vec<_> = test_base
+            .dialogue
+            .run_node("Start")
+            .into_iter()
+            .map(|line| line.text)
+            .collect();
+        assert_eq!(vec!["125s"], lines);
+    }
+}
+
+#[test]
+fn test_substitutions_for_line() {
+    let result = Compiler::from_test_source(
+        "<<declare $name = \"Sally\">>\n\
+        <<declare $gold = 5>>\n\
+        {$name} has {$gold * 2} gold. #line:gold",
+    )
+    .compile()
+    .unwrap();
+    let mut dialogue = TestBase::new().with_compilation(result).dialogue;
+
+    assert_eq!(
+        Some(vec!["Sally".to_owned(), "10".to_owned()]),
+        dialogue.substitutions_for_line(&"line:gold".into())
+    );
+
+    dialogue
+        .variable_storage_mut()
+        .set("$gold".to_owned(), 21.into())
+        .unwrap();
+    assert_eq!(
+        Some(vec!["Sally".to_owned(), "42".to_owned()]),
+        dialogue.substitutions_for_line(&"line:gold".into())
+    );
+    assert_eq!(
+        None,
+        dialogue.substitutions_for_line(&"line:missing".into())
+    );
+}
+
+#[test]
+fn test_stop_command_ends_dialogue_mid_node() {
+    let result = Compiler::from_test_source("Before\n<<stop>>\nAfter")
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_node("Start").unwrap();
+
+    let events: Vec<_> = test_base.dialogue.by_ref().flatten().collect();
+    let lines: Vec<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            DialogueEvent::Line(line) => Some(line.text.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(vec!["Before"], lines);
+    assert_eq!(
+        [
+            DialogueEvent::NodeComplete("Start".to_owned()),
+            DialogueEvent::DialogueComplete
+        ]
+        .as_slice(),
+        &events[events.len() - 2..]
+    );
+    assert!(!test_base.dialogue.is_active());
+}
+
+#[test]
+fn test_line_transformers_rewrite_lines_in_registration_order() {
+    let result = Compiler::from_test_source("Hello [wave]there[/wave]")
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue = test_base
+        .dialogue
+        .with_line_transformer(|line| line.text = line.text.to_uppercase())
+        .with_line_transformer(|line| line.text.push('!'));
+
+    let lines = test_base.dialogue.run_node("Start");
+    assert_eq!(1, lines.len());
+    assert_eq!("HELLO THERE!", lines[0].text);
+    let wave = lines[0].attribute("wave").unwrap();
+    assert_eq!("THERE", lines[0].text_for_attribute(wave));
+}
+
+#[test]
+fn test_substitution_markup_is_literal_by_default() {
+    let line = run_line_with_substituted_name("[b]Sally[/b]", false);
+    assert_eq!("[b]Sally[/b] waves", line.text);
+    assert!(line.attribute("b").is_none());
+}
+
+#[test]
+fn test_substitution_with_trailing_backslash_is_literal() {
+    let line = run_line_with_substituted_name("Sally\\", false);
+    assert_eq!("Sally\\ waves", line.text);
+    assert!(line.attribute("wave").is_some());
+}
+
+#[test]
+fn test_substitution_markup_can_be_parsed() {
+    let line = run_line_with_substituted_name("[b]Sally[/b]", true);
+    assert_eq!("Sally waves", line.text);
+    let bold = line.attribute("b").unwrap();
+    assert_eq!("Sally", line.text_for_attribute(bold));
+}
+
+fn run_line_with_substituted_name(name: &str, substitution_markup: bool) -> Line {
+    let result = Compiler::from_test_source(
+        "<<declare $name = \"\">>\n\
+        {$name}[wave/] waves",
+    )
+    .compile()
+    .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue = test_base
+        .dialogue
+        .with_substitution_markup(substitution_markup);
+    test_base
+        .dialogue
+        .variable_storage_mut()
+        .set("$name".to_owned(), name.into())
+        .unwrap();
+
+    let mut lines = test_base.dialogue.run_node("Start");
+    assert_eq!(1, lines.len());
+    lines.remove(0)
+}
+
+#[test]
+fn test_referenced_command_names() {
+    let result = Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source: "title: Start\n---\n\
+                <<playSound \"door creak\">>\n<<wait 2>>\n<<jump Other>>\n===\n\
+                title: Other\n---\n<<wait {1 + 1}>>\n<<{\"dynamic\"} command>>\n===\n"
+                .to_owned(),
+        })
+        .compile()
+        .unwrap();
+    let dialogue = TestBase::new().with_compilation(result).dialogue;
+    assert_eq!(
+        BTreeSet::from(["playSound".to_owned(), "wait".to_owned()]),
+        dialogue.referenced_command_names()
+    );
+}
+
+#[test]
+fn test_unavailable_option_delivers_requirement_hint() {
+    let result = Compiler::from_test_source(
+        "<<declare $gold = 10>>\n\
+        -> Bribe the guard <<if $gold >= 50>> #requirement:50_gold\n\
+        -> Walk away\n",
+    )
+    .compile()
+    .unwrap();
+    let requirement_hints = &result
+        .program
+        .as_ref()
+        .unwrap()
+        .extensions
+        .requirement_hints;
+    assert_eq!(1, requirement_hints.len());
+    assert!(requirement_hints.values().all(|hint| hint == "50_gold"));
+
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_node("Start").unwrap();
+    let options = test_base
+        .dialogue
+        .continue_()
+        .unwrap()
+        .into_iter()
+        .find_map(|event| match event {
+            DialogueEvent::Options(options) => Some(options),
+            _ => None,
+        })
+        .unwrap();
+
+    assert!(!options[0].is_available);
+    assert_eq!(Some("50_gold"), options[0].requirement_hint.as_deref());
+    assert!(options[1].is_available);
+    assert_eq!(None, options[1].requirement_hint);
+}
+
+#[test]
+fn test_instruction_trace_records_last_instructions() {
+    let result = Compiler::from_test_source("<<set $x to 1>>\nHello")
+        .compile()
+        .unwrap();
+    let instruction_count = result.program.as_ref().unwrap().nodes["Start"]
+        .instructions
+        .len();
+
+    let mut test_base = TestBase::new().with_compilation(result.clone());
+    test_base.dialogue.run_node("Start");
+    assert_eq!(0, test_base.dialogue.instruction_trace().len());
+
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue = test_base.dialogue.with_instruction_trace(3);
+    test_base.dialogue.run_node("Start");
+
+    let trace: Vec<_> = test_base.dialogue.instruction_trace().collect();
+    assert_eq!(3, trace.len());
+    let last = trace.last().unwrap();
+    assert_eq!("Start", last.node_name);
+    assert_eq!(instruction_count - 1, last.instruction_index);
+}
+
+#[test]
+fn test_instruction_trace_records_failing_instruction() {
+    let result = Compiler::from_test_source("<<set $x to 1>>\nHello")
+        .compile()
+        .unwrap();
+    let store_index = result.program.as_ref().unwrap().nodes["Start"]
+        .instructions
+        .iter()
+        .position(|instruction| instruction.opcode == OpCode::StoreVariable as i32)
+        .unwrap();
+
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue = test_base.dialogue.with_instruction_trace(3);
+    test_base
+        .dialogue
+        .add_constant_variables(["$x".to_owned()])
+        .set_node("Start")
+        .unwrap();
+    assert!(test_base.dialogue.continue_().is_err());
+
+    let last = test_base.dialogue.instruction_trace().last().unwrap();
+    assert_eq!("Start", last.node_name);
+    assert_eq!(store_index, last.instruction_index);
+}
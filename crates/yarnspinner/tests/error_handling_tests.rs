@@ -3,6 +3,7 @@
 use crate::test_base::*;
 use test_base::prelude::*;
 use yarnspinner::compiler::*;
+use yarnspinner::core::Position;
 
 mod test_base;
 
@@ -101,3 +102,266 @@ fn test_compiling_same_file_twice_fails() {
         .iter()
         .any(|d| d.message.contains("Duplicate line ID line:794945")));
 }
+
+#[test]
+fn test_diagnostics_have_structured_kinds() {
+    let result = Compiler::from_test_source(
+        "<<declare $gold = 0>>\n\
+        <<declare $gold = \"none\">>\n\
+        <<set $gold to true>>",
+    )
+    .compile()
+    .unwrap_err();
+
+    println!("{}", result);
+    let kinds: Vec<_> = result.0.iter().filter_map(|d| d.kind.clone()).collect();
+    assert!(kinds.contains(&DiagnosticKind::DuplicateDeclaration {
+        variable: "$gold".to_owned()
+    }));
+    assert!(kinds.contains(&DiagnosticKind::Type));
+
+    let result = Compiler::from_test_source("<<if>>\n<<endif>>")
+        .compile()
+        .unwrap_err();
+    assert!(result
+        .0
+        .iter()
+        .any(|d| d.kind == Some(DiagnosticKind::Syntax)));
+}
+
+#[test]
+fn test_every_error_diagnostic_has_a_kind() {
+    let broken_sources = [
+        "<<if>>\n<<endif>>",
+        "<<declare $gold = 0>>\n<<declare $gold = \"none\">>\n<<set $gold to true>>",
+        "<<declare $copper = $gold>>",
+        "<<declare $gold = 0 as Coin>>",
+        "<<const $max_hp = 100>>\n<<set $max_hp to 50>>",
+        "Hello #line:hello\nHi #line:hello",
+        "Hello #shadow:line:missing",
+        "-> Bread #multiselect #line:bread,butter\n-> Cheese #multiselect",
+        "Hello [b]there[/i]",
+        "<<jump {1 + \"a\"}>>",
+    ];
+    let untitled = File {
+        file_name: "untitled.yarn".to_owned(),
+        source: "---\nHello\n===\n".to_owned(),
+    };
+    let compilers = broken_sources
+        .into_iter()
+        .map(Compiler::from_test_source)
+        .chain([Compiler::new().add_file(untitled).clone()]);
+
+    for compiler in compilers {
+        let compiled = compiler.compile().unwrap_err().0;
+        let validated = compiler.validate();
+        for diagnostic in compiled.iter().chain(&validated) {
+            if diagnostic.severity == DiagnosticSeverity::Error {
+                assert!(diagnostic.kind.is_some(), "{diagnostic}");
+            }
+        }
+    }
+}
+
+#[test]
+fn test_compiling_with_diagnostics_returns_program_despite_warnings() {
+    let result = Compiler::from_test_source("Copper: {$copper + 1}").compile_with_diagnostics();
+    assert!(result.program.is_some());
+    assert_eq!(1, result.diagnostics.len());
+    assert_eq!(DiagnosticSeverity::Warning, result.diagnostics[0].severity);
+}
+
+#[test]
+fn test_compiling_with_diagnostics_returns_errors_without_program() {
+    let result = Compiler::from_test_source("<<set $x = 1>>\n<<set $x = \"one\">>")
+        .compile_with_diagnostics();
+    assert!(result.program.is_none());
+    assert!(result
+        .diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error));
+}
+
+#[test]
+fn test_validating_clean_project_returns_no_diagnostics() {
+    let diagnostics = Compiler::from_test_source(
+        "<<declare $gold = 0>>\n<<set $gold to $gold + 1>>\nYou have {$gold} gold.\n<<jump Start>>",
+    )
+    .validate();
+    assert!(diagnostics.is_empty(), "{diagnostics:?}");
+}
+
+#[test]
+fn test_validating_broken_project_returns_diagnostics() {
+    let diagnostics = Compiler::from_test_source("<<set $x = 1>>\n<<set $x = \"one\">>").validate();
+    let compile_diagnostics = Compiler::from_test_source("<<set $x = 1>>\n<<set $x = \"one\">>")
+        .compile()
+        .unwrap_err()
+        .0;
+    assert_eq!(compile_diagnostics, diagnostics);
+}
+
+#[test]
+fn test_validating_reports_missing_node_title() {
+    let diagnostics = Compiler::new()
+        .add_file(File {
+            file_name: "test.yarn".to_owned(),
+            source: "tags: untitled\n---\nHello\n===\n".to_owned(),
+        })
+        .validate();
+    assert_eq!(1, diagnostics.len());
+    assert_eq!("Missing title header for node", diagnostics[0].message);
+}
+
+#[test]
+fn test_unclosed_markup_is_reported_at_marker() {
+    let result = Compiler::from_test_source("Hello [b]there").compile_with_diagnostics();
+    assert!(result.program.is_some());
+    assert_eq!(1, result.diagnostics.len());
+    let diagnostic = &result.diagnostics[0];
+    println!("{diagnostic}");
+    assert_eq!(Some(DiagnosticKind::Markup), diagnostic.kind);
+    assert_eq!(DiagnosticSeverity::Warning, diagnostic.severity);
+    // The body of the test node starts on the third line
+    let start = Position {
+        line: 2,
+        character: 6,
+    };
+    let end = Position {
+        line: 2,
+        character: 9,
+    };
+    assert_eq!(Some(start..end), diagnostic.range);
+}
+
+#[test]
+fn test_mismatched_markup_close_marker_is_an_error() {
+    let result = Compiler::from_test_source("[b]Hello[/i]")
+        .compile()
+        .unwrap_err();
+    println!("{}", result);
+    assert!(result
+        .0
+        .iter()
+        .any(|d| d.kind == Some(DiagnosticKind::Markup)
+            && d.severity == DiagnosticSeverity::Error
+            && d.message.contains("[/i]")));
+}
+
+#[test]
+fn test_assigning_to_constant_is_an_error() {
+    let result = Compiler::from_test_source("<<const $max_hp = 100>>\n<<set $max_hp to 50>>")
+        .compile()
+        .unwrap_err();
+
+    println!("{}", result);
+    let diagnostic = result
+        .0
+        .iter()
+        .find(|d| {
+            d.kind
+                == Some(DiagnosticKind::ConstantAssignment {
+                    variable: "$max_hp".to_owned(),
+                })
+        })
+        .unwrap();
+    assert_eq!(DiagnosticSeverity::Error, diagnostic.severity);
+    assert_eq!(
+        Some(Position {
+            line: 2,
+            character: 8,
+        }),
+        diagnostic.related_information[0]
+            .range
+            .as_ref()
+            .map(|range| range.start)
+    );
+
+    let compilation = Compiler::from_test_source("<<const $max_hp = 100>>\nHP: {$max_hp}")
+        .compile()
+        .unwrap();
+    let declaration = compilation
+        .declarations
+        .iter()
+        .find(|d| d.name == "$max_hp")
+        .unwrap();
+    assert!(declaration.is_constant);
+    assert!(!declaration.is_implicit);
+
+    let program = compilation.program.unwrap();
+    assert_eq!(
+        vec!["$max_hp".to_owned()],
+        program.extensions.constant_variables
+    );
+    assert_eq!(1, program.initial_values.len());
+}
+
+#[test]
+fn test_constant_must_be_finite() {
+    for value in ["inf", "NaN", "1e99"] {
+        let result = Compiler::from_test_source(&format!("<<const $max_hp = {value}>>"))
+            .compile()
+            .unwrap_err();
+        println!("{}", result);
+        assert!(result.0.iter().any(|d| d.kind == Some(DiagnosticKind::Type)
+            && d.message
+                .contains("must be a number, string or boolean literal")));
+    }
+}
+
+#[test]
+fn test_multiselect_line_id_must_not_contain_separator() {
+    let result = Compiler::from_test_source(
+        "-> Bread #multiselect #line:bread,butter\n-> Cheese #multiselect #line:cheese\n",
+    )
+    .compile()
+    .unwrap_err();
+
+    println!("{}", result);
+    let diagnostic = result
+        .0
+        .iter()
+        .find(|d| d.message.contains("line:bread,butter"))
+        .unwrap();
+    assert_eq!(DiagnosticSeverity::Error, diagnostic.severity);
+    assert!(!result.0.iter().any(|d| d.message.contains("line:cheese")));
+}
+
+#[test]
+fn test_invalid_line_group_weight_is_a_syntax_error() {
+    let result = Compiler::from_test_source("=> Hello! #weight:-1\n=> Hi!\n")
+        .compile()
+        .unwrap_err();
+    println!("{}", result);
+    assert!(result
+        .0
+        .iter()
+        .any(|d| d.kind == Some(DiagnosticKind::Syntax)
+            && d.message.contains("Invalid line group weight \"-1\"")));
+}
+
+#[test]
+fn test_markup_between_plain_text_is_validated() {
+    let result = Compiler::from_test_source("Hello [b]there[/b] friend").compile_with_diagnostics();
+    assert!(result.program.is_some());
+    assert!(result.diagnostics.is_empty());
+
+    let result = Compiler::from_test_source("Hello [b]there[/i] friend")
+        .compile()
+        .unwrap_err();
+    let diagnostic = result
+        .0
+        .iter()
+        .find(|d| d.severity == DiagnosticSeverity::Error)
+        .unwrap();
+    assert_eq!(Some(DiagnosticKind::Markup), diagnostic.kind);
+    let start = Position {
+        line: 2,
+        character: 14,
+    };
+    let end = Position {
+        line: 2,
+        character: 18,
+    };
+    assert_eq!(Some(start..end), diagnostic.range);
+}
@@ -220,3 +220,250 @@ fn crashes_on_command_expression_evaluating_whitespace() {
         .with_compilation(result)
         .run_standard_testcase();
 }
+
+#[test]
+fn test_substituted_numbers_use_locale_decimal_separator() {
+    let result = Compiler::from_test_source(
+        "<<declare $price = 1.5>>\nIt costs {$price} coins.\n<<wait {$price}>>\n",
+    )
+    .compile()
+    .unwrap();
+    let string_table: HashMap<_, _> = result
+        .string_table
+        .into_iter()
+        .map(|(id, info)| (id, info.text))
+        .collect();
+    let mut text_provider = StringTableTextProvider::new();
+    text_provider.extend_base_language(string_table.clone());
+    text_provider.extend_translation("de-DE", string_table);
+
+    let mut dialogue = Dialogue::new(
+        Box::new(MemoryVariableStorage::new()),
+        Box::new(text_provider),
+    );
+    dialogue.replace_program(result.program.unwrap());
+    dialogue.set_language_code(Language::from("de-DE"));
+    dialogue.set_node("Start").unwrap();
+
+    let events: Vec<_> = dialogue.by_ref().flatten().collect();
+    let line = events
+        .iter()
+        .find_map(|event| match event {
+            DialogueEvent::Line(line) => Some(line.text.clone()),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!("It costs 1,5 coins.", line);
+
+    // Commands are not localized
+    let command = events
+        .iter()
+        .find_map(|event| match event {
+            DialogueEvent::Command(command) => Some(command.raw.clone()),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!("wait 1.5", command);
+}
+
+#[test]
+fn test_indentation_after_non_ascii_text() {
+    let result = Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source: "title: 開始\n---\nこんにちは\n-> 選択肢一\n    選ばれた\n-> 選択肢二\n    選ばれなかった\n===\n"
+                .to_owned(),
+        })
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_node("開始").unwrap();
+
+    let mut lines = Vec::new();
+    while let Some(events) = test_base.dialogue.next() {
+        for event in events {
+            match event {
+                DialogueEvent::Line(line) => lines.push(line.text),
+                DialogueEvent::Options(options) => {
+                    assert_eq!(2, options.len());
+                    test_base.dialogue.set_selected_option(OptionId(0)).unwrap();
+                }
+                _ => {}
+            }
+        }
+    }
+    assert_eq!(vec!["こんにちは", "選ばれた"], lines);
+}
+
+#[test]
+fn test_shadow_lines_reuse_text_of_source_line() {
+    let result = Compiler::from_test_source(
+        "Hello there #line:greeting\nHello there #shadow:greeting #line:greeting_again\n",
+    )
+    .compile()
+    .unwrap();
+    let shadow_line = &result.string_table[&"line:greeting_again".into()];
+    assert_eq!("Hello there", shadow_line.text);
+    assert_eq!(Some("line:greeting".into()), shadow_line.shadow_line_id);
+
+    let mut text_provider = StringTableTextProvider::new();
+    text_provider.extend_base_language(
+        result
+            .string_table
+            .into_iter()
+            .map(|(id, info)| (id, info.text))
+            .collect(),
+    );
+    // Only the source line is translated
+    text_provider.extend_translation(
+        "de-DE",
+        HashMap::from([("line:greeting".into(), "Hallo".to_owned())]),
+    );
+    text_provider.extend_shadow_lines(HashMap::from([(
+        "line:greeting_again".into(),
+        "line:greeting".into(),
+    )]));
+    let mut dialogue = Dialogue::new(
+        Box::new(MemoryVariableStorage::new()),
+        Box::new(text_provider),
+    );
+    dialogue.replace_program(result.program.unwrap());
+    dialogue.set_language_code(Language::from("de-DE"));
+    dialogue.set_node("Start").unwrap();
+
+    let lines: Vec<_> = dialogue
+        .by_ref()
+        .flatten()
+        .filter_map(|event| match event {
+            DialogueEvent::Line(line) => Some(line.text),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(vec!["Hallo", "Hallo"], lines);
+}
+
+#[test]
+fn test_shadow_line_without_source_is_an_error() {
+    let result = Compiler::from_test_source("Hello there #shadow:missing\n").compile();
+
+    let diagnostics = result.unwrap_err().0;
+    assert!(diagnostics.iter().any(|diagnostic| diagnostic
+        .message
+        .contains("shadows line line:missing, which does not exist")));
+}
+
+#[test]
+fn test_stripped_line_text_is_supplied_by_text_provider() {
+    let result = Compiler::from_test_source("Hello there #line:greeting\n")
+        .with_strip_line_text(true)
+        .compile()
+        .unwrap();
+    let greeting = &result.string_table[&"line:greeting".into()];
+    assert!(greeting.text.is_empty());
+    assert!(greeting.is_text_stripped);
+    assert_eq!(3, greeting.line_number);
+
+    let run = |strings: HashMap<LineId, String>| {
+        let mut text_provider = StringTableTextProvider::new();
+        text_provider.extend_base_language(strings);
+        let mut dialogue = Dialogue::new(
+            Box::new(MemoryVariableStorage::new()),
+            Box::new(text_provider),
+        );
+        dialogue.replace_program(result.program.clone().unwrap());
+        dialogue.set_node("Start").unwrap();
+        dialogue.continue_()
+    };
+
+    // The stripped text is not shown as an empty line
+    let stripped_strings = result
+        .string_table
+        .iter()
+        .filter(|(_, info)| !info.is_text_stripped)
+        .map(|(id, info)| (id.clone(), info.text.clone()))
+        .collect();
+    assert!(matches!(
+        run(stripped_strings),
+        Err(DialogueError::LineProviderError { .. })
+    ));
+
+    // E.g. loaded from a strings file
+    let strings = HashMap::from([("line:greeting".into(), "Hello there".to_owned())]);
+    let events = run(strings).unwrap();
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, DialogueEvent::Line(line) if line.text == "Hello there")));
+}
+
+#[test]
+fn test_literal_expressions_are_folded() {
+    let instruction_count = |source: &str| {
+        let result = Compiler::from_test_source(&format!("<<declare $y = 2>>\n{source}"))
+            .compile()
+            .unwrap();
+        result.program.unwrap().nodes["Start"].instructions.len()
+    };
+    let folded = instruction_count("<<set $x = 2 + 3 * 4>>\n");
+    assert_eq!(instruction_count("<<set $x = 14>>\n"), folded);
+    assert!(folded < instruction_count("<<set $x = $y + 3 * 4>>\n"));
+
+    // Division by zero and number equality are left to the VM
+    assert!(folded < instruction_count("<<set $x = 1 / 0>>\n"));
+    assert!(folded < instruction_count("<<set $z = 1 == 1>>\n"));
+
+    let result = Compiler::from_test_source(
+        "<<set $x = 2 + 3 * 4>>\n<<set $z = !(1 < 2) or \"a\" + \"b\" == \"ab\">>\n",
+    )
+    .compile()
+    .unwrap();
+    let mut dialogue = TestBase::new().with_compilation(result).dialogue;
+    dialogue.run_node("Start");
+    let storage = dialogue.variable_storage();
+    assert_eq!(YarnValue::Number(14.0), storage.get("$x").unwrap());
+    assert_eq!(YarnValue::Boolean(true), storage.get("$z").unwrap());
+}
+
+#[test]
+fn test_whitespace_collapse_modes() {
+    let result = Compiler::from_test_source("Hello there #line:greeting\n")
+        .compile()
+        .unwrap();
+    let run = |mode: WhitespaceMode| {
+        let mut text_provider = StringTableTextProvider::new();
+        text_provider.extend_base_language(HashMap::from([(
+            "line:greeting".into(),
+            "Hello  [b]there[/b],\nfriend [nomarkup]a  b[/nomarkup] [nomarkup  note=x]c  d[/nomarkup]"
+                .to_owned(),
+        )]));
+        let mut dialogue = Dialogue::new(
+            Box::new(MemoryVariableStorage::new()),
+            Box::new(text_provider),
+        )
+        .with_whitespace_collapse(mode);
+        dialogue.replace_program(result.program.clone().unwrap());
+        dialogue.set_node("Start").unwrap();
+        let line = dialogue
+            .continue_()
+            .unwrap()
+            .into_iter()
+            .find_map(|event| match event {
+                DialogueEvent::Line(line) => Some(line),
+                _ => None,
+            })
+            .unwrap();
+        (line.text.clone(), line.attribute("b").unwrap().position)
+    };
+
+    assert_eq!(
+        ("Hello  there,\nfriend a  b c  d".to_owned(), 7),
+        run(WhitespaceMode::Preserve)
+    );
+    assert_eq!(
+        ("Hello there,\nfriend a  b c  d".to_owned(), 6),
+        run(WhitespaceMode::CollapseRuns)
+    );
+    assert_eq!(
+        ("Hello there, friend a  b c  d".to_owned(), 6),
+        run(WhitespaceMode::SingleLine)
+    );
+}
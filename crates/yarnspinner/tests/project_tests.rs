@@ -4,7 +4,7 @@
 //! - TestDeclarationFilesAreGenerated: Tests functionality that, quote "Is intended to be called by tools that let the user manage variable declarations."
 
 use regex::Regex;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use test_base::prelude::*;
 use yarnspinner::compiler::*;
 use yarnspinner_core::prelude::*;
@@ -272,6 +272,57 @@ before 🧑🏾‍❤️‍💋‍🧑🏻after #line:abc130 // with a comment
     assert_eq!(visited_ids.len(), compilation.string_table.len());
 }
 
+#[test]
+fn test_adding_line_tags_preserves_formatting() {
+    let original_text = "title: Start\r\n\
+        ---\r\n\
+        A line without a tag\r\n\
+        -> An option without a tag\r\n\
+        \tA line indented with a tab\r\n\
+        -> An option with a tag #line:expected_abc123\r\n\
+        \x20   A line indented with spaces\r\n\
+        ===\r\n";
+
+    let output = Compiler::add_tags_to_lines(original_text, Vec::new())
+        .unwrap()
+        .unwrap();
+
+    let original_lines: Vec<_> = original_text.split_inclusive('\n').collect();
+    let output_lines: Vec<_> = output.split_inclusive('\n').collect();
+    assert_eq!(original_lines.len(), output_lines.len());
+    let line_tag_regex = Regex::new(r"^ #line:\w+\r\n$").unwrap();
+    let mut changed_lines = Vec::new();
+    for (index, (original, output)) in original_lines.iter().zip(&output_lines).enumerate() {
+        if original == output {
+            continue;
+        }
+        // The only change is a tag right before the line ending
+        let original_content = original.strip_suffix("\r\n").unwrap();
+        let inserted = output.strip_prefix(original_content).unwrap();
+        assert!(line_tag_regex.is_match(inserted), "{inserted:?}");
+        changed_lines.push(index);
+    }
+    assert_eq!(vec![2, 3, 4, 6], changed_lines);
+}
+
+#[test]
+fn test_current_indent_depth_of_partially_typed_source() {
+    let header = "title: Start\n---\n";
+    assert_eq!(0, Compiler::current_indent_depth(header));
+    assert_eq!(
+        4,
+        Compiler::current_indent_depth(&format!("{header}-> Option 1\n    Nice.\n    "))
+    );
+    assert_eq!(
+        0,
+        Compiler::current_indent_depth(&format!("{header}-> Option 1\n    Nice.\nBack\n"))
+    );
+    assert_eq!(
+        0,
+        Compiler::current_indent_depth(&format!("{header}-> Option 1\n    Nice.\n===\n"))
+    );
+}
+
 #[test]
 fn test_debug_output_is_produced() {
     let file = File {
@@ -292,3 +343,255 @@ fn test_debug_output_is_produced() {
     assert_eq!(2, first_line_info.position.unwrap().line);
     assert_eq!(0, first_line_info.position.unwrap().character);
 }
+
+#[test]
+fn test_definition_map_points_to_definitions() {
+    let file = File {
+        file_name: "input".to_owned(),
+        source: "title: Start\n---\n<<declare $gold = 0>>\n<<set $gold to 5>>\n<<jump Other>>\n===\n\
+                 title: Other\n---\n<<if visited(\"Start\")>>\nYou have {$gold} gold.\n<<endif>>\n===\n"
+            .to_owned(),
+    };
+    let result = Compiler::new().add_file(file).compile().unwrap();
+    let definition_of = |line, character| {
+        result
+            .definition_map
+            .get(&("input".to_owned(), Position { line, character }))
+            .cloned()
+    };
+    let start_node = (
+        "input".to_owned(),
+        Position {
+            line: 0,
+            character: 0,
+        },
+    );
+    let other_node = (
+        "input".to_owned(),
+        Position {
+            line: 6,
+            character: 0,
+        },
+    );
+    let gold_declaration = (
+        "input".to_owned(),
+        Position {
+            line: 2,
+            character: 10,
+        },
+    );
+
+    // <<jump Other>>
+    assert_eq!(Some(other_node), definition_of(4, 7));
+    // visited("Start")
+    assert_eq!(Some(start_node), definition_of(8, 13));
+    // <<set $gold to 5>>
+    assert_eq!(Some(gold_declaration.clone()), definition_of(3, 6));
+    // {$gold}
+    assert_eq!(Some(gold_declaration), definition_of(9, 10));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_declarations_json_lists_all_declarations() {
+    let source = "\
+<<declare $coins = 5 as number>>
+<<set $visited_shop to true>>
+";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let json = result.declarations_json();
+    let declarations = json.as_array().unwrap();
+    assert_eq!(result.declarations.len(), declarations.len());
+
+    let coins = declarations
+        .iter()
+        .find(|declaration| declaration["name"] == "$coins")
+        .unwrap();
+    assert_eq!(false, coins["is_implicit"]);
+    let visited_shop = declarations
+        .iter()
+        .find(|declaration| declaration["name"] == "$visited_shop")
+        .unwrap();
+    assert_eq!(true, visited_shop["is_implicit"]);
+}
+
+#[test]
+fn test_conflicting_declarations_across_files_are_errors() {
+    let result = Compiler::new()
+        .add_file(File {
+            file_name: "first.yarn".to_owned(),
+            source: "title: First\n---\n<<declare $x = 1>>\n===\n".to_owned(),
+        })
+        .add_file(File {
+            file_name: "second.yarn".to_owned(),
+            source: "title: Second\n---\n<<declare $x = \"one\">>\n===\n".to_owned(),
+        })
+        .compile()
+        .unwrap_err();
+
+    let diagnostic = result
+        .0
+        .iter()
+        .find(|d| d.message.contains("$x has already been declared"))
+        .unwrap();
+    assert_eq!(DiagnosticSeverity::Error, diagnostic.severity);
+    let related_file_names: Vec<_> = diagnostic
+        .related_information
+        .iter()
+        .map(|info| info.file_name.as_deref())
+        .collect();
+    let mut file_names = vec![diagnostic.file_name.as_deref()];
+    file_names.extend(related_file_names);
+    file_names.sort();
+    assert_eq!(vec![Some("first.yarn"), Some("second.yarn")], file_names);
+    assert!(diagnostic
+        .related_information
+        .iter()
+        .all(|info| info.range.is_some()));
+}
+
+#[test]
+fn test_identical_declarations_across_files_are_warnings() {
+    let declaration = "<<declare $x = 1 as number>>";
+    let result = Compiler::new()
+        .add_file(File {
+            file_name: "first.yarn".to_owned(),
+            source: format!("title: First\n---\n{declaration}\n===\n"),
+        })
+        .add_file(File {
+            file_name: "second.yarn".to_owned(),
+            source: format!("title: Second\n---\n{declaration}\n===\n"),
+        })
+        .compile()
+        .unwrap();
+
+    assert_eq!(
+        1,
+        result
+            .declarations
+            .iter()
+            .filter(|d| d.name == "$x")
+            .count()
+    );
+    assert!(result.warnings.iter().any(|d| d
+        .message
+        .contains("$x has already been declared identically")));
+}
+
+#[test]
+fn test_trailing_newline_does_not_change_compilation() {
+    let source = "title: Start\n---\n-> Option 1\n    Nice.\n-> Option 2\nDone.\n===";
+    let compile = |source: String| {
+        Compiler::new()
+            .add_file(File {
+                file_name: "input".to_owned(),
+                source,
+            })
+            .compile()
+            .unwrap()
+    };
+    let without_newline = compile(source.to_owned());
+    let with_newline = compile(format!("{source}\n"));
+
+    assert_eq!(without_newline.program, with_newline.program);
+    let texts = |compilation: &Compilation| -> HashMap<_, _> {
+        compilation
+            .string_table
+            .iter()
+            .map(|(id, info)| (id.clone(), info.text.clone()))
+            .collect()
+    };
+    assert_eq!(texts(&without_newline), texts(&with_newline));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_external_definitions_check_function_calls() {
+    let definitions = ExternalDefinitions::from_json(
+        r#"{
+            "Commands": [
+                { "YarnName": "play_sound", "Parameters": [{ "Name": "sound", "Type": "string" }] },
+                {
+                    "YarnName": "shake",
+                    "Parameters": [{ "Name": "seconds", "Type": "number" }, { "Name": "loud", "Type": "bool" }]
+                }
+            ],
+            "Functions": [
+                {
+                    "YarnName": "roll_dice",
+                    "Documentation": "Rolls a dice with the given number of sides.",
+                    "Language": "csharp",
+                    "ReturnType": "number",
+                    "Parameters": [{ "Name": "sides", "Type": "number" }]
+                }
+            ]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        definitions,
+        ExternalDefinitions::from_json(&definitions.to_json()).unwrap()
+    );
+
+    let compile = |source: &str| {
+        Compiler::from_test_source(source)
+            .with_external_definitions(&definitions)
+            .unwrap()
+            .compile()
+    };
+    let error_messages = |source: &str| -> Vec<String> {
+        compile(source)
+            .unwrap_err()
+            .0
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect()
+    };
+    assert!(compile(
+        "<<play_sound \"creaking door\">>\n<<shake 1.5 true>>\n<<declare $time = 2>>\n<<shake {$time} false>>\n\
+        <<shake {1} {\"true\"} {\"extra\"}>>\n<<unknown_command a b c>>\nYou rolled a {roll_dice(6)}"
+    )
+    .is_ok());
+
+    assert_eq!(
+        vec!["Function \"roll_dice\" expects 1 parameter, but received 2"],
+        error_messages("You rolled a {roll_dice(6, 2)}")
+    );
+    assert_eq!(
+        vec!["Command \"play_sound\" expects 1 parameter, but received 2"],
+        error_messages("<<play_sound creaking door>>")
+    );
+    assert_eq!(
+        vec!["Command \"shake\" expects 2 parameters, but received 1"],
+        error_messages("<<shake 1>>")
+    );
+    assert_eq!(
+        vec!["shake parameter 1 expects a Number, not \"soon\""],
+        error_messages("<<shake soon true>>")
+    );
+    assert_eq!(
+        vec!["shake parameter 2 expects a Bool, not \"yes\""],
+        error_messages("<<shake 2 yes>>")
+    );
+}
+
+#[test]
+fn test_redeclaration_with_invalid_value_is_an_error() {
+    let result = Compiler::new()
+        .add_file(File {
+            file_name: "first.yarn".to_owned(),
+            source: "title: First\n---\n<<declare $x = 1>>\n===\n".to_owned(),
+        })
+        .add_file(File {
+            file_name: "second.yarn".to_owned(),
+            source: "title: Second\n---\n<<declare $x = $y>>\n===\n".to_owned(),
+        })
+        .compile()
+        .unwrap_err();
+
+    assert!(result
+        .0
+        .iter()
+        .any(|d| d.severity == DiagnosticSeverity::Error
+            && d.message.contains("$x has already been declared")));
+}
@@ -19,3 +19,21 @@ impl TestCompiler for Compiler {
         compiler
     }
 }
+
+pub trait TestDialogue {
+    /// Runs the dialogue from the given node until it completes and returns the lines it delivered.
+    /// The dialogue must not present any options, as none are selected.
+    fn run_node(&mut self, node_name: &str) -> Vec<Line>;
+}
+
+impl TestDialogue for Dialogue {
+    fn run_node(&mut self, node_name: &str) -> Vec<Line> {
+        self.set_node(node_name).unwrap();
+        self.flatten()
+            .filter_map(|event| match event {
+                DialogueEvent::Line(line) => Some(line),
+                _ => None,
+            })
+            .collect()
+    }
+}
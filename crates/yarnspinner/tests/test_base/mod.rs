@@ -130,6 +130,10 @@ impl TestBase {
 
     #[must_use]
     pub fn with_string_table(mut self, string_table: HashMap<LineId, StringInfo>) -> Self {
+        let shadow_lines: HashMap<_, _> = string_table
+            .iter()
+            .filter_map(|(id, info)| Some((id.clone(), info.shadow_line_id.clone()?)))
+            .collect();
         let string_table: HashMap<_, _> = string_table
             .into_iter()
             .map(|(id, info)| (id, info.text))
@@ -137,6 +141,7 @@ impl TestBase {
         let mut string_table_provider = StringTableTextProvider::new();
         string_table_provider.extend_base_language(string_table.clone());
         string_table_provider.extend_translation("en-US", string_table);
+        string_table_provider.extend_shadow_lines(shadow_lines);
         self.string_table.replace(string_table_provider);
         self.dialogue.set_language_code(Language::from("en-US"));
         self
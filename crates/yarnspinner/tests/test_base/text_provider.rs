@@ -1,5 +1,6 @@
 use std::{
     any::Any,
+    collections::HashMap,
     sync::{Arc, RwLock},
 };
 use yarnspinner_core::prelude::*;
@@ -34,6 +35,10 @@ impl TextProvider for SharedTextProvider {
         self.0.read().unwrap().get_text(id)
     }
 
+    fn get_texts(&self, ids: &[LineId]) -> HashMap<LineId, String> {
+        self.0.read().unwrap().get_texts(ids)
+    }
+
     fn set_language(&mut self, language: Option<Language>) {
         self.0.write().unwrap().set_language(language);
     }
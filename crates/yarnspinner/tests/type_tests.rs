@@ -185,6 +185,32 @@ fn test_expressions_allows_using_undeclared_variable() {
     }
 }
 
+#[test]
+fn test_interpolating_variable_that_is_never_assigned_warns() {
+    let result = Compiler::from_test_source(
+        "<<declare $gold = 0>>\n<<set $silver to 1>>\nGold: {$gold}, silver: {$silver}, copper: {$copper + 1}\n",
+    )
+    .compile()
+    .unwrap();
+
+    assert_eq!(1, result.warnings.len());
+    let warning = &result.warnings[0];
+    assert_eq!(DiagnosticSeverity::Warning, warning.severity);
+    assert!(warning.message.contains("$copper"));
+    assert_eq!(
+        Some(
+            Position {
+                line: 4,
+                character: 43,
+            }..Position {
+                line: 4,
+                character: 50,
+            }
+        ),
+        warning.range
+    );
+}
+
 #[test]
 fn test_expressions_require_compatible_types() {
     for declare in [true, false] {
@@ -774,3 +800,31 @@ fn test_if_statement_expressions_must_be_boolean() {
         .message
         .contains("Terms of 'if statement' must be Bool, not String")));
 }
+
+#[test]
+fn test_library_functions_are_known_to_type_checker() {
+    let mut library = Library::new();
+    library.add_function("is_ready", || true);
+
+    let result = Compiler::from_test_source("<<if is_ready()>>\nReady\n<<endif>>\n")
+        .with_library(&library)
+        .compile()
+        .unwrap();
+    assert!(result.warnings.is_empty());
+    // Library functions are not implicitly declared
+    assert!(!result.declarations.iter().any(|d| d.name == "is_ready"));
+
+    let result = Compiler::from_test_source("<<if is_ready()>>\nReady\n<<endif>>\n")
+        .compile()
+        .unwrap();
+    assert!(result.declarations.iter().any(|d| d.name == "is_ready"));
+
+    let result = Compiler::from_test_source("<<if is_ready(1)>>\nReady\n<<endif>>\n")
+        .with_library(&library)
+        .compile()
+        .unwrap_err();
+    assert!(result
+        .0
+        .iter()
+        .any(|d| d.message.contains("expects 0 parameters, but received 1")));
+}